@@ -0,0 +1,82 @@
+//! Compare top-down splaying, as done by `find`, with bottom-up splaying, as
+//! done by `find_bottom_up`, on a few access patterns.
+//!
+//! ```text
+//! cargo bench --features parent --bench splay
+//! ```
+
+use intrusive_splay_tree::SplayTree;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+#[path = "../tests/single.rs"]
+mod single;
+
+use single::{Single, SingleTree};
+
+const ELEMS: usize = 1 << 16;
+const LOOKUPS: usize = 1 << 20;
+
+/// A small, deterministic xorshift generator, so that every run and both
+/// routines see the same keys.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+}
+
+fn keys(pattern: &str) -> Vec<usize> {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    (0..LOOKUPS)
+        .map(|i| match pattern {
+            // A handful of hot keys, the workload splay trees are best at.
+            "hot" => rng.next() % 16 * (ELEMS / 16),
+            "sequential" => i % ELEMS,
+            "uniform" => rng.next() % ELEMS,
+            // Every other key is missing.
+            "misses" => rng.next() % (2 * ELEMS),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn time<'a>(elems: &'a [Single<'a>], keys: &[usize], bottom_up: bool) -> Duration {
+    // Only even values are in the tree, so odd keys miss.
+    let mut tree = SplayTree::<SingleTree>::new();
+    for elem in elems {
+        tree.insert(elem);
+    }
+
+    let start = Instant::now();
+    for key in keys {
+        let found = if bottom_up {
+            tree.find_bottom_up(key)
+        } else {
+            tree.find(key)
+        };
+        black_box(found);
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let elems: Vec<_> = (0..ELEMS).map(|x| Single::new(x * 2)).collect();
+    for pattern in ["hot", "sequential", "uniform", "misses"] {
+        // Keys are in terms of even values, except for misses.
+        let keys: Vec<_> = keys(pattern)
+            .into_iter()
+            .map(|k| if pattern == "misses" { k } else { k * 2 })
+            .collect();
+        let top_down = time(&elems, &keys, false);
+        let bottom_up = time(&elems, &keys, true);
+        println!(
+            "{pattern:>10}: top-down {:>8.2?}, bottom-up {:>8.2?} ({LOOKUPS} lookups over {ELEMS} elements)",
+            top_down, bottom_up,
+        );
+    }
+}
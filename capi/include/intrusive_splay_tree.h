@@ -0,0 +1,97 @@
+#ifndef INTRUSIVE_SPLAY_TREE_H
+#define INTRUSIVE_SPLAY_TREE_H
+
+/* Generated with cbindgen; see cbindgen.toml. */
+
+#include <stdbool.h>
+
+/*
+ An opaque splay tree handle.
+ */
+typedef struct intrusive_splay_tree intrusive_splay_tree;
+
+/*
+ A splay tree node that is embedded within some container type.
+
+ A node that is not in any tree must have both links set to null.
+ */
+typedef struct intrusive_splay_tree_node {
+  struct intrusive_splay_tree_node *left;
+  struct intrusive_splay_tree_node *right;
+} intrusive_splay_tree_node;
+
+/*
+ Compare `key` to the element containing `node`.
+
+ Returns a negative number if the key is less than the element, zero if they
+ are equal, and a positive number if the key is greater than the element.
+ `ctx` is the pointer that was given to `intrusive_splay_tree_new`.
+ */
+typedef int (*intrusive_splay_tree_compare_fn)(void *ctx,
+                                               const void *key,
+                                               const intrusive_splay_tree_node *node);
+
+/*
+ Visit the element containing `node` during a walk.
+
+ Returns `true` to continue walking, or `false` to stop. `ctx` is the
+ pointer that was given to `intrusive_splay_tree_walk`.
+ */
+typedef bool (*intrusive_splay_tree_visit_fn)(void *ctx, intrusive_splay_tree_node *node);
+
+/*
+ Create a new, empty tree that orders its elements with `compare`.
+
+ `ctx` is passed to every call of `compare`. The tree must be freed with
+ `intrusive_splay_tree_free`.
+ */
+intrusive_splay_tree *intrusive_splay_tree_new(intrusive_splay_tree_compare_fn compare, void *ctx);
+
+/*
+ Free a tree created with `intrusive_splay_tree_new`.
+
+ This does not touch the tree's elements, which are owned by the caller.
+ Null is ignored.
+ */
+void intrusive_splay_tree_free(intrusive_splay_tree *tree);
+
+/*
+ Is the tree empty?
+ */
+bool intrusive_splay_tree_is_empty(const intrusive_splay_tree *tree);
+
+/*
+ Insert the element containing `node`, whose key is `key`.
+
+ Returns `true` if the element was inserted, or `false` if the tree already
+ contains an element that compares equal to `key`, in which case the tree
+ is unchanged.
+ */
+bool intrusive_splay_tree_insert(intrusive_splay_tree *tree,
+                                 const void *key,
+                                 intrusive_splay_tree_node *node);
+
+/*
+ Find the element whose key compares equal to `key`.
+
+ Returns its node, or null if there is no such element.
+ */
+intrusive_splay_tree_node *intrusive_splay_tree_find(intrusive_splay_tree *tree, const void *key);
+
+/*
+ Remove the element whose key compares equal to `key`.
+
+ Returns its node, whose links are reset to null, or null if there is no
+ such element.
+ */
+intrusive_splay_tree_node *intrusive_splay_tree_remove(intrusive_splay_tree *tree, const void *key);
+
+/*
+ Walk the tree's elements in order, calling `visit` with each node until it
+ returns `false`.
+ */
+void intrusive_splay_tree_walk(const intrusive_splay_tree *tree,
+                               intrusive_splay_tree_visit_fn visit,
+                               void *ctx);
+
+#endif /* INTRUSIVE_SPLAY_TREE_H */
@@ -0,0 +1,189 @@
+//! A C API for `intrusive_splay_tree`.
+//!
+//! C code embeds a `struct intrusive_splay_tree_node` (see `Node`'s layout
+//! documentation) in each of its elements, and creates opaque tree handles
+//! with a comparison callback. Every operation that searches the tree takes a
+//! `const void *key`, which is passed through to the callback unchanged, so the
+//! same callback works for inserting elements and for finding them by key.
+//!
+//! The header is in `include/intrusive_splay_tree.h`, and can be regenerated
+//! with `cbindgen`; see `cbindgen.toml`.
+
+#![deny(missing_docs)]
+#![deny(missing_debug_implementations)]
+
+use intrusive_splay_tree::__internal::{CompareToNode, SplayTree};
+use std::cmp;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+pub use intrusive_splay_tree::Node;
+
+// The header fixes `struct intrusive_splay_tree_node` at two pointers, so
+// refuse to build if a feature of the core crate makes `Node` any bigger.
+const _: () = assert!(
+    core::mem::size_of::<Node>() == 2 * core::mem::size_of::<*const Node>()
+        && core::mem::align_of::<Node>() == core::mem::align_of::<*const Node>()
+);
+
+/// Compare `key` to the element containing `node`.
+///
+/// Returns a negative number if the key is less than the element, zero if they
+/// are equal, and a positive number if the key is greater than the element.
+/// `ctx` is the pointer that was given to `intrusive_splay_tree_new`.
+pub type CompareFn =
+    unsafe extern "C" fn(ctx: *mut c_void, key: *const c_void, node: *const Node<'static>) -> c_int;
+
+/// Visit the element containing `node` during a walk.
+///
+/// Returns `true` to continue walking, or `false` to stop. `ctx` is the
+/// pointer that was given to `intrusive_splay_tree_walk`.
+pub type VisitFn = unsafe extern "C" fn(ctx: *mut c_void, node: *mut Node<'static>) -> bool;
+
+/// An opaque splay tree handle.
+#[derive(Debug)]
+pub struct Tree {
+    tree: SplayTree<'static>,
+    compare: CompareFn,
+    ctx: *mut c_void,
+}
+
+impl Tree {
+    fn query(&self, key: *const c_void) -> Query {
+        Query {
+            compare: self.compare,
+            ctx: self.ctx,
+            key,
+        }
+    }
+}
+
+struct Query {
+    compare: CompareFn,
+    ctx: *mut c_void,
+    key: *const c_void,
+}
+
+impl CompareToNode<'static> for Query {
+    unsafe fn compare_to_node(&self, node: &'static Node<'static>) -> cmp::Ordering {
+        (self.compare)(self.ctx, self.key, node).cmp(&0)
+    }
+}
+
+fn node_ptr(node: Option<&'static Node<'static>>) -> *mut Node<'static> {
+    node.map_or(ptr::null_mut(), |n| n as *const _ as *mut _)
+}
+
+/// Create a new, empty tree that orders its elements with `compare`.
+///
+/// `ctx` is passed to every call of `compare`. The tree must be freed with
+/// `intrusive_splay_tree_free`.
+#[no_mangle]
+pub extern "C" fn intrusive_splay_tree_new(compare: CompareFn, ctx: *mut c_void) -> *mut Tree {
+    Box::into_raw(Box::new(Tree {
+        tree: SplayTree::new(),
+        compare,
+        ctx,
+    }))
+}
+
+/// Free a tree created with `intrusive_splay_tree_new`.
+///
+/// This does not touch the tree's elements, which are owned by the caller.
+/// Null is ignored.
+///
+/// # Safety
+///
+/// `tree` must be null or a tree from `intrusive_splay_tree_new` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_free(tree: *mut Tree) {
+    if !tree.is_null() {
+        drop(Box::from_raw(tree));
+    }
+}
+
+/// Is the tree empty?
+///
+/// # Safety
+///
+/// `tree` must be a live tree from `intrusive_splay_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_is_empty(tree: *const Tree) -> bool {
+    (*tree).tree.is_empty()
+}
+
+/// Insert the element containing `node`, whose key is `key`.
+///
+/// Returns `true` if the element was inserted, or `false` if the tree already
+/// contains an element that compares equal to `key`, in which case the tree
+/// is unchanged.
+///
+/// # Safety
+///
+/// `tree` must be a live tree from `intrusive_splay_tree_new`. `node` must
+/// have null links, must not be in any tree, and must stay alive and unmoved
+/// until it is removed from this tree or the tree is freed.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_insert(
+    tree: *mut Tree,
+    key: *const c_void,
+    node: *mut Node<'static>,
+) -> bool {
+    let tree = &mut *tree;
+    let query = tree.query(key);
+    tree.tree.insert(&query, &*node)
+}
+
+/// Find the element whose key compares equal to `key`.
+///
+/// Returns its node, or null if there is no such element.
+///
+/// # Safety
+///
+/// `tree` must be a live tree from `intrusive_splay_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_find(
+    tree: *mut Tree,
+    key: *const c_void,
+) -> *mut Node<'static> {
+    let tree = &mut *tree;
+    let query = tree.query(key);
+    node_ptr(tree.tree.find(&query))
+}
+
+/// Remove the element whose key compares equal to `key`.
+///
+/// Returns its node, whose links are reset to null, or null if there is no
+/// such element.
+///
+/// # Safety
+///
+/// `tree` must be a live tree from `intrusive_splay_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_remove(
+    tree: *mut Tree,
+    key: *const c_void,
+) -> *mut Node<'static> {
+    let tree = &mut *tree;
+    let query = tree.query(key);
+    node_ptr(tree.tree.remove(&query))
+}
+
+/// Walk the tree's elements in order, calling `visit` with each node until it
+/// returns `false`.
+///
+/// # Safety
+///
+/// `tree` must be a live tree from `intrusive_splay_tree_new`, and `visit`
+/// must not modify the tree.
+#[no_mangle]
+pub unsafe extern "C" fn intrusive_splay_tree_walk(
+    tree: *const Tree,
+    visit: VisitFn,
+    ctx: *mut c_void,
+) {
+    (*tree)
+        .tree
+        .walk(&mut |node| visit(ctx, node as *const _ as *mut _));
+}
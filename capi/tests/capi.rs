@@ -0,0 +1,86 @@
+use intrusive_splay_tree_capi::*;
+use std::mem;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// An element the way C code would declare it.
+#[repr(C)]
+struct Elem {
+    value: u32,
+    node: Node<'static>,
+}
+
+unsafe fn node_to_elem(node: *const Node<'static>) -> *const Elem {
+    (node as *const u8).sub(mem::offset_of!(Elem, node)) as *const Elem
+}
+
+unsafe extern "C" fn compare(
+    ctx: *mut c_void,
+    key: *const c_void,
+    node: *const Node<'static>,
+) -> c_int {
+    *(ctx as *mut usize) += 1;
+    let key = *(key as *const u32);
+    let value = (*node_to_elem(node)).value;
+    key.cmp(&value) as c_int
+}
+
+unsafe extern "C" fn collect(ctx: *mut c_void, node: *mut Node<'static>) -> bool {
+    let values = &mut *(ctx as *mut Vec<u32>);
+    values.push((*node_to_elem(node)).value);
+    values.len() < 3
+}
+
+#[test]
+fn insert_find_remove_walk() {
+    let elems: Vec<Elem> = [5, 3, 8, 1, 4]
+        .iter()
+        .map(|&value| Elem {
+            value,
+            node: Node::default(),
+        })
+        .collect();
+
+    unsafe {
+        let mut comparisons = 0usize;
+        let tree = intrusive_splay_tree_new(compare, &mut comparisons as *mut usize as *mut c_void);
+        assert!(intrusive_splay_tree_is_empty(tree));
+
+        for elem in &elems {
+            let node = &elem.node as *const _ as *mut _;
+            let key = &elem.value as *const u32 as *const c_void;
+            assert!(intrusive_splay_tree_insert(tree, key, node));
+        }
+        assert!(!intrusive_splay_tree_is_empty(tree));
+        assert!(comparisons > 0);
+
+        let duplicate = Elem {
+            value: 3,
+            node: Node::default(),
+        };
+        assert!(!intrusive_splay_tree_insert(
+            tree,
+            &duplicate.value as *const u32 as *const c_void,
+            &duplicate.node as *const _ as *mut _,
+        ));
+
+        let key = 4u32;
+        let found = intrusive_splay_tree_find(tree, &key as *const u32 as *const c_void);
+        assert_eq!(found as *const _, &elems[4].node as *const _);
+        let missing = 7u32;
+        assert!(intrusive_splay_tree_find(tree, &missing as *const u32 as *const c_void).is_null());
+
+        let mut values: Vec<u32> = vec![];
+        intrusive_splay_tree_walk(tree, collect, &mut values as *mut Vec<u32> as *mut c_void);
+        assert_eq!(values, [1, 3, 4]);
+
+        let key = 5u32;
+        let removed = intrusive_splay_tree_remove(tree, &key as *const u32 as *const c_void);
+        assert_eq!(removed as *const _, &elems[0].node as *const _);
+        assert!((*removed).left().is_none() && (*removed).right().is_none());
+        assert!(intrusive_splay_tree_find(tree, &key as *const u32 as *const c_void).is_null());
+
+        intrusive_splay_tree_free(tree);
+        intrusive_splay_tree_free(ptr::null_mut());
+    }
+}
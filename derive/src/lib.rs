@@ -0,0 +1,373 @@
+//! Derive macros for the [`intrusive_splay_tree`] crate.
+//!
+//! Don't depend on this crate directly. Instead, enable the `derive` feature of
+//! `intrusive_splay_tree` and use the re-exported macros from there.
+//!
+//! This crate intentionally has no dependencies, so it does its own (very
+//! small) parsing of the item it is applied to, rather than using `syn`.
+//!
+//! [`intrusive_splay_tree`]: https://docs.rs/intrusive_splay_tree
+
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Derive `TreeOrd` implementations that order elements by one of their
+/// fields.
+///
+/// Each `#[tree_key(node_field = "key_field", tree = Marker)]` attribute on the
+/// struct generates two impls against the `Marker` tree type:
+///
+/// * `TreeOrd<'a, Marker>` for the element type, comparing the `key_field` of
+///   both elements, and
+/// * `TreeOrd<'a, Marker>` for the type of `key_field`, so the tree can be
+///   queried by key without constructing a whole element.
+///
+/// The key field's type must implement `Ord`. The `node_field` must be the
+/// intrusive `Node` that `Marker`'s `IntrusiveNode` implementation uses; this
+/// is checked with a `debug_assert!`.
+///
+/// ```ignore
+/// #[derive(intrusive_splay_tree::TreeOrd)]
+/// #[tree_key(by_id_node = "id", tree = MonstersById)]
+/// #[tree_key(by_health_node = "health", tree = MonstersByHealth)]
+/// struct Monster<'a> {
+///     id: u64,
+///     health: u64,
+///     by_id_node: intrusive_splay_tree::Node<'a>,
+///     by_health_node: intrusive_splay_tree::Node<'a>,
+/// }
+/// ```
+#[proc_macro_derive(TreeOrd, attributes(tree_key))]
+pub fn derive_tree_ord(input: TokenStream) -> TokenStream {
+    match derive_tree_ord_impl(input) {
+        Ok(ts) => ts,
+        Err(msg) => compile_error(&msg),
+    }
+}
+
+fn derive_tree_ord_impl(input: TokenStream) -> Result<TokenStream, String> {
+    let item = parse_struct(input)?;
+    let lifetime = item
+        .lifetime()
+        .ok_or("`TreeOrd` can only be derived for structs with a lifetime parameter")?;
+
+    let mut out = String::new();
+    for attr in item.attrs.iter().filter(|a| a.name == "tree_key") {
+        let key = TreeKey::parse(&attr.args)?;
+        item.field(&key.node)?;
+        let key_ty = &item.field(&key.key)?.ty;
+        out.push_str(&tree_ord_impls(&item, &lifetime, &key, key_ty));
+    }
+    if out.is_empty() {
+        return Err("`#[derive(TreeOrd)]` requires at least one \
+                    `#[tree_key(node_field = \"key_field\", tree = Marker)]` attribute"
+            .into());
+    }
+
+    out.parse()
+        .map_err(|e| format!("`#[derive(TreeOrd)]` generated bad code: {e:?}"))
+}
+
+fn tree_ord_impls(item: &Struct, lifetime: &str, key: &TreeKey, key_ty: &str) -> String {
+    let Struct {
+        name,
+        generics_decl,
+        generics_args,
+        where_clause,
+        ..
+    } = item;
+    let TreeKey { node, key, tree } = key;
+    format!(
+        "
+        impl<{generics_decl}> ::intrusive_splay_tree::TreeOrd<{lifetime}, {tree}>
+            for {name}<{generics_args}> {where_clause}
+        {{
+            #[inline]
+            fn tree_cmp(
+                &self,
+                elem: &{lifetime} <{tree} as ::intrusive_splay_tree::IntrusiveNode<{lifetime}>>::Elem,
+            ) -> ::core::cmp::Ordering {{
+                debug_assert!(::core::ptr::eq(
+                    <{tree} as ::intrusive_splay_tree::IntrusiveNode<{lifetime}>>::elem_to_node(elem),
+                    &elem.{node},
+                ));
+                ::core::cmp::Ord::cmp(&self.{key}, &elem.{key})
+            }}
+        }}
+
+        impl<{generics_decl}> ::intrusive_splay_tree::TreeOrd<{lifetime}, {tree}>
+            for {key_ty} {where_clause}
+        {{
+            #[inline]
+            fn tree_cmp(
+                &self,
+                elem: &{lifetime} <{tree} as ::intrusive_splay_tree::IntrusiveNode<{lifetime}>>::Elem,
+            ) -> ::core::cmp::Ordering {{
+                ::core::cmp::Ord::cmp(self, &elem.{key})
+            }}
+        }}
+        "
+    )
+}
+
+/// A parsed `#[tree_key(node_field = "key_field", tree = Marker)]` attribute.
+struct TreeKey {
+    node: String,
+    key: String,
+    tree: String,
+}
+
+impl TreeKey {
+    fn parse(args: &[TokenTree]) -> Result<TreeKey, String> {
+        const USAGE: &str = "expected `#[tree_key(node_field = \"key_field\", tree = Marker)]`";
+
+        let mut node = None;
+        let mut tree = None;
+        for arg in split_commas(args) {
+            match arg.as_slice() {
+                [TokenTree::Ident(name), TokenTree::Punct(eq), ty @ ..]
+                    if name.to_string() == "tree" && eq.as_char() == '=' && !ty.is_empty() =>
+                {
+                    tree = Some(tokens_to_string(ty));
+                }
+                [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Literal(lit)]
+                    if eq.as_char() == '=' =>
+                {
+                    let lit = lit.to_string();
+                    let key = lit
+                        .strip_prefix('"')
+                        .and_then(|l| l.strip_suffix('"'))
+                        .ok_or(USAGE)?;
+                    node = Some((name.to_string(), key.to_string()));
+                }
+                _ => return Err(USAGE.into()),
+            }
+        }
+
+        let (node, key) = node.ok_or(USAGE)?;
+        let tree = tree.ok_or(USAGE)?;
+        Ok(TreeKey { node, key, tree })
+    }
+}
+
+/// An outer attribute, e.g. `#[name(args...)]`.
+struct Attr {
+    name: String,
+    args: Vec<TokenTree>,
+}
+
+/// A named struct field.
+struct Field {
+    name: String,
+    ty: String,
+}
+
+/// The parts of a `struct` item that the derives care about.
+struct Struct {
+    attrs: Vec<Attr>,
+    name: String,
+    /// The generic parameters, with their bounds, e.g. `'a, T: Clone`.
+    generics_decl: String,
+    /// The generic parameters as arguments, e.g. `'a, T`.
+    generics_args: String,
+    /// The where clause, including the `where` keyword, if any.
+    where_clause: String,
+    fields: Vec<Field>,
+}
+
+impl Struct {
+    /// The first lifetime parameter, which is used as the tree's lifetime.
+    fn lifetime(&self) -> Option<String> {
+        let first = self.generics_args.split(',').next()?.trim();
+        if first.starts_with('\'') {
+            Some(first.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<&Field, String> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .ok_or_else(|| format!("no field named `{name}` in `{}`", self.name))
+    }
+}
+
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+
+    // Outer attributes.
+    let mut attrs = vec![];
+    while let (Some(TokenTree::Punct(p)), Some(TokenTree::Group(g))) =
+        (tokens.get(i), tokens.get(i + 1))
+    {
+        if p.as_char() != '#' || g.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+        if let Some(TokenTree::Ident(name)) = inner.first() {
+            let args = match inner.get(1) {
+                Some(TokenTree::Group(args)) => args.stream().into_iter().collect(),
+                _ => vec![],
+            };
+            attrs.push(Attr {
+                name: name.to_string(),
+                args,
+            });
+        }
+        i += 2;
+    }
+
+    // Visibility and the `struct` keyword.
+    loop {
+        match tokens.get(i) {
+            Some(TokenTree::Ident(id)) if id.to_string() == "struct" => break,
+            Some(_) => i += 1,
+            None => return Err("this derive can only be used on structs".into()),
+        }
+    }
+    i += 1;
+
+    let name = match tokens.get(i) {
+        Some(TokenTree::Ident(id)) => id.to_string(),
+        _ => return Err("expected a struct name".into()),
+    };
+    i += 1;
+
+    // Generics.
+    let mut generics = vec![];
+    if is_punct(tokens.get(i), '<') {
+        i += 1;
+        let mut depth = 1;
+        while let Some(tt) = tokens.get(i) {
+            i += 1;
+            if is_punct(Some(tt), '<') {
+                depth += 1;
+            } else if is_punct(Some(tt), '>') && !is_arrow(&tokens, i - 1) {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            generics.push(tt.clone());
+        }
+    }
+    let generics_args = split_commas(&generics)
+        .iter()
+        .map(|param| generic_param_name(param))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Where clause and body.
+    let mut where_clause = vec![];
+    let body = loop {
+        match tokens.get(i) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => break g.stream(),
+            Some(tt) => where_clause.push(tt.clone()),
+            None => return Err("this derive can only be used on structs with named fields".into()),
+        }
+        i += 1;
+    };
+
+    let body: Vec<TokenTree> = body.into_iter().collect();
+    let mut fields = vec![];
+    for field in split_commas(&body) {
+        let mut j = 0;
+        // Skip field attributes and visibility.
+        loop {
+            match field.get(j) {
+                Some(TokenTree::Punct(p)) if p.as_char() == '#' => j += 2,
+                Some(TokenTree::Ident(id)) if id.to_string() == "pub" => {
+                    j += 1;
+                    if let Some(TokenTree::Group(g)) = field.get(j) {
+                        if g.delimiter() == Delimiter::Parenthesis {
+                            j += 1;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        match (field.get(j), field.get(j + 1)) {
+            (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(colon)))
+                if colon.as_char() == ':' =>
+            {
+                fields.push(Field {
+                    name: name.to_string(),
+                    ty: tokens_to_string(&field[j + 2..]),
+                });
+            }
+            _ => return Err("this derive can only be used on structs with named fields".into()),
+        }
+    }
+
+    Ok(Struct {
+        attrs,
+        name,
+        generics_decl: tokens_to_string(&generics),
+        generics_args,
+        where_clause: tokens_to_string(&where_clause),
+        fields,
+    })
+}
+
+/// Get the name of a generic parameter as it would be used as an argument:
+/// `'a: 'b` becomes `'a`, `T: Clone` becomes `T`, and `const N: usize` becomes
+/// `N`.
+fn generic_param_name(param: &[TokenTree]) -> String {
+    match param {
+        [TokenTree::Punct(p), TokenTree::Ident(id), ..] if p.as_char() == '\'' => format!("'{id}"),
+        [TokenTree::Ident(kw), TokenTree::Ident(id), ..] if kw.to_string() == "const" => {
+            id.to_string()
+        }
+        [TokenTree::Ident(id), ..] => id.to_string(),
+        _ => tokens_to_string(param),
+    }
+}
+
+/// Split a token sequence on the commas that aren't nested inside angle
+/// brackets. Empty trailing segments are dropped.
+fn split_commas(tokens: &[TokenTree]) -> Vec<Vec<TokenTree>> {
+    let mut out = vec![];
+    let mut current = vec![];
+    let mut depth = 0_usize;
+    for (i, tt) in tokens.iter().enumerate() {
+        if is_punct(Some(tt), '<') {
+            depth += 1;
+        } else if is_punct(Some(tt), '>') && !is_arrow(tokens, i) {
+            depth = depth.saturating_sub(1);
+        } else if is_punct(Some(tt), ',') && depth == 0 {
+            out.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push(tt.clone());
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+fn is_punct(tt: Option<&TokenTree>, c: char) -> bool {
+    matches!(tt, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+/// Is the `>` at `tokens[i]` the second half of a `->`?
+fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    i > 0
+        && matches!(&tokens[i - 1], TokenTree::Punct(p)
+            if p.as_char() == '-' && p.spacing() == Spacing::Joint)
+}
+
+fn tokens_to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("::core::compile_error!({msg:?});").parse().unwrap()
+}
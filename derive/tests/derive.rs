@@ -0,0 +1,66 @@
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::marker::PhantomData;
+
+#[derive(Debug, TreeOrd)]
+#[tree_key(by_id_node = "id", tree = MonstersById)]
+#[tree_key(by_name_node = "name", tree = MonstersByName<'a>)]
+struct Monster<'a> {
+    id: u64,
+    pub name: &'a str,
+    by_id_node: Node<'a>,
+    pub(crate) by_name_node: Node<'a>,
+}
+
+impl<'a> Monster<'a> {
+    fn new(id: u64, name: &'a str) -> Monster<'a> {
+        Monster {
+            id,
+            name,
+            by_id_node: Node::default(),
+            by_name_node: Node::default(),
+        }
+    }
+}
+
+struct MonstersById;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for MonstersById
+    where
+        type Elem = Monster<'a>,
+        node = by_id_node;
+}
+
+struct MonstersByName<'a>(PhantomData<&'a ()>);
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for MonstersByName<'a>
+    where
+        type Elem = Monster<'a>,
+        node = by_name_node;
+}
+
+#[test]
+fn derived_tree_ord() {
+    let arena = bumpalo::Bump::new();
+    let mut by_id = SplayTree::<MonstersById>::new();
+    let mut by_name = SplayTree::<MonstersByName>::new();
+
+    for (id, name) in [(3, "Vegeta"), (1, "Godzilla"), (2, "Dracula")] {
+        let m = arena.alloc(Monster::new(id, name));
+        assert!(by_id.insert(m));
+        assert!(by_name.insert(m));
+    }
+
+    assert_eq!(by_id.find(&2).unwrap().name, "Dracula");
+    assert!(by_id.find(&4).is_none());
+    assert_eq!(by_name.find(&"Vegeta").unwrap().id, 3);
+    assert!(by_name.find(&"Mothra").is_none());
+
+    assert_eq!(by_id.min().unwrap().id, 1);
+    assert_eq!(by_name.min().unwrap().name, "Dracula");
+
+    let dup = arena.alloc(Monster::new(1, "Mothra"));
+    assert!(!by_id.insert(dup));
+    assert!(by_name.insert(dup));
+}
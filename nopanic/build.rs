@@ -0,0 +1,7 @@
+fn main() {
+    // Shared libraries may have undefined symbols by default, to be resolved
+    // when they are loaded. Make them a link error instead, so that a panic
+    // path, which leads to the undefined `a_panic_is_reachable`, fails the
+    // build.
+    println!("cargo:rustc-cdylib-link-arg=-Wl,--no-undefined");
+}
@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+
+# Check that no panic is reachable from `SplayTree`'s core operations, with
+# every combination of features that can't panic by design. See `src/lib.rs`.
+
+set -eux
+
+cd "$(dirname $0)"
+
+for features in "" linked rank parent hooks watchdog monomorphize \
+    "linked,rank,parent,hooks,watchdog"; do
+    cargo build --profile nopanic --features "$features"
+done
@@ -0,0 +1,151 @@
+//! A link-time check that `SplayTree`'s core operations can't panic.
+//!
+//! This library exports a function for each core operation, on a concrete
+//! tree type. Its panic handler calls `a_panic_is_reachable`, which is never
+//! defined, and `build.rs` makes undefined symbols a link error. So if the
+//! optimizer can't remove every panic from the exported functions, through
+//! everything they call in `intrusive_splay_tree` and `core`, linking fails
+//! and names that symbol.
+//!
+//! This only holds without `debug_assertions`, since the crate's debug
+//! assertions and overflow checks are all panics. Run `./check.sh`, which
+//! builds with the `nopanic` profile, with each feature that doesn't panic by
+//! design.
+
+#![no_std]
+
+use core::cmp::Ordering;
+use intrusive_splay_tree::{impl_intrusive_node, Error, Node, SplayTree, TreeOrd};
+
+extern "C" {
+    fn a_panic_is_reachable() -> !;
+}
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    unsafe { a_panic_is_reachable() }
+}
+
+// Parts of `core` are built to unwind, and refer to this, but nothing here
+// ever unwinds, so it is never called.
+#[no_mangle]
+pub extern "C" fn rust_eh_personality() {}
+
+pub struct Elem<'a> {
+    pub key: u64,
+    pub node: Node<'a>,
+}
+
+pub struct ByKey;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByKey
+    where
+        type Elem = Elem<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByKey> for Elem<'a> {
+    fn tree_cmp(&self, rhs: &'a Elem<'a>) -> Ordering {
+        self.key.cmp(&rhs.key)
+    }
+}
+
+impl<'a> TreeOrd<'a, ByKey> for u64 {
+    fn tree_cmp(&self, rhs: &'a Elem<'a>) -> Ordering {
+        self.cmp(&rhs.key)
+    }
+}
+
+type Tree = SplayTree<'static, ByKey>;
+type Found = Option<&'static Elem<'static>>;
+
+#[no_mangle]
+pub extern "C" fn insert(tree: &mut Tree, elem: &'static Elem<'static>) -> bool {
+    tree.insert(elem)
+}
+
+#[no_mangle]
+pub extern "C" fn try_insert(tree: &mut Tree, elem: &'static Elem<'static>) -> bool {
+    tree.try_insert(elem).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn find(tree: &mut Tree, key: u64) -> Found {
+    tree.find(&key)
+}
+
+#[no_mangle]
+pub extern "C" fn get(tree: &Tree, key: u64) -> Found {
+    tree.get(&key)
+}
+
+#[no_mangle]
+pub extern "C" fn remove(tree: &mut Tree, key: u64) -> Found {
+    tree.remove(&key)
+}
+
+#[no_mangle]
+pub extern "C" fn remove_ge(tree: &mut Tree, key: u64) -> Found {
+    tree.remove_ge(&key)
+}
+
+#[no_mangle]
+pub extern "C" fn min(tree: &mut Tree) -> Found {
+    tree.min()
+}
+
+#[no_mangle]
+pub extern "C" fn max(tree: &mut Tree) -> Found {
+    tree.max()
+}
+
+#[no_mangle]
+pub extern "C" fn pop_min(tree: &mut Tree) -> Found {
+    tree.pop_min()
+}
+
+#[no_mangle]
+pub extern "C" fn pop_max(tree: &mut Tree) -> Found {
+    tree.pop_max()
+}
+
+#[no_mangle]
+pub extern "C" fn pop_root(tree: &mut Tree) -> Found {
+    tree.pop_root()
+}
+
+#[no_mangle]
+pub extern "C" fn sum(tree: &Tree) -> u64 {
+    let mut sum = 0u64;
+    tree.walk(|elem| sum = sum.wrapping_add(elem.key));
+    sum
+}
+
+#[no_mangle]
+pub extern "C" fn rebalance(tree: &mut Tree) {
+    tree.rebalance()
+}
+
+#[no_mangle]
+pub extern "C" fn validate(tree: &Tree) -> bool {
+    tree.validate() != Err(Error::Corrupt)
+}
+
+#[cfg(feature = "rank")]
+#[no_mangle]
+pub extern "C" fn len(tree: &Tree) -> usize {
+    tree.len()
+}
+
+#[cfg(feature = "parent")]
+#[no_mangle]
+pub extern "C" fn find_bottom_up(tree: &mut Tree, key: u64) -> Found {
+    tree.find_bottom_up(&key)
+}
+
+#[cfg(feature = "parent")]
+#[no_mangle]
+pub extern "C" fn splay_elem(tree: &mut Tree, elem: &'static Elem<'static>) {
+    tree.splay_elem(elem)
+}
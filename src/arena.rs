@@ -0,0 +1,114 @@
+//! Allocating elements in an arena and inserting them in one step.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+
+/// An arena that hands out references to the values moved into it, for as long
+/// as the arena is borrowed.
+///
+/// The tree never frees its elements, so any arena that can allocate an
+/// element and hand back a shared reference to it will do. The `bumpalo` and
+/// `typed-arena` features implement this for `bumpalo::Bump` and
+/// `typed_arena::Arena`.
+pub trait ElemArena<'a, E> {
+    /// Move `elem` into the arena, and return a reference to it.
+    fn alloc_elem(&'a self, elem: E) -> &'a E;
+}
+
+/// Requires the `bumpalo` feature.
+#[cfg(feature = "bumpalo")]
+impl<'a, E> ElemArena<'a, E> for bumpalo::Bump {
+    #[inline]
+    fn alloc_elem(&'a self, elem: E) -> &'a E {
+        self.alloc(elem)
+    }
+}
+
+/// Requires the `typed-arena` feature.
+#[cfg(feature = "typed-arena")]
+impl<'a, E> ElemArena<'a, E> for typed_arena::Arena<E> {
+    #[inline]
+    fn alloc_elem(&'a self, elem: E) -> &'a E {
+        self.alloc(elem)
+    }
+}
+
+impl<'a, T> SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: Sized,
+{
+    /// Allocate `elem` in `arena`, and insert it into this tree.
+    ///
+    /// Returns the allocated element if it was inserted, like
+    /// `SplayTree::insert`. Returns `None` if there was already an element in
+    /// the tree for which `TreeOrd` returned `Ordering::Equal`; `elem` is still
+    /// allocated in that case, but is not in the tree.
+    ///
+    /// ```
+    /// # #[cfg(feature = "bumpalo")] {
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Word<'a> {
+    ///     text: &'static str,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// impl<'a> Word<'a> {
+    ///     fn new(text: &'static str) -> Self {
+    ///         Word { text, node: Node::default() }
+    ///     }
+    /// }
+    ///
+    /// struct Words;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Words
+    ///     where
+    ///         type Elem = Word<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Words> for Word<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Word<'a>) -> Ordering {
+    ///         self.text.cmp(rhs.text)
+    ///     }
+    /// }
+    ///
+    /// let arena = bumpalo::Bump::new();
+    /// let mut words = SplayTree::<Words>::from_iter_in(&arena, ["b", "c"].map(Word::new));
+    /// assert!(words.insert_with_alloc(&arena, Word::new("a")).is_some());
+    /// assert!(words.insert_with_alloc(&arena, Word::new("c")).is_none());
+    /// assert_eq!(words.min().unwrap().text, "a");
+    /// # }
+    /// ```
+    #[inline]
+    pub fn insert_with_alloc<A>(&mut self, arena: &'a A, elem: T::Elem) -> Option<&'a T::Elem>
+    where
+        A: ?Sized + ElemArena<'a, T::Elem>,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let elem = arena.alloc_elem(elem);
+        if self.insert(elem) {
+            Some(elem)
+        } else {
+            None
+        }
+    }
+
+    /// Allocate each of `elems` in `arena`, and build a tree of them.
+    ///
+    /// Elements that compare equal to an earlier one are allocated, but left
+    /// out of the tree, as with `FromIterator`.
+    #[inline]
+    pub fn from_iter_in<A, I>(arena: &'a A, elems: I) -> SplayTree<'a, T>
+    where
+        A: ?Sized + ElemArena<'a, T::Elem>,
+        I: IntoIterator<Item = T::Elem>,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let mut tree = SplayTree::new();
+        tree.extend(elems.into_iter().map(|elem| arena.alloc_elem(elem)));
+        tree
+    }
+}
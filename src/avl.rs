@@ -0,0 +1,577 @@
+//! An intrusive AVL tree that shares `Node`, `IntrusiveNode`, and `TreeOrd`
+//! with `SplayTree`.
+//!
+//! Like the splay tree, the actual implementation here works only with trait
+//! objects, and the generic `AvlTree<T>` wrapper immediately erases types
+//! before calling into it.
+
+use crate::brand::Brand;
+use crate::internal::{CompareToNode, MaxNode, MinNode};
+use crate::{IntrusiveNode, Node, Query, TreeOrd, WalkControl};
+use core::cmp;
+use core::fmt;
+use core::iter;
+use core::marker::PhantomData;
+
+#[derive(Debug, Default)]
+struct Avl<'a> {
+    root: Option<&'a Node<'a>>,
+}
+
+impl<'a> Avl<'a> {
+    #[inline(never)]
+    unsafe fn find(&self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+        let mut current = self.root;
+        while let Some(node) = current {
+            current = match key.compare_to_node(node) {
+                cmp::Ordering::Less => node.left.get(),
+                cmp::Ordering::Greater => node.right.get(),
+                cmp::Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    #[inline(never)]
+    unsafe fn insert(&mut self, key: &dyn CompareToNode<'a>, node: &'a Node<'a>) -> bool {
+        debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+        node.balance.set(0);
+
+        match self.root {
+            Some(root) => match insert(root, key, node) {
+                Some((root, _grew)) => {
+                    self.root = Some(root);
+                    true
+                }
+                None => false,
+            },
+            None => {
+                self.root = Some(node);
+                true
+            }
+        }
+    }
+
+    #[inline(never)]
+    unsafe fn remove(&mut self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+        let root = self.root?;
+        let mut removed = None;
+        let (root, _shrank) = remove(root, key, &mut removed);
+        self.root = root;
+        let removed = removed?;
+        removed.left.set(None);
+        removed.right.set(None);
+        Some(removed)
+    }
+
+    fn pop_extreme(&mut self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+        let root = self.root?;
+        let (root, _shrank, removed) = remove_extreme(root, key);
+        self.root = root;
+        removed.left.set(None);
+        removed.right.set(None);
+        Some(removed)
+    }
+}
+
+/// Insert `node` into the subtree rooted at `current`.
+///
+/// Returns `None` if there is already an equal node. Otherwise, returns the new
+/// root of the subtree and whether its height grew.
+unsafe fn insert<'a>(
+    current: &'a Node<'a>,
+    key: &dyn CompareToNode<'a>,
+    node: &'a Node<'a>,
+) -> Option<(&'a Node<'a>, bool)> {
+    match key.compare_to_node(current) {
+        cmp::Ordering::Equal => None,
+        cmp::Ordering::Less => {
+            let (left, grew) = match current.left.get() {
+                Some(left) => insert(left, key, node)?,
+                None => (node, true),
+            };
+            current.left.set(Some(left));
+            if !grew {
+                return Some((current, false));
+            }
+            Some(match current.balance.get() {
+                1 => {
+                    current.balance.set(0);
+                    (current, false)
+                }
+                0 => {
+                    current.balance.set(-1);
+                    (current, true)
+                }
+                // Rebalancing after an insertion always restores the
+                // subtree's original height.
+                _ => (rebalance_left_heavy(current).0, false),
+            })
+        }
+        cmp::Ordering::Greater => {
+            let (right, grew) = match current.right.get() {
+                Some(right) => insert(right, key, node)?,
+                None => (node, true),
+            };
+            current.right.set(Some(right));
+            if !grew {
+                return Some((current, false));
+            }
+            Some(match current.balance.get() {
+                -1 => {
+                    current.balance.set(0);
+                    (current, false)
+                }
+                0 => {
+                    current.balance.set(1);
+                    (current, true)
+                }
+                _ => (rebalance_right_heavy(current).0, false),
+            })
+        }
+    }
+}
+
+/// Remove the node matching `key` from the subtree rooted at `current`,
+/// storing it in `removed`.
+///
+/// Returns the new root of the subtree and whether its height shrank.
+unsafe fn remove<'a>(
+    current: &'a Node<'a>,
+    key: &dyn CompareToNode<'a>,
+    removed: &mut Option<&'a Node<'a>>,
+) -> (Option<&'a Node<'a>>, bool) {
+    match key.compare_to_node(current) {
+        cmp::Ordering::Less => match current.left.get() {
+            Some(left) => {
+                let (left, shrank) = remove(left, key, removed);
+                current.left.set(left);
+                if shrank {
+                    left_shrank(current)
+                } else {
+                    (Some(current), false)
+                }
+            }
+            None => (Some(current), false),
+        },
+        cmp::Ordering::Greater => match current.right.get() {
+            Some(right) => {
+                let (right, shrank) = remove(right, key, removed);
+                current.right.set(right);
+                if shrank {
+                    right_shrank(current)
+                } else {
+                    (Some(current), false)
+                }
+            }
+            None => (Some(current), false),
+        },
+        cmp::Ordering::Equal => {
+            *removed = Some(current);
+            unlink(current)
+        }
+    }
+}
+
+/// Remove the minimum (for `MinNode`) or maximum (for `MaxNode`) node from the
+/// subtree rooted at `current`.
+///
+/// Returns the new root of the subtree, whether its height shrank, and the
+/// removed node.
+fn remove_extreme<'a>(
+    current: &'a Node<'a>,
+    key: &dyn CompareToNode<'a>,
+) -> (Option<&'a Node<'a>>, bool, &'a Node<'a>) {
+    // Safe because `MinNode` and `MaxNode` never look at the node.
+    match unsafe { key.compare_to_node(current) } {
+        cmp::Ordering::Less => match current.left.get() {
+            Some(left) => {
+                let (left, shrank, removed) = remove_extreme(left, key);
+                current.left.set(left);
+                if shrank {
+                    let (root, shrank) = left_shrank(current);
+                    (root, shrank, removed)
+                } else {
+                    (Some(current), false, removed)
+                }
+            }
+            None => (current.right.get(), true, current),
+        },
+        _ => match current.right.get() {
+            Some(right) => {
+                let (right, shrank, removed) = remove_extreme(right, key);
+                current.right.set(right);
+                if shrank {
+                    let (root, shrank) = right_shrank(current);
+                    (root, shrank, removed)
+                } else {
+                    (Some(current), false, removed)
+                }
+            }
+            None => (current.left.get(), true, current),
+        },
+    }
+}
+
+/// Replace `node` with the appropriate one of its descendants.
+///
+/// Returns the new root of the subtree and whether its height shrank.
+fn unlink<'a>(node: &'a Node<'a>) -> (Option<&'a Node<'a>>, bool) {
+    match (node.left.get(), node.right.get()) {
+        (None, right) => (right, true),
+        (left, None) => (left, true),
+        (Some(left), Some(right)) => {
+            let (right, shrank, successor) = remove_extreme(right, &MinNode);
+            successor.left.set(Some(left));
+            successor.right.set(right);
+            successor.balance.set(node.balance.get());
+            if shrank {
+                right_shrank(successor)
+            } else {
+                (Some(successor), false)
+            }
+        }
+    }
+}
+
+/// The left subtree of `node` shrank by one.
+fn left_shrank<'a>(node: &'a Node<'a>) -> (Option<&'a Node<'a>>, bool) {
+    match node.balance.get() {
+        -1 => {
+            node.balance.set(0);
+            (Some(node), true)
+        }
+        0 => {
+            node.balance.set(1);
+            (Some(node), false)
+        }
+        _ => {
+            let (root, shrank) = rebalance_right_heavy(node);
+            (Some(root), shrank)
+        }
+    }
+}
+
+/// The right subtree of `node` shrank by one.
+fn right_shrank<'a>(node: &'a Node<'a>) -> (Option<&'a Node<'a>>, bool) {
+    match node.balance.get() {
+        1 => {
+            node.balance.set(0);
+            (Some(node), true)
+        }
+        0 => {
+            node.balance.set(-1);
+            (Some(node), false)
+        }
+        _ => {
+            let (root, shrank) = rebalance_left_heavy(node);
+            (Some(root), shrank)
+        }
+    }
+}
+
+/// Rebalance `node`, whose left subtree is two taller than its right subtree.
+///
+/// Returns the new root of the subtree and whether its height shrank, relative
+/// to the unbalanced subtree.
+fn rebalance_left_heavy<'a>(node: &'a Node<'a>) -> (&'a Node<'a>, bool) {
+    let left = node.left.get().unwrap();
+    if left.balance.get() <= 0 {
+        // Rotate right.
+        node.left.set(left.right.get());
+        left.right.set(Some(node));
+        if left.balance.get() == 0 {
+            node.balance.set(-1);
+            left.balance.set(1);
+            (left, false)
+        } else {
+            node.balance.set(0);
+            left.balance.set(0);
+            (left, true)
+        }
+    } else {
+        // Rotate left at `left`, then right at `node`.
+        let pivot = left.right.get().unwrap();
+        left.right.set(pivot.left.get());
+        node.left.set(pivot.right.get());
+        pivot.left.set(Some(left));
+        pivot.right.set(Some(node));
+        let (left_balance, node_balance) = match pivot.balance.get() {
+            -1 => (0, 1),
+            0 => (0, 0),
+            _ => (-1, 0),
+        };
+        left.balance.set(left_balance);
+        node.balance.set(node_balance);
+        pivot.balance.set(0);
+        (pivot, true)
+    }
+}
+
+/// Rebalance `node`, whose right subtree is two taller than its left subtree.
+///
+/// Returns the new root of the subtree and whether its height shrank, relative
+/// to the unbalanced subtree.
+fn rebalance_right_heavy<'a>(node: &'a Node<'a>) -> (&'a Node<'a>, bool) {
+    let right = node.right.get().unwrap();
+    if right.balance.get() >= 0 {
+        // Rotate left.
+        node.right.set(right.left.get());
+        right.left.set(Some(node));
+        if right.balance.get() == 0 {
+            node.balance.set(1);
+            right.balance.set(-1);
+            (right, false)
+        } else {
+            node.balance.set(0);
+            right.balance.set(0);
+            (right, true)
+        }
+    } else {
+        // Rotate right at `right`, then left at `node`.
+        let pivot = right.left.get().unwrap();
+        right.left.set(pivot.right.get());
+        node.right.set(pivot.left.get());
+        pivot.right.set(Some(right));
+        pivot.left.set(Some(node));
+        let (node_balance, right_balance) = match pivot.balance.get() {
+            1 => (-1, 0),
+            0 => (0, 0),
+            _ => (0, 1),
+        };
+        node.balance.set(node_balance);
+        right.balance.set(right_balance);
+        pivot.balance.set(0);
+        (pivot, true)
+    }
+}
+
+/// An intrusive AVL tree.
+///
+/// This is available with the `avl` cargo feature. It uses the same `Node`,
+/// `IntrusiveNode`, and `TreeOrd` machinery as `SplayTree`, so a tree type
+/// can be switched between the two data structures without changing its
+/// element type.
+///
+/// Unlike a splay tree, an AVL tree does not restructure itself on lookups,
+/// so `find`, `min`, and `max` only need `&self`. In exchange, recently
+/// accessed elements don't get any cheaper to find again.
+///
+/// Each operation uses stack space proportional to the tree's height, which is
+/// at most about `1.44 * log2(n)`.
+pub struct AvlTree<'a, T>
+where
+    T: IntrusiveNode<'a>,
+    T::Elem: 'a,
+{
+    tree: Avl<'a>,
+    brand: Brand,
+    _phantom: PhantomData<&'a T::Elem>,
+}
+
+impl<'a, T> Default for AvlTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + TreeOrd<'a, T>,
+{
+    #[inline]
+    fn default() -> AvlTree<'a, T> {
+        AvlTree::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for AvlTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + TreeOrd<'a, T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.walk(|x| {
+            set.entry(&x);
+        });
+        set.finish()
+    }
+}
+
+impl<'a, T> Extend<&'a T::Elem> for AvlTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T::Elem>>(&mut self, iter: I) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl<'a, T> iter::FromIterator<&'a T::Elem> for AvlTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = &'a T::Elem>>(iter: I) -> Self {
+        let mut me = AvlTree::new();
+        me.extend(iter);
+        me
+    }
+}
+
+impl<'a, T> AvlTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    /// Construct a new, empty tree.
+    #[inline]
+    pub const fn new() -> Self {
+        AvlTree {
+            tree: Avl { root: None },
+            brand: Brand::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Is this tree empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.root.is_none()
+    }
+
+    /// Get a reference to the root element, if any exists.
+    #[inline]
+    pub fn root(&self) -> Option<&'a T::Elem> {
+        self.tree.root.map(|r| unsafe { self.brand.elem::<T>(r) })
+    }
+
+    /// Find an element in the tree.
+    ///
+    /// This does not restructure the tree.
+    #[inline]
+    pub fn find<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .find(&query)
+                .map(|node| self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Insert a new element into this tree.
+    ///
+    /// Returns `true` if the element was inserted into the tree.
+    ///
+    /// Returns `false` if there was already an element in the tree for which
+    /// `TreeOrd` returned `Ordering::Equal`. In this case, the extant element
+    /// is left in the tree, and `elem` is not inserted.
+    ///
+    /// It is a logic error to insert an element that is already inserted in a
+    /// `T` tree.
+    ///
+    /// ## Panics
+    ///
+    /// If `debug_assertions` are enabled, then this function may panic if
+    /// `elem` is already in a `T` tree. If `debug_assertions` are not defined,
+    /// the behavior is safe, but unspecified.
+    ///
+    /// If the `paranoid` feature is enabled, then this function panics if
+    /// `elem`'s node is already in any tree.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        unsafe {
+            let query: Query<_, T> = Query::new(elem, &self.brand);
+            let node = T::elem_to_node(elem);
+            self.brand.check_unbranded(node);
+            let inserted = self.tree.insert(&query, node);
+            if inserted {
+                self.brand.brand(node);
+            }
+            inserted
+        }
+    }
+
+    /// Find and remove an element from the tree.
+    ///
+    /// If a matching element is found and removed, then `Some(removed_element)`
+    /// is returned. Otherwise `None` is returned.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .remove(&query)
+                .map(|node| self.brand.release::<T>(node))
+        }
+    }
+
+    /// Get the minimum element in the tree, if any.
+    #[inline]
+    pub fn min(&self) -> Option<&'a T::Elem> {
+        let mut node = self.tree.root?;
+        while let Some(left) = node.left.get() {
+            node = left;
+        }
+        Some(unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Get the maximum element in the tree, if any.
+    #[inline]
+    pub fn max(&self) -> Option<&'a T::Elem> {
+        let mut node = self.tree.root?;
+        while let Some(right) = node.right.get() {
+            node = right;
+        }
+        Some(unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Pop the minimum element from the tree.
+    ///
+    /// If the tree is non-empty, then the minimum element is removed and
+    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_min(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .pop_extreme(&MinNode)
+            .map(|node| unsafe { self.brand.release::<T>(node) })
+    }
+
+    /// Pop the maximum element from the tree.
+    ///
+    /// If the tree is non-empty, then the maximum element is removed and
+    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_max(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .pop_extreme(&MaxNode)
+            .map(|node| unsafe { self.brand.release::<T>(node) })
+    }
+
+    /// Walk the tree in order.
+    ///
+    /// See `SplayTree::walk` for how the `C` type controls iteration.
+    #[inline]
+    pub fn walk<F, C>(&self, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(&'a T::Elem) -> C,
+        C: WalkControl,
+    {
+        let mut result = None;
+        if let Some(root) = self.tree.root {
+            root.walk(&mut |node| unsafe {
+                let elem = self.brand.elem::<T>(node);
+                result = f(elem).should_break();
+                result.is_none()
+            });
+        }
+        result
+    }
+}
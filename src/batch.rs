@@ -0,0 +1,251 @@
+//! Batched tree operations.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::slice;
+
+/// A single operation in a batch passed to `SplayTree::apply_batch`.
+pub enum BatchOp<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    /// Insert this element, as with `SplayTree::insert`.
+    Insert(&'a T::Elem),
+
+    /// Remove the element equal to this one, as with `SplayTree::remove`.
+    Remove(&'a T::Elem),
+}
+
+impl<'a, T> BatchOp<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    /// The element that this operation is keyed by.
+    #[inline]
+    pub fn elem(&self) -> &'a T::Elem {
+        match *self {
+            BatchOp::Insert(elem) | BatchOp::Remove(elem) => elem,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn cmp_keys(&self, other: &Self) -> cmp::Ordering {
+        self.elem().tree_cmp(other.elem())
+    }
+}
+
+impl<'a, T> Clone for BatchOp<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for BatchOp<'a, T> where T: 'a + IntrusiveNode<'a> {}
+
+impl<'a, T> fmt::Debug for BatchOp<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchOp::Insert(elem) => f.debug_tuple("Insert").field(elem).finish(),
+            BatchOp::Remove(elem) => f.debug_tuple("Remove").field(elem).finish(),
+        }
+    }
+}
+
+/// An iterator that looks up each of a sorted sequence of keys in a
+/// `SplayTree`.
+///
+/// Returned by `SplayTree::find_many`.
+pub struct FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    keys: slice::Iter<'k, K>,
+}
+
+impl<'t, 'a, 'k, T, K> FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t mut SplayTree<'a, T>, keys: &'k [K]) -> Self {
+        FindMany {
+            tree,
+            keys: keys.iter(),
+        }
+    }
+}
+
+impl<'t, 'a, 'k, T, K> Iterator for FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+    type Item = Option<&'a T::Elem>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Option<&'a T::Elem>> {
+        let key = self.keys.next()?;
+        Some(self.tree.find(key))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'t, 'a, 'k, T, K> ExactSizeIterator for FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, 'k, T, K> FusedIterator for FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, 'k, T, K> fmt::Debug for FindMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FindMany")
+            .field("tree", &self.tree)
+            .field("keys", &self.keys.as_slice())
+            .finish()
+    }
+}
+
+/// An iterator that removes the element matching each of a sorted sequence of
+/// keys from a `SplayTree`.
+///
+/// Returned by `SplayTree::remove_many`. Keys that the iterator has not reached
+/// yet when it is dropped are not removed.
+pub struct RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    keys: slice::Iter<'k, K>,
+}
+
+impl<'t, 'a, 'k, T, K> RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t mut SplayTree<'a, T>, keys: &'k [K]) -> Self {
+        RemoveMany {
+            tree,
+            keys: keys.iter(),
+        }
+    }
+}
+
+impl<'t, 'a, 'k, T, K> Iterator for RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+    type Item = Option<&'a T::Elem>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Option<&'a T::Elem>> {
+        let key = self.keys.next()?;
+        Some(self.tree.remove(key))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'t, 'a, 'k, T, K> ExactSizeIterator for RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, 'k, T, K> FusedIterator for RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, 'k, T, K> fmt::Debug for RemoveMany<'t, 'a, 'k, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoveMany")
+            .field("tree", &self.tree)
+            .field("keys", &self.keys.as_slice())
+            .finish()
+    }
+}
+
+/// What happened to the elements passed to `SplayTree::insert_all`.
+///
+/// This counts the elements that were inserted, and the ones that were
+/// rejected because an equal element was already in the tree. The rejected
+/// elements themselves are passed to `insert_all`'s callback, as they are
+/// found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[must_use = "the report counts the rejected duplicates"]
+pub struct InsertReport {
+    inserted: usize,
+    rejected: usize,
+}
+
+impl InsertReport {
+    #[inline]
+    pub(crate) fn record_inserted(&mut self) {
+        self.inserted += 1;
+    }
+
+    #[inline]
+    pub(crate) fn record_rejected(&mut self) {
+        self.rejected += 1;
+    }
+
+    /// Get the number of elements that were inserted.
+    #[inline]
+    pub fn inserted(&self) -> usize {
+        self.inserted
+    }
+
+    /// Get the number of elements that were rejected as duplicates.
+    #[inline]
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// Was every element inserted?
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.rejected == 0
+    }
+}
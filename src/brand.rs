@@ -0,0 +1,181 @@
+//! Runtime tags that tie nodes to the tree they are in.
+//!
+//! With the `paranoid` feature, every tree gets a unique, non-zero brand the
+//! first time it needs one, and stores it in each node it inserts. Converting
+//! a node back into its element checks the node's brand first, so a node that
+//! is in some other tree -- for example because an `IntrusiveNode`
+//! implementation returns the same `Node` field for two different trees -- is
+//! caught with a panic instead of silently corrupting both trees.
+//!
+//! Brands are applied and removed at exactly the points where nodes enter and
+//! leave trees, so `Brand` also maintains the `linked` feature's flag.
+//!
+//! Without either feature, `Brand` is zero-sized and all of its methods are
+//! no-ops.
+
+use crate::{IntrusiveNode, Node};
+
+#[cfg(feature = "paranoid")]
+use core::cell::Cell;
+#[cfg(all(feature = "paranoid", target_has_atomic = "ptr"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(feature = "paranoid", not(target_has_atomic = "ptr")))]
+use portable_atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(
+    feature = "paranoid",
+    not(target_has_atomic = "ptr"),
+    not(feature = "portable-atomic")
+))]
+compile_error!(
+    "the `paranoid` feature needs atomic read-modify-write operations, which \
+     this target only has with the `portable-atomic` feature"
+);
+
+#[cfg(feature = "paranoid")]
+static NEXT_BRAND: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Default)]
+#[repr(transparent)]
+pub(crate) struct Brand {
+    #[cfg(feature = "paranoid")]
+    id: Cell<usize>,
+}
+
+impl Brand {
+    #[inline]
+    pub(crate) const fn new() -> Brand {
+        Brand {
+            #[cfg(feature = "paranoid")]
+            id: Cell::new(0),
+        }
+    }
+
+    #[cfg(feature = "paranoid")]
+    fn id(&self) -> usize {
+        if self.id.get() == 0 {
+            self.id.set(NEXT_BRAND.fetch_add(1, Ordering::Relaxed));
+        }
+        self.id.get()
+    }
+
+    /// Get a brand that is the same as this one, for a tree that was split off
+    /// from this brand's tree.
+    #[inline]
+    pub(crate) fn share(&self) -> Brand {
+        Brand {
+            #[cfg(feature = "paranoid")]
+            id: Cell::new(self.id()),
+        }
+    }
+
+    /// Move every node in the subtree rooted at `root` from `other`'s tree into
+    /// this brand's tree.
+    #[inline]
+    pub(crate) fn adopt<'a>(&self, other: &Brand, root: Option<&'a Node<'a>>) {
+        #[cfg(feature = "paranoid")]
+        if let Some(root) = root {
+            if other.id() != self.id() {
+                root.walk(&mut |node| {
+                    other.check(node);
+                    self.brand(node);
+                    true
+                });
+            }
+        }
+        let _ = (other, root);
+    }
+
+    /// Check that `node` is not in any tree, before inserting it.
+    #[inline]
+    pub(crate) fn check_unbranded(&self, node: &Node) {
+        #[cfg(feature = "paranoid")]
+        assert!(
+            node.brand.get() == 0,
+            "node is already in a tree; is the same `Node` field used for multiple trees?"
+        );
+        let _ = node;
+    }
+
+    /// Does `node` record that it is in a tree? Always `false` without the
+    /// `linked` or `paranoid` feature.
+    #[inline]
+    pub(crate) fn is_branded(node: &Node) -> bool {
+        #[cfg(feature = "paranoid")]
+        if node.brand.get() != 0 {
+            return true;
+        }
+        #[cfg(feature = "linked")]
+        if node.linked.get() {
+            return true;
+        }
+        let _ = node;
+        false
+    }
+
+    /// Is `node` in this brand's tree? Like `check`, but without panicking.
+    /// Always `true` without the `paranoid` feature.
+    #[inline]
+    pub(crate) fn owns(&self, node: &Node) -> bool {
+        #[cfg(feature = "paranoid")]
+        if node.brand.get() != self.id() {
+            return false;
+        }
+        let _ = node;
+        true
+    }
+
+    /// Mark `node` as being in this brand's tree, after inserting it.
+    #[inline]
+    pub(crate) fn brand(&self, node: &Node) {
+        #[cfg(feature = "paranoid")]
+        node.brand.set(self.id());
+        #[cfg(feature = "linked")]
+        node.linked.set(true);
+        let _ = node;
+    }
+
+    /// Check that `node` is in this brand's tree.
+    #[inline]
+    pub(crate) fn check(&self, node: &Node) {
+        #[cfg(feature = "paranoid")]
+        assert!(
+            node.brand.get() == self.id(),
+            "node is not in this tree; is the same `Node` field used for multiple trees?"
+        );
+        let _ = node;
+    }
+
+    /// Check that `node` is in this brand's tree, and convert it into its
+    /// element.
+    #[inline]
+    pub(crate) unsafe fn elem<'a, T>(&self, node: &'a Node<'a>) -> &'a T::Elem
+    where
+        T: IntrusiveNode<'a>,
+    {
+        self.check(node);
+        T::node_to_elem(node)
+    }
+
+    /// Check that `node`, which was just removed, was in this brand's tree,
+    /// and clear its brand.
+    #[inline]
+    pub(crate) fn unbrand(&self, node: &Node) {
+        self.check(node);
+        #[cfg(feature = "paranoid")]
+        node.brand.set(0);
+        #[cfg(feature = "linked")]
+        node.linked.set(false);
+    }
+
+    /// Check that `node`, which was just removed, was in this brand's tree,
+    /// clear its brand, and convert it into its element.
+    #[inline]
+    pub(crate) unsafe fn release<'a, T>(&self, node: &'a Node<'a>) -> &'a T::Elem
+    where
+        T: IntrusiveNode<'a>,
+    {
+        self.unbrand(node);
+        T::node_to_elem(node)
+    }
+}
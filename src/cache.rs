@@ -0,0 +1,280 @@
+//! A bounded cache built on `SplayLru`.
+
+use crate::{IntrusiveLruNode, SplayLru, TreeOrd};
+use core::fmt;
+use core::ptr;
+
+/// How a `SplayCache` chooses which element to evict when it is over
+/// capacity.
+pub trait EvictionPolicy<'a, T>
+where
+    T: IntrusiveLruNode<'a>,
+{
+    /// Choose an element of `lru` to evict.
+    ///
+    /// This is only called when `lru` is not empty. Returning `None` evicts
+    /// nothing, and leaves the cache over capacity.
+    fn victim(&mut self, lru: &mut SplayLru<'a, T>) -> Option<&'a T::Elem>;
+}
+
+/// The default `EvictionPolicy`: evict the least recently used element.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LeastRecent;
+
+impl<'a, T> EvictionPolicy<'a, T> for LeastRecent
+where
+    T: 'a + IntrusiveLruNode<'a>,
+{
+    #[inline]
+    fn victim(&mut self, lru: &mut SplayLru<'a, T>) -> Option<&'a T::Elem> {
+        lru.least_recent()
+    }
+}
+
+/// An `EvictionPolicy` that evicts the element with the smallest key, for
+/// caches that keep the largest keys, like the newest entries by timestamp.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmallestKey;
+
+impl<'a, T> EvictionPolicy<'a, T> for SmallestKey
+where
+    T: 'a + IntrusiveLruNode<'a>,
+{
+    #[inline]
+    fn victim(&mut self, lru: &mut SplayLru<'a, T>) -> Option<&'a T::Elem> {
+        lru.peek_min()
+    }
+}
+
+/// An `EvictionPolicy` that evicts the element with the largest key, for
+/// caches that keep the smallest keys, like the nearest deadlines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LargestKey;
+
+impl<'a, T> EvictionPolicy<'a, T> for LargestKey
+where
+    T: 'a + IntrusiveLruNode<'a>,
+{
+    #[inline]
+    fn victim(&mut self, lru: &mut SplayLru<'a, T>) -> Option<&'a T::Elem> {
+        lru.peek_max()
+    }
+}
+
+/// A cache of at most `capacity` elements, which are indexed by key in a
+/// splay tree and evicted according to a policy `P`.
+///
+/// Lookups splay hot keys toward the root, so a cache with a skewed access
+/// pattern finds its popular entries in a few comparisons. When an insertion
+/// puts the cache over capacity, `P` chooses an element to evict, which is
+/// handed back to the caller to reuse or free. Elements are borrowed, like in
+/// every other tree in this crate, so the cache never allocates or drops
+/// anything itself.
+///
+/// The tree type `T` must implement `IntrusiveLruNode`, since the cache
+/// tracks recency for `LeastRecent` eviction, whichever policy it uses.
+///
+/// ```
+/// use intrusive_splay_tree::{
+///     impl_intrusive_lru_node, impl_intrusive_node, Node, SplayCache, TreeOrd,
+/// };
+/// use std::cmp::Ordering;
+///
+/// struct Page<'a> {
+///     number: u32,
+///     tree_node: Node<'a>,
+///     lru_node: Node<'a>,
+/// }
+///
+/// impl Page<'_> {
+///     fn new(number: u32) -> Self {
+///         Page { number, tree_node: Node::new(), lru_node: Node::new() }
+///     }
+/// }
+///
+/// struct Pages;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Pages
+///     where
+///         type Elem = Page<'a>,
+///         node = tree_node;
+/// }
+///
+/// impl_intrusive_lru_node! {
+///     impl<'a> IntrusiveLruNode<'a> for Pages
+///     where
+///         lru_node = lru_node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, Pages> for Page<'a> {
+///     fn tree_cmp(&self, rhs: &'a Page<'a>) -> Ordering {
+///         self.number.cmp(&rhs.number)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, Pages> for u32 {
+///     fn tree_cmp(&self, rhs: &'a Page<'a>) -> Ordering {
+///         self.cmp(&rhs.number)
+///     }
+/// }
+///
+/// let pages = [Page::new(1), Page::new(2), Page::new(3)];
+/// let mut cache = SplayCache::<Pages>::new(2);
+/// assert!(matches!(cache.insert(&pages[0]), Ok(None)));
+/// assert!(matches!(cache.insert(&pages[1]), Ok(None)));
+///
+/// // Using page 1 makes page 2 the least recently used, so it goes first.
+/// assert!(cache.find(&1).is_some());
+/// let evicted = cache.insert(&pages[2]).ok().flatten();
+/// assert_eq!(evicted.map(|p| p.number), Some(2));
+/// assert_eq!(cache.len(), 2);
+/// ```
+pub struct SplayCache<'a, T, P = LeastRecent>
+where
+    T: IntrusiveLruNode<'a>,
+    T::Elem: 'a,
+{
+    lru: SplayLru<'a, T>,
+    capacity: usize,
+    policy: P,
+}
+
+impl<'a, T, P> fmt::Debug for SplayCache<'a, T, P>
+where
+    T: 'a + IntrusiveLruNode<'a>,
+    T::Elem: 'a + fmt::Debug,
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SplayCache")
+            .field("capacity", &self.capacity)
+            .field("policy", &self.policy)
+            .field("lru", &self.lru)
+            .finish()
+    }
+}
+
+impl<'a, T, P> SplayCache<'a, T, P>
+where
+    T: 'a + IntrusiveLruNode<'a>,
+    P: EvictionPolicy<'a, T>,
+{
+    /// Construct a new, empty cache that holds at most `capacity` elements.
+    #[inline]
+    pub fn new(capacity: usize) -> Self
+    where
+        P: Default,
+    {
+        SplayCache::with_policy(capacity, P::default())
+    }
+
+    /// Construct a new, empty cache that holds at most `capacity` elements,
+    /// and evicts them according to `policy`.
+    #[inline]
+    pub fn with_policy(capacity: usize, policy: P) -> Self {
+        SplayCache {
+            lru: SplayLru::new(),
+            capacity,
+            policy,
+        }
+    }
+
+    /// Get the maximum number of elements the cache holds.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of elements in the cache.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// Is the cache empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lru.is_empty()
+    }
+
+    /// Get a shared reference to the underlying `SplayLru`, for ordered and
+    /// recency walks.
+    #[inline]
+    pub fn lru(&self) -> &SplayLru<'a, T> {
+        &self.lru
+    }
+
+    /// Get the eviction policy.
+    #[inline]
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Get the eviction policy mutably, for policies with state.
+    #[inline]
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.policy
+    }
+
+    /// Insert a new element, making it the most recently used element, and
+    /// evict an element if that puts the cache over capacity.
+    ///
+    /// Returns `Ok` with the evicted element, if any, which may be `elem`
+    /// itself when the policy says so. Returns `Err(elem)`, and leaves
+    /// everything unchanged, if there was already an element for which
+    /// `TreeOrd` returned `Ordering::Equal`.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> Result<Option<&'a T::Elem>, &'a T::Elem> {
+        if !self.lru.insert(elem) {
+            return Err(elem);
+        }
+        if self.len() > self.capacity {
+            Ok(self.evict())
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Evict the element that the policy chooses, whether or not the cache
+    /// is over capacity.
+    #[inline]
+    pub fn evict(&mut self) -> Option<&'a T::Elem> {
+        if self.lru.is_empty() {
+            return None;
+        }
+        let victim = self.policy.victim(&mut self.lru)?;
+        let evicted = self.lru.remove(victim);
+        debug_assert!(evicted.is_some_and(|e| ptr::eq(e, victim)));
+        evicted
+    }
+
+    /// Find an element, and mark it as the most recently used element.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.lru.find(key)
+    }
+
+    /// Find an element without changing its recency.
+    ///
+    /// This still splays the tree.
+    #[inline]
+    pub fn peek<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.lru.peek(key)
+    }
+
+    /// Find and remove an element.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.lru.remove(key)
+    }
+}
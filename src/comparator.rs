@@ -0,0 +1,252 @@
+//! Trees that are ordered by a comparator object chosen at runtime.
+
+use crate::brand::Brand;
+use crate::internal;
+use crate::{IntrusiveNode, Node, SplayTree, WalkControl};
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// A runtime ordering for a `ComparatorTree`, comparing keys of type `K` to
+/// the tree's elements.
+///
+/// Every comparator compares elements to elements, with `K = T::Elem`, to
+/// place them on insertion. It can also implement this for other key types,
+/// like `TreeOrd`, to look elements up by those. Closures of the right shape
+/// are comparators too.
+pub trait Comparator<'a, T, K: ?Sized = <T as IntrusiveNode<'a>>::Elem>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Compare `key` to `elem`.
+    fn compare(&self, key: &K, elem: &'a T::Elem) -> cmp::Ordering;
+}
+
+impl<'a, T, K, F> Comparator<'a, T, K> for F
+where
+    T: IntrusiveNode<'a>,
+    K: ?Sized,
+    F: Fn(&K, &'a T::Elem) -> cmp::Ordering,
+{
+    #[inline]
+    fn compare(&self, key: &K, elem: &'a T::Elem) -> cmp::Ordering {
+        self(key, elem)
+    }
+}
+
+/// A key paired with the comparator to compare it with.
+struct By<'b, T, C, K: ?Sized> {
+    cmp: &'b C,
+    key: &'b K,
+    brand: &'b Brand,
+    _phantom: PhantomData<T>,
+}
+
+impl<'b, T, C, K: ?Sized> By<'b, T, C, K> {
+    #[inline]
+    fn new(cmp: &'b C, key: &'b K, brand: &'b Brand) -> Self {
+        By {
+            cmp,
+            key,
+            brand,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'b, T, C, K> internal::CompareToNode<'a> for By<'b, T, C, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    C: Comparator<'a, T, K>,
+    K: ?Sized,
+{
+    #[inline]
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        self.cmp.compare(self.key, self.brand.elem::<T>(node))
+    }
+}
+
+/// An intrusive splay tree that orders its elements with a comparator object
+/// that it stores, rather than with `TreeOrd`.
+///
+/// This is for orders that are only known at runtime, like a collation table
+/// loaded from a locale, or a sort direction picked by a user. The comparator
+/// is used for every operation on the tree, and can't be swapped out while
+/// the tree has elements, since that would reorder them.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, ComparatorTree, Node};
+///
+/// struct Word<'a> {
+///     text: &'static str,
+///     node: Node<'a>,
+/// }
+///
+/// struct Words;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Words
+///     where
+///         type Elem = Word<'a>,
+///         node = node;
+/// }
+///
+/// // Decided at runtime.
+/// let case_insensitive = true;
+///
+/// let cmp = move |a: &Word, b: &Word| {
+///     if case_insensitive {
+///         a.text.to_lowercase().cmp(&b.text.to_lowercase())
+///     } else {
+///         a.text.cmp(b.text)
+///     }
+/// };
+/// let words = ["banana", "Apple", "cherry"].map(|text| Word { text, node: Node::default() });
+/// let mut tree = ComparatorTree::<Words, _>::new(cmp);
+/// for word in &words {
+///     tree.insert(word);
+/// }
+/// assert_eq!(tree.min().unwrap().text, "Apple");
+/// assert_eq!(tree.max().unwrap().text, "cherry");
+/// ```
+pub struct ComparatorTree<'a, T, C>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: SplayTree<'a, T>,
+    cmp: C,
+}
+
+impl<'a, T, C> fmt::Debug for ComparatorTree<'a, T, C>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ComparatorTree")
+            .field("tree", &self.tree)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T, C> ComparatorTree<'a, T, C>
+where
+    T: 'a + IntrusiveNode<'a>,
+    C: Comparator<'a, T>,
+{
+    /// Construct a new, empty tree that is ordered by `cmp`.
+    #[inline]
+    pub const fn new(cmp: C) -> Self {
+        ComparatorTree {
+            tree: SplayTree::new(),
+            cmp,
+        }
+    }
+
+    /// Get the comparator.
+    #[inline]
+    pub fn comparator(&self) -> &C {
+        &self.cmp
+    }
+
+    /// Is the tree empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the number of elements in the tree.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Insert `elem` into the tree, where the comparator places it.
+    ///
+    /// Returns `false` if the comparator says an element in the tree is equal
+    /// to `elem`; see `SplayTree::insert`.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        let by = By::new(&self.cmp, elem, &self.tree.brand);
+        unsafe {
+            let node = T::elem_to_node(elem);
+            self.tree
+                .tree
+                .insert_branded(&self.tree.brand, internal::erase(&by), node)
+        }
+    }
+
+    /// Find the element that the comparator says is equal to `key`, and splay
+    /// it to the root.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized,
+        C: Comparator<'a, T, K>,
+    {
+        let by = By::new(&self.cmp, key, &self.tree.brand);
+        unsafe {
+            let node = self
+                .tree
+                .tree
+                .find_branded(&self.tree.brand, internal::erase(&by))?;
+            Some(T::node_to_elem(node))
+        }
+    }
+
+    /// Remove the element that the comparator says is equal to `key`, if any.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized,
+        C: Comparator<'a, T, K>,
+    {
+        let by = By::new(&self.cmp, key, &self.tree.brand);
+        unsafe {
+            let node = self
+                .tree
+                .tree
+                .remove_branded(&self.tree.brand, internal::erase(&by))?;
+            Some(T::node_to_elem(node))
+        }
+    }
+
+    /// Get the minimum element, splaying it to the root.
+    #[inline]
+    pub fn min(&mut self) -> Option<&'a T::Elem> {
+        self.tree.min()
+    }
+
+    /// Get the maximum element, splaying it to the root.
+    #[inline]
+    pub fn max(&mut self) -> Option<&'a T::Elem> {
+        self.tree.max()
+    }
+
+    /// Remove and return the minimum element.
+    #[inline]
+    pub fn pop_min(&mut self) -> Option<&'a T::Elem> {
+        self.tree.pop_min()
+    }
+
+    /// Remove and return the maximum element.
+    #[inline]
+    pub fn pop_max(&mut self) -> Option<&'a T::Elem> {
+        self.tree.pop_max()
+    }
+
+    /// Walk the tree in order, without splaying.
+    ///
+    /// See `SplayTree::walk` for how `W` controls the walk.
+    #[inline]
+    pub fn walk<F, W>(&self, f: F) -> Option<W::Result>
+    where
+        F: FnMut(&'a T::Elem) -> W,
+        W: WalkControl,
+    {
+        self.tree.walk(f)
+    }
+}
@@ -0,0 +1,149 @@
+//! Cursors that can be put down and picked back up across tree mutations.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+use core::ptr;
+
+/// A saved position in an in-order scan of a `SplayTree`.
+///
+/// A cursor does not borrow the tree; it only remembers the last element it
+/// yielded. It can be stored anywhere, and every call to `next` resumes the
+/// scan with the first element after that one, even if other elements were
+/// inserted or removed in the meantime, including the remembered element
+/// itself. In that case, the scan resumes after where the element's key would
+/// be.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Cursor, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Job<'a> {
+///     id: u32,
+///     node: Node<'a>,
+/// }
+///
+/// struct ById;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ById
+///     where
+///         type Elem = Job<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ById> for Job<'a> {
+///     fn tree_cmp(&self, rhs: &'a Job<'a>) -> Ordering {
+///         self.id.cmp(&rhs.id)
+///     }
+/// }
+///
+/// let jobs = [1, 2, 3, 4].map(|id| Job { id, node: Node::default() });
+/// let mut tree = SplayTree::<ById>::new();
+/// for job in &jobs[..3] {
+///     tree.insert(job);
+/// }
+///
+/// let mut cursor = Cursor::<ById>::new();
+/// assert_eq!(cursor.next(&mut tree).unwrap().id, 1);
+/// assert_eq!(cursor.next(&mut tree).unwrap().id, 2);
+///
+/// // Remove the cursor's element and add a new one; the scan carries on.
+/// tree.remove(&jobs[1]);
+/// tree.insert(&jobs[3]);
+/// assert!(!cursor.is_valid(&mut tree));
+/// assert_eq!(cursor.next(&mut tree).unwrap().id, 3);
+/// assert_eq!(cursor.next(&mut tree).unwrap().id, 4);
+/// assert!(cursor.next(&mut tree).is_none());
+/// ```
+pub struct Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    last: Option<&'a T::Elem>,
+}
+
+impl<'a, T> Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    /// Create a cursor positioned before the first element of any tree.
+    #[inline]
+    pub const fn new() -> Self {
+        Cursor { last: None }
+    }
+
+    /// Create a cursor positioned at `elem`, so that `next` yields the first
+    /// element after it.
+    #[inline]
+    pub const fn at(elem: &'a T::Elem) -> Self {
+        Cursor { last: Some(elem) }
+    }
+
+    /// Get the element that this cursor last yielded, if any.
+    #[inline]
+    pub fn current(&self) -> Option<&'a T::Elem> {
+        self.last
+    }
+
+    /// Advance to, and return, the first element of `tree` after this
+    /// cursor's position.
+    ///
+    /// When this returns `None`, the cursor stays where it was, so a later
+    /// call picks up elements inserted after it since.
+    #[inline]
+    pub fn next(&mut self, tree: &mut SplayTree<'a, T>) -> Option<&'a T::Elem> {
+        let next = match self.last {
+            None => tree.min(),
+            Some(last) => tree.find_gt(last),
+        }?;
+        self.last = Some(next);
+        Some(next)
+    }
+
+    /// Is the element that this cursor last yielded still in `tree`?
+    #[inline]
+    pub fn is_valid(&self, tree: &mut SplayTree<'a, T>) -> bool {
+        self.last
+            .is_some_and(|last| tree.find(last).is_some_and(|found| ptr::eq(found, last)))
+    }
+}
+
+impl<'a, T> Default for Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    fn default() -> Self {
+        Cursor::new()
+    }
+}
+
+impl<'a, T> Clone for Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+impl<'a, T> fmt::Debug for Cursor<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cursor").field("last", &self.last).finish()
+    }
+}
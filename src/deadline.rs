@@ -0,0 +1,231 @@
+//! Timer queues, ordered by deadline.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// A tree type whose elements are timers that expire at a deadline, for use
+/// with `DeadlineQueue`.
+pub trait IntrusiveDeadline<'a>: IntrusiveNode<'a> {
+    /// The type of deadlines, such as an `Instant` or a tick count.
+    type Instant: 'a + Copy + Ord;
+
+    /// Get the deadline of the given element.
+    ///
+    /// This must not change while the element is scheduled.
+    fn deadline(elem: &'a Self::Elem) -> Self::Instant;
+}
+
+/// A key that orders timers by deadline, and then by address, so that timers
+/// with the same deadline are still distinct.
+struct At<I> {
+    deadline: I,
+    addr: usize,
+}
+
+#[inline]
+fn at<'a, T>(elem: &'a T::Elem) -> At<T::Instant>
+where
+    T: IntrusiveDeadline<'a>,
+{
+    At {
+        deadline: T::deadline(elem),
+        addr: T::elem_to_node(elem) as *const _ as usize,
+    }
+}
+
+impl<'a, T> TreeOrd<'a, T> for At<T::Instant>
+where
+    T: IntrusiveDeadline<'a>,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+        let node = T::elem_to_node(elem) as *const _ as usize;
+        (self.deadline, self.addr).cmp(&(T::deadline(elem), node))
+    }
+}
+
+/// A queue of timers, ordered by when they expire.
+///
+/// Scheduling and cancelling a timer take *O(log n)* amortized time, and since
+/// the next timer to expire is always splayed to the root, expiring due timers
+/// takes constant amortized time apiece. Any number of timers can share a
+/// deadline; they expire in an unspecified order relative to each other.
+///
+/// The queue orders its elements itself, by `IntrusiveDeadline::deadline`.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, DeadlineQueue, IntrusiveDeadline, Node};
+///
+/// struct Timer<'a> {
+///     at: u64,
+///     name: &'static str,
+///     node: Node<'a>,
+/// }
+///
+/// struct Timers;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Timers
+///     where
+///         type Elem = Timer<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> IntrusiveDeadline<'a> for Timers {
+///     type Instant = u64;
+///     fn deadline(timer: &'a Timer<'a>) -> u64 {
+///         timer.at
+///     }
+/// }
+///
+/// let timers = [(30, "c"), (10, "a"), (20, "b"), (10, "a2")]
+///     .map(|(at, name)| Timer { at, name, node: Node::default() });
+/// let mut queue = DeadlineQueue::<Timers>::new();
+/// for timer in &timers {
+///     queue.schedule(timer);
+/// }
+/// assert!(queue.cancel(&timers[2]));
+/// assert_eq!(queue.next_deadline(), Some(10));
+///
+/// let mut expired: Vec<_> = queue.expire(25).map(|t| t.name).collect();
+/// expired.sort();
+/// assert_eq!(expired, ["a", "a2"]);
+/// assert_eq!(queue.next_deadline(), Some(30));
+/// ```
+pub struct DeadlineQueue<'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+{
+    tree: SplayTree<'a, T>,
+}
+
+impl<'a, T> Default for DeadlineQueue<'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+{
+    #[inline]
+    fn default() -> Self {
+        DeadlineQueue::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for DeadlineQueue<'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeadlineQueue")
+            .field("tree", &self.tree)
+            .finish()
+    }
+}
+
+impl<'a, T> DeadlineQueue<'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+{
+    /// Construct a new, empty queue.
+    #[inline]
+    pub const fn new() -> Self {
+        DeadlineQueue {
+            tree: SplayTree::new(),
+        }
+    }
+
+    /// Is the queue empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the timer that expires first, if any.
+    #[inline]
+    pub fn peek(&mut self) -> Option<&'a T::Elem> {
+        self.tree.min()
+    }
+
+    /// Get the earliest deadline in the queue, if any, for example to know how
+    /// long to sleep for.
+    #[inline]
+    pub fn next_deadline(&mut self) -> Option<T::Instant> {
+        self.peek().map(T::deadline)
+    }
+
+    /// Schedule `elem` to expire at its deadline.
+    ///
+    /// Like `SplayTree::insert`, it is a logic error to schedule an element
+    /// that is already scheduled, or is in any other `T` tree.
+    #[inline]
+    pub fn schedule(&mut self, elem: &'a T::Elem) {
+        let inserted = self.tree.insert_by_key(&at::<T>(elem), elem);
+        debug_assert!(inserted);
+    }
+
+    /// Cancel `elem`'s timer, if it is scheduled in this queue.
+    ///
+    /// Timers are identified by address, so this only cancels `elem` itself,
+    /// even when other timers have the same deadline. Returns whether `elem`
+    /// was scheduled.
+    #[inline]
+    pub fn cancel(&mut self, elem: &'a T::Elem) -> bool {
+        self.tree.remove(&at::<T>(elem)).is_some()
+    }
+
+    /// Remove every timer whose deadline is at or before `now`, in deadline
+    /// order.
+    ///
+    /// Timers are only removed as the returned iterator is advanced, so drop it
+    /// early to leave the rest scheduled.
+    #[inline]
+    pub fn expire(&mut self, now: T::Instant) -> Expired<'_, 'a, T> {
+        Expired {
+            tree: &mut self.tree,
+            now,
+        }
+    }
+}
+
+/// An iterator that removes the expired timers from a `DeadlineQueue`.
+///
+/// Returned by `DeadlineQueue::expire`.
+pub struct Expired<'t, 'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    now: T::Instant,
+}
+
+impl<'t, 'a, T> Iterator for Expired<'t, 'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+{
+    type Item = &'a T::Elem;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T::Elem> {
+        match self.tree.min() {
+            Some(min) if T::deadline(min) <= self.now => self.tree.pop_root(),
+            _ => None,
+        }
+    }
+}
+
+impl<'t, 'a, T> FusedIterator for Expired<'t, 'a, T> where T: 'a + IntrusiveDeadline<'a> {}
+
+impl<'t, 'a, T> fmt::Debug for Expired<'t, 'a, T>
+where
+    T: 'a + IntrusiveDeadline<'a>,
+    T::Elem: fmt::Debug,
+    T::Instant: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Expired")
+            .field("tree", &self.tree)
+            .field("now", &self.now)
+            .finish()
+    }
+}
@@ -0,0 +1,65 @@
+//! `Display` for trees of displayable elements.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+
+impl<'a, T> fmt::Display for SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + fmt::Display + TreeOrd<'a, T>,
+{
+    /// List the elements in order, separated by commas, like `1, 2, 3`. Use
+    /// `SplayTree::display_separated` for another separator.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.display_separated(", "), f)
+    }
+}
+
+/// Displays a tree's elements in order, with a separator between each pair.
+///
+/// Returned by `SplayTree::display_separated`. The formatting options, like
+/// width and precision, apply to each element rather than to the whole list.
+pub struct DisplaySeparated<'t, 's, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: &'t SplayTree<'a, T>,
+    separator: &'s str,
+}
+
+impl<'t, 's, 'a, T> DisplaySeparated<'t, 's, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t SplayTree<'a, T>, separator: &'s str) -> Self {
+        DisplaySeparated { tree, separator }
+    }
+}
+
+impl<'t, 's, 'a, T> fmt::Display for DisplaySeparated<'t, 's, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + fmt::Display + TreeOrd<'a, T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, elem) in self.tree.iter().enumerate() {
+            if i > 0 {
+                f.write_str(self.separator)?;
+            }
+            fmt::Display::fmt(elem, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'t, 's, 'a, T> fmt::Debug for DisplaySeparated<'t, 's, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DisplaySeparated")
+            .field("separator", &self.separator)
+            .finish_non_exhaustive()
+    }
+}
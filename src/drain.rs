@@ -0,0 +1,77 @@
+//! Iterators that remove elements from trees as they go.
+
+use crate::{IntrusiveNode, SplayTree};
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// An iterator that pops the minimum element from a `SplayTree` for as long as
+/// a predicate holds.
+///
+/// Returned by `SplayTree::pop_while`.
+pub struct PopWhile<'t, 'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    pred: P,
+    done: bool,
+}
+
+impl<'t, 'a, T, P> PopWhile<'t, 'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t mut SplayTree<'a, T>, pred: P) -> Self {
+        PopWhile {
+            tree,
+            pred,
+            done: false,
+        }
+    }
+}
+
+impl<'t, 'a, T, P> Iterator for PopWhile<'t, 'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    P: FnMut(&'a T::Elem) -> bool,
+{
+    type Item = &'a T::Elem;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T::Elem> {
+        if self.done {
+            return None;
+        }
+        // The minimum is splayed to the root, where it has no left child, so
+        // popping it is just a pointer update.
+        let min = self.tree.min();
+        match min {
+            Some(min) if (self.pred)(min) => self.tree.pop_root(),
+            _ => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl<'t, 'a, T, P> FusedIterator for PopWhile<'t, 'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    P: FnMut(&'a T::Elem) -> bool,
+{
+}
+
+impl<'t, 'a, T, P> fmt::Debug for PopWhile<'t, 'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PopWhile")
+            .field("tree", &self.tree)
+            .field("done", &self.done)
+            .finish()
+    }
+}
@@ -0,0 +1,138 @@
+//! Handles to the elements at either end of a tree.
+
+use crate::{IntrusiveNode, SplayTree};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug)]
+enum End {
+    First,
+    Last,
+}
+
+/// A handle to the minimum or maximum element of a `SplayTree`.
+///
+/// Returned by `SplayTree::first_entry` and `SplayTree::last_entry`. The
+/// element is kept at the root of the tree for as long as the handle lives, so
+/// removing it takes constant time, and finding the element next to it only
+/// has to search one side of the tree.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Packet<'a> {
+///     seq: u32,
+///     node: Node<'a>,
+/// }
+///
+/// struct BySeq;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for BySeq
+///     where
+///         type Elem = Packet<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, BySeq> for Packet<'a> {
+///     fn tree_cmp(&self, rhs: &'a Packet<'a>) -> Ordering {
+///         self.seq.cmp(&rhs.seq)
+///     }
+/// }
+///
+/// let packets = [3, 1, 4, 2].map(|seq| Packet { seq, node: Node::default() });
+/// let mut reorder = SplayTree::<BySeq>::new();
+/// for packet in &packets {
+///     reorder.insert(packet);
+/// }
+///
+/// // Deliver packets in order for as long as there is no gap.
+/// let mut delivered = vec![];
+/// let mut entry = reorder.first_entry();
+/// while let Some(e) = entry {
+///     if e.get().seq != delivered.len() as u32 + 1 {
+///         break;
+///     }
+///     let (packet, next) = e.remove_and_next();
+///     delivered.push(packet.seq);
+///     entry = next;
+/// }
+///
+/// assert_eq!(delivered, [1, 2, 3, 4]);
+/// assert!(reorder.is_empty());
+/// ```
+pub struct OccupiedEntry<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    elem: &'a T::Elem,
+    end: End,
+}
+
+impl<'t, 'a, T> OccupiedEntry<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn first(tree: &'t mut SplayTree<'a, T>) -> Option<Self> {
+        let elem = tree.min()?;
+        Some(OccupiedEntry {
+            tree,
+            elem,
+            end: End::First,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn last(tree: &'t mut SplayTree<'a, T>) -> Option<Self> {
+        let elem = tree.max()?;
+        Some(OccupiedEntry {
+            tree,
+            elem,
+            end: End::Last,
+        })
+    }
+
+    /// Get the element.
+    #[inline]
+    pub fn get(&self) -> &'a T::Elem {
+        self.elem
+    }
+
+    /// Remove the element from the tree, and return it.
+    #[inline]
+    pub fn remove(self) -> &'a T::Elem {
+        let popped = self.tree.pop_root();
+        debug_assert!(popped.is_some_and(|p| core::ptr::eq(p, self.elem)));
+        self.elem
+    }
+
+    /// Remove the element from the tree, and return it along with an entry for
+    /// the new minimum or maximum element, whichever this entry was for.
+    #[inline]
+    pub fn remove_and_next(self) -> (&'a T::Elem, Option<Self>) {
+        let end = self.end;
+        let tree = self.tree;
+        let popped = tree.pop_root();
+        debug_assert!(popped.is_some_and(|p| core::ptr::eq(p, self.elem)));
+        let next = match end {
+            End::First => OccupiedEntry::first(tree),
+            End::Last => OccupiedEntry::last(tree),
+        };
+        (self.elem, next)
+    }
+}
+
+impl<'t, 'a, T> fmt::Debug for OccupiedEntry<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("elem", &self.elem)
+            .field("end", &self.end)
+            .finish()
+    }
+}
@@ -0,0 +1,48 @@
+//! Errors for the operations that report misuse instead of panicking.
+
+use core::fmt;
+
+/// An error from one of `SplayTree`'s fallible operations, like `try_insert`
+/// and `validate`, which report misuse and broken invariants instead of
+/// panicking or debug-asserting.
+///
+/// Only builds without `debug_assertions` are free of panics, though: with
+/// them, the operations that return this still debug-assert along the way,
+/// for example that no link leads to a removed node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// An element that compares equal to the given one is already in the
+    /// tree.
+    Duplicate,
+
+    /// The given element's node is already linked into a tree.
+    ///
+    /// A node with children, or that is this tree's root, is always caught.
+    /// A leaf that is in some other tree is only caught with the `linked` or
+    /// `paranoid` feature, since otherwise nothing in the node records it.
+    AlreadyLinked,
+
+    /// The tree is not a well-formed binary search tree: its elements are out
+    /// of order, or a link leads back into the tree itself, or to a node that
+    /// was removed, or to a node that belongs to another tree, or a subtree
+    /// size or parent pointer kept by the `rank` or `parent` features is
+    /// wrong.
+    ///
+    /// This means that an `IntrusiveNode` or `TreeOrd` implementation is
+    /// broken, for example by changing an element's key while it is in the
+    /// tree, or by sharing one `Node` field between two trees.
+    Corrupt,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Error::Duplicate => "an equal element is already in the tree",
+            Error::AlreadyLinked => "the element's node is already in a tree",
+            Error::Corrupt => "the tree is not a well-formed binary search tree",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
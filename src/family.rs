@@ -0,0 +1,53 @@
+//! Naming tree types independently of their elements' lifetime.
+
+use crate::{IntrusiveNode, TreeOrd};
+
+/// A tree type that is generic over the lifetime of its elements, for use with
+/// `StaticSplaySet` and `OwnedSplayTree`.
+///
+/// Tree and element types usually have a lifetime parameter, like
+/// `MonstersByName<'a>` and `Monster<'a>`. This names that whole family of
+/// types at once, so that a tree that owns its elements can pick the lifetime
+/// itself.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, TreeFamily, TreeOrd};
+/// use std::cmp::Ordering;
+/// use std::marker::PhantomData;
+///
+/// struct Monster<'a> {
+///     name: &'static str,
+///     node: Node<'a>,
+/// }
+///
+/// struct MonstersByName<'a>(PhantomData<&'a ()>);
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for MonstersByName<'a>
+///     where
+///         type Elem = Monster<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, MonstersByName<'a>> for Monster<'a> {
+///     fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+///         self.name.cmp(rhs.name)
+///     }
+/// }
+///
+/// struct Monsters;
+///
+/// impl TreeFamily for Monsters {
+///     type Tree<'a> = MonstersByName<'a>;
+/// }
+/// ```
+pub trait TreeFamily {
+    /// The tree type for elements with lifetime `'a`.
+    ///
+    /// The trees that use families own their elements, and store them by
+    /// value, so the elements must be `Sized`.
+    type Tree<'a>: 'a + IntrusiveNode<'a, Elem: Sized + TreeOrd<'a, Self::Tree<'a>>>;
+}
+
+/// The element type of a `TreeFamily` at lifetime `'a`.
+pub type FamilyElem<'a, F> = <<F as TreeFamily>::Tree<'a> as IntrusiveNode<'a>>::Elem;
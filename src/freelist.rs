@@ -0,0 +1,282 @@
+//! A best-fit free-list allocator, with blocks indexed by both size and address.
+
+use crate::{Node, SplayTree, TreeOrd};
+use core::cell::Cell;
+use core::cmp;
+use core::fmt;
+
+/// A free block of address space in a `FreeList`.
+///
+/// Each block is in two trees at once: one ordered by size, for best-fit
+/// allocation, and one ordered by address, for coalescing with neighbors on
+/// free.
+#[derive(Default)]
+pub struct FreeBlock<'a> {
+    start: Cell<usize>,
+    size: Cell<usize>,
+    by_size: Node<'a>,
+    by_addr: Node<'a>,
+}
+
+impl<'a> FreeBlock<'a> {
+    /// Create a new, unlinked block describing `size` units at `start`.
+    #[inline]
+    pub fn new(start: usize, size: usize) -> FreeBlock<'a> {
+        FreeBlock {
+            start: Cell::new(start),
+            size: Cell::new(size),
+            by_size: Node::default(),
+            by_addr: Node::default(),
+        }
+    }
+
+    /// Get this block's start address.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start.get()
+    }
+
+    /// Get this block's size.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    #[inline]
+    fn end(&self) -> usize {
+        self.start() + self.size()
+    }
+}
+
+impl<'a> fmt::Debug for FreeBlock<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FreeBlock")
+            .field("start", &self.start())
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+struct BlocksBySize;
+
+crate::impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for BlocksBySize
+    where
+        type Elem = FreeBlock<'a>,
+        node = by_size;
+}
+
+struct BlocksByAddr;
+
+crate::impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for BlocksByAddr
+    where
+        type Elem = FreeBlock<'a>,
+        node = by_addr;
+}
+
+// Blocks of the same size are ordered by address, so that every block has a
+// distinct key, and best fit prefers lower addresses.
+impl<'a> TreeOrd<'a, BlocksBySize> for FreeBlock<'a> {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a FreeBlock<'a>) -> cmp::Ordering {
+        (self.size(), self.start()).cmp(&(rhs.size(), rhs.start()))
+    }
+}
+
+impl<'a> TreeOrd<'a, BlocksByAddr> for FreeBlock<'a> {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a FreeBlock<'a>) -> cmp::Ordering {
+        self.start().cmp(&rhs.start())
+    }
+}
+
+/// A key for the smallest block of at least the given size.
+struct AtLeast(usize);
+
+impl<'a> TreeOrd<'a, BlocksBySize> for AtLeast {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a FreeBlock<'a>) -> cmp::Ordering {
+        (self.0, 0).cmp(&(rhs.size(), rhs.start()))
+    }
+}
+
+/// A key for blocks by their start address.
+struct StartAt(usize);
+
+impl<'a> TreeOrd<'a, BlocksByAddr> for StartAt {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a FreeBlock<'a>) -> cmp::Ordering {
+        self.0.cmp(&rhs.start())
+    }
+}
+
+/// A best-fit allocator over a range of addresses, built from intrusive
+/// `FreeBlock`s.
+///
+/// Allocating finds the smallest free block that is big enough, in *O(log n)*
+/// amortized time, and carves the allocation off of its start. Freeing
+/// coalesces the freed range with the free blocks on either side of it, so
+/// the list never holds two adjacent blocks. The addresses are plain `usize`s:
+/// they can be offsets into a heap, page numbers, or anything else.
+///
+/// The list doesn't allocate anything itself. Every freed range needs a
+/// `FreeBlock` to describe it, and when blocks are used up by allocations or
+/// absorbed by coalescing, the `*_with` methods hand them back so that they
+/// can be reused.
+///
+/// ```
+/// use intrusive_splay_tree::{FreeBlock, FreeList};
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut heap = FreeList::new();
+/// heap.free(arena.alloc(FreeBlock::new(0, 100)));
+///
+/// let a = heap.allocate(30).unwrap();
+/// let b = heap.allocate(50).unwrap();
+/// assert_eq!((a, b), (0, 30));
+/// assert_eq!(heap.allocate(30), None);
+///
+/// heap.free(arena.alloc(FreeBlock::new(a, 30)));
+/// heap.free(arena.alloc(FreeBlock::new(b, 50)));
+/// assert_eq!(heap.largest(), Some(100));
+/// ```
+#[derive(Default)]
+pub struct FreeList<'a> {
+    by_size: SplayTree<'a, BlocksBySize>,
+    by_addr: SplayTree<'a, BlocksByAddr>,
+}
+
+impl<'a> fmt::Debug for FreeList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = &mut f.debug_list();
+        self.by_addr.walk(|block| {
+            list.entry(&(block.start()..block.end()));
+        });
+        list.finish()
+    }
+}
+
+impl<'a> FreeList<'a> {
+    /// Construct a new free list, with no free space.
+    #[inline]
+    pub const fn new() -> FreeList<'a> {
+        FreeList {
+            by_size: SplayTree::new(),
+            by_addr: SplayTree::new(),
+        }
+    }
+
+    /// Is there no free space at all?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.by_addr.is_empty()
+    }
+
+    /// Get the size of the largest free block, if any.
+    #[inline]
+    pub fn largest(&mut self) -> Option<usize> {
+        self.by_size.max().map(FreeBlock::size)
+    }
+
+    /// Allocate `size` units, and return the start of the allocation.
+    ///
+    /// See `allocate_with` for details.
+    #[inline]
+    pub fn allocate(&mut self, size: usize) -> Option<usize> {
+        self.allocate_with(size, |_| {})
+    }
+
+    /// Allocate `size` units from the smallest free block that fits them, and
+    /// return the start of the allocation.
+    ///
+    /// The allocation is carved off of the start of the block. When it uses
+    /// the whole block, the block is removed from the list and passed to
+    /// `released`, so that it can be reused. Returns `None` if there is no
+    /// block that is big enough, or if `size` is zero.
+    pub fn allocate_with<F>(&mut self, size: usize, released: F) -> Option<usize>
+    where
+        F: FnOnce(&'a FreeBlock<'a>),
+    {
+        if size == 0 {
+            return None;
+        }
+        let block = self.by_size.remove_ge(&AtLeast(size))?;
+        let start = block.start();
+        if block.size() == size {
+            let removed = self.by_addr.remove(block);
+            debug_assert!(removed.is_some());
+            released(block);
+        } else {
+            // The rest of the block stays in the same place relative to its
+            // neighbors in address order, so only its size key needs fixing.
+            block.start.set(start + size);
+            block.size.set(block.size() - size);
+            let inserted = self.by_size.insert(block);
+            debug_assert!(inserted);
+        }
+        Some(start)
+    }
+
+    /// Return `block`'s range to the free list.
+    ///
+    /// See `free_with` for details.
+    #[inline]
+    pub fn free(&mut self, block: &'a FreeBlock<'a>) {
+        self.free_with(block, |_| {});
+    }
+
+    /// Return `block`'s range to the free list, coalescing it with the free
+    /// blocks that it touches.
+    ///
+    /// `block` must be unlinked, and its range must not overlap any free
+    /// block. It is extended to cover the coalesced range, and the neighbors
+    /// that it absorbs are removed from the list and passed to `absorbed`, so
+    /// that they can be reused. An empty block is not inserted.
+    pub fn free_with<F>(&mut self, block: &'a FreeBlock<'a>, mut absorbed: F)
+    where
+        F: FnMut(&'a FreeBlock<'a>),
+    {
+        if block.size() == 0 {
+            return;
+        }
+        let (mut start, mut end) = (block.start(), block.end());
+
+        if let Some(pred) = self.by_addr.find_le(&StartAt(start)) {
+            debug_assert!(pred.end() <= start, "freed range overlaps a free block");
+            if pred.end() == start {
+                start = pred.start();
+                self.unlink(pred);
+                absorbed(pred);
+            }
+        }
+        if let Some(succ) = self.by_addr.find_gt(&StartAt(start)) {
+            debug_assert!(succ.start() >= end, "freed range overlaps a free block");
+            if succ.start() == end {
+                end = succ.end();
+                self.unlink(succ);
+                absorbed(succ);
+            }
+        }
+
+        block.start.set(start);
+        block.size.set(end - start);
+        let inserted = self.by_addr.insert(block) && self.by_size.insert(block);
+        debug_assert!(inserted);
+    }
+
+    fn unlink(&mut self, block: &'a FreeBlock<'a>) {
+        let by_size = self.by_size.remove(block);
+        let by_addr = self.by_addr.remove(block);
+        debug_assert!(by_size.is_some() && by_addr.is_some());
+    }
+
+    /// Walk the free blocks in address order.
+    #[inline]
+    pub fn walk<F>(&self, f: F)
+    where
+        F: FnMut(&'a FreeBlock<'a>),
+    {
+        self.by_addr.walk(f);
+    }
+}
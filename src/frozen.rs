@@ -0,0 +1,120 @@
+//! Read-only, sorted-array snapshots of trees.
+
+use crate::{IntrusiveNode, TreeOrd};
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+
+/// A read-only snapshot of a tree's elements, stored as a sorted array.
+///
+/// Returned by `SplayTree::freeze_into`, and by `SplayTree::freeze` with the
+/// `alloc` feature. Unlike the tree itself, a view answers lookups through
+/// `&self` with a binary search, so it never restructures anything and can be
+/// shared freely while it lives.
+///
+/// The view does not track later changes to the tree. Elements that are
+/// removed from the tree stay in the view, and inserted elements are missing
+/// from it.
+pub struct FrozenView<'v, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    elems: &'v [&'a T::Elem],
+}
+
+impl<'v, 'a, T> FrozenView<'v, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(elems: &'v [&'a T::Elem]) -> Self {
+        FrozenView { elems }
+    }
+
+    /// Get the number of elements in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.elems.len()
+    }
+
+    /// Is the view empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.elems.is_empty()
+    }
+
+    /// Get the view's elements, in order.
+    #[inline]
+    pub fn as_slice(&self) -> &'v [&'a T::Elem] {
+        self.elems
+    }
+
+    /// Find the element that matches the given `key`.
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let index = self
+            .elems
+            .binary_search_by(|e| key.tree_cmp(e).reverse())
+            .ok()?;
+        Some(self.elems[index])
+    }
+
+    /// Get the number of elements that are less than `key`.
+    #[inline]
+    pub fn rank<K>(&self, key: &K) -> usize
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.elems.partition_point(|e| key.tree_cmp(e).is_gt())
+    }
+
+    /// Get the element with the given rank, that is, the element at `index` in
+    /// order.
+    #[inline]
+    pub fn select(&self, index: usize) -> Option<&'a T::Elem> {
+        self.elems.get(index).copied()
+    }
+
+    /// Get the elements whose keys are within `range`, in order.
+    pub fn range<K, R>(&self, range: R) -> &'v [&'a T::Elem]
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+        R: RangeBounds<K>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.rank(key),
+            Bound::Excluded(key) => self.elems.partition_point(|e| key.tree_cmp(e).is_ge()),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.elems.partition_point(|e| key.tree_cmp(e).is_ge()),
+            Bound::Excluded(key) => self.rank(key),
+            Bound::Unbounded => self.elems.len(),
+        };
+        self.elems.get(start..end).unwrap_or(&[])
+    }
+}
+
+impl<'v, 'a, T> Clone for FrozenView<'v, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'v, 'a, T> Copy for FrozenView<'v, 'a, T> where T: 'a + IntrusiveNode<'a> {}
+
+impl<'v, 'a, T> fmt::Debug for FrozenView<'v, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.elems).finish()
+    }
+}
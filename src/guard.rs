@@ -0,0 +1,138 @@
+//! Scoped tree membership.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+/// A guard that removes an element from a `SplayTree` when it is dropped.
+///
+/// Returned by `SplayTree::insert_scoped`. The guard holds on to the tree, and
+/// dereferences to it, so the tree can still be used while the element is
+/// registered:
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Waiter<'a> {
+///     deadline: u64,
+///     node: Node<'a>,
+/// }
+///
+/// struct ByDeadline;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ByDeadline
+///     where
+///         type Elem = Waiter<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ByDeadline> for Waiter<'a> {
+///     fn tree_cmp(&self, rhs: &'a Waiter<'a>) -> Ordering {
+///         self.deadline.cmp(&rhs.deadline)
+///     }
+/// }
+///
+/// let waiter = Waiter { deadline: 10, node: Node::default() };
+/// let mut timeouts = SplayTree::<ByDeadline>::new();
+///
+/// {
+///     let mut guard = timeouts.insert_scoped(&waiter).unwrap();
+///     assert_eq!(guard.min().unwrap().deadline, 10);
+///     // An early return or panic here still unregisters the waiter.
+/// }
+///
+/// assert!(timeouts.is_empty());
+/// ```
+pub struct InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    elem: &'a T::Elem,
+}
+
+impl<'t, 'a, T> InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t mut SplayTree<'a, T>, elem: &'a T::Elem) -> Self {
+        InsertGuard { tree, elem }
+    }
+
+    /// Get the element that this guard will remove.
+    #[inline]
+    pub fn elem(&self) -> &'a T::Elem {
+        self.elem
+    }
+
+    /// Leave the element in the tree, instead of removing it when the guard is
+    /// dropped.
+    #[inline]
+    pub fn keep(self) -> &'a T::Elem {
+        let elem = self.elem;
+        mem::forget(self);
+        elem
+    }
+}
+
+impl<'t, 'a, T> Drop for InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    fn drop(&mut self) {
+        // The element may have been removed through the guard already, and
+        // replaced with an equal one, so only remove it if it is the very same
+        // element. Finding it splays it to the root.
+        if self
+            .tree
+            .find(self.elem)
+            .is_some_and(|e| ptr::eq(e, self.elem))
+        {
+            self.tree.pop_root();
+        }
+    }
+}
+
+impl<'t, 'a, T> Deref for InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Target = SplayTree<'a, T>;
+
+    #[inline]
+    fn deref(&self) -> &SplayTree<'a, T> {
+        self.tree
+    }
+}
+
+impl<'t, 'a, T> DerefMut for InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut SplayTree<'a, T> {
+        self.tree
+    }
+}
+
+impl<'t, 'a, T> fmt::Debug for InsertGuard<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InsertGuard")
+            .field("elem", &self.elem)
+            .finish()
+    }
+}
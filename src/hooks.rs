@@ -0,0 +1,139 @@
+//! Callbacks for a `SplayTree`'s structural changes.
+
+use crate::internal;
+use crate::{IntrusiveNode, Node};
+use core::marker::PhantomData;
+
+/// Callbacks that a `SplayTree` makes as it changes, for keeping secondary
+/// structures, statistics, or logs in lockstep with it.
+///
+/// Install them with `SplayTree::set_hooks`. Every method has a default that
+/// does nothing, so implementations only need the ones they care about.
+///
+/// The callbacks run in the middle of tree operations, so they take `&self`,
+/// and state they keep needs to be in `Cell`s or `RefCell`s. They can't reach
+/// the tree itself, which is mutably borrowed by the operation that calls
+/// them.
+///
+/// Requires the `hooks` cargo feature.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayHooks, SplayTree, TreeOrd};
+/// use std::cell::Cell;
+/// use std::cmp::Ordering;
+///
+/// struct Job<'a> {
+///     cost: u64,
+///     node: Node<'a>,
+/// }
+///
+/// struct JobsByCost;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for JobsByCost
+///     where
+///         type Elem = Job<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, JobsByCost> for Job<'a> {
+///     fn tree_cmp(&self, rhs: &'a Job<'a>) -> Ordering {
+///         self.cost.cmp(&rhs.cost)
+///     }
+/// }
+///
+/// // Keep a running total of the cost of every job in the tree.
+/// #[derive(Default)]
+/// struct TotalCost(Cell<u64>);
+///
+/// impl<'a> SplayHooks<'a, JobsByCost> for TotalCost {
+///     fn on_insert(&self, job: &'a Job<'a>) {
+///         self.0.set(self.0.get() + job.cost);
+///     }
+///
+///     fn on_remove(&self, job: &'a Job<'a>) {
+///         self.0.set(self.0.get() - job.cost);
+///     }
+/// }
+///
+/// let total = TotalCost::default();
+/// let jobs = [3, 5, 8].map(|cost| Job { cost, node: Node::new() });
+/// let mut tree = SplayTree::<JobsByCost>::new();
+/// tree.set_hooks(&total);
+///
+/// for job in &jobs {
+///     tree.insert(job);
+/// }
+/// assert_eq!(total.0.get(), 16);
+///
+/// tree.pop_min();
+/// assert_eq!(total.0.get(), 13);
+///
+/// // Splitting the tree reports the elements it detaches.
+/// let expensive = tree.split_by(|job| job.cost > 5);
+/// assert_eq!(total.0.get(), 5);
+/// assert_eq!(expensive.root().unwrap().cost, 8);
+/// ```
+pub trait SplayHooks<'a, T>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Called after `elem` is inserted into the tree.
+    #[inline]
+    fn on_insert(&self, elem: &'a T::Elem) {
+        let _ = elem;
+    }
+
+    /// Called after `elem` is removed from the tree.
+    #[inline]
+    fn on_remove(&self, elem: &'a T::Elem) {
+        let _ = elem;
+    }
+
+    /// Called after `child` is rotated above `parent` while splaying.
+    ///
+    /// `child` and `parent` are linked to each other, but the rest of the tree
+    /// may be mid-splay, so this is only a view of the rotation itself. Only
+    /// rotations are reported: the top-down splay also relinks whole
+    /// subtrees, which doesn't change any element's in-order neighbors.
+    #[inline]
+    fn on_rotate(&self, child: &'a T::Elem, parent: &'a T::Elem) {
+        let _ = (child, parent);
+    }
+}
+
+/// Adapts `SplayHooks` on elements to the type-erased `internal::Hooks` on
+/// nodes.
+#[repr(transparent)]
+pub(crate) struct Adapter<T, H>(PhantomData<T>, H);
+
+impl<T, H> Adapter<T, H> {
+    #[inline]
+    pub(crate) fn new(hooks: &H) -> &Self {
+        // Safety: `Adapter` is `repr(transparent)` over `H`.
+        unsafe { &*(hooks as *const H).cast::<Self>() }
+    }
+}
+
+impl<'a, T, H> internal::Hooks<'a> for Adapter<T, H>
+where
+    T: 'a + IntrusiveNode<'a>,
+    H: SplayHooks<'a, T>,
+{
+    #[inline]
+    fn inserted(&self, node: &'a Node<'a>) {
+        self.1.on_insert(unsafe { T::node_to_elem(node) });
+    }
+
+    #[inline]
+    fn removed(&self, node: &'a Node<'a>) {
+        self.1.on_remove(unsafe { T::node_to_elem(node) });
+    }
+
+    #[inline]
+    fn rotated(&self, child: &'a Node<'a>, parent: &'a Node<'a>) {
+        self.1.on_rotate(unsafe { T::node_to_elem(child) }, unsafe {
+            T::node_to_elem(parent)
+        });
+    }
+}
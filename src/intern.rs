@@ -0,0 +1,126 @@
+//! A string interner built on a splay tree and a bump arena.
+
+use crate::{Node, SplayTree, TreeOrd};
+use bumpalo::Bump;
+use core::cmp::Ordering;
+use core::fmt;
+
+struct Symbol<'a> {
+    text: &'a str,
+    node: Node<'a>,
+}
+
+struct Symbols;
+
+crate::impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Symbols
+    where
+        type Elem = Symbol<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, Symbols> for Symbol<'a> {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a Symbol<'a>) -> Ordering {
+        self.text.cmp(rhs.text)
+    }
+}
+
+impl<'a> TreeOrd<'a, Symbols> for str {
+    #[inline]
+    fn tree_cmp(&self, rhs: &'a Symbol<'a>) -> Ordering {
+        self.cmp(rhs.text)
+    }
+}
+
+/// A set of interned strings, which are each allocated once in a bump arena.
+///
+/// Interning a string returns the arena's copy of it, allocating that copy
+/// the first time the string is seen, so equal strings are interned as the
+/// same `&'a str` and can be compared with `ptr::eq` instead of by contents.
+/// Lookups compare strings in a splay tree rather than hashing them, so the
+/// identifiers that come up over and over in a parser or compiler stay near
+/// the root, and there is no hasher to choose or seed. None of it needs `std`.
+///
+/// Each string costs one allocation for its bytes and one for its tree node,
+/// both in the arena, so they live as long as it does.
+///
+/// Requires the `bumpalo` feature.
+///
+/// ```
+/// use intrusive_splay_tree::InternSet;
+/// use std::ptr;
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut strings = InternSet::new(&arena);
+///
+/// let a = strings.intern("identifier");
+/// let b = strings.intern(&String::from("identifier"));
+/// assert!(ptr::eq(a, b));
+///
+/// assert_eq!(strings.get("identifier"), Some(a));
+/// assert_eq!(strings.get("keyword"), None);
+/// ```
+pub struct InternSet<'a> {
+    arena: &'a Bump,
+    tree: SplayTree<'a, Symbols>,
+}
+
+impl fmt::Debug for InternSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.tree.walk(|s| {
+            set.entry(&s.text);
+        });
+        set.finish()
+    }
+}
+
+impl<'a> InternSet<'a> {
+    /// Construct a new, empty set that allocates its strings in `arena`.
+    #[inline]
+    pub fn new(arena: &'a Bump) -> Self {
+        InternSet {
+            arena,
+            tree: SplayTree::new(),
+        }
+    }
+
+    /// Is the set empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the interned copy of `s`, copying it into the arena if this is the
+    /// first time it has been interned.
+    #[inline]
+    pub fn intern(&mut self, s: &str) -> &'a str {
+        if let Some(symbol) = self.tree.find(s) {
+            return symbol.text;
+        }
+        let symbol = self.arena.alloc(Symbol {
+            text: self.arena.alloc_str(s),
+            node: Node::new(),
+        });
+        let inserted = self.tree.insert(symbol);
+        debug_assert!(inserted);
+        symbol.text
+    }
+
+    /// Get the interned copy of `s`, if it has been interned, without
+    /// interning it or restructuring the set.
+    #[inline]
+    pub fn get(&self, s: &str) -> Option<&'a str> {
+        self.tree.get(s).map(|symbol| symbol.text)
+    }
+
+    /// Call `f` with each interned string, in lexicographic order.
+    #[inline]
+    pub fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&'a str),
+    {
+        self.tree.walk(|symbol| f(symbol.text));
+    }
+}
@@ -8,11 +8,37 @@
 //! small. Therefore, it doesn't make sense to allow any of the `internal`
 //! methods working with trait objects to be inlined, or else all our work would
 //! be undone.
+//!
+//! The methods that take keys are written generically over the key type, but
+//! the public API only ever instantiates them with `dyn CompareToNode`, via
+//! `erase`. With the `monomorphize` feature, `erase` is the identity instead,
+//! so each tree type gets its own fully inlined splay loop with static calls
+//! to its comparison, trading code size for lookup speed.
+//!
+//! The exception is `MinNode` and `MaxNode`, which are never erased. Their
+//! comparisons are constant, so `splay`'s instantiations for them fold into
+//! dedicated leftmost and rightmost splays without any calls, which `min`,
+//! `max`, and every removal use. Those are only two more copies of `splay`
+//! for the whole program, since neither depends on the tree type.
+//!
+//! The `*_branded` methods also do the public API's `Brand` bookkeeping, so
+//! that the generic wrappers are left with little more than building a query
+//! and converting the resulting node into an element.
 
 use super::Node;
+use crate::brand::Brand;
+#[cfg(feature = "rank")]
+use core::cell::Cell;
 use core::cmp;
+use core::fmt;
+use core::ptr;
 
 /// Internal trait for anything that can be compared to a `Node`.
+///
+/// Keys that are generic over the tree type, like `ComparatorTree`'s and
+/// `MinGapSet`'s, implement this directly instead of `TreeOrd`, since a blanket
+/// `TreeOrd` implementation for them would overlap with the one for
+/// `Descending`.
 pub trait CompareToNode<'a> {
     /// Compare `self` to the value containing the given `Node`.
     ///
@@ -25,38 +51,263 @@ pub trait CompareToNode<'a> {
     unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering;
 }
 
+/// Erase the type of `key`, so that methods taking it are only instantiated
+/// once.
+#[cfg(not(feature = "monomorphize"))]
+#[inline]
+pub fn erase<'k, 'a, K>(key: &'k K) -> &'k (dyn CompareToNode<'a> + 'k)
+where
+    K: CompareToNode<'a>,
+{
+    key
+}
+
+/// With the `monomorphize` feature, keep the type of `key`, so that methods
+/// taking it are instantiated and inlined for each key type.
+#[cfg(feature = "monomorphize")]
+#[inline]
+pub fn erase<'k, 'a, K>(key: &'k K) -> &'k K
+where
+    K: CompareToNode<'a>,
+{
+    key
+}
+
 /// A node comparator to get the minimum node.
-struct MinNode;
+pub struct MinNode;
 impl<'a> CompareToNode<'a> for MinNode {
+    #[inline]
     unsafe fn compare_to_node(&self, _node: &'a Node<'a>) -> cmp::Ordering {
         cmp::Ordering::Less
     }
 }
 
 /// A node comparator to get the maximum node.
-struct MaxNode;
+pub struct MaxNode;
 impl<'a> CompareToNode<'a> for MaxNode {
+    #[inline]
     unsafe fn compare_to_node(&self, _node: &'a Node<'a>) -> cmp::Ordering {
         cmp::Ordering::Greater
     }
 }
 
-#[derive(Debug)]
+/// A node comparator to get the successor of the given node, which must be
+/// the root: the root compares `Greater`, so the splay goes right, and every
+/// other node compares `Less`, so it ends at the minimum of that subtree.
+pub struct NextNode<'a>(pub &'a Node<'a>);
+impl<'a> CompareToNode<'a> for NextNode<'a> {
+    #[inline]
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        if ptr::eq(node, self.0) {
+            cmp::Ordering::Greater
+        } else {
+            cmp::Ordering::Less
+        }
+    }
+}
+
+/// A node comparator to get the predecessor of the given node, which must be
+/// the root. The mirror image of `NextNode`.
+pub struct PrevNode<'a>(pub &'a Node<'a>);
+impl<'a> CompareToNode<'a> for PrevNode<'a> {
+    #[inline]
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        if ptr::eq(node, self.0) {
+            cmp::Ordering::Less
+        } else {
+            cmp::Ordering::Greater
+        }
+    }
+}
+
+/// A node comparator to get the node at a given position in order, for trees
+/// whose nodes have no keys.
+///
+/// `index` is relative to the subtree of the node being compared, so when a
+/// node compares `Greater`, `index` moves past it and its left subtree, ready
+/// for the right subtree. `splay` sometimes compares the same node twice in a
+/// row, so the last comparison is remembered and repeated instead.
+#[cfg(feature = "rank")]
+pub struct Position<'a> {
+    index: Cell<usize>,
+    last: Cell<Option<(&'a Node<'a>, cmp::Ordering)>>,
+}
+
+#[cfg(feature = "rank")]
+impl<'a> Position<'a> {
+    #[inline]
+    pub fn new(index: usize) -> Self {
+        Position {
+            index: Cell::new(index),
+            last: Cell::new(None),
+        }
+    }
+}
+
+#[cfg(feature = "rank")]
+impl<'a> CompareToNode<'a> for Position<'a> {
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        if let Some((last, order)) = self.last.get() {
+            if ptr::eq(last, node) {
+                return order;
+            }
+        }
+        let left = Node::size_of(node.left.get());
+        let index = self.index.get();
+        let order = index.cmp(&left);
+        if order.is_gt() {
+            self.index.set(index - left - 1);
+        }
+        self.last.set(Some((node, order)));
+        order
+    }
+}
+
+/// Node-level callbacks for a tree's structural changes, which `SplayHooks`
+/// is adapted to.
+#[cfg(feature = "hooks")]
+pub trait Hooks<'a> {
+    fn inserted(&self, node: &'a Node<'a>);
+    fn removed(&self, node: &'a Node<'a>);
+    fn rotated(&self, child: &'a Node<'a>, parent: &'a Node<'a>);
+}
+
+#[cfg_attr(not(any(feature = "hooks", feature = "watchdog")), repr(transparent))]
+#[cfg_attr(any(feature = "hooks", feature = "watchdog"), repr(C))]
 pub struct SplayTree<'a> {
     root: Option<&'a Node<'a>>,
+    #[cfg(feature = "hooks")]
+    hooks: Option<&'a dyn Hooks<'a>>,
+    // Splays that descend deeper than this rebalance the tree afterwards.
+    #[cfg(feature = "watchdog")]
+    depth_limit: usize,
 }
 
 impl<'a> Default for SplayTree<'a> {
     #[inline]
     fn default() -> SplayTree<'a> {
-        SplayTree { root: None }
+        SplayTree::new()
+    }
+}
+
+impl<'a> fmt::Debug for SplayTree<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SplayTree")
+            .field("root", &self.root)
+            .finish()
     }
 }
 
 impl<'a> SplayTree<'a> {
     #[inline]
     pub const fn new() -> Self {
-        SplayTree { root: None }
+        SplayTree {
+            root: None,
+            #[cfg(feature = "hooks")]
+            hooks: None,
+            #[cfg(feature = "watchdog")]
+            depth_limit: usize::MAX,
+        }
+    }
+
+    /// Call `hooks` on every structural change from now on, or stop calling
+    /// any if it is `None`.
+    #[cfg(feature = "hooks")]
+    #[inline]
+    pub fn set_hooks(&mut self, hooks: Option<&'a dyn Hooks<'a>>) {
+        self.hooks = hooks;
+    }
+
+    #[cfg(feature = "hooks")]
+    #[inline]
+    pub fn hooks(&self) -> Option<&'a dyn Hooks<'a>> {
+        self.hooks
+    }
+
+    /// Rebalance the tree after every splay that descends more than `limit`
+    /// nodes deep.
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    pub fn set_depth_limit(&mut self, limit: usize) {
+        self.depth_limit = limit;
+    }
+
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    pub fn depth_limit(&self) -> usize {
+        self.depth_limit
+    }
+
+    /// Rebuild the tree into one of minimal height.
+    #[inline]
+    pub fn rebalance(&mut self) {
+        self.set_root(balanced(self.root));
+    }
+
+    /// Move all of this tree's nodes into a new tree, which has no hooks or
+    /// depth limit, leaving this one empty.
+    #[inline]
+    pub fn take(&mut self) -> SplayTree<'a> {
+        let mut tree = SplayTree::new();
+        tree.root = self.root.take();
+        self.removed_all(tree.root);
+        tree
+    }
+
+    #[inline]
+    fn inserted(&self, node: &'a Node<'a>) {
+        #[cfg(feature = "hooks")]
+        if let Some(hooks) = self.hooks {
+            hooks.inserted(node);
+        }
+        let _ = node;
+    }
+
+    #[inline]
+    fn removed(&self, node: &'a Node<'a>) {
+        #[cfg(feature = "hooks")]
+        if let Some(hooks) = self.hooks {
+            hooks.removed(node);
+        }
+        let _ = node;
+    }
+
+    /// Report every node in the subtree rooted at `root`, which was just
+    /// detached from this tree, as removed.
+    #[inline]
+    fn removed_all(&self, root: Option<&'a Node<'a>>) {
+        #[cfg(feature = "hooks")]
+        if let (Some(hooks), Some(root)) = (self.hooks, root) {
+            root.walk(&mut |node| {
+                hooks.removed(node);
+                true
+            });
+        }
+        let _ = root;
+    }
+
+    /// Report every node in the subtree rooted at `root`, which was just
+    /// attached to this tree, as inserted.
+    #[inline]
+    fn inserted_all(&self, root: Option<&'a Node<'a>>) {
+        #[cfg(feature = "hooks")]
+        if let (Some(hooks), Some(root)) = (self.hooks, root) {
+            root.walk(&mut |node| {
+                hooks.inserted(node);
+                true
+            });
+        }
+        let _ = root;
+    }
+
+    /// Report that `child` was just rotated above `parent`.
+    #[inline]
+    fn rotated(&self, child: &'a Node<'a>, parent: &'a Node<'a>) {
+        #[cfg(feature = "hooks")]
+        if let Some(hooks) = self.hooks {
+            hooks.rotated(child, parent);
+        }
+        let _ = (child, parent);
     }
 
     #[inline]
@@ -69,12 +320,69 @@ impl<'a> SplayTree<'a> {
         self.root
     }
 
-    #[inline(never)]
-    pub unsafe fn find(&mut self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+    /// Make `root` the root, clearing its parent.
+    #[inline]
+    fn set_root(&mut self, root: Option<&'a Node<'a>>) {
+        #[cfg(feature = "parent")]
+        if let Some(root) = root {
+            root.parent.set(None);
+        }
+        self.root = root;
+    }
+
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn len(&self) -> usize {
+        Node::size_of(self.root)
+    }
+
+    /// Get the node at `index` in order, without splaying.
+    #[cfg(feature = "rank")]
+    pub fn nth(&self, mut index: usize) -> Option<&'a Node<'a>> {
+        let mut node = self.root;
+        while let Some(n) = node {
+            n.check_removed();
+            let left = Node::size_of(n.left.get());
+            match index.cmp(&left) {
+                cmp::Ordering::Less => node = n.left.get(),
+                cmp::Ordering::Equal => return Some(n),
+                cmp::Ordering::Greater => {
+                    index -= left + 1;
+                    node = n.right.get();
+                }
+            }
+        }
+        None
+    }
+
+    /// Splay the node closest to `key` to the root, and return it along with
+    /// how `key` compares to it.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[inline]
+    pub unsafe fn splay_to<K>(&mut self, key: &K) -> Option<(&'a Node<'a>, cmp::Ordering)>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        Some(self.splay(root, key))
+    }
+
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn find<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
         match self.root {
             Some(root) => {
-                let root = self.splay(root, key);
-                if let cmp::Ordering::Equal = key.compare_to_node(root) {
+                let (root, order) = self.splay(root, key);
+                if let cmp::Ordering::Equal = order {
                     Some(root)
                 } else {
                     None
@@ -84,42 +392,242 @@ impl<'a> SplayTree<'a> {
         }
     }
 
-    #[inline(never)]
-    pub unsafe fn insert(&mut self, key: &dyn CompareToNode<'a>, node: &'a Node<'a>) -> bool {
+    /// Like `find`, but only splay the found node up to just beneath the
+    /// deepest node on its search path that is `heavier` than it, or all the
+    /// way to the root if there is none.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    pub unsafe fn find_weighted<K>(
+        &mut self,
+        key: &K,
+        heavier: &dyn Fn(&'a Node<'a>, &'a Node<'a>) -> bool,
+    ) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        let found = self.get(key)?;
+
+        // Descend again, now that the found node's weight is known, to find
+        // the subtree it may be splayed to the top of.
+        let mut anchor = None;
+        let mut node = Some(root);
+        while let Some(n) = node.filter(|n| !ptr::eq(*n, found)) {
+            if heavier(n, found) {
+                anchor = Some(n);
+            }
+            node = match key.compare_to_node(n) {
+                cmp::Ordering::Less => n.left.get(),
+                _ => n.right.get(),
+            };
+        }
+
+        match anchor {
+            None => {
+                self.splay(root, key);
+            }
+            // The subtree keeps the same nodes, so the sizes above it are
+            // unchanged.
+            Some(anchor) => {
+                let is_left = key.compare_to_node(anchor).is_lt();
+                let subtree = if is_left {
+                    anchor.left.get()
+                } else {
+                    anchor.right.get()
+                };
+                if let Some(subtree) = subtree {
+                    let (top, _) = self.splay(subtree, key);
+                    self.root = Some(root);
+                    if is_left {
+                        anchor.set_left(Some(top));
+                    } else {
+                        anchor.set_right(Some(top));
+                    }
+                }
+            }
+        }
+        Some(found)
+    }
+
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[inline]
+    pub unsafe fn insert<K>(&mut self, key: &K, node: &'a Node<'a>) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
         debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+        self.insert_unchecked(key, node)
+    }
 
+    /// Like `insert`, but without checking that `node` is unlinked. Its links
+    /// are overwritten, so they may be stale.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree, and
+    /// `node` must not be in any tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn insert_unchecked<K>(&mut self, key: &K, node: &'a Node<'a>) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
         match self.root {
             Some(root) => {
-                let root = self.splay(root, key);
+                let (root, order) = self.splay(root, key);
 
-                match key.compare_to_node(root) {
+                match order {
                     cmp::Ordering::Equal => return false,
                     cmp::Ordering::Less => {
-                        node.left.set(root.left.get());
-                        node.right.set(Some(root));
+                        node.set_left(root.left.get());
+                        node.set_right(Some(root));
                         root.left.set(None);
                     }
                     cmp::Ordering::Greater => {
-                        node.right.set(root.right.get());
-                        node.left.set(Some(root));
+                        node.set_right(root.right.get());
+                        node.set_left(Some(root));
                         root.right.set(None);
                     }
                 }
 
-                self.root = Some(node);
-                true
+                root.fix_size();
+                node.fix_size();
+                self.set_root(Some(node));
             }
             None => {
-                self.root = Some(node);
-                true
+                node.left.set(None);
+                node.right.set(None);
+                node.fix_size();
+                self.set_root(Some(node));
             }
         }
+        self.inserted(node);
+        true
+    }
+
+    /// Insert `node` as the root's successor, without splaying, if `key` orders
+    /// it between the root and the root's current successor. Returns `false`,
+    /// without inserting it, otherwise.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn insert_after_root<K>(&mut self, key: &K, node: &'a Node<'a>) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+
+        let Some(root) = self.root else {
+            return false;
+        };
+        if !key.compare_to_node(root).is_gt() {
+            return false;
+        }
+        if let Some(mut succ) = root.right.get() {
+            while let Some(left) = succ.left.get() {
+                succ = left;
+            }
+            if !key.compare_to_node(succ).is_lt() {
+                return false;
+            }
+        }
+
+        node.set_left(Some(root));
+        node.set_right(root.right.get());
+        root.right.set(None);
+        root.fix_size();
+        node.fix_size();
+        self.set_root(Some(node));
+        self.inserted(node);
+        true
+    }
+
+    /// Splay `node`, which must be in this tree, to the root by rotating it
+    /// up through its ancestors: the "bottom-up" splay from the paper.
+    #[cfg(feature = "parent")]
+    pub fn splay_up(&mut self, node: &'a Node<'a>) {
+        node.check_removed();
+        let is_left = |p: &Node<'a>, c| p.left.get().is_some_and(|l| ptr::eq(l, c));
+        // Each rotation is passed the parent it rotates under, rather than
+        // reading it back out of the node, so that there's no `unwrap`.
+        while let Some(parent) = node.parent.get() {
+            match parent.parent.get() {
+                Some(grandparent) if is_left(grandparent, parent) == is_left(parent, node) => {
+                    // Zig-zig.
+                    parent.rotate_up(grandparent);
+                    self.rotated(parent, grandparent);
+                    node.rotate_up(parent);
+                    self.rotated(node, parent);
+                }
+                Some(grandparent) => {
+                    // Zig-zag.
+                    node.rotate_up(parent);
+                    self.rotated(node, parent);
+                    node.rotate_up(grandparent);
+                    self.rotated(node, grandparent);
+                }
+                None => {
+                    node.rotate_up(parent);
+                    self.rotated(node, parent);
+                }
+            }
+        }
+        self.root = Some(node);
+    }
+
+    /// Find the node matching `key` by descending the tree, without changing
+    /// it.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn get<K>(&self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let mut node = self.root;
+        while let Some(n) = node {
+            n.check_removed();
+            node = match key.compare_to_node(n) {
+                cmp::Ordering::Less => n.left.get(),
+                cmp::Ordering::Greater => n.right.get(),
+                cmp::Ordering::Equal => return Some(n),
+            };
+        }
+        None
+    }
+
+    /// Find the node matching `key` by descending the tree without changing
+    /// it, and then splay it bottom-up if it is found.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg(feature = "parent")]
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn find_bottom_up<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let node = self.get(key)?;
+        self.splay_up(node);
+        Some(node)
     }
 
     #[inline]
     pub fn min(&mut self) -> Option<&'a Node<'a>> {
         let root = self.root()?;
-        Some(unsafe { self.splay(root, &MinNode) })
+        Some(unsafe { self.splay(root, &MinNode).0 })
     }
 
     #[inline]
@@ -131,7 +639,50 @@ impl<'a> SplayTree<'a> {
     #[inline]
     pub fn max(&mut self) -> Option<&'a Node<'a>> {
         let root = self.root()?;
-        Some(unsafe { self.splay(root, &MaxNode) })
+        Some(unsafe { self.splay(root, &MaxNode).0 })
+    }
+
+    /// Splay the root's successor to the root, and return it, or return `None`
+    /// if the root is the maximum or the tree is empty.
+    ///
+    /// This finds neighbors by position in the tree rather than by key, so it
+    /// works however the tree is ordered. Stepping through `k` successors
+    /// takes amortized *O(k + log n)* time, by the sequential access theorem.
+    #[inline]
+    pub fn splay_next(&mut self) -> Option<&'a Node<'a>> {
+        let root = self.root?;
+        match unsafe { self.splay(root, erase(&NextNode(root))) } {
+            (next, cmp::Ordering::Less) => Some(next),
+            _ => None,
+        }
+    }
+
+    /// Splay the root's predecessor to the root, and return it, or return
+    /// `None` if the root is the minimum or the tree is empty.
+    #[inline]
+    pub fn splay_prev(&mut self) -> Option<&'a Node<'a>> {
+        let root = self.root?;
+        match unsafe { self.splay(root, erase(&PrevNode(root))) } {
+            (prev, cmp::Ordering::Greater) => Some(prev),
+            _ => None,
+        }
+    }
+
+    /// Splay the minimum node to the root, and the maximum node to be its
+    /// right child, so that calling this again is constant time.
+    #[inline]
+    pub fn min_max(&mut self) -> Option<(&'a Node<'a>, &'a Node<'a>)> {
+        let min = self.min()?;
+        let max = match min.right.get() {
+            Some(right) => {
+                let (max, _) = unsafe { self.splay(right, &MaxNode) };
+                min.set_right(Some(max));
+                self.set_root(Some(min));
+                max
+            }
+            None => min,
+        };
+        Some((min, max))
     }
 
     #[inline]
@@ -146,32 +697,288 @@ impl<'a> SplayTree<'a> {
         match old_root.left.get() {
             Some(old_root_left) => {
                 let old_root_right = old_root.right.get();
-                unsafe {
-                    self.splay(old_root_left, &MaxNode)
-                        .right
-                        .set(old_root_right)
-                }
+                let (new_root, _) = unsafe { self.splay(old_root_left, &MaxNode) };
+                new_root.set_right(old_root_right);
+                new_root.fix_size();
             }
             None => {
-                self.root = old_root.right.get();
+                self.set_root(old_root.right.get());
             }
         }
 
-        old_root.left.set(None);
-        old_root.right.set(None);
+        old_root.unlink();
+        self.removed(old_root);
         Some(old_root)
     }
 
-    pub unsafe fn remove(&mut self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn remove<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
         let root = self.root?;
-        self.splay(root, key);
-        if self.root.is_some_and(|r| key.compare_to_node(r).is_eq()) {
+        if self.splay(root, key).1.is_eq() {
             self.pop_root()
         } else {
             None
         }
     }
 
+    /// Remove the node matching `key`, and also return its in-order
+    /// predecessor and successor.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn remove_with_neighbors<K>(
+        &mut self,
+        key: &K,
+    ) -> Option<(Option<&'a Node<'a>>, &'a Node<'a>, Option<&'a Node<'a>>)>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        let (root, order) = self.splay(root, key);
+        if !order.is_eq() {
+            return None;
+        }
+
+        // The successor is the minimum of the right subtree, which popping the
+        // root leaves in place.
+        let mut succ = root.right.get();
+        while let Some(left) = succ.and_then(|n| n.left.get()) {
+            succ = Some(left);
+        }
+
+        // Popping the root splays the maximum of its left subtree, which is the
+        // predecessor, up to be the new root.
+        let has_pred = root.left.get().is_some();
+        let removed = self.pop_root()?;
+        let pred = if has_pred { self.root } else { None };
+        Some((pred, removed, succ))
+    }
+
+    /// Remove the smallest node that is greater than or equal to `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn remove_ge<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        let (root, order) = self.splay(root, key);
+        if order.is_le() {
+            return self.pop_root();
+        }
+
+        // The root is the greatest node less than `key`, and everything in its
+        // right subtree is greater than `key`, so we want that subtree's
+        // minimum. It has no left child once it is splayed to the subtree's
+        // root.
+        let right = root.right.get()?;
+        let (min, _) = self.splay(right, &MinNode);
+        debug_assert!(min.left.get().is_none());
+        root.set_right(min.right.get());
+        root.fix_size();
+        self.set_root(Some(root));
+        #[cfg(feature = "parent")]
+        min.parent.set(None);
+        min.unlink();
+        self.removed(min);
+        Some(min)
+    }
+
+    /// Find the largest node that is less than or equal to `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn find_le<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        let (root, order) = self.splay(root, key);
+        if !order.is_lt() {
+            return Some(root);
+        }
+
+        // Everything in the root's left subtree is less than `key`, so we want
+        // that subtree's maximum.
+        let left = root.left.get()?;
+        let (max, _) = self.splay(left, &MaxNode);
+        root.set_left(Some(max));
+        self.set_root(Some(root));
+        Some(max)
+    }
+
+    /// Find the smallest node that is greater than `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub unsafe fn find_gt<K>(&mut self, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let root = self.root?;
+        let (root, order) = self.splay(root, key);
+        if order.is_lt() {
+            return Some(root);
+        }
+
+        // Everything in the root's right subtree is greater than `key`, so we
+        // want that subtree's minimum. Splaying it to the top of the subtree
+        // keeps sequential scans cheap.
+        let right = root.right.get()?;
+        let (min, _) = self.splay(right, &MinNode);
+        root.set_right(Some(min));
+        self.set_root(Some(root));
+        Some(min)
+    }
+
+    /// Like `find`, but also check that the found node has `brand`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub(crate) unsafe fn find_branded<K>(&mut self, brand: &Brand, key: &K) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let node = self.find(key)?;
+        brand.check(node);
+        Some(node)
+    }
+
+    /// Like `insert`, but also check that `node` is not in any tree, and
+    /// give it `brand` once it is inserted.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub(crate) unsafe fn insert_branded<K>(
+        &mut self,
+        brand: &Brand,
+        key: &K,
+        node: &'a Node<'a>,
+    ) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        brand.check_unbranded(node);
+        let inserted = self.insert(key, node);
+        if inserted {
+            brand.brand(node);
+        }
+        inserted
+    }
+
+    /// Like `insert_unchecked`, but also give `node` `brand` once it is
+    /// inserted.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree, and
+    /// `node` must not be in any tree.
+    #[inline]
+    pub(crate) unsafe fn insert_unchecked_branded<K>(
+        &mut self,
+        brand: &Brand,
+        key: &K,
+        node: &'a Node<'a>,
+    ) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let inserted = self.insert_unchecked(key, node);
+        if inserted {
+            brand.brand(node);
+        }
+        inserted
+    }
+
+    /// Like `insert_after_root`, but also check that `node` is not in any
+    /// tree, and give it `brand` once it is inserted.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[inline]
+    pub(crate) unsafe fn insert_after_root_branded<K>(
+        &mut self,
+        brand: &Brand,
+        key: &K,
+        node: &'a Node<'a>,
+    ) -> bool
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        brand.check_unbranded(node);
+        let inserted = self.insert_after_root(key, node);
+        if inserted {
+            brand.brand(node);
+        }
+        inserted
+    }
+
+    /// Like `remove`, but also check and clear the removed node's `brand`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub(crate) unsafe fn remove_branded<K>(
+        &mut self,
+        brand: &Brand,
+        key: &K,
+    ) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let node = self.remove(key)?;
+        brand.unbrand(node);
+        Some(node)
+    }
+
+    /// Like `remove_ge`, but also check and clear the removed node's `brand`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be able to compare itself to every node in this tree.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    pub(crate) unsafe fn remove_ge_branded<K>(
+        &mut self,
+        brand: &Brand,
+        key: &K,
+    ) -> Option<&'a Node<'a>>
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        let node = self.remove_ge(key)?;
+        brand.unbrand(node);
+        Some(node)
+    }
+
     #[inline]
     pub fn walk(&self, f: &mut dyn FnMut(&'a Node<'a>) -> bool) {
         if let Some(root) = self.root {
@@ -179,71 +986,435 @@ impl<'a> SplayTree<'a> {
         }
     }
 
+    #[inline]
+    pub fn walk_rev(&self, f: &mut dyn FnMut(&'a Node<'a>) -> bool) {
+        if let Some(root) = self.root {
+            root.walk_rev(f);
+        }
+    }
+
+    /// Splay the node at `index` in order to the root, or the maximum node if
+    /// `index` is out of bounds.
+    ///
+    /// Returns the new root, and `Ordering::Equal` if it is at `index`.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn splay_at(&mut self, index: usize) -> Option<(&'a Node<'a>, cmp::Ordering)> {
+        let root = self.root?;
+        Some(unsafe { self.splay(root, erase(&Position::new(index))) })
+    }
+
+    /// Insert `node` so that it ends up at `index` in order, shifting the
+    /// nodes from `index` on up by one. `index` must be at most the length of
+    /// the tree.
+    #[cfg(feature = "rank")]
+    pub fn insert_at(&mut self, index: usize, node: &'a Node<'a>) {
+        match self.splay_at(index) {
+            None => {}
+            Some((root, cmp::Ordering::Equal)) => {
+                node.set_left(root.left.take());
+                root.fix_size();
+                node.set_right(Some(root));
+            }
+            // `index` is the length of the tree, and the maximum is the root.
+            Some((root, _)) => node.set_left(Some(root)),
+        }
+        node.fix_size();
+        self.set_root(Some(node));
+        self.inserted(node);
+    }
+
+    /// Remove the node at `index` in order, if any.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn remove_at(&mut self, index: usize) -> Option<&'a Node<'a>> {
+        match self.splay_at(index)? {
+            (_, cmp::Ordering::Equal) => self.pop_root(),
+            _ => None,
+        }
+    }
+
+    /// Move all of `other`'s nodes after this tree's nodes, in order.
+    #[inline]
+    pub fn append(&mut self, other: &mut SplayTree<'a>) {
+        let Some(other_root) = other.root.take() else {
+            return;
+        };
+        other.removed_all(Some(other_root));
+        match self.max() {
+            Some(root) => {
+                root.set_right(Some(other_root));
+                root.fix_size();
+            }
+            None => self.root = Some(other_root),
+        }
+        self.inserted_all(Some(other_root));
+    }
+
+    /// Make `node`, which must not be in any tree and must come after all of
+    /// this tree's nodes, the new root, with the old root as its left child.
+    /// This takes constant time, without comparing any keys.
+    #[inline]
+    pub fn push_max(&mut self, node: &'a Node<'a>) {
+        debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+        node.set_left(self.root);
+        node.fix_size();
+        self.set_root(Some(node));
+        self.inserted(node);
+    }
+
+    /// Detach the root's left subtree into a tree of its own.
+    #[inline]
+    pub fn take_root_left(&mut self) -> SplayTree<'a> {
+        let root = self.root.and_then(|root| {
+            let left = root.left.take();
+            root.fix_size();
+            left
+        });
+        self.removed_all(root);
+        let mut tree = SplayTree::new();
+        tree.set_root(root);
+        tree
+    }
+
+    /// Detach the root's right subtree into a tree of its own.
+    #[inline]
+    pub fn take_root_right(&mut self) -> SplayTree<'a> {
+        let root = self.root.and_then(|root| {
+            let right = root.right.take();
+            root.fix_size();
+            right
+        });
+        self.removed_all(root);
+        let mut tree = SplayTree::new();
+        tree.set_root(root);
+        tree
+    }
+
+    /// Detach the root and its left subtree into a tree of their own, leaving
+    /// the root's right subtree as this tree.
+    #[inline]
+    pub fn take_root_and_left(&mut self) -> SplayTree<'a> {
+        let mut tree = SplayTree::new();
+        if let Some(root) = self.root {
+            let right = root.right.take();
+            root.fix_size();
+            self.set_root(right);
+            self.removed_all(Some(root));
+            tree.set_root(Some(root));
+        }
+        tree
+    }
+
+    /// Detach the root and its right subtree into a tree of their own, leaving
+    /// the root's left subtree as this tree.
+    #[inline]
+    pub fn take_root_and_right(&mut self) -> SplayTree<'a> {
+        let mut tree = SplayTree::new();
+        if let Some(root) = self.root {
+            let left = root.left.take();
+            root.fix_size();
+            self.set_root(left);
+            self.removed_all(Some(root));
+            tree.set_root(Some(root));
+        }
+        tree
+    }
+
     // The "simple top-down splay" routine from the paper.
-    #[inline(never)]
-    unsafe fn splay(
+    //
+    // Returns the new root, along with how `key` compares to it, so that
+    // callers don't have to compare again.
+    #[cfg_attr(not(feature = "monomorphize"), inline(never))]
+    #[cfg_attr(feature = "monomorphize", inline)]
+    unsafe fn splay<K>(
         &mut self,
         mut current: &'a Node<'a>,
-        key: &dyn CompareToNode<'a>,
-    ) -> &'a Node<'a> {
-        let null = Node::default();
-        let mut left = &null;
-        let mut right = &null;
-
-        loop {
-            match key.compare_to_node(current) {
+        key: &K,
+    ) -> (&'a Node<'a>, cmp::Ordering)
+    where
+        K: ?Sized + CompareToNode<'a>,
+    {
+        current.check_removed();
+
+        // Repeated lookups of a hot key find it at the root, so skip setting
+        // up and reassembling the left and right trees for those.
+        let mut order = key.compare_to_node(current);
+        if order.is_eq() {
+            self.set_root(Some(current));
+            return (current, order);
+        }
+
+        // The left and right trees are built by linking nodes onto the ends of
+        // their spines, so keep track of each one's root and the end of its
+        // spine, rather than threading them through a sentinel node.
+        let mut left_root: Option<&'a Node<'a>> = None;
+        let mut left: Option<&'a Node<'a>> = None;
+        let mut right_root: Option<&'a Node<'a>> = None;
+        let mut right: Option<&'a Node<'a>> = None;
+
+        // With the `rank` feature, the sizes of everything linked into the left
+        // and right trees, not counting what ends up beneath the new root.
+        #[cfg(feature = "rank")]
+        let (mut left_size, mut right_size) = (0, 0);
+
+        // With the `watchdog` feature, how many nodes deep the splay has
+        // gone, counting the root as zero.
+        #[cfg(feature = "watchdog")]
+        let mut depth = 0;
+
+        let order = loop {
+            match order {
                 cmp::Ordering::Less => {
                     match current.left.get() {
-                        None => break,
+                        None => break cmp::Ordering::Less,
                         Some(mut current_left) => {
                             if let cmp::Ordering::Less = key.compare_to_node(current_left) {
                                 // Rotate right.
-                                current.left.set(current_left.right.get());
-                                current_left.right.set(Some(current));
+                                current.set_left(current_left.right.get());
+                                current_left.set_right(Some(current));
+                                current.fix_size();
+                                self.rotated(current_left, current);
                                 current = current_left;
+                                #[cfg(feature = "watchdog")]
+                                {
+                                    depth += 1;
+                                }
                                 match current.left.get() {
                                     Some(l) => current_left = l,
-                                    None => break,
+                                    None => break cmp::Ordering::Less,
                                 }
                             }
                             // Link right.
-                            right.left.set(Some(current));
-                            right = current;
+                            match right {
+                                Some(right) => right.left.set(Some(current)),
+                                None => right_root = Some(current),
+                            }
+                            right = Some(current);
+                            #[cfg(feature = "rank")]
+                            {
+                                right_size += 1 + Node::size_of(current.right.get());
+                            }
                             current = current_left;
                         }
                     }
                 }
                 cmp::Ordering::Greater => {
                     match current.right.get() {
-                        None => break,
+                        None => break cmp::Ordering::Greater,
                         Some(mut current_right) => {
                             if let cmp::Ordering::Greater = key.compare_to_node(current_right) {
                                 // Rotate left.
-                                current.right.set(current_right.left.get());
-                                current_right.left.set(Some(current));
+                                current.set_right(current_right.left.get());
+                                current_right.set_left(Some(current));
+                                current.fix_size();
+                                self.rotated(current_right, current);
                                 current = current_right;
+                                #[cfg(feature = "watchdog")]
+                                {
+                                    depth += 1;
+                                }
                                 match current_right.right.get() {
                                     Some(r) => current_right = r,
-                                    None => break,
+                                    None => break cmp::Ordering::Greater,
                                 }
                             }
                             // Link left.
-                            left.right.set(Some(current));
-                            left = current;
+                            match left {
+                                Some(left) => left.right.set(Some(current)),
+                                None => left_root = Some(current),
+                            }
+                            left = Some(current);
+                            #[cfg(feature = "rank")]
+                            {
+                                left_size += 1 + Node::size_of(current.left.get());
+                            }
                             current = current_right;
                         }
                     }
                 }
-                cmp::Ordering::Equal => break,
+                cmp::Ordering::Equal => break cmp::Ordering::Equal,
+            }
+            #[cfg(feature = "watchdog")]
+            {
+                depth += 1;
+            }
+            current.check_removed();
+            order = key.compare_to_node(current);
+        };
+
+        // Assemble. A side that nothing was linked into keeps the subtree
+        // that is already beneath `current`.
+        if let Some(left) = left {
+            left.right.set(current.left.get());
+            current.left.set(left_root);
+        }
+        if let Some(right) = right {
+            right.left.set(current.right.get());
+            current.right.set(right_root);
+        }
+
+        #[cfg(any(feature = "rank", feature = "parent"))]
+        {
+            // The nodes linked into the left tree form the right spine of the
+            // new root's left subtree, in the order they were linked, and each
+            // one's subtree holds everything linked after it. Likewise for the
+            // right tree and the left spine of the right subtree. Those are
+            // the only nodes whose sizes changed, and they and the subtrees
+            // hung off the ends of the spines are the only ones whose parents
+            // changed, other than within rotations.
+            if let Some(left) = left {
+                #[cfg(feature = "rank")]
+                let mut size = left_size + Node::size_of(left.right.get());
+                #[cfg(feature = "parent")]
+                let mut parent = current;
+                let mut node = current.left.get();
+                while let Some(n) = node {
+                    #[cfg(feature = "rank")]
+                    n.size.set(size);
+                    #[cfg(feature = "parent")]
+                    n.parent.set(Some(parent));
+                    if ptr::eq(n, left) {
+                        #[cfg(feature = "parent")]
+                        n.set_right(n.right.get());
+                        break;
+                    }
+                    #[cfg(feature = "rank")]
+                    {
+                        size -= 1 + Node::size_of(n.left.get());
+                    }
+                    #[cfg(feature = "parent")]
+                    {
+                        parent = n;
+                    }
+                    node = n.right.get();
+                }
+            }
+            if let Some(right) = right {
+                #[cfg(feature = "rank")]
+                let mut size = right_size + Node::size_of(right.left.get());
+                #[cfg(feature = "parent")]
+                let mut parent = current;
+                let mut node = current.right.get();
+                while let Some(n) = node {
+                    #[cfg(feature = "rank")]
+                    n.size.set(size);
+                    #[cfg(feature = "parent")]
+                    n.parent.set(Some(parent));
+                    if ptr::eq(n, right) {
+                        #[cfg(feature = "parent")]
+                        n.set_left(n.left.get());
+                        break;
+                    }
+                    #[cfg(feature = "rank")]
+                    {
+                        size -= 1 + Node::size_of(n.right.get());
+                    }
+                    #[cfg(feature = "parent")]
+                    {
+                        parent = n;
+                    }
+                    node = n.left.get();
+                }
             }
         }
+        current.fix_size();
+
+        // Callers expect the splayed node at the root, so only rebuild what
+        // is beneath it.
+        #[cfg(feature = "watchdog")]
+        if depth > self.depth_limit {
+            current.set_left(balanced(current.left.get()));
+            current.set_right(balanced(current.right.get()));
+            current.fix_size();
+        }
 
-        // Assemble.
-        left.right.set(current.left.get());
-        right.left.set(current.right.get());
-        current.left.set(null.right.get());
-        current.right.set(null.left.get());
-        self.root = Some(current);
-        current
+        self.set_root(Some(current));
+        (current, order)
+    }
+}
+
+/// Rebuild the subtree rooted at `root` into one of minimal height, and return
+/// its new root.
+///
+/// This is the Day-Stout-Warren algorithm, which takes linear time and
+/// constant space, without recursing. It relinks whole subtrees without
+/// rotating anything through the hooks, like the top-down splay does, so it
+/// reports nothing to them.
+fn balanced<'a>(root: Option<&'a Node<'a>>) -> Option<&'a Node<'a>> {
+    // Flatten the subtree into a list through its right links, in order, by
+    // rotating every left child up until there are none left.
+    let mut head = None;
+    let mut tail: Option<&'a Node<'a>> = None;
+    let mut len: usize = 0;
+    let mut rest = root;
+    while let Some(node) = rest {
+        match node.left.get() {
+            Some(left) => {
+                node.left.set(left.right.get());
+                left.right.set(Some(node));
+                rest = Some(left);
+            }
+            None => {
+                match tail {
+                    Some(tail) => tail.set_right(Some(node)),
+                    None => head = Some(node),
+                }
+                tail = Some(node);
+                len += 1;
+                rest = node.right.get();
+            }
+        }
+    }
+
+    // Every node in the list is the root of the rest of it.
+    #[cfg(feature = "rank")]
+    {
+        let mut size = len;
+        let mut rest = head;
+        while let Some(node) = rest {
+            node.size.set(size);
+            size -= 1;
+            rest = node.right.get();
+        }
+    }
+
+    // Then fold the list into a tree by rotating every other node of it to
+    // the left of its successor: first enough of them to leave a list whose
+    // length is one less than a power of two, which become the bottom level
+    // of the tree, and then half of what is left, over and over, with each
+    // pass adding a level.
+    let complete = (1usize << (usize::BITS - 1 - (len + 1).leading_zeros())) - 1;
+    compress(&mut head, len - complete);
+    let mut spine = complete;
+    while spine > 1 {
+        spine /= 2;
+        compress(&mut head, spine);
+    }
+    head
+}
+
+/// Rotate each of the first `count` even-numbered nodes of the list of right
+/// links starting at `head` to the left of its successor.
+fn compress<'a>(head: &mut Option<&'a Node<'a>>, count: usize) {
+    // The node whose right link leads to the next pair, or `None` for `head`.
+    let mut scanner: Option<&'a Node<'a>> = None;
+    for _ in 0..count {
+        let child = match scanner {
+            Some(scanner) => scanner.right.get(),
+            None => *head,
+        };
+        let Some((child, next)) = child.and_then(|child| Some((child, child.right.get()?))) else {
+            return;
+        };
+        match scanner {
+            Some(scanner) => scanner.set_right(Some(next)),
+            None => *head = Some(next),
+        }
+        child.set_right(next.left.get());
+        child.fix_size();
+        next.set_left(Some(child));
+        next.fix_size();
+        scanner = Some(next);
     }
 }
@@ -0,0 +1,512 @@
+//! In-order iteration over trees, without allocation and without restructuring
+//! the tree, and merging of two trees' in-order sequences.
+
+use crate::brand::Brand;
+use crate::{IntrusiveNode, Node, TreeOrd};
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// How many pending ancestors an `Iter` remembers.
+const STACK_SIZE: usize = 32;
+
+/// An in-order iterator over the nodes of a tree.
+///
+/// Iterating in order needs a stack of the ancestors whose right subtrees are
+/// yet to be visited, but trees can be arbitrarily deep. So this keeps only
+/// the `STACK_SIZE` deepest ancestors in a fixed-size ring buffer, and when it
+/// runs out, it recovers the forgotten ancestors by searching down from the
+/// root for the successor of the last node it yielded. For trees that are not
+/// too deep, that never happens.
+pub(crate) struct Iter<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    root: Option<&'a Node<'a>>,
+    stack: [Option<&'a Node<'a>>; STACK_SIZE],
+    // The index of the bottom of the stack within `stack`.
+    start: usize,
+    len: usize,
+    // Whether ancestors were dropped from the bottom of the stack.
+    forgot: bool,
+    last: Option<&'a Node<'a>>,
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iter<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    pub(crate) fn new(root: Option<&'a Node<'a>>) -> Self {
+        let mut iter = Iter {
+            root,
+            stack: [None; STACK_SIZE],
+            start: 0,
+            len: 0,
+            forgot: false,
+            last: None,
+            _phantom: PhantomData,
+        };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push(&mut self, node: &'a Node<'a>) {
+        if self.len == STACK_SIZE {
+            self.start = (self.start + 1) % STACK_SIZE;
+            self.len -= 1;
+            self.forgot = true;
+        }
+        self.stack[(self.start + self.len) % STACK_SIZE] = Some(node);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<&'a Node<'a>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.stack[(self.start + self.len) % STACK_SIZE].take()
+    }
+
+    fn push_left_spine(&mut self, mut node: Option<&'a Node<'a>>) {
+        while let Some(n) = node {
+            n.check_removed();
+            self.push(n);
+            node = n.left.get();
+        }
+    }
+
+    /// Rebuild the stack of pending ancestors of the last yielded node's
+    /// successor, by searching for it from the root.
+    fn recover(&mut self) {
+        self.forgot = false;
+        let Some(last) = self.last else {
+            return;
+        };
+        let last = unsafe { T::node_to_elem(last) };
+        let mut node = self.root;
+        while let Some(n) = node {
+            if last.tree_cmp(unsafe { T::node_to_elem(n) }).is_lt() {
+                self.push(n);
+                node = n.left.get();
+            } else {
+                node = n.right.get();
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a Node<'a>;
+
+    fn next(&mut self) -> Option<&'a Node<'a>> {
+        if self.len == 0 && self.forgot {
+            self.recover();
+        }
+        let node = self.pop()?;
+        self.push_left_spine(node.right.get());
+        self.last = Some(node);
+        Some(node)
+    }
+}
+
+/// An in-order iterator over the elements of a tree.
+pub(crate) struct Elems<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    brand: &'t Brand,
+    nodes: Iter<'a, T>,
+}
+
+impl<'t, 'a, T> Elems<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    pub(crate) fn new(brand: &'t Brand, root: Option<&'a Node<'a>>) -> Self {
+        Elems {
+            brand,
+            nodes: Iter::new(root),
+        }
+    }
+}
+
+impl<'t, 'a, T> Iterator for Elems<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a T::Elem;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T::Elem> {
+        let node = self.nodes.next()?;
+        Some(unsafe { self.brand.elem::<T>(node) })
+    }
+}
+
+/// An in-order iterator over raw pointers to the elements of a `SplayTree`.
+///
+/// Returned by `SplayTree::iter_ptrs`.
+pub struct ElemPtrs<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    elems: Elems<'t, 'a, T>,
+}
+
+impl<'t, 'a, T> ElemPtrs<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(elems: Elems<'t, 'a, T>) -> Self {
+        ElemPtrs { elems }
+    }
+}
+
+impl<'t, 'a, T> Iterator for ElemPtrs<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = *const T::Elem;
+
+    #[inline]
+    fn next(&mut self) -> Option<*const T::Elem> {
+        self.elems.next().map(|elem| elem as *const T::Elem)
+    }
+}
+
+impl<'t, 'a, T> FusedIterator for ElemPtrs<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, T> fmt::Debug for ElemPtrs<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ElemPtrs").finish_non_exhaustive()
+    }
+}
+
+/// An item yielded by `MergeIter`.
+pub enum Merged<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    /// An element that is only in the left tree.
+    Left(&'a T::Elem),
+
+    /// An element that is only in the right tree.
+    Right(&'a T::Elem),
+
+    /// An element of the left tree, and the equal element of the right tree.
+    Both(&'a T::Elem, &'a T::Elem),
+}
+
+impl<'a, T> Clone for Merged<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Merged<'a, T> where T: 'a + IntrusiveNode<'a> {}
+
+impl<'a, T> fmt::Debug for Merged<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Merged::Left(a) => f.debug_tuple("Left").field(a).finish(),
+            Merged::Right(b) => f.debug_tuple("Right").field(b).finish(),
+            Merged::Both(a, b) => f.debug_tuple("Both").field(a).field(b).finish(),
+        }
+    }
+}
+
+/// An iterator that walks two `SplayTree`s in order at the same time, pairing
+/// up their equal elements.
+///
+/// Returned by `SplayTree::merge_iter`.
+pub struct MergeIter<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    left: Elems<'t, 'a, T>,
+    right: Elems<'t, 'a, T>,
+    next_left: Option<&'a T::Elem>,
+    next_right: Option<&'a T::Elem>,
+}
+
+impl<'t, 'a, T> MergeIter<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    pub(crate) fn new(mut left: Elems<'t, 'a, T>, mut right: Elems<'t, 'a, T>) -> Self {
+        MergeIter {
+            next_left: left.next(),
+            next_right: right.next(),
+            left,
+            right,
+        }
+    }
+
+    #[inline]
+    fn take_left(&mut self) -> Option<&'a T::Elem> {
+        let a = self.next_left.take()?;
+        self.next_left = self.left.next();
+        Some(a)
+    }
+
+    #[inline]
+    fn take_right(&mut self) -> Option<&'a T::Elem> {
+        let b = self.next_right.take()?;
+        self.next_right = self.right.next();
+        Some(b)
+    }
+}
+
+impl<'t, 'a, T> Iterator for MergeIter<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = Merged<'a, T>;
+
+    fn next(&mut self) -> Option<Merged<'a, T>> {
+        let order = match (self.next_left, self.next_right) {
+            (None, None) => return None,
+            (Some(_), None) => cmp::Ordering::Less,
+            (None, Some(_)) => cmp::Ordering::Greater,
+            (Some(a), Some(b)) => a.tree_cmp(b),
+        };
+        match order {
+            cmp::Ordering::Less => self.take_left().map(Merged::Left),
+            cmp::Ordering::Greater => self.take_right().map(Merged::Right),
+            cmp::Ordering::Equal => {
+                let a = self.take_left()?;
+                let b = self.take_right()?;
+                Some(Merged::Both(a, b))
+            }
+        }
+    }
+}
+
+impl<'t, 'a, T> FusedIterator for MergeIter<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, T> fmt::Debug for MergeIter<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MergeIter")
+            .field("next_left", &self.next_left)
+            .field("next_right", &self.next_right)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An item yielded by `Diff`, comparing an old tree to a new one.
+pub enum DiffEntry<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    /// An element that is only in the old tree.
+    Removed(&'a T::Elem),
+
+    /// An element that is only in the new tree.
+    Added(&'a T::Elem),
+
+    /// An element of the old tree, and the equal element of the new tree.
+    Kept(&'a T::Elem, &'a T::Elem),
+}
+
+impl<'a, T> Clone for DiffEntry<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for DiffEntry<'a, T> where T: 'a + IntrusiveNode<'a> {}
+
+impl<'a, T> fmt::Debug for DiffEntry<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffEntry::Removed(a) => f.debug_tuple("Removed").field(a).finish(),
+            DiffEntry::Added(b) => f.debug_tuple("Added").field(b).finish(),
+            DiffEntry::Kept(a, b) => f.debug_tuple("Kept").field(a).field(b).finish(),
+        }
+    }
+}
+
+/// An iterator over the differences between two `SplayTree`s, in order.
+///
+/// Returned by `SplayTree::diff`.
+pub struct Diff<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    merge: MergeIter<'t, 'a, T>,
+}
+
+impl<'t, 'a, T> Diff<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    pub(crate) fn new(merge: MergeIter<'t, 'a, T>) -> Self {
+        Diff { merge }
+    }
+}
+
+impl<'t, 'a, T> Iterator for Diff<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = DiffEntry<'a, T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<DiffEntry<'a, T>> {
+        Some(match self.merge.next()? {
+            Merged::Left(a) => DiffEntry::Removed(a),
+            Merged::Right(b) => DiffEntry::Added(b),
+            Merged::Both(a, b) => DiffEntry::Kept(a, b),
+        })
+    }
+}
+
+impl<'t, 'a, T> FusedIterator for Diff<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, T> fmt::Debug for Diff<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Diff").field("merge", &self.merge).finish()
+    }
+}
+
+/// An iterator over the elements of a `SplayTree` whose ranks are in a given
+/// range, in order.
+///
+/// Returned by `SplayTree::select_range`.
+#[cfg(feature = "rank")]
+pub struct SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    first: Option<&'a T::Elem>,
+    rest: Elems<'t, 'a, T>,
+    len: usize,
+}
+
+#[cfg(feature = "rank")]
+impl<'t, 'a, T> SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    /// Yield `first` and then the first `len - 1` elements of `rest`.
+    #[inline]
+    pub(crate) fn new(first: Option<&'a T::Elem>, rest: Elems<'t, 'a, T>, len: usize) -> Self {
+        SelectRange { first, rest, len }
+    }
+}
+
+#[cfg(feature = "rank")]
+impl<'t, 'a, T> Iterator for SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a T::Elem;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T::Elem> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        match self.first.take() {
+            Some(first) => Some(first),
+            None => self.rest.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+#[cfg(feature = "rank")]
+impl<'t, 'a, T> ExactSizeIterator for SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+#[cfg(feature = "rank")]
+impl<'t, 'a, T> FusedIterator for SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+#[cfg(feature = "rank")]
+impl<'t, 'a, T> fmt::Debug for SelectRange<'t, 'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SelectRange")
+            .field("first", &self.first)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
@@ -3,27 +3,226 @@
 #![deny(missing_debug_implementations)]
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod arena;
+#[cfg(feature = "avl")]
+mod avl;
+mod batch;
+mod brand;
+mod cache;
+mod comparator;
+mod cursor;
+mod deadline;
+mod display;
+mod drain;
+mod entry;
+mod error;
+mod family;
+mod freelist;
+mod frozen;
+mod guard;
+#[cfg(feature = "hooks")]
+mod hooks;
+#[cfg(feature = "bumpalo")]
+mod intern;
+// Reachable through `__internal`, but not part of the documented API.
+#[allow(missing_docs)]
 mod internal;
+mod iter;
+mod link_cut;
+mod lru;
+mod map;
+mod min_gap;
 mod node;
+mod order;
+mod overhead;
+#[cfg(feature = "bumpalo")]
+mod owned;
+mod persistent;
+mod pool;
+mod prefix;
+mod range_set;
+#[cfg(feature = "rank")]
+mod sequence;
+mod sharded;
+#[cfg(all(
+    feature = "shared",
+    any(target_has_atomic = "8", feature = "portable-atomic")
+))]
+mod shared;
+mod slot;
+#[cfg(any(target_has_atomic = "8", feature = "portable-atomic"))]
+mod statics;
+mod transaction;
+mod treap;
+#[cfg(kani)]
+mod verification;
+mod weight;
 
+pub use arena::ElemArena;
+#[cfg(feature = "avl")]
+pub use avl::AvlTree;
+pub use batch::{BatchOp, FindMany, InsertReport, RemoveMany};
+pub use cache::{EvictionPolicy, LargestKey, LeastRecent, SmallestKey, SplayCache};
+pub use comparator::{Comparator, ComparatorTree};
+pub use cursor::Cursor;
+pub use deadline::{DeadlineQueue, Expired, IntrusiveDeadline};
+pub use display::DisplaySeparated;
+pub use drain::PopWhile;
+pub use entry::OccupiedEntry;
+pub use error::Error;
+pub use family::{FamilyElem, TreeFamily};
+pub use freelist::{FreeBlock, FreeList};
+pub use frozen::FrozenView;
+pub use guard::InsertGuard;
+#[cfg(feature = "hooks")]
+pub use hooks::SplayHooks;
+#[cfg(feature = "bumpalo")]
+pub use intern::InternSet;
+#[cfg(feature = "rank")]
+pub use iter::SelectRange;
+pub use iter::{Diff, DiffEntry, ElemPtrs, MergeIter, Merged};
+pub use link_cut::{IntrusiveVertex, LinkCutNode, LinkCutTree};
+pub use lru::{IntrusiveLruNode, SplayLru};
+pub use map::{Keys, SplayMapAdapter, Values};
+pub use min_gap::{IntrusiveGap, MinGapSet};
 pub use node::Node;
+pub use order::{
+    Descending, F32TotalOrd, F64TotalOrd, IntrusiveSerial, LexicographicKey, Serial16, Serial32,
+    Serial64,
+};
+pub use overhead::MetadataOverhead;
+#[cfg(feature = "bumpalo")]
+pub use owned::OwnedSplayTree;
+pub use persistent::{PersistentNode, PersistentTree};
+pub use pool::{Full, StaticSplaySet};
+pub use prefix::{IntrusivePrefix, PrefixBits};
+pub use range_set::{IntrusiveRange, RangeSet};
+#[cfg(feature = "rank")]
+pub use sequence::SplaySequence;
+pub use sharded::{ShardKey, ShardedIter, ShardedSplayTree};
+#[cfg(all(
+    feature = "shared",
+    any(target_has_atomic = "8", feature = "portable-atomic")
+))]
+pub use shared::{RetireFlag, SharedNode, SharedSplayTree};
+pub use slot::{SlotNode, SlotOrd, SlotSplayTree, SlotTree};
+#[cfg(any(target_has_atomic = "8", feature = "portable-atomic"))]
+pub use statics::StaticElems;
+pub use transaction::Transaction;
+pub use treap::{ByAddress, ByWeight, Treap, TreapPriority, TreapWeight};
+pub use weight::SplayWeight;
+
+#[cfg(feature = "derive")]
+pub use intrusive_splay_tree_derive::TreeOrd;
+
+/// The type-erased tree implementation, exposed for the
+/// `intrusive-splay-tree-capi` crate and `export_splay_tree_c_api!`. This is
+/// not part of the public API and may change in any release.
+#[doc(hidden)]
+pub mod __internal {
+    pub use crate::internal::{CompareToNode, SplayTree};
+
+    /// The size of a `SplayTree`, as `export_splay_tree_c_api!` documents it
+    /// for C: a root pointer, and a word each for the `paranoid` feature's
+    /// brand and the `watchdog` feature's depth limit.
+    pub const C_API_TREE_SIZE: usize = core::mem::size_of::<usize>()
+        * (1 + cfg!(feature = "paranoid") as usize + cfg!(feature = "watchdog") as usize);
+}
 
+use brand::Brand;
+use core::borrow::Borrow;
 use core::cmp;
 use core::fmt;
-use core::iter;
+use core::iter::FromIterator;
 use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ops::Index;
+#[cfg(feature = "rank")]
+use core::ops::{Bound, RangeBounds};
+use core::ptr;
+use core::slice;
 
 /// Defines how to get the intrusive node from a particular kind of
 /// `SplayTree`'s element type.
 ///
 /// Don't implement this by hand -- doing so is both boring and dangerous!
 /// Instead, use the `impl_intrusive_node!` macro.
+///
+/// The exception is a dynamically sized element, like a header followed by a
+/// slice, which the macro doesn't support. Its node must be in the sized
+/// header, and since a pointer to the node doesn't carry the length that a
+/// reference to the whole element needs, the element must store that too, for
+/// `node_to_elem` to read back:
+///
+/// ```
+/// use intrusive_splay_tree::{IntrusiveNode, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+/// use std::{mem, ptr};
+///
+/// #[repr(C)]
+/// struct Record<'a, D: ?Sized = [u8]> {
+///     len: usize,
+///     node: Node<'a>,
+///     data: D,
+/// }
+///
+/// struct Records;
+///
+/// unsafe impl<'a> IntrusiveNode<'a> for Records {
+///     type Elem = Record<'a>;
+///
+///     fn elem_to_node(elem: &'a Record<'a>) -> &'a Node<'a> {
+///         &elem.node
+///     }
+///
+///     unsafe fn node_to_elem(node: &'a Node<'a>) -> &'a Record<'a> {
+///         let offset = mem::offset_of!(Record<'a>, node);
+///         let elem = (node as *const Node<'a>).cast::<u8>().wrapping_sub(offset);
+///         let len = *elem.cast::<usize>();
+///         &*(ptr::slice_from_raw_parts(elem, len) as *const Record<'a>)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, Records> for Record<'a> {
+///     fn tree_cmp(&self, rhs: &'a Record<'a>) -> Ordering {
+///         self.data.cmp(&rhs.data)
+///     }
+/// }
+///
+/// // `len` must be the length of the data, which unsizing then erases.
+/// let record = Record { len: 3, node: Node::new(), data: *b"abc" };
+/// let mut tree = SplayTree::<Records>::new();
+/// tree.insert(&record);
+/// assert_eq!(tree.pop_min().unwrap().data, *b"abc");
+/// ```
+///
+/// Everything that only borrows elements works with unsized ones. What owns
+/// or allocates them, like `ElemArena` and `TreeFamily`, needs them sized.
+///
+/// ## Trees that order themselves
+///
+/// Only the `SplayTree` methods that compare elements with each other, like
+/// `insert`, need the element type to implement `TreeOrd` for the tree type.
+/// That includes the ones that iterate without recursing, like `fold`, since
+/// their iterators search for their place again when a tree is too deep for
+/// them to remember every ancestor. Containers that order their elements some
+/// other way, like `ComparatorTree`, `DeadlineQueue`, `MinGapSet`, and
+/// `SplaySequence`, don't need it at all.
+///
+/// ## Safety
+///
+/// `elem_to_node` must always return the same `Node` field of the given
+/// element, and `node_to_elem` must be its exact inverse. No other tree type
+/// may use that same `Node` field.
 pub unsafe trait IntrusiveNode<'a>
 where
     Self: Sized,
 {
     /// The element struct type that contains a node for this tree.
-    type Elem: TreeOrd<'a, Self>;
+    type Elem: ?Sized;
 
     /// Get the node for this tree from the given element.
     fn elem_to_node(elem: &'a Self::Elem) -> &'a Node<'a>;
@@ -42,36 +241,544 @@ where
 
 /// Implement `IntrusiveNode` for a particular kind of `SplayTree` and its
 /// element type.
+///
+/// Any generic parameters may be used, including const generics. Bounds on
+/// them go in the `where` clause, before the `type Elem` line. Several trees
+/// may be implemented in a single invocation.
+///
+/// The `node` may also be a path through structs that are embedded in the
+/// element, like `node = header.by_id`, for elements that keep their nodes in
+/// a common header. Every field along the path must be stored inline, not
+/// behind a pointer.
+///
+/// The generated `node_to_elem` is a constant offset subtraction, with no
+/// panic paths even in debug builds, so it doesn't pull panic or formatting
+/// machinery into embedded binaries.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+/// use std::marker::PhantomData;
+///
+/// struct Record<'a, T, const N: usize> {
+///     values: [T; N],
+///     by_first: Node<'a>,
+///     by_last: Node<'a>,
+/// }
+///
+/// struct ByFirst<'a, T, const N: usize>(PhantomData<&'a [T; N]>);
+/// struct ByLast<'a, T, const N: usize>(PhantomData<&'a [T; N]>);
+///
+/// impl_intrusive_node! {
+///     impl<'a, T, const N: usize> IntrusiveNode<'a> for ByFirst<'a, T, N>
+///     where
+///         T: 'a + Ord,
+///         type Elem = Record<'a, T, N>,
+///         node = by_first;
+///
+///     impl<'a, T, const N: usize> IntrusiveNode<'a> for ByLast<'a, T, N>
+///     where
+///         T: 'a + Ord,
+///         type Elem = Record<'a, T, N>,
+///         node = by_last;
+/// }
+///
+/// impl<'a, T: 'a + Ord, const N: usize> TreeOrd<'a, ByFirst<'a, T, N>> for Record<'a, T, N> {
+///     fn tree_cmp(&self, rhs: &Record<'a, T, N>) -> Ordering {
+///         self.values.first().cmp(&rhs.values.first())
+///     }
+/// }
+///
+/// impl<'a, T: 'a + Ord, const N: usize> TreeOrd<'a, ByLast<'a, T, N>> for Record<'a, T, N> {
+///     fn tree_cmp(&self, rhs: &Record<'a, T, N>) -> Ordering {
+///         self.values.last().cmp(&rhs.values.last())
+///     }
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut by_last = SplayTree::<ByLast<u32, 2>>::new();
+/// for values in [[1, 5], [2, 3]] {
+///     by_last.insert(arena.alloc(Record {
+///         values,
+///         by_first: Node::default(),
+///         by_last: Node::default(),
+///     }));
+/// }
+/// assert_eq!(by_last.min().unwrap().values, [2, 3]);
+/// ```
 #[macro_export]
 macro_rules! impl_intrusive_node {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_intrusive_node [] $( $rest )*);
+    };
     (
-        impl< $($typarams:tt),* >
-            IntrusiveNode<$intrusive_node_lifetime:tt>
-            for $tree:ty
+        @generics [ $( $params:tt )* ]
+        IntrusiveNode< $lt:lifetime > for $tree:ty
         where
-            type Elem = $elem:ty ,
-            node = $node:ident ;
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_node!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `type Elem`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        type Elem = $elem:ty ,
+        node = $( $node:ident ).+ ;
+        $( $rest:tt )*
     ) => {
-        unsafe impl< $( $typarams )* > $crate::IntrusiveNode<$intrusive_node_lifetime> for $tree {
+        unsafe impl< $( $params )* > $crate::IntrusiveNode< $lt > for $tree
+        where
+            $( $bounds )*
+        {
             type Elem = $elem;
 
-            fn elem_to_node(
-                elem: & $intrusive_node_lifetime Self::Elem
-            ) -> & $intrusive_node_lifetime $crate::Node< $intrusive_node_lifetime > {
-                &elem. $node
+            fn elem_to_node(elem: & $lt Self::Elem) -> & $lt $crate::Node< $lt > {
+                &elem $( . $node )+
             }
 
-            unsafe fn node_to_elem(
-                node: & $intrusive_node_lifetime $crate::Node< $intrusive_node_lifetime >
-            ) -> & $intrusive_node_lifetime Self::Elem {
-                let offset = ::core::mem::offset_of!(Self::Elem, $node);
+            unsafe fn node_to_elem(node: & $lt $crate::Node< $lt >) -> & $lt Self::Elem {
+                let offset = ::core::mem::offset_of!(Self::Elem, $( $node ).+);
 
                 let node = node as *const _ as *const u8;
-                let elem = node.offset(-(offset as isize)) as *const Self::Elem;
+                // Unlike `offset`, `wrapping_sub` has no overflow or pointer
+                // precondition checks in debug builds, so there is no panic
+                // path here. `node` is within an element, so it can't wrap.
+                let elem = node.wrapping_sub(offset) as *const Self::Elem;
                 &*elem
             }
         }
-    }
+
+        $crate::impl_intrusive_node!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_node!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
+/// Munch the generic parameters of an `impl< ... >` up to its closing `>`,
+/// which is the one followed by the implemented trait, for the `impl_*!`
+/// macros. Calls `$callback!(@generics [ params ] Trait< ...)` with the rest.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __munch_impl_generics {
+    (
+        $callback:ident [ $( $params:tt )* ] > $trait:ident < $( $rest:tt )*
+    ) => {
+        $crate::$callback!(@generics [ $( $params )* ] $trait < $( $rest )*);
+    };
+    (
+        $callback:ident [ $( $params:tt )* ] $next:tt $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!($callback [ $( $params )* $next ] $( $rest )*);
+    };
+}
+
+/// Implement `TreeOrd` for a tree's element type and for its key type, where
+/// elements are ordered by a single field.
+///
+/// This generates the same pair of implementations as `#[derive(TreeOrd)]`,
+/// without a proc-macro dependency: one that compares two elements' fields,
+/// and one that compares a key to an element's field, so that the tree can be
+/// queried by key. The key type must implement `Ord`, and can't be one of the
+/// generic parameters, since the orphan rules don't allow implementing
+/// `TreeOrd` for it. As with `impl_intrusive_node!`, bounds on generic
+/// parameters go in the `where` clause, the field may be a path through
+/// embedded structs, and several trees may be implemented in a single
+/// invocation.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, impl_tree_ord_by_field, Node, SplayTree};
+///
+/// struct Monster<'a> {
+///     name: &'static str,
+///     health: u64,
+///     by_health: Node<'a>,
+/// }
+///
+/// struct MonstersByHealth;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for MonstersByHealth
+///     where
+///         type Elem = Monster<'a>,
+///         node = by_health;
+/// }
+///
+/// impl_tree_ord_by_field! {
+///     impl<'a> TreeOrd<'a, MonstersByHealth> for Monster<'a>
+///     where
+///         field = health: u64;
+/// }
+///
+/// let monsters = [("Godzilla", 2000), ("Vegeta", 9001)]
+///     .map(|(name, health)| Monster { name, health, by_health: Node::default() });
+/// let mut tree = SplayTree::<MonstersByHealth>::new();
+/// for monster in &monsters {
+///     tree.insert(monster);
+/// }
+/// assert_eq!(tree.find(&9001).unwrap().name, "Vegeta");
+/// assert_eq!(tree.min().unwrap().name, "Godzilla");
+/// ```
+#[macro_export]
+macro_rules! impl_tree_ord_by_field {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_tree_ord_by_field [] $( $rest )*);
+    };
+    (
+        @generics [ $( $params:tt )* ]
+        TreeOrd< $lt:lifetime , $tree:ty > for $elem:ty
+        where
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_tree_ord_by_field!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $elem ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `field`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $elem:ty ] [ $( $bounds:tt )* ]
+        field = $( $field:ident ).+ : $key:ty ;
+        $( $rest:tt )*
+    ) => {
+        impl< $( $params )* > $crate::TreeOrd< $lt, $tree > for $elem
+        where
+            $( $bounds )*
+        {
+            #[inline]
+            fn tree_cmp(&self, elem: & $lt $elem) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(&self $( . $field )+, &elem $( . $field )+)
+            }
+        }
+
+        impl< $( $params )* > $crate::TreeOrd< $lt, $tree > for $key
+        where
+            $( $bounds )*
+        {
+            #[inline]
+            fn tree_cmp(&self, elem: & $lt $elem) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(self, &elem $( . $field )+)
+            }
+        }
+
+        $crate::impl_tree_ord_by_field!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $elem:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_tree_ord_by_field!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $elem ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
+/// Declare an element struct that lives in several trees at once, along with
+/// a marker type for each tree.
+///
+/// For every `index` line, this macro:
+///
+/// * adds an intrusive `Node` field with the given name to the struct,
+/// * defines a unit struct marker type for the tree,
+/// * implements `IntrusiveNode` for the marker type, and
+/// * implements `TreeOrd` for both the element and the key type, ordering by
+///   the given key field.
+///
+/// It also defines a `new` constructor that takes the struct's own fields, in
+/// order, and initializes all of the intrusive nodes.
+///
+/// ```
+/// use intrusive_splay_tree::{declare_multi_index, SplayTree};
+///
+/// declare_multi_index! {
+///     #[derive(Debug)]
+///     pub struct Monster<'a> {
+///         pub name: &'static str,
+///         pub health: u64,
+///     }
+///
+///     /// Monsters ordered by name.
+///     pub index MonstersByName(by_name_node) by name: &'static str;
+///
+///     /// Monsters ordered by health.
+///     pub index MonstersByHealth(by_health_node) by health: u64;
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut by_name = SplayTree::<MonstersByName>::new();
+/// let mut by_health = SplayTree::<MonstersByHealth>::new();
+///
+/// for (name, health) in [("Godzilla", 2000), ("Vegeta", 9001)] {
+///     let monster = arena.alloc(Monster::new(name, health));
+///     by_name.insert(monster);
+///     by_health.insert(monster);
+/// }
+///
+/// assert_eq!(by_name.find(&"Vegeta").unwrap().health, 9001);
+/// assert_eq!(by_health.find(&2000).unwrap().name, "Godzilla");
+/// ```
+#[macro_export]
+macro_rules! declare_multi_index {
+    (
+        $( #[$attr:meta] )*
+        $vis:vis struct $elem:ident < $lt:lifetime > {
+            $(
+                $( #[$field_attr:meta] )*
+                $field_vis:vis $field:ident : $field_ty:ty
+            ),* $(,)?
+        }
+
+        $(
+            $( #[$index_attr:meta] )*
+            $index_vis:vis index $tree:ident ( $node:ident ) by $key:ident : $key_ty:ty ;
+        )*
+    ) => {
+        $( #[$attr] )*
+        $vis struct $elem< $lt > {
+            $(
+                $( #[$field_attr] )*
+                $field_vis $field : $field_ty,
+            )*
+            $(
+                $index_vis $node : $crate::Node< $lt >,
+            )*
+        }
+
+        impl< $lt > $elem< $lt > {
+            /// Construct a new element that is not yet in any tree.
+            #[allow(clippy::too_many_arguments)]
+            $vis fn new( $( $field : $field_ty ),* ) -> Self {
+                $elem {
+                    $( $field, )*
+                    $( $node : $crate::Node::default(), )*
+                }
+            }
+        }
+
+        $(
+            $( #[$index_attr] )*
+            $index_vis struct $tree;
+
+            $crate::impl_intrusive_node! {
+                impl< $lt > IntrusiveNode< $lt > for $tree
+                where
+                    type Elem = $elem< $lt >,
+                    node = $node;
+            }
+
+            $crate::impl_tree_ord_by_field! {
+                impl< $lt > TreeOrd< $lt, $tree > for $elem< $lt >
+                where
+                    field = $key : $key_ty;
+            }
+        )*
+    };
+}
+
+/// Generate `#[no_mangle] extern "C"` functions for using a `SplayTree` from
+/// C.
+///
+/// For each tree type, this generates the following functions, with the given
+/// names:
+///
+/// * `new`: takes a `*mut SplayTree<'static, Tree>` and initializes an empty
+///   tree there. C should treat the tree as opaque storage for an array of
+///   `uintptr_t`, aligned like one: a single element, plus one more with the
+///   `paranoid` feature and one more with the `watchdog` feature. These sizes
+///   are checked at compile time.
+///
+/// * `insert`: takes a `*mut SplayTree` and a `*const Tree::Elem` and returns
+///   whether the element was inserted, like `SplayTree::insert`.
+///
+/// * `find` and `remove`: take a `*mut SplayTree` and a key, passed by value,
+///   and return a `*const Tree::Elem` that is null if there was no matching
+///   element, like `SplayTree::find` and `SplayTree::remove`.
+///
+/// The key type must implement `TreeOrd` for the tree and be FFI-safe, for
+/// example a primitive integer or a `#[repr(transparent)]` wrapper around one.
+/// All trees and elements have the `'static` lifetime, since C has no way to
+/// express anything shorter.
+///
+/// This macro is a compile error with the `hooks` feature, since a tree's
+/// hooks are a Rust trait object, which C can't hold.
+///
+#[cfg_attr(not(feature = "hooks"), doc = "```")]
+#[cfg_attr(feature = "hooks", doc = "```compile_fail")]
+/// use intrusive_splay_tree::{export_splay_tree_c_api, impl_intrusive_node, Node};
+/// use intrusive_splay_tree::{SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// #[repr(C)]
+/// pub struct Monster<'a> {
+///     id: u32,
+///     health: u32,
+///     by_id: Node<'a>,
+/// }
+///
+/// pub struct MonstersById;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for MonstersById
+///     where
+///         type Elem = Monster<'a>,
+///         node = by_id;
+/// }
+///
+/// impl<'a> TreeOrd<'a, MonstersById> for Monster<'a> {
+///     fn tree_cmp(&self, elem: &'a Monster<'a>) -> Ordering {
+///         self.id.cmp(&elem.id)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, MonstersById> for u32 {
+///     fn tree_cmp(&self, elem: &'a Monster<'a>) -> Ordering {
+///         self.cmp(&elem.id)
+///     }
+/// }
+///
+/// export_splay_tree_c_api! {
+///     type Tree = MonstersById, key = u32;
+///     fn new = monsters_by_id_new;
+///     fn insert = monsters_by_id_insert;
+///     fn find = monsters_by_id_find;
+///     fn remove = monsters_by_id_remove;
+/// }
+///
+/// let monster = Box::leak(Box::new(Monster {
+///     id: 42,
+///     health: 100,
+///     by_id: Node::default(),
+/// }));
+///
+/// unsafe {
+///     let mut tree = std::mem::MaybeUninit::uninit();
+///     monsters_by_id_new(tree.as_mut_ptr());
+///     let tree = tree.as_mut_ptr();
+///     assert!(monsters_by_id_insert(tree, monster));
+///     assert_eq!((*monsters_by_id_find(tree, 42)).health, 100);
+///     assert!(monsters_by_id_find(tree, 7).is_null());
+///     assert_eq!(monsters_by_id_remove(tree, 42), monster as *const _);
+///     assert!((*tree).is_empty());
+/// }
+/// ```
+#[macro_export]
+macro_rules! export_splay_tree_c_api {
+    (
+        $(
+            type Tree = $tree:ty , key = $key:ty ;
+            fn new = $new:ident ;
+            fn insert = $insert:ident ;
+            fn find = $find:ident ;
+            fn remove = $remove:ident ;
+        )*
+    ) => {
+        $crate::__c_api_supported!();
+
+        $(
+            const _: () = {
+                type Tree = $crate::SplayTree<'static, $tree>;
+                assert!(::core::mem::size_of::<Tree>() == $crate::__internal::C_API_TREE_SIZE);
+                assert!(::core::mem::align_of::<Tree>() == ::core::mem::align_of::<usize>());
+            };
+
+            /// Initialize an empty tree at `out`.
+            ///
+            /// # Safety
+            ///
+            /// `out` must be valid for writes of a tree, and aligned for one.
+            /// A tree that is already there is overwritten without removing
+            /// its elements.
+            #[no_mangle]
+            pub unsafe extern "C" fn $new(out: *mut $crate::SplayTree<'static, $tree>) {
+                out.write($crate::SplayTree::new());
+            }
+
+            /// Insert an element into the tree, returning whether it was
+            /// inserted.
+            ///
+            /// # Safety
+            ///
+            /// `tree` must point to a valid tree from the corresponding `new`
+            /// function, and `elem` must point to an element that lives for
+            /// the rest of the program and is not already in a tree of this
+            /// type.
+            #[no_mangle]
+            pub unsafe extern "C" fn $insert(
+                tree: *mut $crate::SplayTree<'static, $tree>,
+                elem: *const <$tree as $crate::IntrusiveNode<'static>>::Elem,
+            ) -> bool {
+                (*tree).insert(&*elem)
+            }
+
+            /// Find the element matching `key`, or null if there is none.
+            ///
+            /// # Safety
+            ///
+            /// `tree` must point to a valid tree from the corresponding `new`
+            /// function.
+            #[no_mangle]
+            pub unsafe extern "C" fn $find(
+                tree: *mut $crate::SplayTree<'static, $tree>,
+                key: $key,
+            ) -> *const <$tree as $crate::IntrusiveNode<'static>>::Elem {
+                (*tree)
+                    .find(&key)
+                    .map_or(::core::ptr::null(), |elem| elem as *const _)
+            }
+
+            /// Remove and return the element matching `key`, or null if there
+            /// is none.
+            ///
+            /// # Safety
+            ///
+            /// `tree` must point to a valid tree from the corresponding `new`
+            /// function.
+            #[no_mangle]
+            pub unsafe extern "C" fn $remove(
+                tree: *mut $crate::SplayTree<'static, $tree>,
+                key: $key,
+            ) -> *const <$tree as $crate::IntrusiveNode<'static>>::Elem {
+                (*tree)
+                    .remove(&key)
+                    .map_or(::core::ptr::null(), |elem| elem as *const _)
+            }
+        )*
+    };
+}
+
+#[cfg(not(feature = "hooks"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __c_api_supported {
+    () => {};
+}
+
+#[cfg(feature = "hooks")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __c_api_supported {
+    () => {
+        ::core::compile_error!(
+            "`export_splay_tree_c_api!` can't be used with the `hooks` feature, \
+             since a tree's hooks are a Rust trait object, which C can't hold"
+        );
+    };
 }
 
 /// A total ordering between the `Self` type and the tree's element type
@@ -86,12 +793,147 @@ pub trait TreeOrd<'a, T: IntrusiveNode<'a>> {
     fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering;
 }
 
+/// Order a tree's elements by a single key that is extracted from each
+/// element.
+///
+/// Implementing `TreeKey` for a tree type `T` provides a blanket `TreeOrd<T>`
+/// implementation for every `Q: Ord` that the key type `Borrow`s as, in the
+/// same way that `std`'s maps allow lookups by borrowed keys. For example, a
+/// tree keyed by `String` can be queried with a `str`.
+///
+/// Coherence rules prevent a second blanket implementation for the element
+/// type itself, so use `impl_tree_key!`, which implements `TreeKey` along
+/// with the element's `TreeOrd`, rather than implementing this by hand.
+pub trait TreeKey<'a>: IntrusiveNode<'a> {
+    /// The type of key that the tree's elements are ordered by.
+    type Key: ?Sized + Ord;
+
+    /// Get the given element's key.
+    fn key(elem: &Self::Elem) -> &Self::Key;
+}
+
+impl<'a, T, Q> TreeOrd<'a, T> for Q
+where
+    T: TreeKey<'a>,
+    T::Key: Borrow<Q>,
+    Q: ?Sized + Ord,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+        self.cmp(T::key(elem).borrow())
+    }
+}
+
+/// Implement `TreeKey` for a tree type, and `TreeOrd` for its element type,
+/// where elements are ordered by a single field.
+///
+/// The tree can then be queried by the key type or anything it `Borrow`s as.
+/// As with `impl_intrusive_node!`, bounds on generic parameters go in the
+/// `where` clause, the field may be a path through embedded structs, and
+/// several trees may be implemented in a single invocation.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, impl_tree_key, Node, SplayTree};
+///
+/// struct User<'a> {
+///     name: String,
+///     node: Node<'a>,
+/// }
+///
+/// struct UsersByName;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for UsersByName
+///     where
+///         type Elem = User<'a>,
+///         node = node;
+/// }
+///
+/// impl_tree_key! {
+///     impl<'a> TreeKey<'a> for UsersByName
+///     where
+///         type Elem = User<'a>,
+///         key = name: String;
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut tree = SplayTree::<UsersByName>::new();
+/// tree.insert(arena.alloc(User { name: "bob".into(), node: Node::default() }));
+/// tree.insert(arena.alloc(User { name: "alice".into(), node: Node::default() }));
+///
+/// // Query by `str`, because `String: Borrow<str>`.
+/// assert!(tree.find("alice").is_some());
+/// assert!(tree.find("carol").is_none());
+/// assert_eq!(tree.min().unwrap().name, "alice");
+/// ```
+#[macro_export]
+macro_rules! impl_tree_key {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_tree_key [] $( $rest )*);
+    };
+    (
+        @generics [ $( $params:tt )* ]
+        TreeKey< $lt:lifetime > for $tree:ty
+        where
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_tree_key!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `type Elem`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        type Elem = $elem:ty ,
+        key = $( $field:ident ).+ : $key:ty ;
+        $( $rest:tt )*
+    ) => {
+        impl< $( $params )* > $crate::TreeKey< $lt > for $tree
+        where
+            $( $bounds )*
+        {
+            type Key = $key;
+
+            #[inline]
+            fn key(elem: &Self::Elem) -> &$key {
+                &elem $( . $field )+
+            }
+        }
+
+        impl< $( $params )* > $crate::TreeOrd< $lt, $tree > for $elem
+        where
+            $( $bounds )*
+        {
+            #[inline]
+            fn tree_cmp(&self, elem: & $lt $elem) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(&self $( . $field )+, &elem $( . $field )+)
+            }
+        }
+
+        $crate::impl_tree_key!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_tree_key!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
 struct Query<'a, 'b, K, T>
 where
     T: 'a + IntrusiveNode<'a>,
     K: 'b + ?Sized + TreeOrd<'a, T>,
 {
     key: &'b K,
+    brand: &'b Brand,
     _phantom: PhantomData<&'a T>,
 }
 
@@ -101,9 +943,10 @@ where
     K: 'b + ?Sized + TreeOrd<'a, T>,
 {
     #[inline]
-    fn new(key: &'b K) -> Query<'a, 'b, K, T> {
+    fn new(key: &'b K, brand: &'b Brand) -> Query<'a, 'b, K, T> {
         Query {
             key,
+            brand,
             _phantom: PhantomData,
         }
     }
@@ -117,11 +960,34 @@ where
 {
     #[inline]
     unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
-        let val = T::node_to_elem(node);
+        let val = self.brand.elem::<T>(node);
         self.key.tree_cmp(val)
     }
 }
 
+/// A query that falls between the elements that don't satisfy a monotone
+/// predicate and the ones that do, for `SplayTree::split_by`.
+struct Transition<'b, T, P> {
+    pred: &'b P,
+    brand: &'b Brand,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, 'b, T, P> internal::CompareToNode<'a> for Transition<'b, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    P: Fn(&'a T::Elem) -> bool,
+{
+    #[inline]
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        if (self.pred)(self.brand.elem::<T>(node)) {
+            cmp::Ordering::Less
+        } else {
+            cmp::Ordering::Greater
+        }
+    }
+}
+
 /// An intrusive splay tree.
 ///
 /// The tree is parameterized by some marker type `T` whose `IntrusiveNode`
@@ -130,13 +996,26 @@ where
 /// * the element type contained in this tree: `T::Elem`,
 /// * how to get the intrusive node for this tree within an element,
 /// * and how to get the container element from a given intrusive node for this
-/// tree.
+///   tree.
+///
+/// ## Layout
+///
+/// `SplayTree` is `#[repr(transparent)]` over a nullable pointer to its root
+/// `Node`, so C can hold one as opaque, pointer-sized storage, as the
+/// functions generated by `export_splay_tree_c_api!` expect. With the
+/// `paranoid` feature, it is instead `#[repr(C)]`, and followed by the tree's
+/// `uintptr_t` brand. With the `hooks` feature, the root is followed by a Rust
+/// trait object for the tree's hooks, which has no C equivalent, and with the
+/// `watchdog` feature it is followed by the tree's `size_t` depth limit.
+#[cfg_attr(not(feature = "paranoid"), repr(transparent))]
+#[cfg_attr(feature = "paranoid", repr(C))]
 pub struct SplayTree<'a, T>
 where
     T: IntrusiveNode<'a>,
     T::Elem: 'a,
 {
     tree: internal::SplayTree<'a>,
+    brand: Brand,
     _phantom: PhantomData<&'a T::Elem>,
 }
 
@@ -149,6 +1028,7 @@ where
     fn default() -> SplayTree<'a, T> {
         SplayTree {
             tree: internal::SplayTree::default(),
+            brand: Brand::new(),
             _phantom: PhantomData,
         }
     }
@@ -162,15 +1042,84 @@ where
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let set = &mut f.debug_set();
         self.walk(|x| {
-            set.entry(x);
+            set.entry(&x);
         });
         set.finish()
     }
 }
 
+impl<'a, T> SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    /// Iterate over the elements in order, without splaying.
+    #[inline]
+    pub(crate) fn iter(&self) -> iter::Elems<'_, 'a, T> {
+        iter::Elems::new(&self.brand, self.tree.root())
+    }
+}
+
+/// Trees are equal when they contain equal elements, in the same order.
+impl<'a, T> PartialEq for SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: PartialEq + TreeOrd<'a, T>,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T> Eq for SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: Eq + TreeOrd<'a, T>,
+{
+}
+
+/// Trees are ordered lexicographically by their elements, in order, like
+/// `BTreeSet`.
+///
+/// `Ord` is deliberately not implemented, because its `min` and `max` methods
+/// would take priority over `SplayTree::min` and `SplayTree::max` when called
+/// on a tree by value. To sort trees whose elements are `Ord`, use
+/// `trees.sort_by(|a, b| a.partial_cmp(b).unwrap())`.
+impl<'a, T> PartialOrd for SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: PartialOrd + TreeOrd<'a, T>,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+/// Look up the element that matches a key, without splaying, as with
+/// `SplayTree::get`.
+///
+/// ## Panics
+///
+/// Panics if no element matches the key.
+impl<'a, T, K> Index<&K> for SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: ?Sized + TreeOrd<'a, T>,
+{
+    type Output = T::Elem;
+
+    #[inline]
+    fn index(&self, key: &K) -> &T::Elem {
+        self.get(key).expect("no element found for key")
+    }
+}
+
 impl<'a, T> Extend<&'a T::Elem> for SplayTree<'a, T>
 where
     T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
 {
     #[inline]
     fn extend<I: IntoIterator<Item = &'a T::Elem>>(&mut self, iter: I) {
@@ -180,10 +1129,10 @@ where
     }
 }
 
-impl<'a, T> iter::FromIterator<&'a T::Elem> for SplayTree<'a, T>
+impl<'a, T> FromIterator<&'a T::Elem> for SplayTree<'a, T>
 where
     T: 'a + IntrusiveNode<'a>,
-    T::Elem: fmt::Debug,
+    T::Elem: fmt::Debug + TreeOrd<'a, T>,
 {
     #[inline]
     fn from_iter<I: IntoIterator<Item = &'a T::Elem>>(iter: I) -> Self {
@@ -202,6 +1151,7 @@ where
     pub const fn new() -> Self {
         Self {
             tree: internal::SplayTree::new(),
+            brand: Brand::new(),
             _phantom: PhantomData,
         }
     }
@@ -212,123 +1162,1676 @@ where
         self.tree.is_empty()
     }
 
-    /// Get a reference to the root element, if any exists.
-    pub fn root(&self) -> Option<&'a T::Elem> {
-        self.tree.root().map(|r| unsafe { T::node_to_elem(r) })
-    }
-
-    /// Find an element in the tree.
+    /// Call `hooks` whenever this tree inserts or removes an element or
+    /// rotates while splaying, from now on. This replaces any hooks that were
+    /// set before.
     ///
-    /// This operation will splay the queried element to the root of the tree.
+    /// Operations that split the tree, like `split_by` and `take_smallest`,
+    /// report each element they detach as removed, so they take *O(k)* time
+    /// for *k* detached elements while hooks are set. Trees split off from
+    /// this one start without hooks.
     ///
-    /// The `key` must be of a type that implements `TreeOrd` for this tree's
-    /// `T` type. The element type `T::Elem` must always implement `TreeOrd<T>`,
-    /// so you can search the tree by element. You can also implement
-    /// `TreeOrd<T>` for additional key types. This allows you to search the
-    /// tree without constructing a full element.
+    /// Requires the `hooks` cargo feature.
+    #[cfg(feature = "hooks")]
     #[inline]
-    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    pub fn set_hooks<H>(&mut self, hooks: &'a H)
     where
-        K: ?Sized + TreeOrd<'a, T>,
+        H: SplayHooks<'a, T>,
     {
-        unsafe {
-            let query: Query<_, T> = Query::new(key);
-            self.tree.find(&query).map(|node| T::node_to_elem(node))
-        }
+        self.tree
+            .set_hooks(Some(hooks::Adapter::<T, H>::new(hooks)));
     }
 
-    /// Insert a new element into this tree.
+    /// Stop calling the hooks that were set with `set_hooks`.
     ///
-    /// Returns `true` if the element was inserted into the tree.
+    /// Requires the `hooks` cargo feature.
+    #[cfg(feature = "hooks")]
+    #[inline]
+    pub fn clear_hooks(&mut self) {
+        self.tree.set_hooks(None);
+    }
+
+    /// Rebalance the tree whenever a splay has to descend more than `limit`
+    /// nodes deep, or stop if it is `None`.
     ///
-    /// Returns `false` if there was already an element in the tree for which
-    /// `TreeOrd` returned `Ordering::Equal`. In this case, the extant element
-    /// is left in the tree, and `elem` is not inserted.
+    /// Splay trees only promise good amortized performance, so an unlucky or
+    /// adversarial access pattern, like inserting keys in order and then
+    /// looking up the smallest, can make a single lookup walk the whole tree.
+    /// The splay already halves that path's depth, and with a limit set, the
+    /// tree then also rebuilds everything beneath the new root, as with
+    /// `rebalance`, so that the next deep lookup isn't just as slow. A limit
+    /// of a few times the base-two logarithm of the tree's expected size only
+    /// rebuilds after lookups that are slow anyway, and each rebuild takes
+    /// time linear in the size of the tree.
     ///
-    /// This operation will splay the inserted element to the root of the tree.
+    /// This is not copied to trees split off from this one.
     ///
-    /// It is a logic error to insert an element that is already inserted in a
+    /// Requires the `watchdog` cargo feature.
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    pub fn set_depth_limit(&mut self, limit: Option<usize>) {
+        self.tree.set_depth_limit(limit.unwrap_or(usize::MAX));
+    }
+
+    /// Get the limit set with `set_depth_limit`, if any.
+    ///
+    /// Requires the `watchdog` cargo feature.
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    pub fn depth_limit(&self) -> Option<usize> {
+        Some(self.tree.depth_limit()).filter(|&limit| limit != usize::MAX)
+    }
+
+    /// Rebuild the tree into one of minimal height, so that every element is
+    /// within about the base-two logarithm of the number of elements from the
+    /// root.
+    ///
+    /// This takes linear time and doesn't allocate. Like the bulk operations,
+    /// it doesn't call any `SplayHooks`, since no element's neighbors change.
+    #[inline]
+    pub fn rebalance(&mut self) {
+        self.tree.rebalance();
+    }
+
+    /// Build a tree of minimal height from elements in ascending order.
+    ///
+    /// Each ascending element only has to be compared with the previous one,
+    /// so this takes linear time, without recursing, for a tree that can be
+    /// walked and validated however many elements it has. Inserting the same
+    /// elements with `extend` instead would leave a path as deep as the tree
+    /// has elements, since a splay tree only splits that up as it is searched.
+    /// Elements that are out of order still end up in the right place, but
+    /// each costs a splay through the elements before it.
+    ///
+    /// Returns an error, and stops building, if an element's node is already
+    /// linked, or an element compares equal to an earlier one. The elements
+    /// before it are left linked to each other.
+    pub fn from_sorted<I>(elems: I) -> Result<SplayTree<'a, T>, Error>
+    where
+        I: IntoIterator<Item = &'a T::Elem>,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let mut tree = SplayTree::new();
+        for elem in elems {
+            tree.try_insert(elem)?;
+        }
+        tree.rebalance();
+        Ok(tree)
+    }
+
+    /// Check that the tree is a well-formed binary search tree, without
+    /// splaying or panicking.
+    ///
+    /// This visits every element, checking that each one's `TreeOrd`
+    /// implementation puts it strictly between its ancestors, and that the
+    /// subtree sizes and parent pointers of the `rank` and `parent` features
+    /// are consistent. Out-of-order elements and links that lead back up the
+    /// tree are both reported as `Err(Error::Corrupt)`, so it can't be sent
+    /// into a loop by the corruption it is looking for.
+    ///
+    /// This takes linear time, and doesn't recurse, so it can check the long
+    /// paths that inserting elements in order builds. It keeps a fixed stack
+    /// of the subtrees that it still has to visit, one for each ancestor with
+    /// two children that aren't leaves, up to 64 of them, and reports a tree
+    /// that needs more as `Err(Error::Corrupt)`. No tree of minimal height, as
+    /// `rebalance` builds, needs more than its height. This is for self-tests
+    /// and debugging, for example after changing the keys of elements that
+    /// are in the tree, or in a firmware health check that reports a broken
+    /// tree instead of tripping a debug assertion.
+    pub fn validate(&self) -> Result<(), Error>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        type Bounded<'a> = (&'a Node<'a>, Option<&'a Node<'a>>, Option<&'a Node<'a>>);
+
+        let Some(root) = self.tree.root() else {
+            return Ok(());
+        };
+        #[cfg(feature = "parent")]
+        if root.parent.get().is_some() {
+            return Err(Error::Corrupt);
+        }
+
+        let mut pending: [Bounded<'a>; 64] = [(root, None, None); 64];
+        let mut len = 0;
+        let mut next = Some((root, None, None));
+        while let Some((node, lo, hi)) = next {
+            self.validate_node(node, lo, hi)?;
+            let is_leaf = |n: &Node| n.left.get().is_none() && n.right.get().is_none();
+            next = match (node.left.get(), node.right.get()) {
+                // Check a leaf child right away, and otherwise come back to
+                // the right subtree after the left one.
+                (Some(left), Some(right)) if is_leaf(right) => {
+                    self.validate_node(right, Some(node), hi)?;
+                    Some((left, lo, Some(node)))
+                }
+                (Some(left), Some(right)) if is_leaf(left) => {
+                    self.validate_node(left, lo, Some(node))?;
+                    Some((right, Some(node), hi))
+                }
+                (Some(left), Some(right)) => {
+                    *pending.get_mut(len).ok_or(Error::Corrupt)? = (right, Some(node), hi);
+                    len += 1;
+                    Some((left, lo, Some(node)))
+                }
+                (Some(left), None) => Some((left, lo, Some(node))),
+                (None, Some(right)) => Some((right, Some(node), hi)),
+                (None, None) => len.checked_sub(1).and_then(|i| {
+                    len = i;
+                    pending.get(i).copied()
+                }),
+            };
+        }
+        Ok(())
+    }
+
+    /// Check that `node` is strictly between `lo` and `hi`, and that its
+    /// subtree size and its children's parent pointers are consistent.
+    fn validate_node(
+        &self,
+        node: &'a Node<'a>,
+        lo: Option<&'a Node<'a>>,
+        hi: Option<&'a Node<'a>>,
+    ) -> Result<(), Error>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        if node.is_removed() || !self.brand.owns(node) {
+            return Err(Error::Corrupt);
+        }
+        let elem = unsafe { T::node_to_elem(node) };
+        let elem_of = |n: &'a Node<'a>| unsafe { T::node_to_elem(n) };
+        if lo.is_some_and(|lo| !elem_of(lo).tree_cmp(elem).is_lt())
+            || hi.is_some_and(|hi| !elem_of(hi).tree_cmp(elem).is_gt())
+        {
+            return Err(Error::Corrupt);
+        }
+
+        #[cfg(feature = "parent")]
+        for child in [node.left.get(), node.right.get()].into_iter().flatten() {
+            if !child.parent.get().is_some_and(|p| ptr::eq(p, node)) {
+                return Err(Error::Corrupt);
+            }
+        }
+
+        // If every size is one more than the sum of its children's, then they
+        // are all right. Corrupt sizes may be huge, so this can't overflow.
+        #[cfg(feature = "rank")]
+        if Node::size_of(node.left.get())
+            .checked_add(Node::size_of(node.right.get()))
+            .and_then(|children| children.checked_add(1))
+            != Some(node.size.get())
+        {
+            return Err(Error::Corrupt);
+        }
+        Ok(())
+    }
+
+    /// Get a reference to the root element, if any exists.
+    pub fn root(&self) -> Option<&'a T::Elem> {
+        self.tree.root().map(|r| unsafe { self.brand.elem::<T>(r) })
+    }
+
+    /// Find an element in the tree.
+    ///
+    /// This operation will splay the queried element to the root of the tree.
+    ///
+    /// The `key` must be of a type that implements `TreeOrd` for this tree's
+    /// `T` type. The element type `T::Elem` must always implement `TreeOrd<T>`,
+    /// so you can search the tree by element. You can also implement
+    /// `TreeOrd<T>` for additional key types. This allows you to search the
+    /// tree without constructing a full element.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self
+                .tree
+                .find_branded(&self.brand, internal::erase(&query))?;
+            Some(T::node_to_elem(node))
+        }
+    }
+
+    /// Find an element in the tree, without splaying it.
+    ///
+    /// This only descends the tree, so it takes `&self` and can be used
+    /// through a shared reference. It doesn't get `find`'s amortized
+    /// guarantees, though: looking up the same deep element over and over
+    /// costs its whole depth every time.
+    ///
+    /// Indexing the tree with `&tree[&key]` does the same lookup, but panics
+    /// if there is no match.
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self.tree.get(internal::erase(&query))?;
+            Some(self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Find an element in the tree, and splay it to the root bottom-up.
+    ///
+    /// Unlike `find`, this first descends the tree without changing it, and
+    /// only restructures it if `key` was found, by rotating the element up
+    /// through its ancestors with their parent pointers. So misses don't
+    /// restructure the tree at all. The tradeoff is that they don't speed up
+    /// later misses either: repeatedly missing at the bottom of a long path
+    /// costs the whole path every time.
+    ///
+    /// Requires the `parent` feature.
+    #[cfg(feature = "parent")]
+    #[inline]
+    pub fn find_bottom_up<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self.tree.find_bottom_up(internal::erase(&query))?;
+            Some(self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Splay `elem`, which must be in this tree, to the root bottom-up.
+    ///
+    /// This needs no comparisons, so it can restructure the tree for an
+    /// element that was found some other way, for example by a lookup that
+    /// didn't splay, or through another tree that the element is in.
+    ///
+    /// With the `paranoid` feature, this panics if `elem` is not in this tree.
+    /// Otherwise, splaying an element that is not in this tree leaves both
+    /// this tree and the element's tree in an unspecified, but memory-safe,
+    /// state.
+    ///
+    /// Requires the `parent` feature.
+    ///
+    /// ```
+    /// # #[cfg(feature = "parent")] {
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Page<'a> {
+    ///     number: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct Pages;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Pages
+    ///     where
+    ///         type Elem = Page<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Pages> for Page<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Page<'a>) -> Ordering {
+    ///         self.number.cmp(&rhs.number)
+    ///     }
+    /// }
+    ///
+    /// let pages = [1, 2, 3, 4].map(|number| Page { number, node: Node::default() });
+    /// let mut tree = SplayTree::<Pages>::new();
+    /// for page in &pages {
+    ///     tree.insert(page);
+    /// }
+    ///
+    /// tree.splay_elem(&pages[1]);
+    /// assert_eq!(tree.root().unwrap().number, 2);
+    /// # }
+    /// ```
+    #[cfg(feature = "parent")]
+    #[inline]
+    pub fn splay_elem(&mut self, elem: &'a T::Elem) {
+        let node = T::elem_to_node(elem);
+        self.brand.check(node);
+        self.tree.splay_up(node);
+    }
+
+    /// Splay the tree toward `key`, whether or not any element matches it, and
+    /// return the new root along with how `key` compares to it.
+    ///
+    /// This is the restructuring that `find` does on its own: afterwards, the
+    /// root is the element matching `key` if there is one, and otherwise its
+    /// predecessor or successor. Use it to warm the tree before a burst of
+    /// queries near `key`, or to build other lookups on top, like finding
+    /// the nearest element. Returns `None` if the tree is empty.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Port<'a> {
+    ///     number: u16,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByNumber;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByNumber
+    ///     where
+    ///         type Elem = Port<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByNumber> for Port<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Port<'a>) -> Ordering {
+    ///         self.number.cmp(&rhs.number)
+    ///     }
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByNumber> for u16 {
+    ///     fn tree_cmp(&self, rhs: &'a Port<'a>) -> Ordering {
+    ///         self.cmp(&rhs.number)
+    ///     }
+    /// }
+    ///
+    /// let ports = [22, 80, 443, 8080].map(|number| Port { number, node: Node::new() });
+    /// let mut tree = SplayTree::<ByNumber>::new();
+    /// for port in &ports {
+    ///     tree.insert(port);
+    /// }
+    ///
+    /// // There's no port 100, so the root is one of its neighbors.
+    /// let (root, order) = tree.splay_to(&100).unwrap();
+    /// assert!(matches!((root.number, order), (80, Ordering::Greater) | (443, Ordering::Less)));
+    /// assert_eq!(tree.root().unwrap().number, root.number);
+    ///
+    /// let (root, order) = tree.splay_to(&443).unwrap();
+    /// assert_eq!((root.number, order), (443, Ordering::Equal));
+    /// ```
+    #[inline]
+    pub fn splay_to<K>(&mut self, key: &K) -> Option<(&'a T::Elem, cmp::Ordering)>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let (root, order) = self.tree.splay_to(internal::erase(&query))?;
+            Some((self.brand.elem::<T>(root), order))
+        }
+    }
+
+    /// Find the largest element that is less than or equal to `key`.
+    #[inline]
+    pub(crate) fn find_le<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .find_le(internal::erase(&query))
+                .map(|node| self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Find the smallest element that is greater than `key`.
+    #[inline]
+    pub(crate) fn find_gt<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .find_gt(internal::erase(&query))
+                .map(|node| self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Find the element matching each of the given keys, which should be in
+    /// ascending order.
+    ///
+    /// The returned iterator yields one result for each key, in order, like
+    /// calling `find` with each key in turn. Because each lookup starts from
+    /// the element that the previous one splayed to the root, a sweep over
+    /// nearby, ascending keys touches only a little of the tree per key. Keys
+    /// that are not in ascending order still get the right results, but lose
+    /// this advantage.
+    #[inline]
+    pub fn find_many<'k, K>(&mut self, keys: &'k [K]) -> FindMany<'_, 'a, 'k, T, K>
+    where
+        K: TreeOrd<'a, T>,
+    {
+        FindMany::new(self, keys)
+    }
+
+    /// Insert a new element into this tree.
+    ///
+    /// Returns `true` if the element was inserted into the tree.
+    ///
+    /// Returns `false` if there was already an element in the tree for which
+    /// `TreeOrd` returned `Ordering::Equal`. In this case, the extant element
+    /// is left in the tree, and `elem` is not inserted.
+    ///
+    /// This operation will splay the inserted element to the root of the tree.
+    ///
+    /// It is a logic error to insert an element that is already inserted in a
     /// `T` tree.
     ///
     /// ## Panics
     ///
-    /// If `debug_assertions` are enabled, then this function may panic if
-    /// `elem` is already in a `T` tree. If `debug_assertions` are not defined,
-    /// the behavior is safe, but unspecified.
+    /// If `debug_assertions` are enabled, then this function may panic if
+    /// `elem` is already in a `T` tree. If `debug_assertions` are not defined,
+    /// the behavior is safe, but unspecified.
+    ///
+    /// If the `paranoid` feature is enabled, then this function panics if
+    /// `elem`'s node is already in any tree.
+    ///
+    /// `try_insert` reports these cases as errors instead.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(elem, &self.brand);
+            let node = T::elem_to_node(elem);
+            self.tree
+                .insert_branded(&self.brand, internal::erase(&query), node)
+        }
+    }
+
+    /// Insert a new element into this tree, or report why it can't be, without
+    /// panicking.
+    ///
+    /// This is `insert` with its checks turned into errors, in every build:
+    /// it returns `Err(Error::AlreadyLinked)` instead of debug-asserting or,
+    /// with the `paranoid` feature, panicking when `elem`'s node is already
+    /// in a tree, and `Err(Error::Duplicate)` where `insert` returns `false`.
+    /// See `Error::AlreadyLinked` for which linked nodes it can catch.
+    ///
+    /// It can't panic without `debug_assertions`, as `nopanic/check.sh`
+    /// verifies at link time. With them, splaying still panics on a link to a
+    /// removed node, so a debug build can panic on a corrupt tree.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Error, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Port<'a> {
+    ///     number: u16,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct Ports;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Ports
+    ///     where
+    ///         type Elem = Port<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Ports> for Port<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Port<'a>) -> Ordering {
+    ///         self.number.cmp(&rhs.number)
+    ///     }
+    /// }
+    ///
+    /// let ports = [80, 443, 80].map(|number| Port { number, node: Node::new() });
+    /// let mut tree = SplayTree::<Ports>::new();
+    /// assert_eq!(tree.try_insert(&ports[0]), Ok(()));
+    /// assert_eq!(tree.try_insert(&ports[1]), Ok(()));
+    /// assert_eq!(tree.try_insert(&ports[1]), Err(Error::AlreadyLinked));
+    /// assert_eq!(tree.try_insert(&ports[2]), Err(Error::Duplicate));
+    /// ```
+    pub fn try_insert(&mut self, elem: &'a T::Elem) -> Result<(), Error>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let node = T::elem_to_node(elem);
+        if node.left.get().is_some()
+            || node.right.get().is_some()
+            || self.tree.root().is_some_and(|root| ptr::eq(root, node))
+            || Brand::is_branded(node)
+        {
+            return Err(Error::AlreadyLinked);
+        }
+
+        // Safety: `node` is unlinked, which is all that `insert_unchecked`
+        // skips checking.
+        if unsafe { self.insert_unchecked(elem) } {
+            Ok(())
+        } else {
+            Err(Error::Duplicate)
+        }
+    }
+
+    /// Insert every element of `elems`, and report how many were rejected
+    /// because an equal element was already in the tree.
+    ///
+    /// `extend` inserts the same elements, but silently skips the duplicates.
+    /// This passes each rejected element to `on_rejected`, in the order they
+    /// were given, and returns a report that counts both kinds. A rejected
+    /// element is left exactly as it was, so it may be in another tree, or
+    /// even be the element already in this one.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Reading<'a> {
+    ///     sensor: u32,
+    ///     value: f64,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct BySensor;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for BySensor
+    ///     where
+    ///         type Elem = Reading<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, BySensor> for Reading<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Reading<'a>) -> Ordering {
+    ///         self.sensor.cmp(&rhs.sensor)
+    ///     }
+    /// }
+    ///
+    /// let readings = [(1, 0.5), (2, 1.5), (1, 0.7)]
+    ///     .map(|(sensor, value)| Reading { sensor, value, node: Node::new() });
+    /// let mut tree = SplayTree::<BySensor>::new();
+    ///
+    /// let mut rejected = vec![];
+    /// let report = tree.insert_all(&readings, |r| rejected.push(r.value));
+    /// assert_eq!(report.inserted(), 2);
+    /// assert_eq!(report.rejected(), 1);
+    /// assert_eq!(rejected, [0.7]);
+    /// ```
+    pub fn insert_all<I, F>(&mut self, elems: I, mut on_rejected: F) -> InsertReport
+    where
+        I: IntoIterator<Item = &'a T::Elem>,
+        F: FnMut(&'a T::Elem),
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let mut report = InsertReport::default();
+        for elem in elems {
+            if self.insert(elem) {
+                report.record_inserted();
+            } else {
+                report.record_rejected();
+                on_rejected(elem);
+            }
+        }
+        report
+    }
+
+    /// Insert a new element into this tree, without checking that it is not
+    /// already in one.
+    ///
+    /// This is `insert` without its `debug_assertions` check that `elem`'s
+    /// node is unlinked, and without the `paranoid` feature's check that it is
+    /// not in any tree, for hot loops in debug builds where those checks
+    /// dominate. `elem` is still branded, so later operations on it keep
+    /// their checks.
+    ///
+    /// ## Safety
+    ///
+    /// `elem` must not be in a `T` tree, or in any tree if the `paranoid`
+    /// feature is enabled. Elements that were removed from a tree are fine,
+    /// and their stale links are overwritten.
+    #[inline]
+    pub unsafe fn insert_unchecked(&mut self, elem: &'a T::Elem) -> bool
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let query: Query<_, T> = Query::new(elem, &self.brand);
+        let node = T::elem_to_node(elem);
+        self.tree
+            .insert_unchecked_branded(&self.brand, internal::erase(&query), node)
+    }
+
+    /// Insert a new element into this tree, right after `hint` if that is where
+    /// it goes.
+    ///
+    /// When `hint` is the root, which it is right after it was inserted or
+    /// found, and `elem` goes between `hint` and its successor, `elem` is
+    /// linked in as the new root without splaying. This makes appending
+    /// mostly ascending keys cost a comparison or two each, by passing the
+    /// previously inserted element as the hint. Otherwise, this falls back to
+    /// `insert`. Either way, it returns whether `elem` was inserted, like
+    /// `insert`.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Sample<'a> {
+    ///     time: u64,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByTime;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByTime
+    ///     where
+    ///         type Elem = Sample<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByTime> for Sample<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Sample<'a>) -> Ordering {
+    ///         self.time.cmp(&rhs.time)
+    ///     }
+    /// }
+    ///
+    /// let samples = [1, 2, 4, 3, 5].map(|time| Sample { time, node: Node::default() });
+    /// let mut tree = SplayTree::<ByTime>::new();
+    /// let mut last = &samples[0];
+    /// tree.insert(last);
+    /// for sample in &samples[1..] {
+    ///     assert!(tree.insert_after_hint(last, sample));
+    ///     last = sample;
+    /// }
+    ///
+    /// let mut times = vec![];
+    /// tree.walk(|s| times.push(s.time));
+    /// assert_eq!(times, [1, 2, 3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn insert_after_hint(&mut self, hint: &'a T::Elem, elem: &'a T::Elem) -> bool
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let hint_is_root = self
+            .tree
+            .root()
+            .is_some_and(|root| ptr::eq(root, T::elem_to_node(hint)));
+        if hint_is_root {
+            let inserted = unsafe {
+                let query: Query<_, T> = Query::new(elem, &self.brand);
+                let node = T::elem_to_node(elem);
+                self.tree
+                    .insert_after_root_branded(&self.brand, internal::erase(&query), node)
+            };
+            if inserted {
+                return true;
+            }
+        }
+        self.insert(elem)
+    }
+
+    /// Insert `elem` where `key` says it goes, for trees whose order comes from
+    /// a wrapper rather than from the element's own `TreeOrd`.
+    #[inline]
+    pub(crate) fn insert_by_key<K>(&mut self, key: &K, elem: &'a T::Elem) -> bool
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = T::elem_to_node(elem);
+            self.tree
+                .insert_branded(&self.brand, internal::erase(&query), node)
+        }
+    }
+
+    /// Insert a new element into this tree, and remove it again when the
+    /// returned guard is dropped.
+    ///
+    /// Returns `None`, without inserting `elem`, if there was already an
+    /// element in the tree for which `TreeOrd` returned `Ordering::Equal`. See
+    /// `InsertGuard` for details.
+    #[inline]
+    pub fn insert_scoped(&mut self, elem: &'a T::Elem) -> Option<InsertGuard<'_, 'a, T>>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        if self.insert(elem) {
+            Some(InsertGuard::new(self, elem))
+        } else {
+            None
+        }
+    }
+
+    /// Start a transaction, whose insertions and removals are undone unless it
+    /// is committed. Its log has room for `N` changes.
+    ///
+    /// See `Transaction` for details.
+    #[inline]
+    pub fn transaction<const N: usize>(&mut self) -> Transaction<'_, 'a, T, N>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        Transaction::new(self)
+    }
+
+    /// Find and remove an element from the tree.
+    ///
+    /// If a matching element is found and removed, then `Some(removed_element)`
+    /// is returned. Otherwise `None` is returned.
+    ///
+    /// The `key` must be of a type that implements `TreeOrd` for this tree's
+    /// `T` type. The element type `T::Elem` must always implement `TreeOrd<T>`,
+    /// so you can remove an element directly. You can also implement
+    /// `TreeOrd<T>` for additional key types. This allows you to search the
+    /// tree without constructing a full element, and remove the element that
+    /// matches the given key, if any.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self
+                .tree
+                .remove_branded(&self.brand, internal::erase(&query))?;
+            Some(T::node_to_elem(node))
+        }
+    }
+
+    /// Remove the element matching each of the given keys, which should be in
+    /// ascending order.
+    ///
+    /// The returned iterator yields one result for each key, in order, like
+    /// calling `remove` with each key in turn, and removes each element as it
+    /// yields it. Like `find_many`, a sweep over nearby, ascending keys
+    /// touches only a little of the tree per key.
+    #[inline]
+    pub fn remove_many<'k, K>(&mut self, keys: &'k [K]) -> RemoveMany<'_, 'a, 'k, T, K>
+    where
+        K: TreeOrd<'a, T>,
+    {
+        RemoveMany::new(self, keys)
+    }
+
+    /// Find and remove an element from the tree, and also return its
+    /// neighbors.
+    ///
+    /// If a matching element is found and removed, then `Some((predecessor,
+    /// removed_element, successor))` is returned, where the predecessor and
+    /// successor are the elements that came immediately before and after the
+    /// removed element in order, if any. They remain in the tree. Otherwise,
+    /// `None` is returned.
+    ///
+    /// This removes the element and finds both of its neighbors with a single
+    /// splay, for example to coalesce a freed block with the free blocks
+    /// around it in an address-ordered tree.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn remove_with_neighbors<K>(
+        &mut self,
+        key: &K,
+    ) -> Option<(Option<&'a T::Elem>, &'a T::Elem, Option<&'a T::Elem>)>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let (pred, removed, succ) = self.tree.remove_with_neighbors(internal::erase(&query))?;
+            Some((
+                pred.map(|node| self.brand.elem::<T>(node)),
+                self.brand.release::<T>(removed),
+                succ.map(|node| self.brand.elem::<T>(node)),
+            ))
+        }
+    }
+
+    /// Remove the smallest element that is greater than or equal to `key`.
+    ///
+    /// If there is such an element, it is removed and `Some(_)` is returned.
+    /// Otherwise, `None` is returned. This is a best-fit search, for example
+    /// finding the smallest free block that is at least as large as a
+    /// requested size, and removes the element along the way instead of
+    /// searching for it a second time.
+    #[inline]
+    pub fn remove_ge<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self
+                .tree
+                .remove_ge_branded(&self.brand, internal::erase(&query))?;
+            Some(T::node_to_elem(node))
+        }
+    }
+
+    /// Find and remove an element from the tree, but only if `pred` returns
+    /// `true` for it.
+    ///
+    /// Returns `Some(removed_element)` if a matching element was found and
+    /// `pred` accepted it. Otherwise `None` is returned and the tree keeps all
+    /// of its elements.
+    ///
+    /// Like `find`, this splays the matching element to the root of the tree,
+    /// and it does so only once, whether or not the element is removed.
+    #[inline]
+    pub fn remove_if<K, P>(&mut self, key: &K, pred: P) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+        P: FnOnce(&'a T::Elem) -> bool,
+    {
+        let elem = self.find(key)?;
+        if pred(elem) {
+            self.pop_root()
+        } else {
+            None
+        }
+    }
+
+    /// Change the key of an element, keeping the tree ordered.
+    ///
+    /// Finds and removes the element matching `key`, passes it to `f`, which
+    /// may change its ordering (through interior mutability, since elements
+    /// are shared), and reinserts it at its new position. Mutating the
+    /// ordering of an element while it is in the tree is a logic error, so
+    /// this is the way to re-key elements.
+    ///
+    /// Returns `None` if there was no matching element. Otherwise, returns
+    /// `Some(Ok(elem))` if the element was reinserted, or `Some(Err(elem))` if
+    /// another element in the tree is equal to its new key, in which case the
+    /// element is left out of the tree. If `f` panics, the element is also left
+    /// out of the tree.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cell::Cell;
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Task<'a> {
+    ///     priority: Cell<u32>,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByPriority;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByPriority
+    ///     where
+    ///         type Elem = Task<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByPriority> for Task<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Task<'a>) -> Ordering {
+    ///         self.priority.get().cmp(&rhs.priority.get())
+    ///     }
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByPriority> for u32 {
+    ///     fn tree_cmp(&self, rhs: &'a Task<'a>) -> Ordering {
+    ///         self.cmp(&rhs.priority.get())
+    ///     }
+    /// }
+    ///
+    /// let tasks = [1, 2, 3].map(|p| Task { priority: Cell::new(p), node: Node::default() });
+    /// let mut tree = SplayTree::<ByPriority>::new();
+    /// for task in &tasks {
+    ///     tree.insert(task);
+    /// }
+    ///
+    /// assert!(tree.update_key(&1, |t| t.priority.set(10)).unwrap().is_ok());
+    /// assert_eq!(tree.max().unwrap().priority.get(), 10);
+    ///
+    /// // Priority 3 is already taken, so the task is left out of the tree.
+    /// assert!(tree.update_key(&2, |t| t.priority.set(3)).unwrap().is_err());
+    /// assert!(tree.find(&2).is_none());
+    /// ```
+    #[inline]
+    pub fn update_key<K, F>(&mut self, key: &K, f: F) -> Option<Result<&'a T::Elem, &'a T::Elem>>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+        F: FnOnce(&'a T::Elem),
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let elem = self.remove(key)?;
+        f(elem);
+        Some(if self.insert(elem) {
+            Ok(elem)
+        } else {
+            Err(elem)
+        })
+    }
+
+    /// Pop the root element from the tree.
+    ///
+    /// If the tree has a root, it is removed and `Some(root)` is
+    /// returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_root(&mut self) -> Option<&'a T::Elem> {
+        unsafe {
+            self.tree
+                .pop_root()
+                .map(|node| self.brand.release::<T>(node))
+        }
+    }
+
+    /// Pop the root element from the tree, but only if `pred` returns `true`
+    /// for it.
+    ///
+    /// If the tree has a root and `pred` accepts it, it is removed and
+    /// `Some(root)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_root_if<P>(&mut self, pred: P) -> Option<&'a T::Elem>
+    where
+        P: FnOnce(&'a T::Elem) -> bool,
+    {
+        if pred(self.root()?) {
+            self.pop_root()
+        } else {
+            None
+        }
+    }
+
+    /// Get the minimum element in the tree.
+    ///
+    /// If the tree is non-empty, then the minimum element is splayed to the
+    /// root and `Some(min_elem)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn min(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .min()
+            .map(|node| unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Pop the minimum element from the tree.
+    ///
+    /// If the tree is non-empty, then the minimum element is removed and
+    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_min(&mut self) -> Option<&'a T::Elem> {
+        unsafe {
+            self.tree
+                .pop_min()
+                .map(|node| self.brand.release::<T>(node))
+        }
+    }
+
+    /// Get a handle to the minimum element in the tree, which is splayed to
+    /// the root.
+    ///
+    /// Returns `None` if the tree is empty. See `OccupiedEntry` for details.
+    #[inline]
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, 'a, T>> {
+        OccupiedEntry::first(self)
+    }
+
+    /// Pop elements from the tree, in order from the minimum, for as long as
+    /// `pred` returns `true`.
+    ///
+    /// The returned iterator removes each element as it yields it, and stops
+    /// at the first element that `pred` rejects, which is left in the tree at
+    /// its root. Elements that the iterator has not reached yet when it is
+    /// dropped also stay in the tree.
+    ///
+    /// This is the loop at the heart of a deadline queue, with one splay per
+    /// element:
+    ///
+    /// ```
+    /// # use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// # use std::cmp::Ordering;
+    /// struct Timer<'a> {
+    ///     deadline: u64,
+    ///     node: Node<'a>,
+    /// }
+    /// #
+    /// # struct ByDeadline;
+    /// #
+    /// # impl_intrusive_node! {
+    /// #     impl<'a> IntrusiveNode<'a> for ByDeadline
+    /// #     where
+    /// #         type Elem = Timer<'a>,
+    /// #         node = node;
+    /// # }
+    /// #
+    /// # impl<'a> TreeOrd<'a, ByDeadline> for Timer<'a> {
+    /// #     fn tree_cmp(&self, rhs: &'a Timer<'a>) -> Ordering {
+    /// #         self.deadline.cmp(&rhs.deadline)
+    /// #     }
+    /// # }
+    ///
+    /// let timers = [30, 10, 20].map(|deadline| Timer { deadline, node: Node::default() });
+    /// let mut tree = SplayTree::<ByDeadline>::new();
+    /// for timer in &timers {
+    ///     tree.insert(timer);
+    /// }
+    ///
+    /// let now = 25;
+    /// let expired: Vec<u64> = tree.pop_while(|t| t.deadline <= now).map(|t| t.deadline).collect();
+    /// assert_eq!(expired, [10, 20]);
+    /// assert_eq!(tree.min().unwrap().deadline, 30);
+    /// ```
+    #[inline]
+    pub fn pop_while<P>(&mut self, pred: P) -> PopWhile<'_, 'a, T, P>
+    where
+        P: FnMut(&'a T::Elem) -> bool,
+    {
+        PopWhile::new(self, pred)
+    }
+
+    /// Get the maximum element in the tree.
+    ///
+    /// If the tree is non-empty, then the maximum element is splayed to the
+    /// root and `Some(max_elem)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn max(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .max()
+            .map(|node| unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Get both the minimum and maximum elements in the tree.
+    ///
+    /// The minimum element is splayed to the root, and the maximum element to
+    /// be the root's right child, so this is two splays, and asking again
+    /// while the tree is unchanged takes constant time. For a one-element tree,
+    /// both are the same element. Returns `None` if the tree is empty.
+    #[inline]
+    pub fn min_max(&mut self) -> Option<(&'a T::Elem, &'a T::Elem)> {
+        let (min, max) = self.tree.min_max()?;
+        unsafe { Some((self.brand.elem::<T>(min), self.brand.elem::<T>(max))) }
+    }
+
+    /// Get a handle to the maximum element in the tree, which is splayed to
+    /// the root.
+    ///
+    /// Returns `None` if the tree is empty. See `OccupiedEntry` for details.
+    #[inline]
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, 'a, T>> {
+        OccupiedEntry::last(self)
+    }
+
+    /// Pop the maximum element from the tree.
+    ///
+    /// If the tree is non-empty, then the maximum element is removed and
+    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    #[inline]
+    pub fn pop_max(&mut self) -> Option<&'a T::Elem> {
+        unsafe {
+            self.tree
+                .pop_max()
+                .map(|node| self.brand.release::<T>(node))
+        }
+    }
+
+    /// Apply a batch of insertions and removals.
+    ///
+    /// The operations are first sorted by key, in place, and then applied in
+    /// that order. A splay tree makes a sequence of accesses to nearby keys
+    /// cheap, since each one starts from the root that the previous one left
+    /// behind, so this is much faster than applying a large batch in a random
+    /// order.
+    ///
+    /// The sort is not stable, so operations whose keys are equal are applied
+    /// in an unspecified order. A batch should therefore have at most one
+    /// operation per key, like a delta between two states.
+    ///
+    /// Returns how many of the operations took effect: insertions that
+    /// inserted their element, and removals that found an element to remove.
     #[inline]
-    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
-        unsafe {
-            let query: Query<_, T> = Query::new(elem);
-            let node = T::elem_to_node(elem);
-            self.tree.insert(&query, node)
+    pub fn apply_batch(&mut self, ops: &mut [BatchOp<'a, T>]) -> usize
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        ops.sort_unstable_by(|a, b| a.cmp_keys(b));
+        ops.iter()
+            .filter(|op| match **op {
+                BatchOp::Insert(elem) => self.insert(elem),
+                BatchOp::Remove(elem) => self.remove(elem).is_some(),
+            })
+            .count()
+    }
+
+    /// Move every element of `other` into this tree.
+    ///
+    /// Each element is removed from `other` before it is inserted here, so
+    /// unlike `extend`, its node is properly unlinked from `other` first.
+    /// Elements that compare equal to an element already in this tree are
+    /// left in `other`.
+    ///
+    /// Returns how many elements were moved.
+    pub fn extend_by_draining(&mut self, other: &mut SplayTree<'a, T>) -> usize
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        // Only the nodes move, so `other` keeps its hooks and depth limit. Its
+        // hooks see its elements removed, and put back if they don't fit
+        // here.
+        let nodes = other.tree.take();
+        let mut source = other.split_off_tree(nodes);
+        let mut moved = 0;
+        while let Some(elem) = source.pop_min() {
+            if self.insert(elem) {
+                moved += 1;
+            } else {
+                // These arrive in ascending order, so each of these insertions
+                // takes constant time.
+                other.insert(elem);
+            }
         }
+        moved
     }
 
-    /// Find and remove an element from the tree.
+    /// Detach the `k` smallest elements from the tree, and return them as a
+    /// tree of their own.
     ///
-    /// If a matching element is found and removed, then `Some(removed_element)`
-    /// is returned. Otherwise `None` is returned.
+    /// If the tree has `k` or fewer elements, all of them are taken, leaving
+    /// this tree empty. Finding the boundary takes amortized *O(log n)* time
+    /// with the `rank` feature, and *O(k + log n)* without it, by splaying
+    /// one element at a time; cutting the tree there takes constant time.
+    #[inline]
+    pub fn take_smallest(&mut self, k: usize) -> SplayTree<'a, T> {
+        let taken = if self.splay_nth(k) {
+            self.tree.take_root_left()
+        } else {
+            self.tree.take()
+        };
+        self.split_off_tree(taken)
+    }
+
+    /// Detach the `k` largest elements from the tree, and return them as a
+    /// tree of their own.
     ///
-    /// The `key` must be of a type that implements `TreeOrd` for this tree's
-    /// `T` type. The element type `T::Elem` must always implement `TreeOrd<T>`,
-    /// so you can remove an element directly. You can also implement
-    /// `TreeOrd<T>` for additional key types. This allows you to search the
-    /// tree without constructing a full element, and remove the element that
-    /// matches the given key, if any.
+    /// If the tree has `k` or fewer elements, all of them are taken, leaving
+    /// this tree empty. Finding the boundary takes amortized *O(log n)* time
+    /// with the `rank` feature, and *O(k + log n)* without it, by splaying
+    /// one element at a time; cutting the tree there takes constant time.
     #[inline]
-    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    pub fn take_largest(&mut self, k: usize) -> SplayTree<'a, T> {
+        let taken = if self.splay_nth_back(k) {
+            self.tree.take_root_right()
+        } else {
+            self.tree.take()
+        };
+        self.split_off_tree(taken)
+    }
+
+    /// Keep only the `k` smallest elements, and return the rest as a tree of
+    /// their own.
+    ///
+    /// If the tree has `k` or fewer elements, it keeps all of them, and the
+    /// returned tree is empty. Finding the boundary takes amortized
+    /// *O(log n)* time with the `rank` feature, and *O(k + log n)* without it,
+    /// by splaying one element at a time; cutting the tree there takes
+    /// constant time, however many elements are removed.
+    #[inline]
+    pub fn truncate_smallest(&mut self, k: usize) -> SplayTree<'a, T> {
+        let rest = if self.splay_nth(k) {
+            self.tree.take_root_and_right()
+        } else {
+            internal::SplayTree::new()
+        };
+        self.split_off_tree(rest)
+    }
+
+    /// Keep only the `k` largest elements, and return the rest as a tree of
+    /// their own.
+    ///
+    /// If the tree has `k` or fewer elements, it keeps all of them, and the
+    /// returned tree is empty. Finding the boundary takes amortized
+    /// *O(log n)* time with the `rank` feature, and *O(k + log n)* without it,
+    /// by splaying one element at a time; cutting the tree there takes
+    /// constant time, however many elements are removed.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Score<'a> {
+    ///     points: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct Leaderboard;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Leaderboard
+    ///     where
+    ///         type Elem = Score<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Leaderboard> for Score<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Score<'a>) -> Ordering {
+    ///         self.points.cmp(&rhs.points)
+    ///     }
+    /// }
+    ///
+    /// let scores = [40, 10, 30, 50, 20].map(|points| Score { points, node: Node::new() });
+    /// let mut board = SplayTree::<Leaderboard>::new();
+    /// for score in &scores {
+    ///     board.insert(score);
+    /// }
+    ///
+    /// // Keep the top three scores.
+    /// let dropped = board.truncate_largest(3);
+    /// let (mut kept, mut rest) = (vec![], vec![]);
+    /// board.walk(|s| kept.push(s.points));
+    /// dropped.walk(|s| rest.push(s.points));
+    /// assert_eq!(kept, [30, 40, 50]);
+    /// assert_eq!(rest, [10, 20]);
+    /// ```
+    #[inline]
+    pub fn truncate_largest(&mut self, k: usize) -> SplayTree<'a, T> {
+        let rest = if self.splay_nth_back(k) {
+            self.tree.take_root_and_left()
+        } else {
+            internal::SplayTree::new()
+        };
+        self.split_off_tree(rest)
+    }
+
+    /// Splay the element with `k` elements before it to the root, and return
+    /// whether there is one.
+    #[cfg(feature = "rank")]
+    fn splay_nth(&mut self, k: usize) -> bool {
+        matches!(self.tree.splay_at(k), Some((_, cmp::Ordering::Equal)))
+    }
+
+    /// Splay the element with `k` elements before it to the root, by stepping
+    /// up from the minimum, and return whether there is one.
+    #[cfg(not(feature = "rank"))]
+    fn splay_nth(&mut self, k: usize) -> bool {
+        self.tree.min().is_some() && (0..k).all(|_| self.tree.splay_next().is_some())
+    }
+
+    /// Splay the element with `k` elements after it to the root, and return
+    /// whether there is one.
+    #[cfg(feature = "rank")]
+    fn splay_nth_back(&mut self, k: usize) -> bool {
+        let len = self.tree.len();
+        k < len && self.splay_nth(len - 1 - k)
+    }
+
+    /// Splay the element with `k` elements after it to the root, by stepping
+    /// down from the maximum, and return whether there is one.
+    #[cfg(not(feature = "rank"))]
+    fn splay_nth_back(&mut self, k: usize) -> bool {
+        self.tree.max().is_some() && (0..k).all(|_| self.tree.splay_prev().is_some())
+    }
+
+    /// Split the tree where `pred` changes from `false` to `true`, and return
+    /// the elements that satisfy it as a tree of their own.
+    ///
+    /// `pred` must be monotone along the key order: `false` for every element
+    /// up to some point, and `true` for every element after it. The boundary
+    /// is found by binary search, like `slice::partition_point`, so it can be
+    /// a computed condition rather than a key value, and this takes one splay:
+    /// amortized *O(log n)* time. If `pred` isn't monotone, the split point is
+    /// unspecified, but both trees are still valid.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Block<'a> {
+    ///     offset: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByOffset;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByOffset
+    ///     where
+    ///         type Elem = Block<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByOffset> for Block<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Block<'a>) -> Ordering {
+    ///         self.offset.cmp(&rhs.offset)
+    ///     }
+    /// }
+    ///
+    /// let blocks = [0, 4096, 8192, 12288].map(|offset| Block { offset, node: Node::new() });
+    /// let mut tree = SplayTree::<ByOffset>::new();
+    /// for block in &blocks {
+    ///     tree.insert(block);
+    /// }
+    ///
+    /// // Split off the blocks in the second half of a 16 KiB region.
+    /// let high = tree.split_by(|b| b.offset / 8192 >= 1);
+    /// assert_eq!(tree.fold(vec![], |mut v, b| { v.push(b.offset); v }), [0, 4096]);
+    /// assert_eq!(high.fold(vec![], |mut v, b| { v.push(b.offset); v }), [8192, 12288]);
+    /// ```
+    #[inline]
+    pub fn split_by<P>(&mut self, pred: P) -> SplayTree<'a, T>
     where
-        K: ?Sized + TreeOrd<'a, T>,
+        P: Fn(&'a T::Elem) -> bool,
     {
+        let transition: Transition<T, P> = Transition {
+            pred: &pred,
+            brand: &self.brand,
+            _phantom: PhantomData,
+        };
+        // No element is equal to the transition, so this splays one of its
+        // neighbors to the root.
         unsafe {
-            let query: Query<_, T> = Query::new(key);
-            self.tree.remove(&query).map(|node| T::node_to_elem(node))
+            self.tree
+                .find_branded(&self.brand, internal::erase(&transition));
         }
+        let taken = match self.root() {
+            Some(root) if pred(root) => self.tree.take_root_and_right(),
+            Some(_) => self.tree.take_root_right(),
+            None => internal::SplayTree::new(),
+        };
+        self.split_off_tree(taken)
     }
 
-    /// Pop the root element from the tree.
+    #[inline]
+    fn split_off_tree(&self, tree: internal::SplayTree<'a>) -> SplayTree<'a, T> {
+        SplayTree {
+            tree,
+            brand: self.brand.share(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Build a new tree containing a copy of each of this tree's elements.
     ///
-    /// If the tree has a root, it is removed and `Some(root)` is
-    /// returned. Otherwise, `None` is returned.
+    /// `clone` is called with each element in order, and must return a new
+    /// element, with an unlinked node, that compares the same as the original;
+    /// typically it allocates the copy in an arena. Because the copies arrive
+    /// in ascending order, each insertion into the new tree is constant time,
+    /// and the new tree is then rebalanced to minimal height, so copying takes
+    /// linear time overall. This tree is not splayed.
+    ///
+    /// Returns an error, and stops copying, if `clone` returns an element
+    /// whose node is already linked, or one that compares equal to an earlier
+    /// copy. The copies made so far are left linked to each other.
+    pub fn copy_into<F>(&self, clone: F) -> Result<SplayTree<'a, T>, Error>
+    where
+        F: FnMut(&'a T::Elem) -> &'a T::Elem,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        SplayTree::from_sorted(self.iter().map(clone))
+    }
+
+    /// Get the number of elements in the tree.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
     #[inline]
-    pub fn pop_root(&mut self) -> Option<&'a T::Elem> {
-        unsafe { self.tree.pop_root().map(|node| T::node_to_elem(node)) }
+    pub fn len(&self) -> usize {
+        self.tree.len()
     }
 
-    /// Get the minimum element in the tree.
+    /// Find the element that matches the given `key`, along with its rank,
+    /// that is, its zero-based position in order.
     ///
-    /// If the tree is non-empty, then the minimum element is splayed to the
-    /// root and `Some(min_elem)` is returned. Otherwise, `None` is returned.
+    /// Like `find`, this splays the element to the root, which leaves its rank
+    /// as the size of its left subtree, so it costs no more than `find`.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
     #[inline]
-    pub fn min(&mut self) -> Option<&'a T::Elem> {
-        self.tree.min().map(|node| unsafe { T::node_to_elem(node) })
+    pub fn find_with_rank<K>(&mut self, key: &K) -> Option<(&'a T::Elem, usize)>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let elem = self.find(key)?;
+        let rank = self.tree.root().map_or(0, |root| root.rank());
+        Some((elem, rank))
     }
 
-    /// Pop the minimum element from the tree.
+    /// Remove the first `n` elements of the tree, in order, and return them as
+    /// a new tree.
     ///
-    /// If the tree is non-empty, then the minimum element is removed and
-    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    /// The element at rank `n` is splayed to the root and its left subtree
+    /// detached, for *O(log n)* amortized time. If `n` is at least the length of
+    /// the tree, every element is taken.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
+    pub fn split_at_rank(&mut self, n: usize) -> SplayTree<'a, T> {
+        let taken = match self.tree.splay_at(n) {
+            Some((_, cmp::Ordering::Equal)) => self.tree.take_root_left(),
+            _ => self.tree.take(),
+        };
+        self.split_off_tree(taken)
+    }
+
+    /// Iterate over the elements whose ranks, that is, their zero-based
+    /// positions in order, are within `range`.
+    ///
+    /// This splays the first element in the range to the root, and then walks
+    /// its successors, for *O(log n + k)* amortized time to yield *k*
+    /// elements. Ranks past the end of the tree are ignored.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
+    pub fn select_range<R>(&mut self, range: R) -> SelectRange<'_, 'a, T>
+    where
+        R: RangeBounds<usize>,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&j) => j.saturating_add(1),
+            Bound::Excluded(&j) => j,
+            Bound::Unbounded => len,
+        }
+        .min(len);
+
+        let first = match self.tree.splay_at(start) {
+            Some((root, cmp::Ordering::Equal)) if start < end => {
+                Some(unsafe { self.brand.elem::<T>(root) })
+            }
+            _ => None,
+        };
+        let rest = iter::Elems::new(&self.brand, self.tree.root().and_then(|r| r.right.get()));
+        SelectRange::new(first, rest, end.saturating_sub(start))
+    }
+
+    /// Iterate over raw pointers to the elements, in order, without splaying.
+    ///
+    /// This is for FFI layers that hand elements to C, which has no use for
+    /// the `'a` lifetime of `&'a T::Elem`. The pointers are valid for as long
+    /// as the elements are, which is the same as the references that `walk`
+    /// provides, but nothing checks that once they leave Rust.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// #[repr(C)]
+    /// struct Point<'a> {
+    ///     x: i32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByX;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByX
+    ///     where
+    ///         type Elem = Point<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByX> for Point<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Point<'a>) -> Ordering {
+    ///         self.x.cmp(&rhs.x)
+    ///     }
+    /// }
+    ///
+    /// // Stands in for a C function that takes `const struct point *`.
+    /// extern "C" fn visit(point: *const Point<'static>, sum: &mut i32) {
+    ///     *sum += unsafe { (*point).x };
+    /// }
+    ///
+    /// let points: &'static [Point<'static>; 3] =
+    ///     Box::leak(Box::new([-2, 5, 3].map(|x| Point { x, node: Node::new() })));
+    /// let mut tree = SplayTree::<ByX>::new();
+    /// for point in points {
+    ///     tree.insert(point);
+    /// }
+    ///
+    /// let mut sum = 0;
+    /// for point in tree.iter_ptrs() {
+    ///     visit(point, &mut sum);
+    /// }
+    /// assert_eq!(sum, 6);
+    /// assert_eq!(tree.iter_ptrs().next(), Some(&points[0] as *const _));
+    /// ```
     #[inline]
-    pub fn pop_min(&mut self) -> Option<&'a T::Elem> {
-        unsafe { self.tree.pop_min().map(|node| T::node_to_elem(node)) }
+    pub fn iter_ptrs(&self) -> ElemPtrs<'_, 'a, T>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        ElemPtrs::new(self.iter())
     }
 
-    /// Get the maximum element in the tree.
+    /// Measure how much memory this tree's intrusive metadata takes up.
     ///
-    /// If the tree is non-empty, then the maximum element is splayed to the
-    /// root and `Some(max_elem)` is returned. Otherwise, `None` is returned.
+    /// With the `rank` feature, this takes constant time. Otherwise, it
+    /// counts the nodes with an in-order walk, without splaying. Overheads can
+    /// be added together, to total several indices over the same elements.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    /// use std::mem;
+    ///
+    /// struct Sample<'a> {
+    ///     value: u64,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByValue;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByValue
+    ///     where
+    ///         type Elem = Sample<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByValue> for Sample<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Sample<'a>) -> Ordering {
+    ///         self.value.cmp(&rhs.value)
+    ///     }
+    /// }
+    ///
+    /// let samples = [1, 2, 3].map(|value| Sample { value, node: Node::new() });
+    /// let mut tree = SplayTree::<ByValue>::new();
+    /// for sample in &samples {
+    ///     tree.insert(sample);
+    /// }
+    ///
+    /// let overhead = tree.metadata_overhead();
+    /// assert_eq!(overhead.nodes(), 3);
+    /// assert_eq!(overhead.node_bytes(), 3 * mem::size_of::<Node>());
+    /// assert_eq!(
+    ///     overhead.total_bytes(),
+    ///     overhead.node_bytes() + mem::size_of::<SplayTree<ByValue>>(),
+    /// );
+    /// ```
     #[inline]
-    pub fn max(&mut self) -> Option<&'a T::Elem> {
-        self.tree.max().map(|node| unsafe { T::node_to_elem(node) })
+    pub fn metadata_overhead(&self) -> MetadataOverhead
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        #[cfg(feature = "rank")]
+        let nodes = self.len();
+        #[cfg(not(feature = "rank"))]
+        let nodes = self.iter().count();
+        MetadataOverhead::new(nodes, mem::size_of::<Self>())
     }
 
-    /// Pop the maximum element from the tree.
+    /// Walk this tree and `other` in order at the same time, like a sorted
+    /// merge join.
     ///
-    /// If the tree is non-empty, then the maximum element is removed and
-    /// `Some(_)` is returned. Otherwise, `None` is returned.
+    /// The returned iterator yields each key that is in either tree once, in
+    /// ascending order: `Merged::Left` for elements only in this tree,
+    /// `Merged::Right` for elements only in `other`, and `Merged::Both` for a
+    /// pair of equal elements. Neither tree is splayed.
+    ///
+    /// To diff two snapshots of an index, use `diff`, which names the three
+    /// cases accordingly.
     #[inline]
-    pub fn pop_max(&mut self) -> Option<&'a T::Elem> {
-        unsafe { self.tree.pop_max().map(|node| T::node_to_elem(node)) }
+    pub fn merge_iter<'t>(&'t self, other: &'t SplayTree<'a, T>) -> MergeIter<'t, 'a, T>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        MergeIter::new(self.iter(), other.iter())
+    }
+
+    /// Compare this tree, as an old snapshot of an index, to `other`, as a
+    /// new one, in a single linear merge.
+    ///
+    /// The returned iterator yields each key that is in either tree once, in
+    /// ascending order: `DiffEntry::Removed` for elements only in this tree,
+    /// `DiffEntry::Added` for elements only in `other`, and `DiffEntry::Kept`
+    /// for a pair of equal elements, which can be compared further for
+    /// changes that don't affect the key. Neither tree is splayed.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, DiffEntry, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct File<'a> {
+    ///     path: &'static str,
+    ///     hash: u64,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByPath;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByPath
+    ///     where
+    ///         type Elem = File<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByPath> for File<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a File<'a>) -> Ordering {
+    ///         self.path.cmp(rhs.path)
+    ///     }
+    /// }
+    ///
+    /// let file = |(path, hash)| File { path, hash, node: Node::new() };
+    /// let old_files = [("a", 1), ("b", 2), ("c", 3)].map(file);
+    /// let new_files = [("b", 2), ("c", 4), ("d", 5)].map(file);
+    ///
+    /// let mut old = SplayTree::<ByPath>::new();
+    /// let mut new = SplayTree::<ByPath>::new();
+    /// for (a, b) in old_files.iter().zip(&new_files) {
+    ///     old.insert(a);
+    ///     new.insert(b);
+    /// }
+    ///
+    /// let mut changes = vec![];
+    /// for entry in old.diff(&new) {
+    ///     match entry {
+    ///         DiffEntry::Removed(file) => changes.push(("removed", file.path)),
+    ///         DiffEntry::Added(file) => changes.push(("added", file.path)),
+    ///         DiffEntry::Kept(a, b) if a.hash != b.hash => changes.push(("changed", a.path)),
+    ///         DiffEntry::Kept(..) => {}
+    ///     }
+    /// }
+    /// assert_eq!(changes, [("removed", "a"), ("changed", "c"), ("added", "d")]);
+    /// ```
+    #[inline]
+    pub fn diff<'t>(&'t self, other: &'t SplayTree<'a, T>) -> Diff<'t, 'a, T>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        Diff::new(self.merge_iter(other))
     }
 
     /// Walk the tree in order.
@@ -347,12 +2850,436 @@ where
     {
         let mut result = None;
         self.tree.walk(&mut |node| unsafe {
-            let elem = T::node_to_elem(node);
+            let elem = self.brand.elem::<T>(node);
             result = f(elem).should_break();
             result.is_none()
         });
         result
     }
+
+    /// Does any element satisfy `pred`?
+    ///
+    /// This walks the tree in order, without splaying, and stops at the first
+    /// element that does.
+    #[inline]
+    pub fn any<P>(&self, mut pred: P) -> bool
+    where
+        P: FnMut(&'a T::Elem) -> bool,
+    {
+        self.walk(|elem| if pred(elem) { Some(()) } else { None })
+            .is_some()
+    }
+
+    /// Does every element satisfy `pred`? This is `true` for an empty tree.
+    ///
+    /// This walks the tree in order, without splaying, and stops at the first
+    /// element that doesn't.
+    #[inline]
+    pub fn all<P>(&self, mut pred: P) -> bool
+    where
+        P: FnMut(&'a T::Elem) -> bool,
+    {
+        !self.any(|elem| !pred(elem))
+    }
+
+    /// Find the smallest element that satisfies `pred`.
+    ///
+    /// Unlike `find`, `pred` can test anything about an element, not just
+    /// where it falls in key order, so this walks the tree in order, without
+    /// splaying, until it finds one: *O(n)* time in the worst case.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Task<'a> {
+    ///     id: u32,
+    ///     done: bool,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ById;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ById
+    ///     where
+    ///         type Elem = Task<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ById> for Task<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Task<'a>) -> Ordering {
+    ///         self.id.cmp(&rhs.id)
+    ///     }
+    /// }
+    ///
+    /// let tasks = [(3, false), (1, true), (2, false)]
+    ///     .map(|(id, done)| Task { id, done, node: Node::new() });
+    /// let mut tree = SplayTree::<ById>::new();
+    /// for task in &tasks {
+    ///     tree.insert(task);
+    /// }
+    ///
+    /// assert_eq!(tree.find_first(|t| !t.done).map(|t| t.id), Some(2));
+    /// assert!(tree.any(|t| t.done));
+    /// assert!(!tree.all(|t| t.done));
+    /// ```
+    #[inline]
+    pub fn find_first<P>(&self, mut pred: P) -> Option<&'a T::Elem>
+    where
+        P: FnMut(&'a T::Elem) -> bool,
+    {
+        self.walk(|elem| if pred(elem) { Some(elem) } else { None })
+    }
+
+    /// Display the elements in order, with `separator` between each pair.
+    ///
+    /// The tree's own `Display` implementation uses `", "`. The tree is not
+    /// splayed.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    /// use std::fmt;
+    ///
+    /// struct Word<'a> {
+    ///     text: &'static str,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// impl fmt::Display for Word<'_> {
+    ///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    ///         f.write_str(self.text)
+    ///     }
+    /// }
+    ///
+    /// struct Words;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Words
+    ///     where
+    ///         type Elem = Word<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Words> for Word<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Word<'a>) -> Ordering {
+    ///         self.text.cmp(rhs.text)
+    ///     }
+    /// }
+    ///
+    /// let words = ["pear", "apple", "fig"].map(|text| Word { text, node: Node::new() });
+    /// let mut tree = SplayTree::<Words>::new();
+    /// for word in &words {
+    ///     tree.insert(word);
+    /// }
+    ///
+    /// assert_eq!(tree.to_string(), "apple, fig, pear");
+    /// assert_eq!(tree.display_separated(" | ").to_string(), "apple | fig | pear");
+    /// ```
+    #[inline]
+    pub fn display_separated<'t, 's>(
+        &'t self,
+        separator: &'s str,
+    ) -> DisplaySeparated<'t, 's, 'a, T> {
+        DisplaySeparated::new(self, separator)
+    }
+
+    /// Walk the tree in order, threading an accumulator through `f`, and
+    /// return its final value.
+    ///
+    /// This is `walk` for reductions that visit every element, like sums,
+    /// which would otherwise need a mutable capture. The tree is not splayed.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Item<'a> {
+    ///     weight: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByWeight;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByWeight
+    ///     where
+    ///         type Elem = Item<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByWeight> for Item<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Item<'a>) -> Ordering {
+    ///         self.weight.cmp(&rhs.weight)
+    ///     }
+    /// }
+    ///
+    /// let items = [3, 1, 4].map(|weight| Item { weight, node: Node::new() });
+    /// let mut tree = SplayTree::<ByWeight>::new();
+    /// for item in &items {
+    ///     tree.insert(item);
+    /// }
+    ///
+    /// assert_eq!(tree.fold(0, |total, item| total + item.weight), 8);
+    /// let digits = tree.fold(0, |n, item| n * 10 + item.weight);
+    /// assert_eq!(digits, 134);
+    /// ```
+    #[inline]
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where
+        F: FnMut(B, &'a T::Elem) -> B,
+        T::Elem: TreeOrd<'a, T>,
+    {
+        self.iter().fold(init, f)
+    }
+
+    /// Walk the tree in order, and remove every element that `same` says
+    /// duplicates the last element that was kept, returning the removed
+    /// elements as a tree of their own.
+    ///
+    /// `same` is called with the kept element and the one after it, like
+    /// `Vec::dedup_by`, so a run of elements that it deems equal keeps only
+    /// its first one. This is for compacting trees whose order is finer than
+    /// the notion of a duplicate, like events ordered by key and then
+    /// timestamp. Each step splays the next element, so this takes amortized
+    /// *O(n)* time.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Event<'a> {
+    ///     key: char,
+    ///     time: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct Events;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Events
+    ///     where
+    ///         type Elem = Event<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Events> for Event<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Event<'a>) -> Ordering {
+    ///         (self.key, self.time).cmp(&(rhs.key, rhs.time))
+    ///     }
+    /// }
+    ///
+    /// let events = [('a', 3), ('b', 1), ('a', 1), ('a', 2)]
+    ///     .map(|(key, time)| Event { key, time, node: Node::new() });
+    /// let mut tree = SplayTree::<Events>::new();
+    /// for event in &events {
+    ///     tree.insert(event);
+    /// }
+    ///
+    /// // Keep the earliest event for each key.
+    /// let removed = tree.dedup_by(|kept, next| kept.key == next.key);
+    /// let kept = tree.fold(vec![], |mut v, e| { v.push((e.key, e.time)); v });
+    /// assert_eq!(kept, [('a', 1), ('b', 1)]);
+    /// let removed = removed.fold(vec![], |mut v, e| { v.push((e.key, e.time)); v });
+    /// assert_eq!(removed, [('a', 2), ('a', 3)]);
+    /// ```
+    pub fn dedup_by<F>(&mut self, mut same: F) -> SplayTree<'a, T>
+    where
+        F: FnMut(&'a T::Elem, &'a T::Elem) -> bool,
+    {
+        let mut removed = self.split_off_tree(internal::SplayTree::new());
+        let mut kept = match self.tree.min() {
+            Some(min) => unsafe { self.brand.elem::<T>(min) },
+            None => return removed,
+        };
+        // `kept` is always at the root, so its successor is splayed up from
+        // the root's right subtree, and popping that successor, whose left
+        // subtree then ends at `kept`, splays `kept` back to the root.
+        while let Some(next) = self.tree.splay_next() {
+            let elem = unsafe { self.brand.elem::<T>(next) };
+            if same(kept, elem) {
+                let popped = self.pop_root();
+                debug_assert!(popped.is_some_and(|p| core::ptr::eq(p, elem)));
+                // These arrive in ascending order, so each one goes on top.
+                removed.tree.push_max(next);
+                removed.brand.brand(next);
+            } else {
+                kept = elem;
+            }
+        }
+        removed
+    }
+
+    /// Walk the tree in order, removing the elements for which `f` returns
+    /// `Verdict::Remove`, until it returns `Verdict::Stop` or every element has
+    /// been visited.
+    ///
+    /// Returns the number of elements removed. Each step splays the next
+    /// element, so a complete walk takes amortized `O(n)` time.
+    pub fn walk_and_remove<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&'a T::Elem) -> Verdict,
+    {
+        let mut removed = 0;
+        // The element being visited is always at the root.
+        let mut next = self.tree.min();
+        while let Some(node) = next {
+            next = match f(unsafe { self.brand.elem::<T>(node) }) {
+                Verdict::Keep => self.tree.splay_next(),
+                Verdict::Remove => {
+                    // Popping the root splays its predecessor up in its
+                    // place, if it has one, and otherwise it was the minimum.
+                    let first = node.left.get().is_none();
+                    self.pop_root();
+                    removed += 1;
+                    if first {
+                        self.tree.min()
+                    } else {
+                        self.tree.splay_next()
+                    }
+                }
+                Verdict::Stop => break,
+            };
+        }
+        removed
+    }
+
+    /// Take a read-only snapshot of the tree's elements, stored in `buf`.
+    ///
+    /// Returns `None` if `buf` is too small to hold every element. This tree
+    /// is not splayed.
+    pub fn freeze_into<'v>(
+        &self,
+        buf: &'v mut [MaybeUninit<&'a T::Elem>],
+    ) -> Option<FrozenView<'v, 'a, T>>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        let mut len = 0;
+        for elem in self.iter() {
+            buf.get_mut(len)?.write(elem);
+            len += 1;
+        }
+        // The first `len` entries of `buf` were just initialized.
+        let elems = unsafe { slice::from_raw_parts(buf.as_ptr().cast::<&'a T::Elem>(), len) };
+        Some(FrozenView::new(elems))
+    }
+
+    /// Take a read-only snapshot of the tree's elements, stored in `buf`,
+    /// replacing its contents.
+    ///
+    /// Requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn freeze<'v>(&self, buf: &'v mut alloc::vec::Vec<&'a T::Elem>) -> FrozenView<'v, 'a, T>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        buf.clear();
+        buf.extend(self.iter());
+        FrozenView::new(buf)
+    }
+
+    /// Iterate over a snapshot of the tree's elements, in order, which is
+    /// captured up front so that the tree can be changed while iterating.
+    ///
+    /// The snapshot doesn't borrow the tree, so the loop over it can insert
+    /// and remove elements, including the ones in the snapshot. It still
+    /// yields exactly the elements that were in the tree when it was taken,
+    /// whether or not they are in the tree anymore. This allocates a buffer
+    /// for all of them, and doesn't splay the tree.
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Timer<'a> {
+    ///     deadline: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct ByDeadline;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for ByDeadline
+    ///     where
+    ///         type Elem = Timer<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, ByDeadline> for Timer<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Timer<'a>) -> Ordering {
+    ///         self.deadline.cmp(&rhs.deadline)
+    ///     }
+    /// }
+    ///
+    /// let timers = [10, 20, 30].map(|deadline| Timer { deadline, node: Node::new() });
+    /// let rearmed = [15, 25].map(|deadline| Timer { deadline, node: Node::new() });
+    /// let mut tree = SplayTree::<ByDeadline>::new();
+    /// for timer in &timers {
+    ///     tree.insert(timer);
+    /// }
+    ///
+    /// // Fire the first two timers, and rearm each of them for later.
+    /// for (timer, next) in tree.snapshot_iter().zip(&rearmed) {
+    ///     tree.remove(timer);
+    ///     tree.insert(next);
+    /// }
+    /// let mut deadlines = vec![];
+    /// tree.walk(|t| deadlines.push(t.deadline));
+    /// assert_eq!(deadlines, [15, 25, 30]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn snapshot_iter(&self) -> alloc::vec::IntoIter<&'a T::Elem>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        self.collect_refs().into_iter()
+    }
+
+    /// Collect references to all of the tree's elements into a `Vec`, in
+    /// order.
+    ///
+    /// Requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn collect_refs(&self) -> alloc::vec::Vec<&'a T::Elem>
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        self.iter().collect()
+    }
+
+    /// Remove all of the tree's elements, and return them in a `Vec`, in
+    /// order.
+    ///
+    /// Unlike dropping the tree after `collect_refs`, this leaves every
+    /// element unlinked and ready to be inserted into another tree.
+    ///
+    /// Requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn into_sorted_vec(mut self) -> alloc::vec::Vec<&'a T::Elem> {
+        let mut elems = alloc::vec::Vec::new();
+        while let Some(elem) = self.pop_min() {
+            elems.push(elem);
+        }
+        elems
+    }
+}
+
+/// What `SplayTree::walk_and_remove` should do with the element it just
+/// visited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Leave the element in the tree, and move on to the next one.
+    Keep,
+
+    /// Remove the element from the tree, and move on to the next one.
+    Remove,
+
+    /// Leave the element in the tree, and stop walking.
+    Stop,
 }
 
 /// A trait that guides whether `SplayTree::walk` should continue or break, and
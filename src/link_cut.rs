@@ -0,0 +1,494 @@
+//! Link-cut trees, for maintaining a forest of rooted trees under links and
+//! cuts.
+
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr;
+
+/// The intrusive links and path aggregate of a vertex in a `LinkCutTree`.
+///
+/// Like `Node`, this is embedded in the vertex type, and must outlive the
+/// forest it is used in. Each vertex starts out as a single-vertex tree.
+pub struct LinkCutNode<'a, A: Copy> {
+    left: Cell<Option<&'a LinkCutNode<'a, A>>>,
+    right: Cell<Option<&'a LinkCutNode<'a, A>>>,
+    // The parent within this node's splay tree, or, for the root of a splay
+    // tree, the path-parent: the vertex that the path hangs off of.
+    parent: Cell<Option<&'a LinkCutNode<'a, A>>>,
+    // Whether this node's subtree is pending a left-right swap.
+    reversed: Cell<bool>,
+    value: Cell<A>,
+    // The aggregate of every value in this node's splay subtree.
+    sum: Cell<A>,
+}
+
+impl<'a, A: Copy> LinkCutNode<'a, A> {
+    /// Create a new, unlinked vertex node with the given value.
+    #[inline]
+    pub const fn new(value: A) -> Self {
+        LinkCutNode {
+            left: Cell::new(None),
+            right: Cell::new(None),
+            parent: Cell::new(None),
+            reversed: Cell::new(false),
+            value: Cell::new(value),
+            sum: Cell::new(value),
+        }
+    }
+
+    /// Get this vertex's own value.
+    #[inline]
+    pub fn value(&self) -> A {
+        self.value.get()
+    }
+}
+
+impl<'a, A: Copy + Default> Default for LinkCutNode<'a, A> {
+    #[inline]
+    fn default() -> Self {
+        LinkCutNode::new(A::default())
+    }
+}
+
+impl<'a, A: Copy + fmt::Debug> fmt::Debug for LinkCutNode<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LinkCutNode")
+            .field("value", &self.value.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Defines how to get the `LinkCutNode` from a vertex type, and how to
+/// aggregate vertex values along paths.
+///
+/// Don't implement this by hand -- use the `impl_intrusive_vertex!` macro.
+///
+/// ## Safety
+///
+/// `elem_to_node` must always return the same `LinkCutNode` field of the given
+/// vertex, and `node_to_elem` must be its exact inverse. No other forest type
+/// may use that same field.
+pub unsafe trait IntrusiveVertex<'a>: Sized {
+    /// The vertex type that contains a node for this forest.
+    type Elem: 'a;
+
+    /// The type of values that are aggregated along paths.
+    type Aggregate: Copy;
+
+    /// Get the node for this forest from the given vertex.
+    fn elem_to_node(elem: &'a Self::Elem) -> &'a LinkCutNode<'a, Self::Aggregate>;
+
+    /// Get the vertex for this node.
+    ///
+    /// ## Safety
+    ///
+    /// Given a node that is not this forest's node within a vertex of type
+    /// `Self::Elem`, this method will result in memory unsafety.
+    #[doc(hidden)]
+    unsafe fn node_to_elem(node: &'a LinkCutNode<'a, Self::Aggregate>) -> &'a Self::Elem;
+
+    /// Combine two aggregates.
+    ///
+    /// This must be associative and commutative, like sum, min, and max,
+    /// because paths are aggregated in whichever direction the forest happens
+    /// to store them.
+    fn combine(a: Self::Aggregate, b: Self::Aggregate) -> Self::Aggregate;
+}
+
+/// Implement `IntrusiveVertex` for a forest type.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_vertex, LinkCutNode};
+///
+/// struct Router<'a> {
+///     vertex: LinkCutNode<'a, u32>,
+/// }
+///
+/// struct Network;
+///
+/// impl_intrusive_vertex! {
+///     impl<'a> IntrusiveVertex<'a> for Network
+///     where
+///         type Elem = Router<'a>,
+///         type Aggregate = u32,
+///         node = vertex,
+///         combine = |a: u32, b| a.min(b);
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! impl_intrusive_vertex {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_intrusive_vertex [] $( $rest )*);
+    };
+    (
+        @generics [ $( $params:tt )* ]
+        IntrusiveVertex< $lt:lifetime > for $forest:ty
+        where
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_vertex!(
+            @bounds [ $( $params )* ] [ $lt ] [ $forest ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `type Elem`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $forest:ty ] [ $( $bounds:tt )* ]
+        type Elem = $elem:ty ,
+        type Aggregate = $agg:ty ,
+        node = $node:ident ,
+        combine = $combine:expr ;
+        $( $rest:tt )*
+    ) => {
+        unsafe impl< $( $params )* > $crate::IntrusiveVertex< $lt > for $forest
+        where
+            $( $bounds )*
+        {
+            type Elem = $elem;
+            type Aggregate = $agg;
+
+            fn elem_to_node(
+                elem: & $lt Self::Elem,
+            ) -> & $lt $crate::LinkCutNode< $lt, Self::Aggregate > {
+                &elem. $node
+            }
+
+            unsafe fn node_to_elem(
+                node: & $lt $crate::LinkCutNode< $lt, Self::Aggregate >,
+            ) -> & $lt Self::Elem {
+                let offset = ::core::mem::offset_of!(Self::Elem, $node);
+
+                let node = node as *const _ as *const u8;
+                let elem = node.wrapping_sub(offset) as *const Self::Elem;
+                &*elem
+            }
+
+            fn combine(a: Self::Aggregate, b: Self::Aggregate) -> Self::Aggregate {
+                ($combine)(a, b)
+            }
+        }
+
+        $crate::impl_intrusive_vertex!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $forest:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_vertex!(
+            @bounds [ $( $params )* ] [ $lt ] [ $forest ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
+/// A forest of trees over intrusive vertices, maintained with link-cut trees.
+///
+/// Each tree of the forest is represented as a set of vertex-disjoint paths,
+/// each in its own splay tree, so that linking two trees with an edge,
+/// cutting an edge, finding a tree's root, and aggregating the values along
+/// the path between two vertices all take *O(log n)* amortized time. Nothing
+/// is allocated: the forest lives entirely in the vertices' `LinkCutNode`s,
+/// and this type is just a handle for operating on it.
+///
+/// The trees are unrooted from the outside: `link` and `path_aggregate` reroot
+/// trees as they need to, and `find_root` reports whichever vertex is the
+/// root at the time, which is only stable between links and cuts.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_vertex, LinkCutNode, LinkCutTree};
+///
+/// struct Router<'a> {
+///     bandwidth: u32,
+///     vertex: LinkCutNode<'a, u32>,
+/// }
+///
+/// struct Network;
+///
+/// impl_intrusive_vertex! {
+///     impl<'a> IntrusiveVertex<'a> for Network
+///     where
+///         type Elem = Router<'a>,
+///         type Aggregate = u32,
+///         node = vertex,
+///         combine = |a: u32, b| a.min(b);
+/// }
+///
+/// let routers = [100, 10, 50, 80].map(|bandwidth| Router {
+///     bandwidth,
+///     vertex: LinkCutNode::new(bandwidth),
+/// });
+/// let [a, b, c, d] = &routers;
+///
+/// let mut network = LinkCutTree::<Network>::new();
+/// assert!(network.link(a, b));
+/// assert!(network.link(b, c));
+/// assert!(network.link(a, d));
+/// assert!(!network.link(c, d), "already connected");
+///
+/// // The bottleneck between `c` and `d` is `b`.
+/// assert_eq!(network.path_aggregate(c, d), Some(10));
+///
+/// assert!(network.cut(a, b));
+/// assert!(!network.connected(c, d));
+/// assert_eq!(network.path_aggregate(a, d), Some(80));
+/// ```
+pub struct LinkCutTree<'a, T>
+where
+    T: 'a + IntrusiveVertex<'a>,
+{
+    _phantom: PhantomData<&'a T::Elem>,
+}
+
+impl<'a, T> Default for LinkCutTree<'a, T>
+where
+    T: 'a + IntrusiveVertex<'a>,
+{
+    #[inline]
+    fn default() -> Self {
+        LinkCutTree::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for LinkCutTree<'a, T>
+where
+    T: 'a + IntrusiveVertex<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LinkCutTree").finish_non_exhaustive()
+    }
+}
+
+type VertexNode<'a, T> = LinkCutNode<'a, <T as IntrusiveVertex<'a>>::Aggregate>;
+
+impl<'a, T> LinkCutTree<'a, T>
+where
+    T: 'a + IntrusiveVertex<'a>,
+{
+    /// Get a handle to the forest of `T` vertices.
+    #[inline]
+    pub const fn new() -> Self {
+        LinkCutTree {
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Add an edge between `u` and `v`, joining their trees.
+    ///
+    /// Returns `false`, without adding the edge, if `u` and `v` are already in
+    /// the same tree.
+    pub fn link(&mut self, u: &'a T::Elem, v: &'a T::Elem) -> bool {
+        let (u, v) = (T::elem_to_node(u), T::elem_to_node(v));
+        Self::make_root(u);
+        if ptr::eq(Self::find_root_node(v), u) {
+            return false;
+        }
+        // `u` is now the root of its tree and of its splay tree, so hanging it
+        // off of `v` as a path-parent adds the edge.
+        u.parent.set(Some(v));
+        true
+    }
+
+    /// Remove the edge between `u` and `v`, splitting their tree in two.
+    ///
+    /// Returns `false` if there is no such edge.
+    pub fn cut(&mut self, u: &'a T::Elem, v: &'a T::Elem) -> bool {
+        let (u, v) = (T::elem_to_node(u), T::elem_to_node(v));
+        Self::make_root(u);
+        Self::access(v);
+        // Now the path from `u` to `v` is exactly `v`'s splay tree, and there
+        // is an edge between them when that path is just the two of them.
+        match v.left.get() {
+            Some(left) if ptr::eq(left, u) => {
+                Self::push(u);
+                if u.right.get().is_some() {
+                    return false;
+                }
+                v.left.set(None);
+                u.parent.set(None);
+                Self::update(v);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the root of `v`'s tree.
+    #[inline]
+    pub fn find_root(&mut self, v: &'a T::Elem) -> &'a T::Elem {
+        let root = Self::find_root_node(T::elem_to_node(v));
+        unsafe { T::node_to_elem(root) }
+    }
+
+    /// Are `u` and `v` in the same tree?
+    #[inline]
+    pub fn connected(&mut self, u: &'a T::Elem, v: &'a T::Elem) -> bool {
+        let (u, v) = (T::elem_to_node(u), T::elem_to_node(v));
+        ptr::eq(Self::find_root_node(u), Self::find_root_node(v))
+    }
+
+    /// Combine the values of every vertex on the path between `u` and `v`,
+    /// inclusive, with `T::combine`.
+    ///
+    /// Returns `None` if `u` and `v` are not in the same tree.
+    pub fn path_aggregate(&mut self, u: &'a T::Elem, v: &'a T::Elem) -> Option<T::Aggregate> {
+        if !self.connected(u, v) {
+            return None;
+        }
+        let (u, v) = (T::elem_to_node(u), T::elem_to_node(v));
+        Self::make_root(u);
+        Self::access(v);
+        Some(v.sum.get())
+    }
+
+    /// Set `v`'s value.
+    #[inline]
+    pub fn set_value(&mut self, v: &'a T::Elem, value: T::Aggregate) {
+        let v = T::elem_to_node(v);
+        Self::access(v);
+        v.value.set(value);
+        Self::update(v);
+    }
+
+    fn is_splay_root(x: &'a VertexNode<'a, T>) -> bool {
+        match x.parent.get() {
+            None => true,
+            Some(p) => {
+                !p.left.get().is_some_and(|l| ptr::eq(l, x))
+                    && !p.right.get().is_some_and(|r| ptr::eq(r, x))
+            }
+        }
+    }
+
+    /// Apply `x`'s pending reversal to its children.
+    fn push(x: &'a VertexNode<'a, T>) {
+        if x.reversed.replace(false) {
+            let (left, right) = (x.left.get(), x.right.get());
+            x.left.set(right);
+            x.right.set(left);
+            for child in [left, right].into_iter().flatten() {
+                child.reversed.set(!child.reversed.get());
+            }
+        }
+    }
+
+    /// Recompute `x`'s aggregate from its children's.
+    fn update(x: &'a VertexNode<'a, T>) {
+        let mut sum = x.value.get();
+        if let Some(left) = x.left.get() {
+            sum = T::combine(left.sum.get(), sum);
+        }
+        if let Some(right) = x.right.get() {
+            sum = T::combine(sum, right.sum.get());
+        }
+        x.sum.set(sum);
+    }
+
+    /// Rotate `x` above its parent, which must be in the same splay tree.
+    fn rotate(x: &'a VertexNode<'a, T>) {
+        let p = x.parent.get().expect("rotated node has a parent");
+        let g = p.parent.get();
+        let p_was_root = Self::is_splay_root(p);
+
+        if p.left.get().is_some_and(|l| ptr::eq(l, x)) {
+            let b = x.right.get();
+            p.left.set(b);
+            if let Some(b) = b {
+                b.parent.set(Some(p));
+            }
+            x.right.set(Some(p));
+        } else {
+            let b = x.left.get();
+            p.right.set(b);
+            if let Some(b) = b {
+                b.parent.set(Some(p));
+            }
+            x.left.set(Some(p));
+        }
+        p.parent.set(Some(x));
+        // If `p` was a splay root, `x` inherits its path-parent.
+        x.parent.set(g);
+        if let (false, Some(g)) = (p_was_root, g) {
+            if g.left.get().is_some_and(|l| ptr::eq(l, p)) {
+                g.left.set(Some(x));
+            } else {
+                g.right.set(Some(x));
+            }
+        }
+
+        Self::update(p);
+        Self::update(x);
+    }
+
+    /// Splay `x` to the root of its splay tree.
+    ///
+    /// Reversals pending above `x`'s grandparent cover both the old and new
+    /// shape of the rotated nodes, so they can stay pending; only the flags of
+    /// the rotated nodes themselves must be applied first.
+    fn splay(x: &'a VertexNode<'a, T>) {
+        while !Self::is_splay_root(x) {
+            let p = x.parent.get().expect("non-root node has a parent");
+            if Self::is_splay_root(p) {
+                Self::push(p);
+                Self::push(x);
+            } else {
+                let g = p.parent.get().expect("non-root node has a parent");
+                Self::push(g);
+                Self::push(p);
+                Self::push(x);
+                let x_is_left = p.left.get().is_some_and(|l| ptr::eq(l, x));
+                let p_is_left = g.left.get().is_some_and(|l| ptr::eq(l, p));
+                if x_is_left == p_is_left {
+                    Self::rotate(p);
+                } else {
+                    Self::rotate(x);
+                }
+            }
+            Self::rotate(x);
+        }
+        Self::push(x);
+    }
+
+    /// Make the path from `x`'s tree's root to `x` into `x`'s splay tree,
+    /// with `x` at its root and nothing deeper than `x` on it.
+    fn access(x: &'a VertexNode<'a, T>) {
+        let mut last = None;
+        let mut next = Some(x);
+        while let Some(n) = next {
+            Self::splay(n);
+            // The old deeper part of the path stays hanging off of `n` as a
+            // path-parent.
+            n.right.set(last);
+            Self::update(n);
+            last = Some(n);
+            next = n.parent.get();
+        }
+        Self::splay(x);
+    }
+
+    /// Make `x` the root of its tree.
+    fn make_root(x: &'a VertexNode<'a, T>) {
+        Self::access(x);
+        x.reversed.set(!x.reversed.get());
+        Self::push(x);
+    }
+
+    fn find_root_node(x: &'a VertexNode<'a, T>) -> &'a VertexNode<'a, T> {
+        Self::access(x);
+        let mut root = x;
+        loop {
+            Self::push(root);
+            match root.left.get() {
+                Some(left) => root = left,
+                None => break,
+            }
+        }
+        // Splay the root, to pay for the walk down to it.
+        Self::splay(root);
+        root
+    }
+}
@@ -0,0 +1,417 @@
+//! A splay tree combined with an intrusive recency list.
+
+use crate::{IntrusiveNode, Node, SplayTree, TreeOrd};
+use core::fmt;
+
+/// Defines how to get the intrusive recency-list node from a `SplayLru`'s
+/// element type.
+///
+/// The recency list reuses the `Node` type for its links, so an element in a
+/// `SplayLru` has two `Node` fields: one for the tree and one for the list.
+///
+/// Don't implement this by hand -- use the `impl_intrusive_lru_node!` macro.
+///
+/// ## Safety
+///
+/// `elem_to_lru_node` must always return the same `Node` field of the given
+/// element, that field must be different from the one that `IntrusiveNode`
+/// uses, and `lru_node_to_elem` must be its exact inverse.
+pub unsafe trait IntrusiveLruNode<'a>: IntrusiveNode<'a, Elem: TreeOrd<'a, Self>> {
+    /// Get the recency-list node from the given element.
+    fn elem_to_lru_node(elem: &'a Self::Elem) -> &'a Node<'a>;
+
+    /// Get the element for this recency-list node.
+    ///
+    /// ## Safety
+    ///
+    /// Given a node that is not this tree's recency-list node within an element
+    /// of type `Self::Elem`, this method will result in memory unsafety.
+    #[doc(hidden)]
+    unsafe fn lru_node_to_elem(node: &'a Node<'a>) -> &'a Self::Elem;
+}
+
+/// Implement `IntrusiveLruNode` for a tree type that already implements
+/// `IntrusiveNode`.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_lru_node, impl_intrusive_node, Node};
+///
+/// struct Entry<'a> {
+///     key: u32,
+///     tree_node: Node<'a>,
+///     lru_node: Node<'a>,
+/// }
+///
+/// struct Cache;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Cache
+///     where
+///         type Elem = Entry<'a>,
+///         node = tree_node;
+/// }
+///
+/// impl_intrusive_lru_node! {
+///     impl<'a> IntrusiveLruNode<'a> for Cache
+///     where
+///         lru_node = lru_node;
+/// }
+/// # impl<'a> intrusive_splay_tree::TreeOrd<'a, Cache> for Entry<'a> {
+/// #     fn tree_cmp(&self, rhs: &Entry<'a>) -> std::cmp::Ordering {
+/// #         self.key.cmp(&rhs.key)
+/// #     }
+/// # }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! impl_intrusive_lru_node {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_intrusive_lru_node [] $( $rest )*);
+    };
+    (
+        @generics [ $( $params:tt )* ]
+        IntrusiveLruNode< $lt:lifetime > for $tree:ty
+        where
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_lru_node!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `lru_node`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        lru_node = $node:ident ;
+        $( $rest:tt )*
+    ) => {
+        unsafe impl< $( $params )* > $crate::IntrusiveLruNode< $lt > for $tree
+        where
+            $( $bounds )*
+        {
+            fn elem_to_lru_node(elem: & $lt Self::Elem) -> & $lt $crate::Node< $lt > {
+                &elem. $node
+            }
+
+            unsafe fn lru_node_to_elem(node: & $lt $crate::Node< $lt >) -> & $lt Self::Elem {
+                let offset = ::core::mem::offset_of!(Self::Elem, $node);
+
+                let node = node as *const _ as *const u8;
+                let elem = node.wrapping_sub(offset) as *const Self::Elem;
+                &*elem
+            }
+        }
+
+        $crate::impl_intrusive_lru_node!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_intrusive_lru_node!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
+/// An intrusive, doubly-linked list of nodes, ordered from most to least
+/// recently used.
+///
+/// Like `internal::SplayTree`, this is not generic, so that it is only
+/// compiled once. A node's `left` link points to the next more recently used
+/// node, and its `right` link to the next less recently used node.
+#[derive(Debug, Default)]
+struct RecencyList<'a> {
+    most_recent: Option<&'a Node<'a>>,
+    least_recent: Option<&'a Node<'a>>,
+    len: usize,
+}
+
+impl<'a> RecencyList<'a> {
+    const fn new() -> Self {
+        RecencyList {
+            most_recent: None,
+            least_recent: None,
+            len: 0,
+        }
+    }
+
+    fn push_most_recent(&mut self, node: &'a Node<'a>) {
+        debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+        node.right.set(self.most_recent);
+        match self.most_recent {
+            Some(old) => old.left.set(Some(node)),
+            None => self.least_recent = Some(node),
+        }
+        self.most_recent = Some(node);
+        self.len += 1;
+    }
+
+    fn unlink(&mut self, node: &'a Node<'a>) {
+        let more = node.left.take();
+        let less = node.right.take();
+        match more {
+            Some(more) => more.right.set(less),
+            None => self.most_recent = less,
+        }
+        match less {
+            Some(less) => less.left.set(more),
+            None => self.least_recent = more,
+        }
+        self.len -= 1;
+    }
+
+    fn touch(&mut self, node: &'a Node<'a>) {
+        if !self.most_recent.is_some_and(|n| core::ptr::eq(n, node)) {
+            self.unlink(node);
+            self.push_most_recent(node);
+        }
+    }
+}
+
+/// An intrusive splay tree whose elements are also threaded onto an intrusive
+/// recency list.
+///
+/// This is the classic cache layout: lookup by key is *O(log n)* amortized,
+/// while finding and evicting the least recently used element is *O(1)* plus
+/// the cost of removing it from the tree.
+///
+/// The tree type `T` must implement both `IntrusiveNode`, for the tree node,
+/// and `IntrusiveLruNode`, for the recency-list node.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_lru_node, impl_intrusive_node, Node, SplayLru, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Entry<'a> {
+///     key: u32,
+///     tree_node: Node<'a>,
+///     lru_node: Node<'a>,
+/// }
+///
+/// struct Cache;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Cache
+///     where
+///         type Elem = Entry<'a>,
+///         node = tree_node;
+/// }
+///
+/// impl_intrusive_lru_node! {
+///     impl<'a> IntrusiveLruNode<'a> for Cache
+///     where
+///         lru_node = lru_node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, Cache> for Entry<'a> {
+///     fn tree_cmp(&self, rhs: &Entry<'a>) -> Ordering {
+///         self.key.cmp(&rhs.key)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, Cache> for u32 {
+///     fn tree_cmp(&self, rhs: &Entry<'a>) -> Ordering {
+///         self.cmp(&rhs.key)
+///     }
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut lru = SplayLru::<Cache>::new();
+/// for key in 0..3 {
+///     lru.insert(arena.alloc(Entry {
+///         key,
+///         tree_node: Node::default(),
+///         lru_node: Node::default(),
+///     }));
+/// }
+///
+/// // Using `0` makes `1` the least recently used entry.
+/// assert!(lru.find(&0).is_some());
+/// assert_eq!(lru.pop_least_recent().unwrap().key, 1);
+/// assert_eq!(lru.pop_least_recent().unwrap().key, 2);
+/// assert_eq!(lru.pop_least_recent().unwrap().key, 0);
+/// assert!(lru.is_empty());
+/// ```
+pub struct SplayLru<'a, T>
+where
+    T: IntrusiveLruNode<'a>,
+    T::Elem: 'a,
+{
+    tree: SplayTree<'a, T>,
+    list: RecencyList<'a>,
+}
+
+impl<'a, T> Default for SplayLru<'a, T>
+where
+    T: 'a + IntrusiveLruNode<'a>,
+    T::Elem: 'a,
+{
+    #[inline]
+    fn default() -> SplayLru<'a, T> {
+        SplayLru::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for SplayLru<'a, T>
+where
+    T: 'a + IntrusiveLruNode<'a>,
+    T::Elem: 'a + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = &mut f.debug_list();
+        self.walk_by_recency(|x| {
+            list.entry(&x);
+        });
+        list.finish()
+    }
+}
+
+impl<'a, T> SplayLru<'a, T>
+where
+    T: 'a + IntrusiveLruNode<'a>,
+{
+    /// Construct a new, empty `SplayLru`.
+    #[inline]
+    pub const fn new() -> Self {
+        SplayLru {
+            tree: SplayTree::new(),
+            list: RecencyList::new(),
+        }
+    }
+
+    /// Is this `SplayLru` empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the number of elements in this `SplayLru`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Get a shared reference to the underlying tree.
+    ///
+    /// This can be used for ordered walks. Mutating the tree directly would
+    /// desynchronize it from the recency list, so only shared access is
+    /// provided.
+    #[inline]
+    pub fn tree(&self) -> &SplayTree<'a, T> {
+        &self.tree
+    }
+
+    /// Insert a new element, making it the most recently used element.
+    ///
+    /// Returns `true` if the element was inserted. Returns `false`, and leaves
+    /// everything unchanged, if there was already an element for which
+    /// `TreeOrd` returned `Ordering::Equal`.
+    ///
+    /// It is a logic error to insert an element that is already inserted in a
+    /// `T` tree.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        if !self.tree.insert(elem) {
+            return false;
+        }
+        self.list.push_most_recent(T::elem_to_lru_node(elem));
+        true
+    }
+
+    /// Find an element, and mark it as the most recently used element.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let elem = self.tree.find(key)?;
+        self.list.touch(T::elem_to_lru_node(elem));
+        Some(elem)
+    }
+
+    /// Find an element without changing its recency.
+    ///
+    /// This still splays the tree.
+    #[inline]
+    pub fn peek<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree.find(key)
+    }
+
+    /// Get the element with the smallest key, without changing its recency.
+    ///
+    /// This still splays the tree.
+    #[inline]
+    pub fn peek_min(&mut self) -> Option<&'a T::Elem> {
+        self.tree.min()
+    }
+
+    /// Get the element with the largest key, without changing its recency.
+    ///
+    /// This still splays the tree.
+    #[inline]
+    pub fn peek_max(&mut self) -> Option<&'a T::Elem> {
+        self.tree.max()
+    }
+
+    /// Find and remove an element.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let elem = self.tree.remove(key)?;
+        self.list.unlink(T::elem_to_lru_node(elem));
+        Some(elem)
+    }
+
+    /// Get the least recently used element, without changing its recency.
+    #[inline]
+    pub fn least_recent(&self) -> Option<&'a T::Elem> {
+        self.list
+            .least_recent
+            .map(|n| unsafe { T::lru_node_to_elem(n) })
+    }
+
+    /// Get the most recently used element, without changing its recency.
+    #[inline]
+    pub fn most_recent(&self) -> Option<&'a T::Elem> {
+        self.list
+            .most_recent
+            .map(|n| unsafe { T::lru_node_to_elem(n) })
+    }
+
+    /// Remove and return the least recently used element, if any.
+    #[inline]
+    pub fn pop_least_recent(&mut self) -> Option<&'a T::Elem> {
+        let elem = self.least_recent()?;
+        let removed = self.remove(elem);
+        debug_assert!(removed.is_some_and(|r| core::ptr::eq(r, elem)));
+        removed
+    }
+
+    /// Walk the elements from most to least recently used.
+    ///
+    /// See `SplayTree::walk` for how the `C` type controls iteration.
+    pub fn walk_by_recency<F, C>(&self, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(&'a T::Elem) -> C,
+        C: crate::WalkControl,
+    {
+        let mut next = self.list.most_recent;
+        while let Some(node) = next {
+            next = node.right.get();
+            if let Some(result) = f(unsafe { T::lru_node_to_elem(node) }).should_break() {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
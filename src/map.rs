@@ -0,0 +1,282 @@
+//! A key-value view of a tree.
+
+use crate::iter::Elems;
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// A map-flavored API over a `SplayTree`, given projections of each element's
+/// key and value.
+///
+/// The tree is still ordered by `TreeOrd`, which should agree with the key
+/// projection: any key type that implements `TreeOrd<T>` can be used for
+/// lookups.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayMapAdapter, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Setting<'a> {
+///     name: &'static str,
+///     value: u32,
+///     node: Node<'a>,
+/// }
+///
+/// struct ByName;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ByName
+///     where
+///         type Elem = Setting<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ByName> for Setting<'a> {
+///     fn tree_cmp(&self, rhs: &'a Setting<'a>) -> Ordering {
+///         self.name.cmp(rhs.name)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, ByName> for str {
+///     fn tree_cmp(&self, rhs: &'a Setting<'a>) -> Ordering {
+///         self.cmp(rhs.name)
+///     }
+/// }
+///
+/// let settings = [("width", 80), ("height", 24)]
+///     .map(|(name, value)| Setting { name, value, node: Node::default() });
+///
+/// let mut map = SplayMapAdapter::<ByName, _, _>::new(|s| &s.name, |s| &s.value);
+/// for setting in &settings {
+///     map.insert(setting);
+/// }
+///
+/// assert_eq!(map.get("width"), Some(&80));
+/// assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["height", "width"]);
+/// assert_eq!(map.values().copied().collect::<Vec<_>>(), [24, 80]);
+/// ```
+pub struct SplayMapAdapter<'a, T, K, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: 'a + ?Sized,
+    V: 'a + ?Sized,
+{
+    tree: SplayTree<'a, T>,
+    key: fn(&'a T::Elem) -> &'a K,
+    value: fn(&'a T::Elem) -> &'a V,
+}
+
+impl<'a, T, K, V> SplayMapAdapter<'a, T, K, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    K: 'a + ?Sized,
+    V: 'a + ?Sized,
+{
+    /// Create a new, empty map that projects each element into a key and a
+    /// value with the given functions.
+    #[inline]
+    pub fn new(key: fn(&'a T::Elem) -> &'a K, value: fn(&'a T::Elem) -> &'a V) -> Self {
+        SplayMapAdapter::with_tree(SplayTree::new(), key, value)
+    }
+
+    /// Wrap an existing tree.
+    #[inline]
+    pub fn with_tree(
+        tree: SplayTree<'a, T>,
+        key: fn(&'a T::Elem) -> &'a K,
+        value: fn(&'a T::Elem) -> &'a V,
+    ) -> Self {
+        SplayMapAdapter { tree, key, value }
+    }
+
+    /// Get the underlying tree.
+    #[inline]
+    pub fn tree(&self) -> &SplayTree<'a, T> {
+        &self.tree
+    }
+
+    /// Get the underlying tree, mutably.
+    #[inline]
+    pub fn tree_mut(&mut self) -> &mut SplayTree<'a, T> {
+        &mut self.tree
+    }
+
+    /// Unwrap the underlying tree.
+    #[inline]
+    pub fn into_tree(self) -> SplayTree<'a, T> {
+        self.tree
+    }
+
+    /// Is the map empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the value for `key`, if any.
+    #[inline]
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&'a V>
+    where
+        Q: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree.find(key).map(self.value)
+    }
+
+    /// Get the key and value for `key`, if any.
+    #[inline]
+    pub fn get_key_value<Q>(&mut self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        Q: ?Sized + TreeOrd<'a, T>,
+    {
+        let elem = self.tree.find(key)?;
+        Some(((self.key)(elem), (self.value)(elem)))
+    }
+
+    /// Does the map contain `key`?
+    #[inline]
+    pub fn contains_key<Q>(&mut self, key: &Q) -> bool
+    where
+        Q: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree.find(key).is_some()
+    }
+
+    /// Insert an element, as with `SplayTree::insert`.
+    ///
+    /// Returns `false`, leaving the map unchanged, if there is already an
+    /// element with an equal key.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        self.tree.insert(elem)
+    }
+
+    /// Remove the element for `key`, and return it.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<&'a T::Elem>
+    where
+        Q: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree.remove(key)
+    }
+
+    /// Iterate over the keys, in order, without splaying.
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, 'a, T, K> {
+        Keys {
+            elems: self.tree.iter(),
+            key: self.key,
+        }
+    }
+
+    /// Iterate over the values, in key order, without splaying.
+    #[inline]
+    pub fn values(&self) -> Values<'_, 'a, T, V> {
+        Values {
+            elems: self.tree.iter(),
+            value: self.value,
+        }
+    }
+}
+
+impl<'a, T, K, V> fmt::Debug for SplayMapAdapter<'a, T, K, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    K: 'a + ?Sized + fmt::Debug,
+    V: 'a + ?Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.keys().zip(self.values()))
+            .finish()
+    }
+}
+
+/// An iterator over the keys of a `SplayMapAdapter`, in order.
+///
+/// Returned by `SplayMapAdapter::keys`.
+pub struct Keys<'t, 'a, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: 'a + ?Sized,
+{
+    elems: Elems<'t, 'a, T>,
+    key: fn(&'a T::Elem) -> &'a K,
+}
+
+impl<'t, 'a, T, K> Iterator for Keys<'t, 'a, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: 'a + ?Sized,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a K> {
+        self.elems.next().map(self.key)
+    }
+}
+
+impl<'t, 'a, T, K> FusedIterator for Keys<'t, 'a, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    K: 'a + ?Sized,
+{
+}
+
+impl<'t, 'a, T, K> fmt::Debug for Keys<'t, 'a, T, K>
+where
+    T: 'a + IntrusiveNode<'a>,
+    K: 'a + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keys").finish_non_exhaustive()
+    }
+}
+
+/// An iterator over the values of a `SplayMapAdapter`, in key order.
+///
+/// Returned by `SplayMapAdapter::values`.
+pub struct Values<'t, 'a, T, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    V: 'a + ?Sized,
+{
+    elems: Elems<'t, 'a, T>,
+    value: fn(&'a T::Elem) -> &'a V,
+}
+
+impl<'t, 'a, T, V> Iterator for Values<'t, 'a, T, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    V: 'a + ?Sized,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a V> {
+        self.elems.next().map(self.value)
+    }
+}
+
+impl<'t, 'a, T, V> FusedIterator for Values<'t, 'a, T, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    V: 'a + ?Sized,
+{
+}
+
+impl<'t, 'a, T, V> fmt::Debug for Values<'t, 'a, T, V>
+where
+    T: 'a + IntrusiveNode<'a>,
+    V: 'a + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Values").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,324 @@
+//! Sets that track the closest pair of adjacent keys.
+
+use crate::brand::Brand;
+use crate::internal;
+use crate::{IntrusiveNode, Node, SplayTree, TreeOrd};
+use core::cell::Cell;
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// A tree type whose elements have keys, for use with `MinGapSet`, which
+/// tracks the smallest distance between two of them.
+///
+/// Every element has a second node, for the tree type `ByGap`, which the set
+/// uses to order elements by the gap between them and their successor. That
+/// gap is stored in the element, since it changes as neighbors come and go,
+/// and the set keeps it up to date; its initial value doesn't matter.
+///
+/// A gap set orders its elements itself, by key and then by address, so that
+/// any number of elements can share a key with a gap of zero between them.
+pub trait IntrusiveGap<'a>: IntrusiveNode<'a> {
+    /// The type of the elements' keys, such as an `Instant` or a tick count.
+    type Key: 'a + Copy + Ord;
+
+    /// The type of distances between keys, such as a `Duration`.
+    type Gap: 'a + Copy + Ord;
+
+    /// The tree type for the elements' second node.
+    type ByGap: 'a + IntrusiveNode<'a, Elem = Self::Elem>;
+
+    /// Get the key of the given element.
+    ///
+    /// This must not change while the element is in a set.
+    fn key(elem: &'a Self::Elem) -> Self::Key;
+
+    /// Get the cell that holds the gap between the given element and its
+    /// successor.
+    fn gap(elem: &'a Self::Elem) -> &'a Cell<Self::Gap>;
+
+    /// Get the distance from `lo` up to `hi`, where `lo <= hi`.
+    fn distance(lo: Self::Key, hi: Self::Key) -> Self::Gap;
+}
+
+#[inline]
+fn addr<'a, T>(elem: &'a T::Elem) -> usize
+where
+    T: IntrusiveGap<'a>,
+{
+    T::elem_to_node(elem) as *const _ as usize
+}
+
+/// A key that orders elements by key, and then by address.
+struct KeyAt<K> {
+    key: K,
+    addr: usize,
+}
+
+#[inline]
+fn key_at<'a, T>(elem: &'a T::Elem) -> KeyAt<T::Key>
+where
+    T: IntrusiveGap<'a>,
+{
+    KeyAt {
+        key: T::key(elem),
+        addr: addr::<T>(elem),
+    }
+}
+
+impl<'a, T> TreeOrd<'a, T> for KeyAt<T::Key>
+where
+    T: IntrusiveGap<'a>,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+        (self.key, self.addr).cmp(&(T::key(elem), addr::<T>(elem)))
+    }
+}
+
+/// A key that orders elements by the gap to their successor, and then by
+/// address.
+struct GapAt<'b, T, G> {
+    gap: G,
+    addr: usize,
+    brand: &'b Brand,
+    _phantom: PhantomData<T>,
+}
+
+#[inline]
+fn gap_at<'a, 'b, T>(elem: &'a T::Elem, brand: &'b Brand) -> GapAt<'b, T, T::Gap>
+where
+    T: IntrusiveGap<'a>,
+{
+    GapAt {
+        gap: T::gap(elem).get(),
+        addr: addr::<T>(elem),
+        brand,
+        _phantom: PhantomData,
+    }
+}
+
+impl<'a, 'b, T> internal::CompareToNode<'a> for GapAt<'b, T, T::Gap>
+where
+    T: 'a + IntrusiveGap<'a>,
+{
+    #[inline]
+    unsafe fn compare_to_node(&self, node: &'a Node<'a>) -> cmp::Ordering {
+        let elem = self.brand.elem::<T::ByGap>(node);
+        (self.gap, self.addr).cmp(&(T::gap(elem).get(), addr::<T>(elem)))
+    }
+}
+
+/// A set of keyed elements that knows the smallest gap between any two of
+/// them, such as the closest pair of deadlines in a schedule.
+///
+/// Alongside the tree of elements by key, the set keeps a second tree of every
+/// element but the last, ordered by the gap to its successor. Inserting or
+/// removing an element changes at most two gaps, so it takes *O(log n)*
+/// amortized time, and the smallest gap is always the minimum of the second
+/// tree, instead of a walk over every element.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, IntrusiveGap, MinGapSet, Node};
+/// use std::cell::Cell;
+///
+/// struct Deadline<'a> {
+///     at: u64,
+///     gap: Cell<u64>,
+///     by_time: Node<'a>,
+///     by_gap: Node<'a>,
+/// }
+///
+/// impl Deadline<'_> {
+///     fn new(at: u64) -> Self {
+///         Deadline { at, gap: Cell::new(0), by_time: Node::new(), by_gap: Node::new() }
+///     }
+/// }
+///
+/// struct ByTime;
+/// struct ByGap;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ByTime
+///     where
+///         type Elem = Deadline<'a>,
+///         node = by_time;
+/// }
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ByGap
+///     where
+///         type Elem = Deadline<'a>,
+///         node = by_gap;
+/// }
+///
+/// impl<'a> IntrusiveGap<'a> for ByTime {
+///     type Key = u64;
+///     type Gap = u64;
+///     type ByGap = ByGap;
+///
+///     fn key(d: &'a Deadline<'a>) -> u64 {
+///         d.at
+///     }
+///     fn gap(d: &'a Deadline<'a>) -> &'a Cell<u64> {
+///         &d.gap
+///     }
+///     fn distance(lo: u64, hi: u64) -> u64 {
+///         hi - lo
+///     }
+/// }
+///
+/// let deadlines = [10, 40, 25, 90, 30].map(Deadline::new);
+/// let mut set = MinGapSet::<ByTime>::new();
+/// for d in &deadlines {
+///     set.insert(d);
+/// }
+/// assert_eq!(set.min_gap(), Some(5));
+/// let (lo, hi) = set.tightest_pair().unwrap();
+/// assert_eq!((lo.at, hi.at), (25, 30));
+///
+/// // Removing 30 closes up the gap around it.
+/// assert!(set.remove(&deadlines[4]));
+/// assert_eq!(set.min_gap(), Some(15));
+/// ```
+pub struct MinGapSet<'a, T>
+where
+    T: 'a + IntrusiveGap<'a>,
+{
+    keys: SplayTree<'a, T>,
+    gaps: SplayTree<'a, T::ByGap>,
+}
+
+impl<'a, T> Default for MinGapSet<'a, T>
+where
+    T: 'a + IntrusiveGap<'a>,
+{
+    #[inline]
+    fn default() -> Self {
+        MinGapSet::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for MinGapSet<'a, T>
+where
+    T: 'a + IntrusiveGap<'a>,
+    T::Key: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.keys.walk(|elem| {
+            set.entry(&T::key(elem));
+        });
+        set.finish()
+    }
+}
+
+impl<'a, T> MinGapSet<'a, T>
+where
+    T: 'a + IntrusiveGap<'a>,
+{
+    /// Construct a new, empty set.
+    #[inline]
+    pub const fn new() -> Self {
+        MinGapSet {
+            keys: SplayTree::new(),
+            gaps: SplayTree::new(),
+        }
+    }
+
+    /// Is the set empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Get the underlying tree of elements by key.
+    #[inline]
+    pub fn tree(&self) -> &SplayTree<'a, T> {
+        &self.keys
+    }
+
+    /// Insert `elem` into the set.
+    ///
+    /// Like `SplayTree::insert`, it is a logic error to insert an element that
+    /// is already in the set, or is in any other `T` or `T::ByGap` tree.
+    pub fn insert(&mut self, elem: &'a T::Elem) {
+        let key = key_at::<T>(elem);
+        let pred = self.keys.find_le(&key);
+        let succ = self.keys.find_gt(&key);
+        let inserted = self.keys.insert_by_key(&key, elem);
+        debug_assert!(inserted);
+
+        if let Some(pred) = pred {
+            if succ.is_some() {
+                self.unlink_gap(pred);
+            }
+            self.link_gap(pred, elem);
+        }
+        if let Some(succ) = succ {
+            self.link_gap(elem, succ);
+        }
+    }
+
+    /// Remove `elem` from the set, if it is in it.
+    ///
+    /// Elements are identified by address, so this only removes `elem` itself,
+    /// even when other elements have the same key. Returns whether `elem` was
+    /// in the set.
+    pub fn remove(&mut self, elem: &'a T::Elem) -> bool {
+        let (pred, _, succ) = match self.keys.remove_with_neighbors(&key_at::<T>(elem)) {
+            Some(removed) => removed,
+            None => return false,
+        };
+
+        if succ.is_some() {
+            self.unlink_gap(elem);
+        }
+        if let Some(pred) = pred {
+            self.unlink_gap(pred);
+            if let Some(succ) = succ {
+                self.link_gap(pred, succ);
+            }
+        }
+        true
+    }
+
+    /// Get the smallest gap between two adjacent elements, or `None` if there
+    /// are fewer than two.
+    #[inline]
+    pub fn min_gap(&mut self) -> Option<T::Gap> {
+        self.gaps.min().map(|elem| T::gap(elem).get())
+    }
+
+    /// Get the two adjacent elements with the smallest gap between them, in
+    /// order, or `None` if there are fewer than two elements. Ties are broken
+    /// arbitrarily.
+    #[inline]
+    pub fn tightest_pair(&mut self) -> Option<(&'a T::Elem, &'a T::Elem)> {
+        let lo = self.gaps.min()?;
+        let hi = self.keys.find_gt(&key_at::<T>(lo))?;
+        Some((lo, hi))
+    }
+
+    /// Record the gap from `lo` to its successor `hi`, and put `lo` in the
+    /// tree of gaps.
+    fn link_gap(&mut self, lo: &'a T::Elem, hi: &'a T::Elem) {
+        T::gap(lo).set(T::distance(T::key(lo), T::key(hi)));
+        let gaps = &mut self.gaps;
+        let key = gap_at::<T>(lo, &gaps.brand);
+        let node = T::ByGap::elem_to_node(lo);
+        let inserted = unsafe {
+            gaps.tree
+                .insert_branded(&gaps.brand, internal::erase(&key), node)
+        };
+        debug_assert!(inserted);
+    }
+
+    /// Take `lo` out of the tree of gaps, before its successor changes.
+    fn unlink_gap(&mut self, lo: &'a T::Elem) {
+        let gaps = &mut self.gaps;
+        let key = gap_at::<T>(lo, &gaps.brand);
+        let removed = unsafe { gaps.tree.remove_branded(&gaps.brand, internal::erase(&key)) };
+        debug_assert!(removed.is_some());
+    }
+}
@@ -1,5 +1,8 @@
 use core::cell::Cell;
 use core::fmt;
+use core::mem;
+#[cfg(feature = "parent")]
+use core::ptr;
 
 /// A splay tree node that is embedded within some container type.
 ///
@@ -23,18 +26,96 @@ use core::fmt;
 ///     health: usize,
 /// }
 /// ```
+///
+/// ## Layout
+///
+/// `Node` is `#[repr(C)]` and its layout is guaranteed to be equivalent to the
+/// following C struct:
+///
+/// ```c
+/// struct intrusive_splay_tree_node {
+///     struct intrusive_splay_tree_node *left;
+///     struct intrusive_splay_tree_node *right;
+/// };
+/// ```
+///
+/// Both links point at the *node* within the child element, not at the element
+/// itself, and a null pointer means there is no child. This allows C and C++
+/// code to embed nodes in its own structs and share those elements with Rust
+/// trees across FFI. A node that is not in any tree must have both links set
+/// to null, which is also what `Node::default()` produces.
+///
+/// `SplayTree` nulls the links of the nodes it removes, so they keep to this.
+/// With the `linked` feature and `debug_assertions`, walking or splaying
+/// through a node that is not linked panics, so using an element's node after
+/// removing it, for example through another tree that shares the same `Node`
+/// field, is caught where it happens rather than corrupting the tree.
+///
+/// Some cargo features append fields to the layout, in this order, and all of
+/// them are initially zero:
+///
+/// * `avl` appends `signed char balance;`, which is used by `AvlTree`.
+/// * `linked` appends `bool linked;`, which backs `Node::is_linked`.
+/// * `paranoid` appends `uintptr_t brand;`, which identifies the tree the
+///   node is in.
+/// * `rank` appends `size_t size;`, the number of nodes in the subtree rooted
+///   at this node, which `SplayTree` maintains for rank queries.
+/// * `parent` appends `struct intrusive_splay_tree_node *parent;`, which
+///   points at the node within the parent element, and which `SplayTree`
+///   maintains for bottom-up splaying.
+#[repr(C)]
 pub struct Node<'a> {
     pub(crate) left: Cell<Option<&'a Node<'a>>>,
     pub(crate) right: Cell<Option<&'a Node<'a>>>,
+
+    /// The AVL balance factor: the height of the right subtree minus the
+    /// height of the left subtree. Only meaningful while in an `AvlTree`.
+    #[cfg(feature = "avl")]
+    pub(crate) balance: Cell<i8>,
+
+    /// Whether this node is currently in a tree.
+    #[cfg(feature = "linked")]
+    pub(crate) linked: Cell<bool>,
+
+    /// The brand of the tree this node is in, or zero if it is not in a tree.
+    #[cfg(feature = "paranoid")]
+    pub(crate) brand: Cell<usize>,
+
+    /// The number of nodes in this node's subtree, including itself. Only
+    /// maintained by `SplayTree`.
+    #[cfg(feature = "rank")]
+    pub(crate) size: Cell<usize>,
+
+    /// This node's parent, or `None` if it is a root. Only maintained by
+    /// `SplayTree`.
+    #[cfg(feature = "parent")]
+    pub(crate) parent: Cell<Option<&'a Node<'a>>>,
 }
 
+// `Option<&Node>` is guaranteed to have the same layout as a nullable pointer,
+// and `Cell` is `repr(transparent)`, so a `Node` is exactly two pointers.
+const _: () = {
+    assert!(mem::align_of::<Node>() == mem::align_of::<*const Node>());
+    assert!(mem::offset_of!(Node, left) == 0);
+    assert!(mem::offset_of!(Node, right) == mem::size_of::<*const Node>());
+
+    #[cfg(not(any(
+        feature = "avl",
+        feature = "linked",
+        feature = "paranoid",
+        feature = "rank",
+        feature = "parent"
+    )))]
+    assert!(mem::size_of::<Node>() == 2 * mem::size_of::<*const Node>());
+
+    #[cfg(feature = "avl")]
+    assert!(mem::offset_of!(Node, balance) == 2 * mem::size_of::<*const Node>());
+};
+
 impl<'a> Default for Node<'a> {
     #[inline]
     fn default() -> Node<'a> {
-        Node {
-            left: Cell::new(None),
-            right: Cell::new(None),
-        }
+        Node::new()
     }
 }
 
@@ -45,6 +126,29 @@ impl<'a> fmt::Debug for Node<'a> {
 }
 
 impl<'a> Node<'a> {
+    /// Create a new node that is not in any tree.
+    ///
+    /// This is the same as `Node::default()`, but it is a `const fn`, so it can
+    /// be used to initialize elements in `const`s and `static`s. See
+    /// `StaticElems` for building trees over elements in `static`s.
+    #[inline]
+    pub const fn new() -> Node<'a> {
+        Node {
+            left: Cell::new(None),
+            right: Cell::new(None),
+            #[cfg(feature = "avl")]
+            balance: Cell::new(0),
+            #[cfg(feature = "linked")]
+            linked: Cell::new(false),
+            #[cfg(feature = "paranoid")]
+            brand: Cell::new(0),
+            #[cfg(feature = "rank")]
+            size: Cell::new(0),
+            #[cfg(feature = "parent")]
+            parent: Cell::new(None),
+        }
+    }
+
     /// Get this node's left subtree.
     ///
     /// This is a low-level API, and should only be used for custom tree walking
@@ -53,7 +157,7 @@ impl<'a> Node<'a> {
     /// Use the unsafe `IntrusiveNode::node_to_elem` method to convert the
     /// resulting `Node` reference into a reference to its container element
     /// type.
-    pub fn left(&self) -> Option<&'a Node> {
+    pub fn left(&self) -> Option<&'a Node<'a>> {
         self.left.get()
     }
 
@@ -65,11 +169,122 @@ impl<'a> Node<'a> {
     /// Use the unsafe `IntrusiveNode::node_to_elem` method to convert the
     /// resulting `Node` reference into a reference to its container element
     /// type.
-    pub fn right(&self) -> Option<&'a Node> {
+    pub fn right(&self) -> Option<&'a Node<'a>> {
         self.right.get()
     }
 
+    /// Is this node currently in a tree?
+    ///
+    /// A node is linked from when it is inserted into a `SplayTree`,
+    /// `AvlTree`, or `Treap` (or a structure built on them, like `SplayLru`)
+    /// until it is removed. Since each `Node` field belongs to exactly one
+    /// tree type, this tells you whether the element is in a tree of that
+    /// type, for example to choose between inserting an element and removing
+    /// and reinserting it.
+    ///
+    /// Requires the `linked` cargo feature, which adds a flag to every `Node`.
+    #[cfg(feature = "linked")]
+    #[inline]
+    pub fn is_linked(&self) -> bool {
+        self.linked.get()
+    }
+
+    /// Clear this node's links after removing it from a `SplayTree`.
+    #[inline]
+    pub(crate) fn unlink(&self) {
+        self.left.set(None);
+        self.right.set(None);
+    }
+
+    /// Was this node removed from its tree? Only the `linked` feature keeps
+    /// track, so this is always `false` without it.
+    #[inline]
+    pub(crate) fn is_removed(&self) -> bool {
+        #[cfg(feature = "linked")]
+        return !self.linked.get();
+        #[cfg(not(feature = "linked"))]
+        false
+    }
+
+    /// Panic in debug builds if this node was removed from its tree, since
+    /// that means something is traversing it afterwards.
+    #[inline]
+    #[track_caller]
+    pub(crate) fn check_removed(&self) {
+        debug_assert!(
+            !self.is_removed(),
+            "traversed a node after it was removed from its tree"
+        );
+    }
+
+    /// Get the number of nodes in the subtree rooted at `node`.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub(crate) fn size_of(node: Option<&Node>) -> usize {
+        node.map_or(0, |n| n.size.get())
+    }
+
+    /// Get the number of nodes in this node's left subtree, which is its rank
+    /// when it is the root.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub(crate) fn rank(&self) -> usize {
+        Node::size_of(self.left.get())
+    }
+
+    /// Recompute this node's subtree size from its children's, after its
+    /// children changed.
+    #[inline]
+    pub(crate) fn fix_size(&self) {
+        #[cfg(feature = "rank")]
+        self.size
+            .set(1 + Node::size_of(self.left.get()) + Node::size_of(self.right.get()));
+    }
+
+    /// Set this node's left child, and that child's parent.
+    #[inline]
+    pub(crate) fn set_left(&'a self, child: Option<&'a Node<'a>>) {
+        self.left.set(child);
+        #[cfg(feature = "parent")]
+        if let Some(child) = child {
+            child.parent.set(Some(self));
+        }
+    }
+
+    /// Set this node's right child, and that child's parent.
+    #[inline]
+    pub(crate) fn set_right(&'a self, child: Option<&'a Node<'a>>) {
+        self.right.set(child);
+        #[cfg(feature = "parent")]
+        if let Some(child) = child {
+            child.parent.set(Some(self));
+        }
+    }
+
+    /// Rotate this node above `parent`, which must be its parent.
+    #[cfg(feature = "parent")]
+    pub(crate) fn rotate_up(&'a self, parent: &'a Node<'a>) {
+        let grandparent = parent.parent.get();
+        if parent.left.get().is_some_and(|left| ptr::eq(left, self)) {
+            parent.set_left(self.right.get());
+            self.set_right(Some(parent));
+        } else {
+            parent.set_right(self.left.get());
+            self.set_left(Some(parent));
+        }
+        match grandparent {
+            None => self.parent.set(None),
+            Some(g) if g.left.get().is_some_and(|left| ptr::eq(left, parent)) => {
+                g.set_left(Some(self))
+            }
+            Some(g) => g.set_right(Some(self)),
+        }
+        parent.fix_size();
+        self.fix_size();
+    }
+
     pub(crate) fn walk(&'a self, f: &mut dyn FnMut(&'a Node<'a>) -> bool) -> bool {
+        self.check_removed();
         if let Some(left) = self.left.get() {
             if !left.walk(f) {
                 return false;
@@ -88,4 +303,25 @@ impl<'a> Node<'a> {
 
         true
     }
+
+    pub(crate) fn walk_rev(&'a self, f: &mut dyn FnMut(&'a Node<'a>) -> bool) -> bool {
+        self.check_removed();
+        if let Some(right) = self.right.get() {
+            if !right.walk_rev(f) {
+                return false;
+            }
+        }
+
+        if !f(self) {
+            return false;
+        }
+
+        if let Some(left) = self.left.get() {
+            if !left.walk_rev(f) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
@@ -0,0 +1,490 @@
+//! Ordering adapters for trees and their keys.
+
+use crate::{IntrusiveNode, Node, TreeOrd};
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// A tree type that orders its elements in the reverse of `T`'s order.
+///
+/// `SplayTree<Descending<T>>` uses the same element type and intrusive node as
+/// `T`, and every `TreeOrd<T>` implementation (for the element and for any
+/// extra key types) is flipped into a `TreeOrd<Descending<T>>` implementation.
+/// That is, `min` returns `T`'s largest element, walks go from largest to
+/// smallest, and so on.
+///
+/// Because the node is shared with `T`, an element cannot be in a `T` tree and
+/// a `Descending<T>` tree at the same time. To keep both an ascending and a
+/// descending index, define a second tree type for another node in the
+/// element, and wrap that one in `Descending`.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Descending, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Task<'a> {
+///     priority: u32,
+///     node: Node<'a>,
+/// }
+///
+/// struct ByPriority;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ByPriority
+///     where
+///         type Elem = Task<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ByPriority> for Task<'a> {
+///     fn tree_cmp(&self, rhs: &Task<'a>) -> Ordering {
+///         self.priority.cmp(&rhs.priority)
+///     }
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut tree = SplayTree::<Descending<ByPriority>>::new();
+/// for priority in [3, 1, 4, 1, 5] {
+///     tree.insert(arena.alloc(Task { priority, node: Node::default() }));
+/// }
+///
+/// assert_eq!(tree.pop_min().unwrap().priority, 5);
+/// assert_eq!(tree.pop_min().unwrap().priority, 4);
+/// ```
+pub struct Descending<T>(PhantomData<T>);
+
+impl<T> fmt::Debug for Descending<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Descending").finish()
+    }
+}
+
+unsafe impl<'a, T> IntrusiveNode<'a> for Descending<T>
+where
+    T: IntrusiveNode<'a>,
+{
+    type Elem = T::Elem;
+
+    #[inline]
+    fn elem_to_node(elem: &'a Self::Elem) -> &'a Node<'a> {
+        T::elem_to_node(elem)
+    }
+
+    #[inline]
+    unsafe fn node_to_elem(node: &'a Node<'a>) -> &'a Self::Elem {
+        T::node_to_elem(node)
+    }
+}
+
+impl<'a, T, K> TreeOrd<'a, Descending<T>> for K
+where
+    T: IntrusiveNode<'a>,
+    K: ?Sized + TreeOrd<'a, T>,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+        TreeOrd::<T>::tree_cmp(self, elem).reverse()
+    }
+}
+
+/// A tree type whose elements are ordered by a key that is computed from each
+/// of them, usually a tuple of fields.
+///
+/// Ordering "by priority, then by timestamp" by hand means writing a chain of
+/// comparisons for the element, and again for every key type that looks
+/// elements up, and if any of them disagree, the tree is silently corrupted.
+/// Instead, this computes the whole key, and tuples compare lexicographically,
+/// so every comparison agrees by construction. Wrap a component in
+/// `core::cmp::Reverse` to sort by it in descending order.
+///
+/// Unlike `TreeKey`, the key is returned by value, so it doesn't have to be
+/// stored in the element. Use `impl_lexicographic_key!` to implement this
+/// along with the `TreeOrd` implementations for the element and for the key
+/// type, so that the tree can be queried with a key.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, impl_lexicographic_key, Node, SplayTree};
+/// use std::cmp::Reverse;
+///
+/// struct Task<'a> {
+///     priority: u32,
+///     submitted: u64,
+///     node: Node<'a>,
+/// }
+///
+/// struct Tasks;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Tasks
+///     where
+///         type Elem = Task<'a>,
+///         node = node;
+/// }
+///
+/// // Highest priority first, then first come, first served.
+/// impl_lexicographic_key! {
+///     impl<'a> LexicographicKey<'a> for Tasks
+///     where
+///         type Elem = Task<'a>,
+///         type Key = (Reverse<u32>, u64),
+///         key = |task| (Reverse(task.priority), task.submitted);
+/// }
+///
+/// let tasks = [(1, 10), (5, 30), (5, 20), (3, 0)]
+///     .map(|(priority, submitted)| Task { priority, submitted, node: Node::default() });
+/// let mut tree = SplayTree::<Tasks>::new();
+/// for task in &tasks {
+///     tree.insert(task);
+/// }
+///
+/// let next = tree.pop_min().unwrap();
+/// assert_eq!((next.priority, next.submitted), (5, 20));
+/// assert!(tree.find(&(Reverse(5), 30)).is_some());
+/// assert!(tree.find(&(Reverse(5), 20)).is_none());
+/// ```
+pub trait LexicographicKey<'a>: IntrusiveNode<'a> {
+    /// The key that elements are ordered by.
+    type Key: Ord;
+
+    /// Compute the given element's key.
+    ///
+    /// The key must not change while the element is in a tree.
+    fn key(elem: &Self::Elem) -> Self::Key;
+}
+
+/// Implement `LexicographicKey` for a tree type, along with the `TreeOrd`
+/// implementations for its element type and its key type, which compare
+/// keys.
+///
+/// The syntax mirrors `impl_intrusive_node!`, with the key type and a closure
+/// that computes an element's key. See `LexicographicKey` for an example.
+#[macro_export]
+macro_rules! impl_lexicographic_key {
+    () => {};
+
+    (
+        impl < $( $rest:tt )*
+    ) => {
+        $crate::__munch_impl_generics!(impl_lexicographic_key [] $( $rest )*);
+    };
+    (
+        @generics [ $( $params:tt )* ]
+        LexicographicKey< $lt:lifetime > for $tree:ty
+        where
+        $( $rest:tt )*
+    ) => {
+        $crate::impl_lexicographic_key!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [] $( $rest )*
+        );
+    };
+
+    // Munch the where clause's bounds up to `type Elem`.
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        type Elem = $elem:ty ,
+        type Key = $key:ty ,
+        key = | $arg:ident | $body:expr ;
+        $( $rest:tt )*
+    ) => {
+        impl< $( $params )* > $crate::LexicographicKey< $lt > for $tree
+        where
+            $( $bounds )*
+        {
+            type Key = $key;
+
+            #[inline]
+            fn key( $arg : &Self::Elem) -> Self::Key {
+                $body
+            }
+        }
+
+        impl< $( $params )* > $crate::TreeOrd< $lt, $tree > for $elem
+        where
+            $( $bounds )*
+        {
+            #[inline]
+            fn tree_cmp(&self, rhs: & $lt $elem) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(
+                    &<$tree as $crate::LexicographicKey< $lt >>::key(self),
+                    &<$tree as $crate::LexicographicKey< $lt >>::key(rhs),
+                )
+            }
+        }
+
+        impl< $( $params )* > $crate::TreeOrd< $lt, $tree > for $key
+        where
+            $( $bounds )*
+        {
+            #[inline]
+            fn tree_cmp(&self, rhs: & $lt $elem) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(self, &<$tree as $crate::LexicographicKey< $lt >>::key(rhs))
+            }
+        }
+
+        $crate::impl_lexicographic_key!( $( $rest )* );
+    };
+    (
+        @bounds [ $( $params:tt )* ] [ $lt:lifetime ] [ $tree:ty ] [ $( $bounds:tt )* ]
+        $next:tt $( $rest:tt )*
+    ) => {
+        $crate::impl_lexicographic_key!(
+            @bounds [ $( $params )* ] [ $lt ] [ $tree ] [ $( $bounds )* $next ] $( $rest )*
+        );
+    };
+}
+
+macro_rules! total_ord_float {
+    ( $( #[$attr:meta] )* $name:ident($float:ty) ) => {
+        $( #[$attr] )*
+        #[derive(Clone, Copy, Default)]
+        #[repr(transparent)]
+        pub struct $name(pub $float);
+
+        impl $name {
+            /// Wrap the given float.
+            #[inline]
+            pub const fn new(value: $float) -> Self {
+                $name(value)
+            }
+
+            /// Get the wrapped float.
+            #[inline]
+            pub const fn get(self) -> $float {
+                self.0
+            }
+        }
+
+        impl From<$float> for $name {
+            #[inline]
+            fn from(value: $float) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $float {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl PartialEq for $name {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other).is_eq()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            #[inline]
+            fn cmp(&self, other: &Self) -> cmp::Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+    };
+}
+
+total_ord_float! {
+    /// An `f32` key that is totally ordered by `f32::total_cmp`.
+    ///
+    /// Ordering a tree by a raw float's `partial_cmp` is broken as soon as a
+    /// NaN shows up, because NaN is neither less than, equal to, nor greater
+    /// than anything, and an inconsistent ordering silently corrupts the tree.
+    /// Use this wrapper as the key type instead: it implements `Ord`, so it can
+    /// be used with `TreeKey`, `#[derive(TreeOrd)]`, and `declare_multi_index!`
+    /// like any other key.
+    ///
+    /// Note that, under the total order, `-0.0` is less than `+0.0`, and NaNs
+    /// are ordered by their sign and payload, negative NaNs before all other
+    /// values and positive NaNs after.
+    F32TotalOrd(f32)
+}
+
+total_ord_float! {
+    /// An `f64` key that is totally ordered by `f64::total_cmp`.
+    ///
+    /// See [`F32TotalOrd`] for details.
+    F64TotalOrd(f64)
+}
+
+/// A tree type whose elements are ordered by an RFC 1982 serial number, one of
+/// `Serial16`, `Serial32`, or `Serial64`.
+///
+/// Serial numbers don't implement `Ord`, so they can't be a `TreeKey`.
+/// Implementing this instead provides a `TreeOrd` implementation for the
+/// serial number type, so the tree can be searched by serial number.
+pub trait IntrusiveSerial<'a>: IntrusiveNode<'a> {
+    /// The type of serial number.
+    type Serial;
+
+    /// Get the serial number of the given element.
+    ///
+    /// This must not change while the element is in the tree.
+    fn serial(elem: &'a Self::Elem) -> Self::Serial;
+}
+
+macro_rules! serial_number {
+    ( $( #[$attr:meta] )* $name:ident($uint:ty, $int:ty) ) => {
+        $( #[$attr] )*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $name(pub $uint);
+
+        impl $name {
+            /// Wrap the given counter value.
+            #[inline]
+            pub const fn new(value: $uint) -> Self {
+                $name(value)
+            }
+
+            /// Get the wrapped counter value.
+            #[inline]
+            pub const fn get(self) -> $uint {
+                self.0
+            }
+
+            /// Get the serial number `n` steps after this one, wrapping around.
+            #[inline]
+            pub const fn wrapping_add(self, n: $uint) -> Self {
+                $name(self.0.wrapping_add(n))
+            }
+
+            /// Compare this serial number to `other` by serial number
+            /// arithmetic.
+            ///
+            /// The result is only meaningful when the two are less than half
+            /// the number space apart. At exactly half, both directions compare
+            /// `Less`.
+            #[inline]
+            pub fn serial_cmp(self, other: Self) -> cmp::Ordering {
+                (self.0.wrapping_sub(other.0) as $int).cmp(&0)
+            }
+        }
+
+        impl From<$uint> for $name {
+            #[inline]
+            fn from(value: $uint) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $uint {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl<'a, T> TreeOrd<'a, T> for $name
+        where
+            T: IntrusiveSerial<'a, Serial = $name>,
+        {
+            #[inline]
+            fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+                self.serial_cmp(T::serial(elem))
+            }
+        }
+    };
+}
+
+serial_number! {
+    /// A `u32` sequence number that is ordered by RFC 1982 serial number
+    /// arithmetic, so that it keeps its order as the counter wraps around.
+    ///
+    /// `a` is before `b` when `b` is less than 2<sup>31</sup> steps ahead of
+    /// `a`, going forward with wrapping. So `u32::MAX` is before `0`, which is
+    /// the order a wrapping counter generates them in.
+    ///
+    /// This is not a total order, so `Serial32` implements neither `Ord` nor
+    /// `PartialOrd`: it isn't transitive, and two numbers exactly
+    /// 2<sup>31</sup> apart each compare less than the other. Compare them
+    /// with `serial_cmp` instead, and order a tree by them with
+    /// `IntrusiveSerial`.
+    ///
+    /// A tree keyed by serial numbers is only valid while every key in it, and
+    /// every key it is searched for, fits in a window of fewer than
+    /// 2<sup>31</sup> consecutive values. That is, the oldest live key must
+    /// not fall half the number space behind the newest. A queue that keeps
+    /// inserting new keys and removing old ones satisfies this as long as no
+    /// key outlives 2<sup>31</sup> newer ones; breaking the window silently
+    /// corrupts the tree.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, IntrusiveSerial, Node, Serial32, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Segment<'a> {
+    ///     seq: Serial32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct BySeq;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for BySeq
+    ///     where
+    ///         type Elem = Segment<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> IntrusiveSerial<'a> for BySeq {
+    ///     type Serial = Serial32;
+    ///
+    ///     fn serial(segment: &'a Segment<'a>) -> Serial32 {
+    ///         segment.seq
+    ///     }
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, BySeq> for Segment<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Segment<'a>) -> Ordering {
+    ///         self.seq.serial_cmp(rhs.seq)
+    ///     }
+    /// }
+    ///
+    /// let segments = [u32::MAX, 0].map(|seq| Segment { seq: Serial32(seq), node: Node::default() });
+    /// let mut tree = SplayTree::<BySeq>::new();
+    /// tree.extend(&segments);
+    /// assert_eq!(tree.min().unwrap().seq, Serial32(u32::MAX));
+    /// assert!(tree.find(&Serial32(0)).is_some());
+    /// ```
+    Serial32(u32, i32)
+}
+
+serial_number! {
+    /// A `u16` sequence number that is ordered by RFC 1982 serial number
+    /// arithmetic.
+    ///
+    /// The window is fewer than 2<sup>15</sup> consecutive values. See
+    /// [`Serial32`] for details.
+    Serial16(u16, i16)
+}
+
+serial_number! {
+    /// A `u64` sequence number that is ordered by RFC 1982 serial number
+    /// arithmetic.
+    ///
+    /// The window is fewer than 2<sup>63</sup> consecutive values. See
+    /// [`Serial32`] for details.
+    Serial64(u64, i64)
+}
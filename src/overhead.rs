@@ -0,0 +1,64 @@
+//! Accounting for the memory that trees add to their elements.
+
+use crate::Node;
+use core::mem;
+
+/// How much memory a tree's intrusive metadata takes up.
+///
+/// Returned by `SplayTree::metadata_overhead`. This only counts what the tree
+/// adds: each element's `Node` for the tree, which grows with the features
+/// that add fields to it, and the tree itself. The rest of the elements,
+/// padding around their nodes, and the nodes for other trees that the same
+/// elements are in are not included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MetadataOverhead {
+    nodes: usize,
+    tree_bytes: usize,
+}
+
+impl MetadataOverhead {
+    #[inline]
+    pub(crate) fn new(nodes: usize, tree_bytes: usize) -> Self {
+        MetadataOverhead { nodes, tree_bytes }
+    }
+
+    /// Get the number of nodes in the tree, which is its number of elements.
+    #[inline]
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Get the number of bytes that the tree's nodes take up, including the
+    /// fields that enabled features add to each one.
+    #[inline]
+    pub fn node_bytes(&self) -> usize {
+        self.nodes * mem::size_of::<Node>()
+    }
+
+    /// Get the number of bytes that the tree itself takes up.
+    #[inline]
+    pub fn tree_bytes(&self) -> usize {
+        self.tree_bytes
+    }
+
+    /// Get the total number of bytes of metadata, for the nodes and the tree
+    /// together.
+    #[inline]
+    pub fn total_bytes(&self) -> usize {
+        self.node_bytes() + self.tree_bytes
+    }
+}
+
+impl core::ops::Add for MetadataOverhead {
+    type Output = MetadataOverhead;
+
+    /// Combine the overheads of two trees, for example several indices over
+    /// the same elements.
+    #[inline]
+    fn add(self, rhs: MetadataOverhead) -> MetadataOverhead {
+        MetadataOverhead {
+            nodes: self.nodes + rhs.nodes,
+            tree_bytes: self.tree_bytes + rhs.tree_bytes,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! A splay tree that owns the arena its elements are allocated in.
+
+use crate::{FamilyElem, SplayTree, TreeFamily, TreeOrd};
+use bumpalo::Bump;
+use core::fmt;
+
+/// A splay tree bundled with the bump arena that its elements live in.
+///
+/// A `SplayTree<'a, T>` borrows its elements for `'a`, so it can't be stored
+/// next to the arena that they were allocated in without a self-referential
+/// struct. This type is that struct: it owns a `bumpalo::Bump`, allocates
+/// inserted elements in it, and never lets a reference to an element outlive
+/// a borrow of the tree. So it can be returned from functions, stored in other
+/// structs, and moved around, with no lifetime parameter at all.
+///
+/// Elements are only handed out to closures that must work for any lifetime,
+/// so that nothing that borrows from the arena can escape them. For anything
+/// beyond inserting, finding, and removing, `with` gives such a closure the
+/// underlying tree and arena.
+///
+/// Like any `Bump`, the arena never drops its contents: removed elements stay
+/// allocated until the whole `OwnedSplayTree` is dropped, and the elements'
+/// destructors are never run.
+///
+/// Requires the `bumpalo` feature.
+///
+/// ```
+/// # use intrusive_splay_tree::{impl_intrusive_node, Node, TreeFamily, TreeOrd};
+/// # use std::cmp::Ordering;
+/// # use std::marker::PhantomData;
+/// # struct Monster<'a> {
+/// #     name: &'static str,
+/// #     node: Node<'a>,
+/// # }
+/// # struct MonstersByName<'a>(PhantomData<&'a ()>);
+/// # impl_intrusive_node! {
+/// #     impl<'a> IntrusiveNode<'a> for MonstersByName<'a>
+/// #     where
+/// #         type Elem = Monster<'a>,
+/// #         node = node;
+/// # }
+/// # impl<'a> TreeOrd<'a, MonstersByName<'a>> for Monster<'a> {
+/// #     fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+/// #         self.name.cmp(rhs.name)
+/// #     }
+/// # }
+/// # impl<'a> TreeOrd<'a, MonstersByName<'a>> for str {
+/// #     fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+/// #         self.cmp(rhs.name)
+/// #     }
+/// # }
+/// # struct Monsters;
+/// # impl TreeFamily for Monsters {
+/// #     type Tree<'a> = MonstersByName<'a>;
+/// # }
+/// use intrusive_splay_tree::OwnedSplayTree;
+///
+/// fn spawn() -> OwnedSplayTree<Monsters> {
+///     let mut monsters = OwnedSplayTree::new();
+///     for name in ["Godzilla", "Mothra", "Vegeta"] {
+///         monsters.insert(Monster { name, node: Node::default() });
+///     }
+///     monsters
+/// }
+///
+/// let mut monsters = spawn();
+/// assert_eq!(monsters.find("Mothra", |m| m.name.len()), Some(6));
+/// assert!(monsters.remove("Godzilla"));
+/// assert_eq!(monsters.with(|tree, _| tree.min().unwrap().name), "Mothra");
+/// ```
+pub struct OwnedSplayTree<F>
+where
+    F: TreeFamily,
+{
+    // The tree borrows from the arena, so it is declared first, to be dropped
+    // before the arena is. It has no destructor of its own, but this keeps
+    // the drop order right if it ever gets one. The `'static` lifetime is a
+    // stand-in: `with` hands out the tree at a lifetime that can't outlive
+    // `self`.
+    tree: SplayTree<'static, F::Tree<'static>>,
+    arena: Bump,
+}
+
+impl<F> Default for OwnedSplayTree<F>
+where
+    F: TreeFamily,
+{
+    #[inline]
+    fn default() -> Self {
+        OwnedSplayTree::new()
+    }
+}
+
+impl<F> fmt::Debug for OwnedSplayTree<F>
+where
+    F: TreeFamily,
+    for<'a> FamilyElem<'a, F>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedSplayTree")
+            .field("tree", &self.tree)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> OwnedSplayTree<F>
+where
+    F: TreeFamily,
+{
+    /// Construct a new, empty tree, with a new, empty arena.
+    #[inline]
+    pub fn new() -> Self {
+        OwnedSplayTree {
+            tree: SplayTree::new(),
+            arena: Bump::new(),
+        }
+    }
+
+    /// Is the tree empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the number of elements in the tree.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Get the arena, for example to check how much it has allocated.
+    #[inline]
+    pub fn arena(&self) -> &Bump {
+        &self.arena
+    }
+
+    /// Call `f` with the tree and its arena.
+    ///
+    /// `f` must work for any lifetime `'a`, so it can only insert elements that
+    /// it allocates in `arena`, or that are `'static`, and it can't return
+    /// anything that borrows from either.
+    #[inline]
+    pub fn with<R, G>(&mut self, f: G) -> R
+    where
+        G: for<'a> FnOnce(&mut SplayTree<'a, F::Tree<'a>>, &'a Bump) -> R,
+    {
+        // Safety: the tree only ever holds elements from the arena, or that
+        // are `'static`, and `'a` is a borrow of `self`, so every element
+        // outlives `'a`. Types that differ only in lifetimes have the same
+        // layout.
+        let tree = unsafe {
+            let tree: *mut SplayTree<'static, F::Tree<'static>> = &mut self.tree;
+            &mut *tree.cast::<SplayTree<'_, F::Tree<'_>>>()
+        };
+        f(tree, &self.arena)
+    }
+
+    /// Allocate `elem` in the arena, and insert it into the tree.
+    ///
+    /// Returns `false` if there was already an element in the tree for which
+    /// `TreeOrd` returned `Ordering::Equal`, like `SplayTree::insert`. `elem`
+    /// is still allocated in that case.
+    #[inline]
+    pub fn insert(&mut self, elem: FamilyElem<'static, F>) -> bool {
+        // Safety: the element can only hold `'static` references, and its
+        // reference only ever escapes at a lifetime that can't outlive `self`,
+        // through `with`.
+        let elem = unsafe { &*(self.arena.alloc(elem) as *const FamilyElem<'static, F>) };
+        self.tree.insert(elem)
+    }
+
+    /// Find the element that matches `key`, and call `f` with it.
+    ///
+    /// Returns `None`, without calling `f`, if there is no such element.
+    #[inline]
+    pub fn find<K, R, G>(&mut self, key: &K, f: G) -> Option<R>
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+        G: for<'a> FnOnce(&'a FamilyElem<'a, F>) -> R,
+    {
+        self.with(|tree, _| tree.find(key).map(f))
+    }
+
+    /// Does the tree contain an element that matches `key`?
+    #[inline]
+    pub fn contains<K>(&mut self, key: &K) -> bool
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+    {
+        self.find(key, |_| ()).is_some()
+    }
+
+    /// Remove the element that matches `key` from the tree, and return whether
+    /// there was one.
+    ///
+    /// The element stays allocated in the arena.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> bool
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+    {
+        self.with(|tree, _| tree.remove(key).is_some())
+    }
+}
@@ -0,0 +1,361 @@
+//! A persistent, path-copying treap, for cheap snapshots.
+
+use crate::WalkControl;
+use core::cmp;
+use core::fmt;
+
+/// The `splitmix64` finalizer.
+#[inline]
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A node in a `PersistentTree`.
+///
+/// Nodes are immutable once allocated, and are shared between every version
+/// of a tree that contains them. They are allocated by the closure that is
+/// passed to each update, typically in an arena.
+pub struct PersistentNode<'a, K> {
+    key: K,
+    priority: u64,
+    left: Option<&'a PersistentNode<'a, K>>,
+    right: Option<&'a PersistentNode<'a, K>>,
+}
+
+impl<'a, K: fmt::Debug> fmt::Debug for PersistentNode<'a, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PersistentNode")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+type Link<'a, K> = Option<&'a PersistentNode<'a, K>>;
+
+impl<'a, K> PersistentNode<'a, K> {
+    fn walk<F>(&'a self, f: &mut F) -> bool
+    where
+        F: FnMut(&'a K) -> bool,
+    {
+        if let Some(left) = self.left {
+            if !left.walk(f) {
+                return false;
+            }
+        }
+        if !f(&self.key) {
+            return false;
+        }
+        match self.right {
+            Some(right) => right.walk(f),
+            None => true,
+        }
+    }
+}
+
+impl<'a, K: Clone> PersistentNode<'a, K> {
+    /// Copy this node with new children.
+    #[inline]
+    fn with_children<A>(&self, left: Link<'a, K>, right: Link<'a, K>, alloc: &mut A) -> &'a Self
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        alloc(PersistentNode {
+            key: self.key.clone(),
+            priority: self.priority,
+            left,
+            right,
+        })
+    }
+}
+
+/// A persistent ordered set, where every update returns a new version of the
+/// tree and leaves the old one readable.
+///
+/// Unlike `SplayTree`, whose lookups restructure the tree in place, this is a
+/// treap whose nodes are never modified after they are allocated. An insertion
+/// or removal copies only the *O(log n)* expected nodes on the path to the
+/// updated key, and shares the rest of the tree with the version it was made
+/// from. A version is a `Copy` handle to its root, so taking a snapshot for a
+/// reader, MVCC-style, is free, and readers never contend with writers.
+///
+/// Nodes are not freed individually; every version must be dropped before the
+/// arena that their nodes were allocated from. Keys are cloned into the copied
+/// nodes, so they should be cheap to clone.
+///
+/// ```
+/// use intrusive_splay_tree::PersistentTree;
+///
+/// let arena = bumpalo::Bump::new();
+/// let mut alloc = |node| &*arena.alloc(node);
+///
+/// let v0 = PersistentTree::new();
+/// let v1 = v0.insert(3, &mut alloc).insert(1, &mut alloc);
+/// let v2 = v1.insert(2, &mut alloc).remove(&3, &mut alloc);
+///
+/// let keys = |t: PersistentTree<u32>| {
+///     let mut keys = vec![];
+///     t.walk(|&k| keys.push(k));
+///     keys
+/// };
+/// assert_eq!(keys(v0), []);
+/// assert_eq!(keys(v1), [1, 3]);
+/// assert_eq!(keys(v2), [1, 2]);
+/// ```
+pub struct PersistentTree<'a, K> {
+    root: Link<'a, K>,
+    len: usize,
+    // The state for the next inserted node's priority.
+    seed: u64,
+}
+
+impl<'a, K> Clone for PersistentTree<'a, K> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K> Copy for PersistentTree<'a, K> {}
+
+impl<'a, K> Default for PersistentTree<'a, K> {
+    #[inline]
+    fn default() -> Self {
+        PersistentTree::new()
+    }
+}
+
+impl<'a, K: fmt::Debug> fmt::Debug for PersistentTree<'a, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.walk(|k| {
+            set.entry(k);
+        });
+        set.finish()
+    }
+}
+
+impl<'a, K> PersistentTree<'a, K> {
+    /// Construct a new, empty tree.
+    #[inline]
+    pub const fn new() -> Self {
+        PersistentTree {
+            root: None,
+            len: 0,
+            seed: 0,
+        }
+    }
+
+    /// Is this version of the tree empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Get the number of keys in this version of the tree.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the smallest key, if any.
+    pub fn min(&self) -> Option<&'a K> {
+        let mut node = self.root?;
+        while let Some(left) = node.left {
+            node = left;
+        }
+        Some(&node.key)
+    }
+
+    /// Get the largest key, if any.
+    pub fn max(&self) -> Option<&'a K> {
+        let mut node = self.root?;
+        while let Some(right) = node.right {
+            node = right;
+        }
+        Some(&node.key)
+    }
+
+    /// Walk this version's keys in order.
+    ///
+    /// See `SplayTree::walk` for how `C` controls the walk.
+    #[inline]
+    pub fn walk<F, C>(&self, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(&'a K) -> C,
+        C: WalkControl,
+    {
+        let mut result = None;
+        if let Some(root) = self.root {
+            root.walk(&mut |key| {
+                result = f(key).should_break();
+                result.is_none()
+            });
+        }
+        result
+    }
+}
+
+impl<'a, K: Ord> PersistentTree<'a, K> {
+    /// Get the stored key that is equal to `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&'a K> {
+        let mut link = self.root;
+        while let Some(node) = link {
+            link = match key.cmp(&node.key) {
+                cmp::Ordering::Less => node.left,
+                cmp::Ordering::Greater => node.right,
+                cmp::Ordering::Equal => return Some(&node.key),
+            };
+        }
+        None
+    }
+
+    /// Does this version of the tree contain `key`?
+    #[inline]
+    pub fn contains(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<'a, K: Clone + Ord> PersistentTree<'a, K> {
+    /// Return a new version of the tree that contains `key`, replacing any
+    /// equal key.
+    ///
+    /// `alloc` is called for each node on the copied path, and must return a
+    /// reference to its argument that lives for `'a`, typically by moving it
+    /// into an arena. This version is left unchanged.
+    pub fn insert<A>(&self, key: K, alloc: &mut A) -> Self
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        let len = if self.contains(&key) {
+            self.len
+        } else {
+            self.len + 1
+        };
+        let priority = mix(self.seed);
+        PersistentTree {
+            root: Some(Self::insert_at(self.root, key, priority, alloc)),
+            len,
+            seed: self.seed.wrapping_add(1),
+        }
+    }
+
+    /// Return a new version of the tree without `key`.
+    ///
+    /// See `insert` for `alloc`. If `key` is not in the tree, nothing is
+    /// allocated and this version is returned as is.
+    pub fn remove<A>(&self, key: &K, alloc: &mut A) -> Self
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        if !self.contains(key) {
+            return *self;
+        }
+        PersistentTree {
+            root: Self::remove_at(self.root, key, alloc),
+            len: self.len - 1,
+            seed: self.seed,
+        }
+    }
+
+    fn insert_at<A>(
+        link: Link<'a, K>,
+        key: K,
+        priority: u64,
+        alloc: &mut A,
+    ) -> &'a PersistentNode<'a, K>
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        match link {
+            Some(node) if node.priority > priority => match key.cmp(&node.key) {
+                cmp::Ordering::Less => {
+                    let left = Self::insert_at(node.left, key, priority, alloc);
+                    node.with_children(Some(left), node.right, alloc)
+                }
+                cmp::Ordering::Greater => {
+                    let right = Self::insert_at(node.right, key, priority, alloc);
+                    node.with_children(node.left, Some(right), alloc)
+                }
+                cmp::Ordering::Equal => alloc(PersistentNode {
+                    key,
+                    priority: node.priority,
+                    left: node.left,
+                    right: node.right,
+                }),
+            },
+            // The new node belongs here: split the subtree around it.
+            _ => {
+                let (left, right) = Self::split(link, &key, alloc);
+                alloc(PersistentNode {
+                    key,
+                    priority,
+                    left,
+                    right,
+                })
+            }
+        }
+    }
+
+    /// Split a subtree into the keys less than and greater than `key`,
+    /// dropping any key equal to it.
+    fn split<A>(link: Link<'a, K>, key: &K, alloc: &mut A) -> (Link<'a, K>, Link<'a, K>)
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        let node = match link {
+            Some(node) => node,
+            None => return (None, None),
+        };
+        match key.cmp(&node.key) {
+            cmp::Ordering::Less => {
+                let (left, right) = Self::split(node.left, key, alloc);
+                (left, Some(node.with_children(right, node.right, alloc)))
+            }
+            cmp::Ordering::Greater => {
+                let (left, right) = Self::split(node.right, key, alloc);
+                (Some(node.with_children(node.left, left, alloc)), right)
+            }
+            cmp::Ordering::Equal => (node.left, node.right),
+        }
+    }
+
+    fn remove_at<A>(link: Link<'a, K>, key: &K, alloc: &mut A) -> Link<'a, K>
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        let node = link?;
+        Some(match key.cmp(&node.key) {
+            cmp::Ordering::Less => {
+                let left = Self::remove_at(node.left, key, alloc);
+                node.with_children(left, node.right, alloc)
+            }
+            cmp::Ordering::Greater => {
+                let right = Self::remove_at(node.right, key, alloc);
+                node.with_children(node.left, right, alloc)
+            }
+            cmp::Ordering::Equal => return Self::merge(node.left, node.right, alloc),
+        })
+    }
+
+    /// Merge two subtrees, where every key in `left` is less than every key in
+    /// `right`.
+    fn merge<A>(left: Link<'a, K>, right: Link<'a, K>, alloc: &mut A) -> Link<'a, K>
+    where
+        A: FnMut(PersistentNode<'a, K>) -> &'a PersistentNode<'a, K>,
+    {
+        match (left, right) {
+            (None, link) | (link, None) => link,
+            (Some(l), Some(r)) if l.priority > r.priority => {
+                let right = Self::merge(l.right, Some(r), alloc);
+                Some(l.with_children(l.left, right, alloc))
+            }
+            (Some(l), Some(r)) => {
+                let left = Self::merge(Some(l), r.left, alloc);
+                Some(r.with_children(left, r.right, alloc))
+            }
+        }
+    }
+}
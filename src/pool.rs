@@ -0,0 +1,369 @@
+//! A splay set that stores its elements in a fixed-capacity inline pool.
+
+use crate::{FamilyElem, SplayTree, TreeFamily, TreeOrd, WalkControl};
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::marker::PhantomPinned;
+use core::mem::{self, MaybeUninit};
+use core::pin::Pin;
+
+/// The error returned when inserting into a full `StaticSplaySet`, or making
+/// a change in a `Transaction` whose log is full. It holds the value that did
+/// not fit, if there is one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Full<E>(pub E);
+
+/// A splay set of at most `N` elements, which stores them in an inline pool
+/// rather than borrowing them.
+///
+/// Values are moved into a free slot in the pool when they are inserted, and
+/// dropped, freeing their slot for reuse, when they are removed. Nothing is
+/// ever allocated, so this suits targets without `alloc`, where the capacity
+/// of every structure is fixed at compile time.
+///
+/// The tree links point into the pool, so the set can't move once it has
+/// elements: anything that inserts or splays takes it as `Pin<&mut Self>`.
+/// Since `new` is a `const fn`, the set can also live in a `static`, by
+/// wrapping it in a `StaticElems` and claiming it with
+/// `StaticElems::claim_mut`. Like `OwnedSplayTree`, it is generic over a
+/// `TreeFamily` rather than a tree type, and only hands out elements to
+/// closures that work for any lifetime, so they can't be put in another tree
+/// that outlives them.
+///
+/// ```
+/// use intrusive_splay_tree::{
+///     impl_intrusive_node, Full, Node, StaticSplaySet, TreeFamily, TreeOrd,
+/// };
+/// use std::cmp::Ordering;
+/// use std::marker::PhantomData;
+/// use std::pin::pin;
+///
+/// struct Timer<'a> {
+///     id: u32,
+///     node: Node<'a>,
+/// }
+///
+/// impl Timer<'_> {
+///     fn new(id: u32) -> Self {
+///         Timer { id, node: Node::new() }
+///     }
+/// }
+///
+/// struct TimersById<'a>(PhantomData<&'a ()>);
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for TimersById<'a>
+///     where
+///         type Elem = Timer<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, TimersById<'a>> for Timer<'a> {
+///     fn tree_cmp(&self, rhs: &'a Timer<'a>) -> Ordering {
+///         self.id.cmp(&rhs.id)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, TimersById<'a>> for u32 {
+///     fn tree_cmp(&self, rhs: &'a Timer<'a>) -> Ordering {
+///         self.cmp(&rhs.id)
+///     }
+/// }
+///
+/// struct Timers;
+///
+/// impl TreeFamily for Timers {
+///     type Tree<'a> = TimersById<'a>;
+/// }
+///
+/// let mut timers = pin!(StaticSplaySet::<Timers, 2>::new());
+/// assert!(timers.as_mut().insert(Timer::new(7)).is_ok());
+/// assert!(timers.as_mut().insert(Timer::new(3)).is_ok());
+/// assert!(matches!(timers.as_mut().insert(Timer::new(5)), Err(Full(_))));
+///
+/// // Removing a timer frees its slot.
+/// assert!(timers.as_mut().remove(&7));
+/// assert!(timers.as_mut().insert(Timer::new(5)).is_ok());
+/// assert_eq!(timers.as_mut().max(|timer| timer.id), Some(5));
+/// ```
+pub struct StaticSplaySet<F, const N: usize>
+where
+    F: TreeFamily,
+{
+    // As in `OwnedSplayTree`, `'static` is a stand-in for the lifetime of a
+    // borrow of the set, which is what elements are handed out at, to
+    // closures that must work for any lifetime.
+    tree: SplayTree<'static, F::Tree<'static>>,
+    pool: Pool<FamilyElem<'static, F>, N>,
+    _pinned: PhantomPinned,
+}
+
+/// The slots that a `StaticSplaySet`'s elements live in.
+///
+/// It is only ever used through shared references, since the tree has shared
+/// references to the elements in it.
+struct Pool<E, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<E>>; N],
+    // Slots below `used` have held an element; the first `free_len` entries
+    // of `free` are the indices of those that were freed since.
+    used: Cell<usize>,
+    free: [Cell<usize>; N],
+    free_len: Cell<usize>,
+}
+
+impl<E, const N: usize> Pool<E, N> {
+    #[inline]
+    const fn new() -> Self {
+        Pool {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            used: Cell::new(0),
+            free: [const { Cell::new(0) }; N],
+            free_len: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.used.get() - self.free_len.get()
+    }
+
+    /// Move `value` into a free slot, and return a pointer to it, or give it
+    /// back if there is none.
+    #[inline]
+    fn alloc(&self, value: E) -> Result<*const E, E> {
+        let slot = match self.free_len.get() {
+            0 if self.used.get() == N => return Err(value),
+            0 => {
+                self.used.set(self.used.get() + 1);
+                self.used.get() - 1
+            }
+            len => {
+                self.free_len.set(len - 1);
+                self.free[len - 1].get()
+            }
+        };
+        let ptr = self.slots[slot].get().cast::<E>();
+        // Safety: the slot is free, so nothing refers to it.
+        unsafe { ptr.write(value) };
+        Ok(ptr)
+    }
+
+    /// Drop the element at `elem`, and free its slot.
+    ///
+    /// # Safety
+    ///
+    /// `elem` must point at an element in this pool that nothing refers to
+    /// anymore.
+    #[inline]
+    unsafe fn release<T>(&self, elem: *const T) {
+        let offset = elem as usize - self.slots.as_ptr() as usize;
+        let slot = offset / mem::size_of::<UnsafeCell<MaybeUninit<E>>>();
+        self.slots[slot].get().cast::<E>().drop_in_place();
+        self.free[self.free_len.get()].set(slot);
+        self.free_len.set(self.free_len.get() + 1);
+    }
+}
+
+impl<F, const N: usize> Default for StaticSplaySet<F, N>
+where
+    F: TreeFamily,
+{
+    #[inline]
+    fn default() -> Self {
+        StaticSplaySet::new()
+    }
+}
+
+impl<F, const N: usize> fmt::Debug for StaticSplaySet<F, N>
+where
+    F: TreeFamily,
+    for<'a> FamilyElem<'a, F>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticSplaySet")
+            .field("capacity", &N)
+            .field("tree", &self.tree)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, const N: usize> Drop for StaticSplaySet<F, N>
+where
+    F: TreeFamily,
+{
+    fn drop(&mut self) {
+        // Every element in the pool is in the tree, so dropping the ones in
+        // the tree drops them all.
+        while let Some(elem) = self.tree.pop_min() {
+            // Safety: the element was just removed from the tree.
+            unsafe { self.pool.release(elem) }
+        }
+    }
+}
+
+impl<F, const N: usize> StaticSplaySet<F, N>
+where
+    F: TreeFamily,
+{
+    /// Construct a new, empty set.
+    #[inline]
+    pub const fn new() -> Self {
+        StaticSplaySet {
+            tree: SplayTree::new(),
+            pool: Pool::new(),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Get the number of elements the set can hold.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Get the number of elements in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Is the set empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Is every slot in the pool taken?
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Split the set into its tree, at the lifetime of a borrow of the set,
+    /// and its pool.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn parts<'b>(
+        self: Pin<&'b mut Self>,
+    ) -> (
+        &'b mut SplayTree<'b, F::Tree<'b>>,
+        &'b Pool<FamilyElem<'static, F>, N>,
+    ) {
+        // Safety: nothing is moved out of the set. Every element in the tree
+        // is in the pool, which doesn't move and outlives `'b`. Types that
+        // differ only in lifetimes have the same layout.
+        unsafe {
+            let this = self.get_unchecked_mut();
+            let tree: *mut SplayTree<'static, F::Tree<'static>> = &mut this.tree;
+            (&mut *tree.cast::<SplayTree<'b, F::Tree<'b>>>(), &this.pool)
+        }
+    }
+
+    /// Move `value` into a free slot in the pool, and insert it into the set.
+    ///
+    /// If the set already has an element that is equal to `value`, `value`
+    /// replaces it, and the old element is dropped. Returns `Err(Full(value))`
+    /// if there is no free slot, even if `value` would have replaced an
+    /// element.
+    #[inline]
+    pub fn insert(
+        self: Pin<&mut Self>,
+        value: FamilyElem<'static, F>,
+    ) -> Result<(), Full<FamilyElem<'static, F>>> {
+        let (tree, pool) = self.parts();
+        let elem = pool.alloc(value).map_err(Full)?;
+        // Safety: the pool doesn't move, so the element lives as long as the
+        // set is borrowed.
+        let elem = unsafe { &*elem.cast::<FamilyElem<'_, F>>() };
+        if !tree.insert(elem) {
+            let old = tree.remove(elem).unwrap();
+            tree.insert(elem);
+            // Safety: the old element was just removed from the tree.
+            unsafe { pool.release(old) }
+        }
+        Ok(())
+    }
+
+    /// Find the element that matches `key`, splay it to the root, and call
+    /// `f` with it.
+    ///
+    /// Returns `None`, without calling `f`, if there is no such element.
+    #[inline]
+    pub fn find<K, R, G>(self: Pin<&mut Self>, key: &K, f: G) -> Option<R>
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+        G: for<'a> FnOnce(&'a FamilyElem<'a, F>) -> R,
+    {
+        self.parts().0.find(key).map(f)
+    }
+
+    /// Does the set contain an element that matches `key`?
+    #[inline]
+    pub fn contains<K>(self: Pin<&mut Self>, key: &K) -> bool
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+    {
+        self.find(key, |_| ()).is_some()
+    }
+
+    /// Remove the element that matches `key` and drop it, freeing its slot.
+    /// Returns whether there was one.
+    ///
+    /// The element can't be moved out instead, since it may still have stale
+    /// links from other trees that it was put in while it was borrowed.
+    #[inline]
+    pub fn remove<K>(self: Pin<&mut Self>, key: &K) -> bool
+    where
+        K: ?Sized + for<'a> TreeOrd<'a, F::Tree<'a>>,
+    {
+        let (tree, pool) = self.parts();
+        match tree.remove(key) {
+            // Safety: the element was just removed from the tree.
+            Some(elem) => unsafe {
+                pool.release(elem);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Splay the minimum element to the root, and call `f` with it.
+    ///
+    /// Returns `None`, without calling `f`, if the set is empty.
+    #[inline]
+    pub fn min<R, G>(self: Pin<&mut Self>, f: G) -> Option<R>
+    where
+        G: for<'a> FnOnce(&'a FamilyElem<'a, F>) -> R,
+    {
+        self.parts().0.min().map(f)
+    }
+
+    /// Splay the maximum element to the root, and call `f` with it.
+    ///
+    /// Returns `None`, without calling `f`, if the set is empty.
+    #[inline]
+    pub fn max<R, G>(self: Pin<&mut Self>, f: G) -> Option<R>
+    where
+        G: for<'a> FnOnce(&'a FamilyElem<'a, F>) -> R,
+    {
+        self.parts().0.max().map(f)
+    }
+
+    /// Walk the set in order, without splaying.
+    ///
+    /// See `SplayTree::walk` for how `W` controls the walk.
+    #[inline]
+    pub fn walk<G, W>(&self, f: G) -> Option<W::Result>
+    where
+        G: for<'a> FnMut(&'a FamilyElem<'a, F>) -> W,
+        W: WalkControl,
+    {
+        // Safety: the set can't be moved or modified while it is borrowed,
+        // and types that differ only in lifetimes have the same layout.
+        let tree = unsafe {
+            &*(&self.tree as *const SplayTree<'static, F::Tree<'static>>)
+                .cast::<SplayTree<'_, F::Tree<'_>>>()
+        };
+        tree.walk(f)
+    }
+}
@@ -0,0 +1,163 @@
+//! Longest-prefix-match queries over trees of bit-string prefixes.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::cmp::Ordering;
+
+/// An unsigned integer used as a string of bits, most significant bit first,
+/// for `IntrusivePrefix`.
+pub trait PrefixBits: Copy + Ord {
+    /// The number of bits, and so the longest possible prefix.
+    const BITS: u32;
+
+    /// Keep the first `len` bits, and clear the rest.
+    fn truncate(self, len: u32) -> Self;
+
+    /// Get the number of leading bits that `self` and `other` share.
+    fn common_prefix_len(self, other: Self) -> u32;
+}
+
+macro_rules! impl_prefix_bits {
+    ( $( $int:ty ),* ) => {
+        $(
+            impl PrefixBits for $int {
+                const BITS: u32 = <$int>::BITS;
+
+                #[inline]
+                fn truncate(self, len: u32) -> Self {
+                    let mask = (!0 as $int).checked_shl(Self::BITS - len).unwrap_or(0);
+                    self & mask
+                }
+
+                #[inline]
+                fn common_prefix_len(self, other: Self) -> u32 {
+                    (self ^ other).leading_zeros()
+                }
+            }
+        )*
+    };
+}
+
+impl_prefix_bits!(u8, u16, u32, u64, u128);
+
+/// A tree type whose elements are prefixes of bit strings, like the CIDR
+/// blocks `10.0.0.0/8` or `2001:db8::/32` in a routing table, for use with
+/// `SplayTree::longest_prefix_match`.
+///
+/// A prefix is its bits, with everything past its length cleared, and its
+/// length. The element's `TreeOrd` implementation must order elements by
+/// their bits and then by their length, as `(bits, len)` tuples compare.
+pub trait IntrusivePrefix<'a>: IntrusiveNode<'a> {
+    /// The type of the prefixes' bits, like `u32` for IPv4 or `u128` for IPv6.
+    type Bits: PrefixBits;
+
+    /// Get the given element's prefix, as its bits and the number of them
+    /// that are significant, which is at most `Self::Bits::BITS`.
+    fn prefix(elem: &'a Self::Elem) -> (Self::Bits, u32);
+}
+
+/// A key that compares to elements by their prefix.
+struct PrefixKey<B>(B, u32);
+
+impl<'a, T> TreeOrd<'a, T> for PrefixKey<T::Bits>
+where
+    T: IntrusivePrefix<'a>,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> Ordering {
+        (self.0, self.1).cmp(&T::prefix(elem))
+    }
+}
+
+impl<'a, T> SplayTree<'a, T>
+where
+    T: 'a + IntrusivePrefix<'a>,
+{
+    /// Find the element with the longest prefix that `bits` starts with,
+    /// which is the most specific of the prefixes that cover it.
+    ///
+    /// Prefixes that cover `bits` are not the only ones that sort before it,
+    /// so this can't be a single `find_le`: the nearest prefix below `bits`
+    /// may be a sibling of the one that covers it, like `10.1.0.0/16` is for
+    /// `10.2.0.1` under `10.0.0.0/8`. Instead, each miss narrows the search to
+    /// the bits that the missed prefix shares with `bits`, so there are at
+    /// most `T::Bits::BITS + 1` lookups, and usually one or two.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, IntrusivePrefix, Node, SplayTree, TreeOrd};
+    /// use std::cmp::Ordering;
+    /// use std::net::Ipv4Addr;
+    ///
+    /// struct Route<'a> {
+    ///     network: u32,
+    ///     len: u32,
+    ///     via: &'static str,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// impl<'a> Route<'a> {
+    ///     fn new(network: [u8; 4], len: u32, via: &'static str) -> Self {
+    ///         let network = u32::from(Ipv4Addr::from(network));
+    ///         Route { network, len, via, node: Node::new() }
+    ///     }
+    /// }
+    ///
+    /// struct Routes;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Routes
+    ///     where
+    ///         type Elem = Route<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl<'a> TreeOrd<'a, Routes> for Route<'a> {
+    ///     fn tree_cmp(&self, rhs: &'a Route<'a>) -> Ordering {
+    ///         (self.network, self.len).cmp(&(rhs.network, rhs.len))
+    ///     }
+    /// }
+    ///
+    /// impl<'a> IntrusivePrefix<'a> for Routes {
+    ///     type Bits = u32;
+    ///     fn prefix(route: &'a Route<'a>) -> (u32, u32) {
+    ///         (route.network, route.len)
+    ///     }
+    /// }
+    ///
+    /// let routes = [
+    ///     Route::new([0, 0, 0, 0], 0, "default"),
+    ///     Route::new([10, 0, 0, 0], 8, "core"),
+    ///     Route::new([10, 1, 0, 0], 16, "lab"),
+    ///     Route::new([10, 1, 2, 0], 24, "rack"),
+    /// ];
+    /// let mut table = SplayTree::<Routes>::new();
+    /// for route in &routes {
+    ///     table.insert(route);
+    /// }
+    ///
+    /// let via = |table: &mut SplayTree<Routes>, addr: [u8; 4]| {
+    ///     table.longest_prefix_match(Ipv4Addr::from(addr).into()).map(|r| r.via)
+    /// };
+    /// assert_eq!(via(&mut table, [10, 1, 2, 3]), Some("rack"));
+    /// assert_eq!(via(&mut table, [10, 1, 3, 3]), Some("lab"));
+    /// assert_eq!(via(&mut table, [10, 2, 0, 1]), Some("core"));
+    /// assert_eq!(via(&mut table, [192, 168, 0, 1]), Some("default"));
+    /// ```
+    pub fn longest_prefix_match(&mut self, bits: T::Bits) -> Option<&'a T::Elem> {
+        let mut len = T::Bits::BITS;
+        loop {
+            let elem = self.find_le(&PrefixKey(bits.truncate(len), len))?;
+            let (prefix, prefix_len) = T::prefix(elem);
+            let common = prefix.common_prefix_len(bits);
+            if prefix_len <= common {
+                return Some(elem);
+            }
+
+            // Every prefix that covers `bits` and is longer than `common`
+            // sorts between `elem` and `bits`, where there is nothing, so the
+            // only candidates left are at most `common` long. Since `elem` is
+            // not one of them, `common` is shorter than `len`.
+            debug_assert!(common < len);
+            len = common;
+        }
+    }
+}
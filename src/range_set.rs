@@ -0,0 +1,317 @@
+//! Sets of disjoint ranges that coalesce as they are inserted.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::cell::Cell;
+use core::cmp;
+use core::fmt;
+use core::ops::Range;
+
+/// A tree type whose elements are half-open ranges, `start..end`, for use with
+/// `RangeSet`.
+///
+/// The range set moves the bounds of its elements as it merges and splits
+/// them, so they are stored in `Cell`s. The element's `TreeOrd` implementation
+/// must order elements by their `start`.
+pub trait IntrusiveRange<'a>: IntrusiveNode<'a, Elem: TreeOrd<'a, Self>> {
+    /// The type of the ranges' bounds.
+    type Point: 'a + Copy + Ord;
+
+    /// Get the inclusive start of the given element's range.
+    fn start(elem: &'a Self::Elem) -> &'a Cell<Self::Point>;
+
+    /// Get the exclusive end of the given element's range.
+    fn end(elem: &'a Self::Elem) -> &'a Cell<Self::Point>;
+}
+
+/// A key that compares to elements by their start.
+struct StartAt<P>(P);
+
+impl<'a, T> TreeOrd<'a, T> for StartAt<T::Point>
+where
+    T: IntrusiveRange<'a>,
+{
+    #[inline]
+    fn tree_cmp(&self, elem: &'a T::Elem) -> cmp::Ordering {
+        self.0.cmp(&T::start(elem).get())
+    }
+}
+
+/// A set of points, stored as a tree of disjoint, non-adjacent ranges.
+///
+/// Inserting a range merges it with every range that it overlaps or touches,
+/// and removing a range trims, splits, or removes the ranges it overlaps, so
+/// the set always holds the fewest ranges that cover its points. Since the
+/// elements are intrusive, merging leaves some of them unused, and splitting
+/// needs a new one; the `*_with` methods report the former and every removal
+/// asks for the latter.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, IntrusiveRange, Node, RangeSet, TreeOrd};
+/// use std::cell::Cell;
+/// use std::cmp::Ordering;
+///
+/// #[derive(Default)]
+/// struct Extent<'a> {
+///     start: Cell<u64>,
+///     end: Cell<u64>,
+///     node: Node<'a>,
+/// }
+///
+/// struct Extents;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Extents
+///     where
+///         type Elem = Extent<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, Extents> for Extent<'a> {
+///     fn tree_cmp(&self, rhs: &'a Extent<'a>) -> Ordering {
+///         self.start.get().cmp(&rhs.start.get())
+///     }
+/// }
+///
+/// impl<'a> IntrusiveRange<'a> for Extents {
+///     type Point = u64;
+///     fn start(e: &'a Extent<'a>) -> &'a Cell<u64> {
+///         &e.start
+///     }
+///     fn end(e: &'a Extent<'a>) -> &'a Cell<u64> {
+///         &e.end
+///     }
+/// }
+///
+/// let arena = bumpalo::Bump::new();
+/// let extent = |range: std::ops::Range<u64>| {
+///     &*arena.alloc(Extent {
+///         start: Cell::new(range.start),
+///         end: Cell::new(range.end),
+///         node: Node::default(),
+///     })
+/// };
+///
+/// let mut allocated = RangeSet::<Extents>::new();
+/// allocated.insert(extent(0x1000..0x2000));
+/// allocated.insert(extent(0x3000..0x4000));
+/// allocated.insert(extent(0x2000..0x3000));
+/// assert!(allocated.covering(0x1000..0x4000).is_some());
+///
+/// allocated.remove(0x1800..0x2800, || &*arena.alloc(Extent::default()));
+/// assert!(allocated.contains(0x17ff));
+/// assert!(!allocated.contains(0x2000));
+/// assert!(allocated.contains(0x2800));
+/// ```
+pub struct RangeSet<'a, T>
+where
+    T: 'a + IntrusiveRange<'a>,
+{
+    tree: SplayTree<'a, T>,
+}
+
+impl<'a, T> Default for RangeSet<'a, T>
+where
+    T: 'a + IntrusiveRange<'a>,
+{
+    #[inline]
+    fn default() -> Self {
+        RangeSet::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for RangeSet<'a, T>
+where
+    T: 'a + IntrusiveRange<'a>,
+    T::Point: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.tree.walk(|elem| {
+            set.entry(&(T::start(elem).get()..T::end(elem).get()));
+        });
+        set.finish()
+    }
+}
+
+impl<'a, T> RangeSet<'a, T>
+where
+    T: 'a + IntrusiveRange<'a>,
+{
+    /// Construct a new, empty range set.
+    #[inline]
+    pub const fn new() -> Self {
+        RangeSet {
+            tree: SplayTree::new(),
+        }
+    }
+
+    /// Is the set empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the underlying tree of ranges.
+    #[inline]
+    pub fn tree(&self) -> &SplayTree<'a, T> {
+        &self.tree
+    }
+
+    /// Get the range that contains `point`, if any.
+    #[inline]
+    pub fn get(&mut self, point: T::Point) -> Option<&'a T::Elem> {
+        let elem = self.tree.find_le(&StartAt(point))?;
+        if point < T::end(elem).get() {
+            Some(elem)
+        } else {
+            None
+        }
+    }
+
+    /// Is `point` in the set?
+    #[inline]
+    pub fn contains(&mut self, point: T::Point) -> bool {
+        self.get(point).is_some()
+    }
+
+    /// Get the range that covers all of `range`, if any.
+    ///
+    /// Because ranges are always coalesced, `range` is entirely in the set
+    /// exactly when a single range covers it. An empty `range` is never
+    /// covered.
+    #[inline]
+    pub fn covering(&mut self, range: Range<T::Point>) -> Option<&'a T::Elem> {
+        if range.is_empty() {
+            return None;
+        }
+        let elem = self.get(range.start)?;
+        if range.end <= T::end(elem).get() {
+            Some(elem)
+        } else {
+            None
+        }
+    }
+
+    /// Insert `elem`'s range into the set.
+    ///
+    /// See `insert_with` for details.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) {
+        self.insert_with(elem, |_| {});
+    }
+
+    /// Insert `elem`'s range into the set, merging it with every range that it
+    /// overlaps or touches.
+    ///
+    /// `elem` is extended to cover the merged range, and the elements it
+    /// absorbs are removed from the tree and passed to `absorbed`, so that they
+    /// can be reused. An empty range is not inserted.
+    pub fn insert_with<F>(&mut self, elem: &'a T::Elem, mut absorbed: F)
+    where
+        F: FnMut(&'a T::Elem),
+    {
+        let (mut start, mut end) = (T::start(elem).get(), T::end(elem).get());
+        if start >= end {
+            return;
+        }
+
+        // Only the last range that starts at or before `start` can reach it.
+        if let Some(pred) = self.tree.find_le(&StartAt(start)) {
+            if T::end(pred).get() >= start {
+                start = T::start(pred).get();
+                end = cmp::max(end, T::end(pred).get());
+                self.tree.remove(pred);
+                absorbed(pred);
+            }
+        }
+
+        // Then absorb every range that starts within, or right at the end of,
+        // the merged range.
+        while let Some(next) = self.tree.find_gt(&StartAt(start)) {
+            if T::start(next).get() > end {
+                break;
+            }
+            end = cmp::max(end, T::end(next).get());
+            self.tree.remove(next);
+            absorbed(next);
+        }
+
+        T::start(elem).set(start);
+        T::end(elem).set(end);
+        let inserted = self.tree.insert(elem);
+        debug_assert!(inserted);
+    }
+
+    /// Remove `range` from the set.
+    ///
+    /// See `remove_with` for details.
+    #[inline]
+    pub fn remove<S>(&mut self, range: Range<T::Point>, split: S)
+    where
+        S: FnOnce() -> &'a T::Elem,
+    {
+        self.remove_with(range, split, |_| {});
+    }
+
+    /// Remove `range` from the set, trimming the ranges that it overlaps.
+    ///
+    /// If `range` is strictly inside one of the set's ranges, that range is
+    /// split in two, and `split` is called to get a new, unlinked element for
+    /// the upper half. Otherwise, `split` is not called. Ranges that `range`
+    /// covers entirely are removed from the tree and passed to `removed`, so
+    /// that they can be reused.
+    pub fn remove_with<S, F>(&mut self, range: Range<T::Point>, split: S, mut removed: F)
+    where
+        S: FnOnce() -> &'a T::Elem,
+        F: FnMut(&'a T::Elem),
+    {
+        let Range { start, end } = range;
+        if start >= end {
+            return;
+        }
+
+        // A range that starts before `start` keeps its lower part, and maybe
+        // gets split.
+        if let Some(pred) = self.tree.find_le(&StartAt(start)) {
+            let pred_end = T::end(pred).get();
+            if T::start(pred).get() < start && pred_end > start {
+                T::end(pred).set(start);
+                if pred_end > end {
+                    let upper = split();
+                    T::start(upper).set(end);
+                    T::end(upper).set(pred_end);
+                    let inserted = self.tree.insert(upper);
+                    debug_assert!(inserted);
+                    return;
+                }
+            }
+        }
+
+        // Ranges that start within `range` are removed, except for an upper
+        // part that sticks out past `end`.
+        while let Some(next) = self.tree.remove_ge(&StartAt(start)) {
+            if T::start(next).get() >= end {
+                let inserted = self.tree.insert(next);
+                debug_assert!(inserted);
+                break;
+            }
+            if T::end(next).get() > end {
+                T::start(next).set(end);
+                let inserted = self.tree.insert(next);
+                debug_assert!(inserted);
+                break;
+            }
+            removed(next);
+        }
+    }
+
+    /// Walk the set's ranges in order.
+    #[inline]
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(Range<T::Point>),
+    {
+        self.tree
+            .walk(|elem| f(T::start(elem).get()..T::end(elem).get()));
+    }
+}
@@ -0,0 +1,241 @@
+//! Sequences of elements that are addressed by position instead of by key.
+
+use crate::brand::Brand;
+use crate::internal;
+use crate::{IntrusiveNode, WalkControl};
+use core::cmp;
+use core::fmt;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem;
+
+/// An intrusive splay tree that orders its elements by position, like a `Vec`,
+/// rather than by key.
+///
+/// The elements have no keys. Instead, each one is addressed by its index,
+/// which is kept implicitly in the subtree sizes of the `rank` feature.
+/// Inserting or removing at any position, looking up an index, splitting a
+/// sequence in two, and concatenating two sequences all take *O(log n)*
+/// amortized time, which makes this a good fit for ropes, playlists, and other
+/// lists that are edited in the middle.
+///
+/// Requires the `rank` feature.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplaySequence};
+///
+/// struct Song<'a> {
+///     title: &'static str,
+///     node: Node<'a>,
+/// }
+///
+/// struct Playlist;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Playlist
+///     where
+///         type Elem = Song<'a>,
+///         node = node;
+/// }
+///
+/// let songs = ["a", "b", "c", "d"].map(|title| Song { title, node: Node::default() });
+/// let mut playlist = SplaySequence::<Playlist>::new();
+/// playlist.push_back(&songs[0]);
+/// playlist.push_back(&songs[2]);
+/// playlist.insert_at(1, &songs[1]);
+/// playlist.push_front(&songs[3]);
+///
+/// let titles = |p: &SplaySequence<Playlist>| {
+///     let mut titles = vec![];
+///     p.walk(|s| titles.push(s.title));
+///     titles
+/// };
+/// assert_eq!(titles(&playlist), ["d", "a", "b", "c"]);
+///
+/// let mut rest = playlist.split_off(2);
+/// assert_eq!(rest.get(0).unwrap().title, "b");
+/// rest.append(&mut playlist);
+/// assert_eq!(titles(&rest), ["b", "c", "d", "a"]);
+/// assert_eq!(rest.remove_at(3).unwrap().title, "a");
+/// ```
+pub struct SplaySequence<'a, T>
+where
+    T: IntrusiveNode<'a>,
+    T::Elem: 'a,
+{
+    tree: internal::SplayTree<'a>,
+    brand: Brand,
+    _phantom: PhantomData<&'a T::Elem>,
+}
+
+impl<'a, T> Default for SplaySequence<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn default() -> SplaySequence<'a, T> {
+        SplaySequence::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for SplaySequence<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = &mut f.debug_list();
+        self.walk(|x| {
+            list.entry(&x);
+        });
+        list.finish()
+    }
+}
+
+impl<'a, T> Extend<&'a T::Elem> for SplaySequence<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T::Elem>>(&mut self, iterable: I) {
+        for elem in iterable {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<'a, T> FromIterator<&'a T::Elem> for SplaySequence<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = &'a T::Elem>>(iterable: I) -> Self {
+        let mut sequence = SplaySequence::new();
+        sequence.extend(iterable);
+        sequence
+    }
+}
+
+impl<'a, T> SplaySequence<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    /// Construct a new, empty sequence.
+    #[inline]
+    pub const fn new() -> SplaySequence<'a, T> {
+        SplaySequence {
+            tree: internal::SplayTree::new(),
+            brand: Brand::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Is the sequence empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the number of elements in the sequence.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Get the element at `index`, splaying it to the root.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    #[inline]
+    pub fn get(&mut self, index: usize) -> Option<&'a T::Elem> {
+        match self.tree.splay_at(index)? {
+            (node, cmp::Ordering::Equal) => Some(unsafe { self.brand.elem::<T>(node) }),
+            _ => None,
+        }
+    }
+
+    /// Insert `elem` at `index`, shifting every element after it along by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the sequence's length.
+    pub fn insert_at(&mut self, index: usize, elem: &'a T::Elem) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        let node = T::elem_to_node(elem);
+        self.brand.check_unbranded(node);
+        self.tree.insert_at(index, node);
+        self.brand.brand(node);
+    }
+
+    /// Insert `elem` at the start of the sequence.
+    #[inline]
+    pub fn push_front(&mut self, elem: &'a T::Elem) {
+        self.insert_at(0, elem);
+    }
+
+    /// Insert `elem` at the end of the sequence.
+    #[inline]
+    pub fn push_back(&mut self, elem: &'a T::Elem) {
+        self.insert_at(self.len(), elem);
+    }
+
+    /// Remove the element at `index`, and return it.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    #[inline]
+    pub fn remove_at(&mut self, index: usize) -> Option<&'a T::Elem> {
+        let node = self.tree.remove_at(index)?;
+        Some(unsafe { self.brand.release::<T>(node) })
+    }
+
+    /// Split the sequence in two at `index`: this sequence keeps the elements
+    /// before `index`, and the rest are returned as a new sequence.
+    ///
+    /// If `index` is at least the sequence's length, the returned sequence is
+    /// empty.
+    pub fn split_off(&mut self, index: usize) -> SplaySequence<'a, T> {
+        let tail = match self.tree.splay_at(index) {
+            Some((_, cmp::Ordering::Equal)) => {
+                let head = self.tree.take_root_left();
+                mem::replace(&mut self.tree, head)
+            }
+            _ => internal::SplayTree::new(),
+        };
+        SplaySequence {
+            tree: tail,
+            brand: self.brand.share(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Move every element of `other` to the end of this sequence, leaving
+    /// `other` empty.
+    #[inline]
+    pub fn append(&mut self, other: &mut SplaySequence<'a, T>) {
+        self.brand.adopt(&other.brand, other.tree.root());
+        self.tree.append(&mut other.tree);
+    }
+
+    /// Walk the sequence in order, without splaying.
+    ///
+    /// See `SplayTree::walk` for how `C` controls the walk.
+    #[inline]
+    pub fn walk<F, C>(&self, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(&'a T::Elem) -> C,
+        C: WalkControl,
+    {
+        let mut result = None;
+        self.tree.walk(&mut |node| unsafe {
+            let elem = self.brand.elem::<T>(node);
+            result = f(elem).should_break();
+            result.is_none()
+        });
+        result
+    }
+}
@@ -0,0 +1,267 @@
+//! Trees that partition their key space across several inner splay trees.
+
+use crate::iter::Elems;
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::array;
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// Choose which shard of a `ShardedSplayTree` a key, or an element, lives in.
+///
+/// This is implemented by the tree's element type, to place elements, and by
+/// every key type that is used for lookups. A key and every element that it
+/// compares equal to must choose the same shard, or lookups will miss. The
+/// result is taken modulo the number of shards.
+pub trait ShardKey<'a, T>: TreeOrd<'a, T>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Get the index of this key's shard.
+    fn shard(&self) -> usize;
+}
+
+/// A set of `N` splay trees, each holding the elements whose keys map to it
+/// with `ShardKey`.
+///
+/// Every lookup only splays the one shard that its key maps to, so accesses to
+/// different shards don't disturb each other's recently used elements, and
+/// each shard is about `1 / N`th the size of the whole set, which bounds how
+/// deep its splay tree gets. The shards can be a hash partition, or a range
+/// partition by key, and `iter` merges them back into one ordered sequence
+/// either way.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, ShardKey, ShardedSplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Conn<'a> {
+///     port: u16,
+///     node: Node<'a>,
+/// }
+///
+/// struct ConnsByPort;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ConnsByPort
+///     where
+///         type Elem = Conn<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ConnsByPort> for Conn<'a> {
+///     fn tree_cmp(&self, rhs: &'a Conn<'a>) -> Ordering {
+///         self.port.cmp(&rhs.port)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, ConnsByPort> for u16 {
+///     fn tree_cmp(&self, rhs: &'a Conn<'a>) -> Ordering {
+///         self.cmp(&rhs.port)
+///     }
+/// }
+///
+/// impl<'a> ShardKey<'a, ConnsByPort> for Conn<'a> {
+///     fn shard(&self) -> usize {
+///         self.port.shard()
+///     }
+/// }
+///
+/// impl<'a> ShardKey<'a, ConnsByPort> for u16 {
+///     fn shard(&self) -> usize {
+///         *self as usize
+///     }
+/// }
+///
+/// let conns = [80, 443, 22, 8080].map(|port| Conn { port, node: Node::default() });
+/// let mut table = ShardedSplayTree::<ConnsByPort, 4>::new();
+/// for conn in &conns {
+///     table.insert(conn);
+/// }
+///
+/// assert_eq!(table.find(&443).unwrap().port, 443);
+/// assert_eq!(table.min().unwrap().port, 22);
+/// let ports: Vec<_> = table.iter().map(|c| c.port).collect();
+/// assert_eq!(ports, [22, 80, 443, 8080]);
+/// ```
+pub struct ShardedSplayTree<'a, T, const N: usize>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    shards: [SplayTree<'a, T>; N],
+}
+
+impl<'a, T, const N: usize> Default for ShardedSplayTree<'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: ShardKey<'a, T>,
+{
+    #[inline]
+    fn default() -> Self {
+        ShardedSplayTree::new()
+    }
+}
+
+impl<'a, T, const N: usize> fmt::Debug for ShardedSplayTree<'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(&self.shards).finish()
+    }
+}
+
+impl<'a, T, const N: usize> ShardedSplayTree<'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: ShardKey<'a, T>,
+{
+    /// Construct a new sharded tree, with `N` empty shards.
+    ///
+    /// `N` must not be zero; that fails to compile.
+    #[inline]
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "a sharded tree needs at least one shard") };
+        ShardedSplayTree {
+            shards: [const { SplayTree::new() }; N],
+        }
+    }
+
+    #[inline]
+    fn shard_for<K>(&mut self, key: &K) -> &mut SplayTree<'a, T>
+    where
+        K: ?Sized + ShardKey<'a, T>,
+    {
+        &mut self.shards[key.shard() % N]
+    }
+
+    /// Get the shards.
+    #[inline]
+    pub fn shards(&self) -> &[SplayTree<'a, T>; N] {
+        &self.shards
+    }
+
+    /// Are all of the shards empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(SplayTree::is_empty)
+    }
+
+    /// Get the total number of elements in every shard.
+    ///
+    /// Requires the `rank` feature.
+    #[cfg(feature = "rank")]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(SplayTree::len).sum()
+    }
+
+    /// Find the element that matches the given `key`, splaying it to the root
+    /// of its shard.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + ShardKey<'a, T>,
+    {
+        self.shard_for(key).find(key)
+    }
+
+    /// Insert `elem` into its shard.
+    ///
+    /// Returns `false` if an element that compares equal to `elem` is already
+    /// in the tree, like `SplayTree::insert`.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        self.shard_for(elem).insert(elem)
+    }
+
+    /// Remove and return the element that matches the given `key`, if any.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + ShardKey<'a, T>,
+    {
+        self.shard_for(key).remove(key)
+    }
+
+    /// Get the minimum element of every shard.
+    ///
+    /// This splays each shard's minimum to its root.
+    pub fn min(&mut self) -> Option<&'a T::Elem> {
+        self.shards
+            .iter_mut()
+            .filter_map(SplayTree::min)
+            .min_by(|a, b| TreeOrd::<T>::tree_cmp(*a, b))
+    }
+
+    /// Get the maximum element of every shard.
+    ///
+    /// This splays each shard's maximum to its root.
+    pub fn max(&mut self) -> Option<&'a T::Elem> {
+        self.shards
+            .iter_mut()
+            .filter_map(SplayTree::max)
+            .max_by(|a, b| TreeOrd::<T>::tree_cmp(*a, b))
+    }
+
+    /// Iterate over the elements of every shard, merged into one ascending
+    /// sequence, without splaying.
+    #[inline]
+    pub fn iter(&self) -> ShardedIter<'_, 'a, T, N> {
+        let mut shards: [Elems<'_, 'a, T>; N] = array::from_fn(|i| self.shards[i].iter());
+        let heads = array::from_fn(|i| shards[i].next());
+        ShardedIter { shards, heads }
+    }
+}
+
+/// An in-order iterator over the elements of a `ShardedSplayTree`.
+///
+/// Returned by `ShardedSplayTree::iter`. Each step compares the next element
+/// of every shard, so it takes *O(N)* time.
+pub struct ShardedIter<'t, 'a, T, const N: usize>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    shards: [Elems<'t, 'a, T>; N],
+    heads: [Option<&'a T::Elem>; N],
+}
+
+impl<'t, 'a, T, const N: usize> Iterator for ShardedIter<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Item = &'a T::Elem;
+
+    fn next(&mut self) -> Option<&'a T::Elem> {
+        let mut next: Option<(usize, &'a T::Elem)> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some(elem) = *head {
+                match next {
+                    Some((_, min)) if elem.tree_cmp(min) != cmp::Ordering::Less => {}
+                    _ => next = Some((i, elem)),
+                }
+            }
+        }
+        let (i, elem) = next?;
+        self.heads[i] = self.shards[i].next();
+        Some(elem)
+    }
+}
+
+impl<'t, 'a, T, const N: usize> FusedIterator for ShardedIter<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+}
+
+impl<'t, 'a, T, const N: usize> fmt::Debug for ShardedIter<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShardedIter").finish_non_exhaustive()
+    }
+}
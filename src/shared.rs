@@ -0,0 +1,265 @@
+//! Trees that readers on other threads can search while a writer retires
+//! elements, which are unlinked later.
+
+use crate::{IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_has_atomic = "8"))]
+use portable_atomic::{AtomicBool, Ordering};
+
+/// A flag, embedded in each element of a `SharedSplayTree`, that marks the
+/// element as logically removed.
+pub struct RetireFlag(AtomicBool);
+
+impl RetireFlag {
+    /// Construct a new flag, for an element that is not retired.
+    #[inline]
+    pub const fn new() -> RetireFlag {
+        RetireFlag(AtomicBool::new(false))
+    }
+
+    /// Has the element been retired from its tree, and not unlinked since?
+    #[inline]
+    pub fn is_retired(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl Default for RetireFlag {
+    #[inline]
+    fn default() -> RetireFlag {
+        RetireFlag::new()
+    }
+}
+
+impl fmt::Debug for RetireFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RetireFlag")
+            .field(&self.is_retired())
+            .finish()
+    }
+}
+
+/// A tree type whose elements can be shared with other threads through a
+/// `SharedSplayTree`.
+///
+/// ## Safety
+///
+/// `retire_flag` must always return the same `RetireFlag` field of the given
+/// element.
+///
+/// A `SharedSplayTree` hands out its elements to every thread that it is
+/// shared with, even though elements aren't `Sync`, since their nodes are made
+/// of `Cell`s. The tree only ever reads this tree type's node while it is
+/// shared, but everything else that those threads touch, through the element
+/// or through `TreeOrd` implementations, must be safe to use from several
+/// threads at once, as if it were `Sync`. That rules out using the element's
+/// nodes for other trees on any thread while it is shared.
+pub unsafe trait SharedNode<'a>: IntrusiveNode<'a> {
+    /// Get the retire flag of the given element.
+    fn retire_flag(elem: &Self::Elem) -> &RetireFlag;
+}
+
+/// A splay tree that can be searched from many threads at once, while its
+/// elements are retired, and unlinked once the searches are done.
+///
+/// Every lookup in a `SplayTree` restructures it, and its links are `Cell`s,
+/// so a tree can't be shared with a writer. This wrapper is `Sync`, but only
+/// searches without splaying through a shared reference, with `get`, and only
+/// removes elements logically, with `retire`, which sets an atomic flag in the
+/// element that `get` then checks. The links are only ever changed through a
+/// unique reference, by `insert` and `unlink_retired`, so those can't overlap
+/// with any search: getting the `&mut` back from the threads, for example by
+/// joining them or taking a write lock, is the quiescent point that an
+/// epoch-based scheme would wait for.
+///
+/// Requires the `shared` feature. Retiring is an atomic swap, so on targets
+/// that don't have one for bytes, this also requires the `portable-atomic`
+/// feature.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, impl_tree_ord_by_field, Node};
+/// use intrusive_splay_tree::{RetireFlag, SharedNode, SharedSplayTree};
+///
+/// struct Route<'a> {
+///     prefix: u32,
+///     retired: RetireFlag,
+///     node: Node<'a>,
+/// }
+///
+/// struct Routes;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for Routes
+///     where
+///         type Elem = Route<'a>,
+///         node = node;
+/// }
+///
+/// impl_tree_ord_by_field! {
+///     impl<'a> TreeOrd<'a, Routes> for Route<'a>
+///     where
+///         field = prefix: u32;
+/// }
+///
+/// // Safety: routes only ever have this one node, and `prefix` is never
+/// // changed.
+/// unsafe impl<'a> SharedNode<'a> for Routes {
+///     fn retire_flag(route: &Self::Elem) -> &RetireFlag {
+///         &route.retired
+///     }
+/// }
+///
+/// let routes = [10, 20, 30]
+///     .map(|prefix| Route { prefix, retired: RetireFlag::new(), node: Node::new() });
+/// let mut table = SharedSplayTree::<Routes>::new();
+/// for route in &routes {
+///     table.insert(route);
+/// }
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| assert!(table.get(&20).is_some()));
+///     s.spawn(|| assert!(table.retire(&30).is_some()));
+/// });
+/// assert!(table.get(&30).is_none());
+///
+/// // Back on one thread, the retired route can be unlinked and reused.
+/// let mut unlinked = vec![];
+/// assert_eq!(table.unlink_retired(|route| unlinked.push(route.prefix)), 1);
+/// assert_eq!(unlinked, [30]);
+/// assert!(table.insert(&routes[2]));
+/// ```
+pub struct SharedSplayTree<'a, T>
+where
+    T: 'a + SharedNode<'a>,
+{
+    tree: SplayTree<'a, T>,
+}
+
+// Safety: through a shared reference, the tree is only searched without
+// splaying, which only reads the nodes, and retired, which only touches the
+// atomic flags. `SharedNode`'s implementer promises that the rest of the
+// elements can be shared.
+unsafe impl<'a, T> Sync for SharedSplayTree<'a, T> where T: 'a + SharedNode<'a> {}
+
+// Safety: as above, the tree only links to elements, which `SharedNode`'s
+// implementer promises can be used from other threads.
+unsafe impl<'a, T> Send for SharedSplayTree<'a, T> where T: 'a + SharedNode<'a> {}
+
+impl<'a, T> Default for SharedSplayTree<'a, T>
+where
+    T: 'a + SharedNode<'a>,
+{
+    #[inline]
+    fn default() -> Self {
+        SharedSplayTree::new()
+    }
+}
+
+impl<'a, T> fmt::Debug for SharedSplayTree<'a, T>
+where
+    T: 'a + SharedNode<'a>,
+    T::Elem: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedSplayTree")
+            .field("tree", &self.tree)
+            .finish()
+    }
+}
+
+impl<'a, T> From<SplayTree<'a, T>> for SharedSplayTree<'a, T>
+where
+    T: 'a + SharedNode<'a>,
+{
+    /// Share a tree, whose elements must not already be retired.
+    #[inline]
+    fn from(tree: SplayTree<'a, T>) -> Self {
+        SharedSplayTree { tree }
+    }
+}
+
+impl<'a, T> SharedSplayTree<'a, T>
+where
+    T: 'a + SharedNode<'a>,
+{
+    /// Construct a new, empty shared tree.
+    #[inline]
+    pub const fn new() -> Self {
+        SharedSplayTree {
+            tree: SplayTree::new(),
+        }
+    }
+
+    /// Find the element that matches `key`, unless it is retired, without
+    /// splaying.
+    ///
+    /// Like `SplayTree::get`, this only descends the tree, so it doesn't get
+    /// `find`'s amortized guarantees.
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree
+            .get(key)
+            .filter(|elem| !T::retire_flag(elem).is_retired())
+    }
+
+    /// Retire the element that matches `key`, so that `get` no longer finds
+    /// it, and return it, or return `None` if there is no such element or it
+    /// was already retired.
+    ///
+    /// The element stays linked into the tree, and still can't be inserted
+    /// into another tree of this type or dropped, until `unlink_retired`.
+    #[inline]
+    pub fn retire<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let elem = self.tree.get(key)?;
+        let flag = &T::retire_flag(elem).0;
+        (!flag.swap(true, Ordering::AcqRel)).then_some(elem)
+    }
+
+    /// Insert a new element into the tree, as with `SplayTree::insert`.
+    ///
+    /// Returns `false` if an equal element is already in the tree, even if it
+    /// is retired but not yet unlinked.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool
+    where
+        T::Elem: TreeOrd<'a, T>,
+    {
+        self.tree.insert(elem)
+    }
+
+    /// Unlink every retired element from the tree, passing each to `f` in
+    /// order just before it is unlinked, and return how many there were.
+    ///
+    /// Each unlinked element's flag is cleared, so that it can be inserted
+    /// again. This walks the whole tree, in linear amortized time.
+    pub fn unlink_retired<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&'a T::Elem),
+    {
+        self.tree.walk_and_remove(|elem| {
+            let flag = &T::retire_flag(elem).0;
+            if flag.load(Ordering::Acquire) {
+                flag.store(false, Ordering::Relaxed);
+                f(elem);
+                crate::Verdict::Remove
+            } else {
+                crate::Verdict::Keep
+            }
+        })
+    }
+
+    /// Get the underlying tree, which still contains any retired elements
+    /// that haven't been unlinked.
+    #[inline]
+    pub fn into_inner(self) -> SplayTree<'a, T> {
+        self.tree
+    }
+}
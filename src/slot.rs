@@ -0,0 +1,444 @@
+//! Splay trees whose links are keys into external storage, like a slot map.
+
+use crate::WalkControl;
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// The links that a `SlotSplayTree` keeps in each of its elements, as keys of
+/// the storage they live in.
+///
+/// This is the slot-keyed counterpart of `Node`: embed one in the element type
+/// for each tree the element can be in.
+pub struct SlotNode<K> {
+    left: Cell<Option<K>>,
+    right: Cell<Option<K>>,
+}
+
+impl<K> Default for SlotNode<K> {
+    #[inline]
+    fn default() -> Self {
+        SlotNode::new()
+    }
+}
+
+impl<K> fmt::Debug for SlotNode<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SlotNode").finish()
+    }
+}
+
+impl<K> SlotNode<K> {
+    /// Create a new node that is not in any tree.
+    #[inline]
+    pub const fn new() -> Self {
+        SlotNode {
+            left: Cell::new(None),
+            right: Cell::new(None),
+        }
+    }
+}
+
+/// Defines where a kind of `SlotSplayTree` finds its elements, and the
+/// `SlotNode` within each of them.
+///
+/// It is usually implemented on a marker type, like `IntrusiveNode`, for
+/// storage like `slotmap::SlotMap` or `generational_arena::Arena`, whose keys
+/// carry a generation. A key whose element was removed from the storage then
+/// fails to resolve, so a tree that still links to it panics instead of
+/// reading whatever element moved into its slot.
+///
+/// ```
+/// use intrusive_splay_tree::{SlotNode, SlotOrd, SlotSplayTree, SlotTree};
+/// use std::cmp::Ordering;
+///
+/// // A stand-in for a slot map, which doesn't reuse slots at all.
+/// struct Task {
+///     deadline: u64,
+///     node: SlotNode<usize>,
+/// }
+///
+/// struct TasksByDeadline;
+///
+/// impl SlotTree for TasksByDeadline {
+///     type Storage = Vec<Option<Task>>;
+///     type Key = usize;
+///     type Elem = Task;
+///
+///     fn elem(tasks: &Vec<Option<Task>>, key: usize) -> Option<&Task> {
+///         tasks.get(key)?.as_ref()
+///     }
+///
+///     fn node(task: &Task) -> &SlotNode<usize> {
+///         &task.node
+///     }
+/// }
+///
+/// impl SlotOrd<TasksByDeadline> for Task {
+///     fn slot_cmp(&self, rhs: &Task) -> Ordering {
+///         self.deadline.cmp(&rhs.deadline)
+///     }
+/// }
+///
+/// impl SlotOrd<TasksByDeadline> for u64 {
+///     fn slot_cmp(&self, rhs: &Task) -> Ordering {
+///         self.cmp(&rhs.deadline)
+///     }
+/// }
+///
+/// let mut tasks = vec![];
+/// let mut queue = SlotSplayTree::<TasksByDeadline>::new();
+/// for deadline in [30, 10, 20] {
+///     tasks.push(Some(Task { deadline, node: SlotNode::new() }));
+///     assert!(queue.insert(&tasks, tasks.len() - 1));
+/// }
+///
+/// assert_eq!(queue.find(&tasks, &20), Some(2));
+/// let next = queue.pop_min(&tasks).unwrap();
+/// assert_eq!(tasks[next].take().unwrap().deadline, 10);
+/// assert_eq!(queue.min(&tasks), Some(2));
+/// ```
+pub trait SlotTree {
+    /// The storage that the elements live in.
+    type Storage: ?Sized;
+
+    /// The keys that identify elements in the storage.
+    type Key: Copy + Eq;
+
+    /// The element type, which contains a `SlotNode` for this tree.
+    type Elem: ?Sized + SlotOrd<Self>;
+
+    /// Get the element for `key`, or `None` if it is no longer in `storage`.
+    fn elem(storage: &Self::Storage, key: Self::Key) -> Option<&Self::Elem>;
+
+    /// Get the node for this tree from the given element.
+    ///
+    /// This must always return the same `SlotNode` field, and no other tree
+    /// type may use it.
+    fn node(elem: &Self::Elem) -> &SlotNode<Self::Key>;
+}
+
+/// Compare a query, or another element, with an element of a `SlotSplayTree`.
+///
+/// This is the counterpart of `TreeOrd`, and elements must implement it for
+/// their own type to be inserted.
+pub trait SlotOrd<T: ?Sized + SlotTree> {
+    /// Compare `self` with `rhs`.
+    fn slot_cmp(&self, rhs: &T::Elem) -> Ordering;
+}
+
+/// A splay tree whose elements live in keyed storage, and which links them by
+/// their keys instead of by reference.
+///
+/// The tree holds only a root key, so it has no lifetime parameter, and is
+/// `Send` and `Sync` whenever its keys are. Every operation takes the storage
+/// to resolve keys in. Elements must stay in the storage while they are in the
+/// tree: an operation that comes across a key that doesn't resolve panics,
+/// since the tree's links to it can't be repaired. With generational storage,
+/// that catches elements that were freed too early, rather than silently
+/// following a key to the slot's next occupant.
+///
+/// See `SlotTree` for an example.
+pub struct SlotSplayTree<T>
+where
+    T: SlotTree,
+{
+    root: Option<T::Key>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for SlotSplayTree<T>
+where
+    T: SlotTree,
+{
+    #[inline]
+    fn default() -> Self {
+        SlotSplayTree::new()
+    }
+}
+
+impl<T> fmt::Debug for SlotSplayTree<T>
+where
+    T: SlotTree,
+    T::Key: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SlotSplayTree")
+            .field("root", &self.root)
+            .finish()
+    }
+}
+
+/// Get the element for `key`, which the tree links to, so it must be there.
+#[inline]
+fn resolve<'s, T>(storage: &'s T::Storage, key: T::Key) -> &'s T::Elem
+where
+    T: SlotTree,
+    T::Elem: 's,
+{
+    match T::elem(storage, key) {
+        Some(elem) => elem,
+        None => panic!("a `SlotSplayTree` links to an element that is not in its storage"),
+    }
+}
+
+#[inline]
+fn node<'s, T>(storage: &'s T::Storage, key: T::Key) -> &'s SlotNode<T::Key>
+where
+    T: SlotTree,
+    T::Elem: 's,
+{
+    T::node(resolve::<T>(storage, key))
+}
+
+impl<T> SlotSplayTree<T>
+where
+    T: SlotTree,
+{
+    /// Construct a new, empty tree.
+    #[inline]
+    pub const fn new() -> Self {
+        SlotSplayTree {
+            root: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Is this tree empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Get the key of the root element, if any.
+    #[inline]
+    pub fn root(&self) -> Option<T::Key> {
+        self.root
+    }
+
+    /// Splay the element that `cmp` finds, or the last one on the way to where
+    /// it would be, to the root. Returns how `cmp` compares to the new root,
+    /// or `None` if the tree is empty.
+    fn splay<F>(&mut self, storage: &T::Storage, mut cmp: F) -> Option<Ordering>
+    where
+        F: FnMut(&T::Elem) -> Ordering,
+    {
+        let mut current = self.root?;
+        let mut left_root = None;
+        let mut left: Option<T::Key> = None;
+        let mut right_root = None;
+        let mut right: Option<T::Key> = None;
+
+        let order = loop {
+            let current_elem = resolve::<T>(storage, current);
+            let current_node = T::node(current_elem);
+            match cmp(current_elem) {
+                Ordering::Less => {
+                    let Some(mut next) = current_node.left.get() else {
+                        break Ordering::Less;
+                    };
+                    let next_elem = resolve::<T>(storage, next);
+                    if cmp(next_elem).is_lt() {
+                        // Rotate right.
+                        let next_node = T::node(next_elem);
+                        current_node.left.set(next_node.right.get());
+                        next_node.right.set(Some(current));
+                        current = next;
+                        match next_node.left.get() {
+                            Some(l) => next = l,
+                            None => break Ordering::Less,
+                        }
+                    }
+                    // Link right.
+                    match right {
+                        Some(right) => node::<T>(storage, right).left.set(Some(current)),
+                        None => right_root = Some(current),
+                    }
+                    right = Some(current);
+                    current = next;
+                }
+                Ordering::Greater => {
+                    let Some(mut next) = current_node.right.get() else {
+                        break Ordering::Greater;
+                    };
+                    let next_elem = resolve::<T>(storage, next);
+                    if cmp(next_elem).is_gt() {
+                        // Rotate left.
+                        let next_node = T::node(next_elem);
+                        current_node.right.set(next_node.left.get());
+                        next_node.left.set(Some(current));
+                        current = next;
+                        match next_node.right.get() {
+                            Some(r) => next = r,
+                            None => break Ordering::Greater,
+                        }
+                    }
+                    // Link left.
+                    match left {
+                        Some(left) => node::<T>(storage, left).right.set(Some(current)),
+                        None => left_root = Some(current),
+                    }
+                    left = Some(current);
+                    current = next;
+                }
+                Ordering::Equal => break Ordering::Equal,
+            }
+        };
+
+        // Assemble.
+        let current_node = node::<T>(storage, current);
+        if let Some(left) = left {
+            node::<T>(storage, left).right.set(current_node.left.get());
+            current_node.left.set(left_root);
+        }
+        if let Some(right) = right {
+            node::<T>(storage, right).left.set(current_node.right.get());
+            current_node.right.set(right_root);
+        }
+        self.root = Some(current);
+        Some(order)
+    }
+
+    /// Insert the element for `key`.
+    ///
+    /// Returns `true` if it was inserted, and `false` if there was already an
+    /// element for which `SlotOrd` returned `Ordering::Equal`. The element
+    /// must not already be in this tree.
+    ///
+    /// Panics if `key` isn't in `storage`.
+    pub fn insert(&mut self, storage: &T::Storage, key: T::Key) -> bool {
+        let elem = resolve::<T>(storage, key);
+        let elem_node = T::node(elem);
+        let order = self.splay(storage, |other| elem.slot_cmp(other));
+        match (order, self.root) {
+            (Some(Ordering::Equal), _) => return false,
+            (Some(Ordering::Less), Some(root)) => {
+                elem_node.left.set(node::<T>(storage, root).left.take());
+                elem_node.right.set(Some(root));
+            }
+            (Some(Ordering::Greater), Some(root)) => {
+                elem_node.right.set(node::<T>(storage, root).right.take());
+                elem_node.left.set(Some(root));
+            }
+            _ => {
+                elem_node.left.set(None);
+                elem_node.right.set(None);
+            }
+        }
+        self.root = Some(key);
+        true
+    }
+
+    /// Find the element for the given query, and return its key.
+    #[inline]
+    pub fn find<Q>(&mut self, storage: &T::Storage, query: &Q) -> Option<T::Key>
+    where
+        Q: ?Sized + SlotOrd<T>,
+    {
+        match self.splay(storage, |elem| query.slot_cmp(elem))? {
+            Ordering::Equal => self.root,
+            _ => None,
+        }
+    }
+
+    /// Remove the root element, and return its key.
+    fn pop_root(&mut self, storage: &T::Storage) -> Option<T::Key> {
+        let root = self.root?;
+        let root_node = node::<T>(storage, root);
+        let right = root_node.right.take();
+        self.root = root_node.left.take();
+        if self.splay(storage, |_| Ordering::Greater).is_some() {
+            if let Some(new_root) = self.root {
+                node::<T>(storage, new_root).right.set(right);
+            }
+        } else {
+            self.root = right;
+        }
+        Some(root)
+    }
+
+    /// Remove the element for the given query, and return its key.
+    ///
+    /// The element is still in `storage`, and can be freed from it now.
+    #[inline]
+    pub fn remove<Q>(&mut self, storage: &T::Storage, query: &Q) -> Option<T::Key>
+    where
+        Q: ?Sized + SlotOrd<T>,
+    {
+        match self.splay(storage, |elem| query.slot_cmp(elem))? {
+            Ordering::Equal => self.pop_root(storage),
+            _ => None,
+        }
+    }
+
+    /// Get the key of the smallest element.
+    #[inline]
+    pub fn min(&mut self, storage: &T::Storage) -> Option<T::Key> {
+        self.splay(storage, |_| Ordering::Less)?;
+        self.root
+    }
+
+    /// Get the key of the largest element.
+    #[inline]
+    pub fn max(&mut self, storage: &T::Storage) -> Option<T::Key> {
+        self.splay(storage, |_| Ordering::Greater)?;
+        self.root
+    }
+
+    /// Remove the smallest element, and return its key.
+    #[inline]
+    pub fn pop_min(&mut self, storage: &T::Storage) -> Option<T::Key> {
+        self.min(storage)?;
+        self.pop_root(storage)
+    }
+
+    /// Remove the largest element, and return its key.
+    #[inline]
+    pub fn pop_max(&mut self, storage: &T::Storage) -> Option<T::Key> {
+        self.max(storage)?;
+        self.pop_root(storage)
+    }
+
+    /// Walk the tree's keys and elements in order.
+    ///
+    /// See `SplayTree::walk` for how `C` controls the walk.
+    #[inline]
+    pub fn walk<'s, F, C>(&self, storage: &'s T::Storage, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(T::Key, &'s T::Elem) -> C,
+        C: WalkControl,
+        T::Elem: 's,
+    {
+        fn walk<'s, T, F>(storage: &'s T::Storage, key: T::Key, f: &mut F) -> bool
+        where
+            T: SlotTree,
+            T::Elem: 's,
+            F: FnMut(T::Key, &'s T::Elem) -> bool,
+        {
+            let elem = resolve::<T>(storage, key);
+            let node = T::node(elem);
+            if let Some(left) = node.left.get() {
+                if !walk::<T, F>(storage, left, f) {
+                    return false;
+                }
+            }
+            if !f(key, elem) {
+                return false;
+            }
+            match node.right.get() {
+                Some(right) => walk::<T, F>(storage, right, f),
+                None => true,
+            }
+        }
+
+        let mut result = None;
+        if let Some(root) = self.root {
+            walk::<T, _>(storage, root, &mut |key, elem| {
+                result = f(key, elem).should_break();
+                result.is_none()
+            });
+        }
+        result
+    }
+}
@@ -0,0 +1,139 @@
+//! Elements that live in `static`s.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_has_atomic = "8"))]
+use portable_atomic::{AtomicBool, Ordering};
+
+/// A wrapper that lets elements with intrusive nodes live in a `static`, and
+/// be claimed by one thread to build trees over.
+///
+/// Nodes are made of `Cell`s, so elements that contain them are not `Sync`,
+/// and can't be put in a `static` directly. This wrapper is `Sync`, but only
+/// gives out a reference to its contents once, to whichever thread calls
+/// `claim` or `claim_mut` first. Since the contents are not `Sync`, that
+/// reference can't be shared with other threads, so the `Cell`s are only ever
+/// touched by the thread that claimed them.
+///
+/// Elements are not `Send` either, when their nodes link to other elements,
+/// so the wrapper can't check that handing them to the claiming thread is
+/// sound; constructing it is `unsafe` instead.
+///
+/// Claiming is an atomic swap, so on targets that don't have one for bytes,
+/// like the Cortex-M0's `thumbv6m`, this is only available with the
+/// `portable-atomic` feature.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, StaticElems, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct Handler<'a> {
+///     irq: u32,
+///     node: Node<'a>,
+/// }
+///
+/// impl Handler<'_> {
+///     const fn new(irq: u32) -> Self {
+///         Handler { irq, node: Node::new() }
+///     }
+/// }
+///
+/// struct HandlersByIrq;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for HandlersByIrq
+///     where
+///         type Elem = Handler<'a>,
+///         node = node;
+/// }
+///
+/// impl<'a> TreeOrd<'a, HandlersByIrq> for Handler<'a> {
+///     fn tree_cmp(&self, rhs: &'a Handler<'a>) -> Ordering {
+///         self.irq.cmp(&rhs.irq)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, HandlersByIrq> for u32 {
+///     fn tree_cmp(&self, rhs: &'a Handler<'a>) -> Ordering {
+///         self.cmp(&rhs.irq)
+///     }
+/// }
+///
+/// // Safety: the handlers are built in the static's initializer, so they can't
+/// // share anything with other threads.
+/// static HANDLERS: StaticElems<[Handler<'static>; 3]> =
+///     unsafe { StaticElems::new([Handler::new(7), Handler::new(3), Handler::new(12)]) };
+///
+/// let handlers = HANDLERS.claim().unwrap();
+/// let mut tree = SplayTree::<HandlersByIrq>::new();
+/// for handler in handlers {
+///     tree.insert(handler);
+/// }
+/// assert_eq!(tree.find(&12).unwrap().irq, 12);
+///
+/// // The elements can only be claimed once.
+/// assert!(HANDLERS.claim().is_none());
+/// ```
+pub struct StaticElems<T> {
+    claimed: AtomicBool,
+    elems: UnsafeCell<T>,
+}
+
+// Safety: the only access to `elems` is through `claim` and `claim_mut`,
+// which hand out at most one reference, to one thread. That moves the
+// elements to the claiming thread, in effect, which `new`'s caller promises
+// is fine.
+unsafe impl<T> Sync for StaticElems<T> {}
+
+impl<T> fmt::Debug for StaticElems<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticElems")
+            .field("claimed", &self.claimed.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> StaticElems<T> {
+    /// Wrap `elems`, for use in a `static`.
+    ///
+    /// # Safety
+    ///
+    /// `elems` must not share anything with the thread that creates them that
+    /// is not `Sync`, like an `Rc` or a reference to a `Cell`, since whichever
+    /// thread claims them can use it. Elements built in a `static`'s
+    /// initializer never do.
+    #[inline]
+    pub const unsafe fn new(elems: T) -> StaticElems<T> {
+        StaticElems {
+            claimed: AtomicBool::new(false),
+            elems: UnsafeCell::new(elems),
+        }
+    }
+
+    /// Claim the elements for the current thread.
+    ///
+    /// Returns `None` if they were already claimed.
+    #[inline]
+    pub fn claim(&'static self) -> Option<&'static T> {
+        self.claim_mut().map(|elems| &*elems)
+    }
+
+    /// Claim the elements for the current thread, mutably.
+    ///
+    /// This is for contents that are changed through `&mut` or `Pin<&mut _>`,
+    /// like a `StaticSplaySet`, which `Pin::static_mut` can pin in place.
+    /// Returns `None` if the elements were already claimed, by either method.
+    #[inline]
+    // The claim flag makes the returned reference unique.
+    #[allow(clippy::mut_from_ref)]
+    pub fn claim_mut(&'static self) -> Option<&'static mut T> {
+        if self.claimed.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            // Safety: this is the only reference that is ever handed out.
+            Some(unsafe { &mut *self.elems.get() })
+        }
+    }
+}
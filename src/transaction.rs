@@ -0,0 +1,248 @@
+//! Batches of tree changes that can be rolled back.
+
+use crate::{BatchOp, Full, IntrusiveNode, SplayTree, TreeOrd};
+use core::fmt;
+use core::mem;
+use core::ops::Deref;
+use core::ptr;
+
+/// A set of insertions and removals on a `SplayTree` that can be undone as a
+/// whole.
+///
+/// Returned by `SplayTree::transaction`. Every change made through the
+/// transaction is recorded in a log with room for `N` of them, and unless the
+/// transaction is committed, they are undone, newest first, when it is rolled
+/// back or dropped. This keeps several indices over the same elements
+/// consistent when a change to one of them can fail: make the change in each
+/// index's transaction, and only commit them all once every index accepted
+/// it.
+///
+/// Rolling back restores which elements are in the tree, but not its shape,
+/// which lookups change anyway.
+///
+/// ```
+/// use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+/// use std::cmp::Ordering;
+///
+/// struct User<'a> {
+///     id: u32,
+///     email: &'static str,
+///     by_id: Node<'a>,
+///     by_email: Node<'a>,
+/// }
+///
+/// impl<'a> User<'a> {
+///     fn new(id: u32, email: &'static str) -> Self {
+///         User { id, email, by_id: Node::new(), by_email: Node::new() }
+///     }
+/// }
+///
+/// struct ById;
+/// struct ByEmail;
+///
+/// impl_intrusive_node! {
+///     impl<'a> IntrusiveNode<'a> for ById
+///     where
+///         type Elem = User<'a>,
+///         node = by_id;
+///
+///     impl<'a> IntrusiveNode<'a> for ByEmail
+///     where
+///         type Elem = User<'a>,
+///         node = by_email;
+/// }
+///
+/// impl<'a> TreeOrd<'a, ById> for User<'a> {
+///     fn tree_cmp(&self, rhs: &'a User<'a>) -> Ordering {
+///         self.id.cmp(&rhs.id)
+///     }
+/// }
+///
+/// impl<'a> TreeOrd<'a, ByEmail> for User<'a> {
+///     fn tree_cmp(&self, rhs: &'a User<'a>) -> Ordering {
+///         self.email.cmp(rhs.email)
+///     }
+/// }
+///
+/// let users = [
+///     User::new(1, "a@example.com"),
+///     User::new(2, "b@example.com"),
+///     User::new(3, "a@example.com"),
+/// ];
+/// let mut by_id = SplayTree::<ById>::new();
+/// let mut by_email = SplayTree::<ByEmail>::new();
+///
+/// let mut ids = by_id.transaction::<4>();
+/// let mut emails = by_email.transaction::<4>();
+/// let mut all_unique = true;
+/// for user in &users {
+///     all_unique &= matches!(ids.insert(user), Ok(true));
+///     all_unique &= matches!(emails.insert(user), Ok(true));
+/// }
+///
+/// // The third user's email is taken, so neither index keeps any of them.
+/// assert!(!all_unique);
+/// ids.rollback();
+/// emails.rollback();
+/// assert!(by_id.is_empty() && by_email.is_empty());
+/// ```
+pub struct Transaction<'t, 'a, T, const N: usize>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    tree: &'t mut SplayTree<'a, T>,
+    log: [Option<BatchOp<'a, T>>; N],
+    len: usize,
+}
+
+impl<'t, 'a, T, const N: usize> Transaction<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    #[inline]
+    pub(crate) fn new(tree: &'t mut SplayTree<'a, T>) -> Self {
+        Transaction {
+            tree,
+            log: [None; N],
+            len: 0,
+        }
+    }
+
+    /// Get the number of changes recorded so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Has nothing been changed yet?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Is the log out of room for more changes?
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Insert `elem`, as with `SplayTree::insert`, and record it so that it
+    /// can be undone.
+    ///
+    /// Returns `Err(Full(elem))`, without inserting it, if the log is full.
+    /// Nothing is recorded if an equal element was already in the tree.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> Result<bool, Full<&'a T::Elem>> {
+        if self.is_full() {
+            return Err(Full(elem));
+        }
+        let inserted = self.tree.insert(elem);
+        if inserted {
+            self.record(BatchOp::Insert(elem));
+        }
+        Ok(inserted)
+    }
+
+    /// Remove the element that matches `key`, as with `SplayTree::remove`,
+    /// and record it so that it can be undone.
+    ///
+    /// Returns `Err(Full(()))`, without removing anything, if the log is
+    /// full.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Result<Option<&'a T::Elem>, Full<()>>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        if self.is_full() {
+            return Err(Full(()));
+        }
+        let removed = self.tree.remove(key);
+        if let Some(elem) = removed {
+            self.record(BatchOp::Remove(elem));
+        }
+        Ok(removed)
+    }
+
+    /// Find the element that matches `key`, as with `SplayTree::find`.
+    /// Lookups don't change which elements are in the tree, so they aren't
+    /// recorded.
+    #[inline]
+    pub fn find<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        self.tree.find(key)
+    }
+
+    #[inline]
+    fn record(&mut self, op: BatchOp<'a, T>) {
+        self.log[self.len] = Some(op);
+        self.len += 1;
+    }
+
+    /// Keep every change that was made.
+    #[inline]
+    pub fn commit(self) {
+        mem::forget(self);
+    }
+
+    /// Undo every change that was made, newest first.
+    ///
+    /// This is what dropping the transaction does, too.
+    #[inline]
+    pub fn rollback(self) {}
+}
+
+impl<'t, 'a, T, const N: usize> Drop for Transaction<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    fn drop(&mut self) {
+        // Undoing the changes in reverse order puts the tree back in the
+        // state that each change was made in, so each inserted element is
+        // still there to remove, and nothing equal to a removed element is
+        // there to block reinserting it.
+        while self.len > 0 {
+            self.len -= 1;
+            match self.log[self.len].take() {
+                Some(BatchOp::Insert(elem)) => {
+                    let removed = self.tree.remove(elem);
+                    debug_assert!(removed.is_some_and(|r| ptr::eq(r, elem)));
+                }
+                Some(BatchOp::Remove(elem)) => {
+                    let inserted = self.tree.insert(elem);
+                    debug_assert!(inserted);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+impl<'t, 'a, T, const N: usize> Deref for Transaction<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    type Target = SplayTree<'a, T>;
+
+    #[inline]
+    fn deref(&self) -> &SplayTree<'a, T> {
+        self.tree
+    }
+}
+
+impl<'t, 'a, T, const N: usize> fmt::Debug for Transaction<'t, 'a, T, N>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("log", &&self.log[..self.len])
+            .finish_non_exhaustive()
+    }
+}
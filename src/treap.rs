@@ -0,0 +1,582 @@
+//! An intrusive treap that shares `Node`, `IntrusiveNode`, and `TreeOrd` with
+//! `SplayTree`.
+//!
+//! By default, each node's heap priority is derived by hashing its address, so
+//! nodes don't need any extra space and there is no random number generator to
+//! seed. With `ByWeight`, the hash is biased by a per-element weight instead.
+//! Like the splay tree, the implementation works only with trait objects, and
+//! the generic `Treap<T, P>` wrapper immediately erases types before calling
+//! into it.
+
+use crate::brand::Brand;
+use crate::internal::{CompareToNode, MaxNode, MinNode};
+use crate::{IntrusiveNode, Node, Query, TreeOrd, WalkControl};
+use core::cmp;
+use core::fmt;
+use core::iter;
+use core::marker::PhantomData;
+
+/// A hash of a node's address.
+///
+/// The hash is a bijection on `u64`, so distinct nodes always have distinct
+/// hashes.
+#[inline]
+fn hash(node: &Node) -> u64 {
+    // The `splitmix64` finalizer.
+    let mut x = node as *const Node as usize as u64;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// How a `Treap` assigns heap priorities to its elements.
+///
+/// Elements with higher priorities sit closer to the root. An element's
+/// priority must not change while it is in a treap.
+pub trait TreapPriority<'a, T>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Get the heap priority of `elem`.
+    fn priority(elem: &'a T::Elem) -> u64;
+}
+
+/// The default `TreapPriority`: a hash of each element's node address.
+///
+/// This makes every element equally likely to be anywhere in the treap, for
+/// expected *O(log n)* depth.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByAddress;
+
+impl<'a, T> TreapPriority<'a, T> for ByAddress
+where
+    T: IntrusiveNode<'a>,
+{
+    #[inline]
+    fn priority(elem: &'a T::Elem) -> u64 {
+        hash(T::elem_to_node(elem))
+    }
+}
+
+/// A per-element access weight, for treaps that use `ByWeight` priorities.
+pub trait TreapWeight<'a, T>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Get this element's weight, for example how often it is expected to be
+    /// looked up. A weight of zero is treated as one.
+    fn weight(&self) -> u32;
+}
+
+/// A `TreapPriority` that biases the address hash by each element's
+/// `TreapWeight`, so heavier elements tend to sit closer to the root.
+///
+/// This is a weighted randomized search tree: an element with weight *w*, out
+/// of a total weight *W*, has expected depth *O(log(W / w))*, which is within
+/// a constant factor of an optimal binary search tree for the distribution
+/// described by the weights. Unlike a splay tree, which adapts to the access
+/// pattern as it happens, this needs the weights up front, but never
+/// restructures on lookups. `SplayTree::find_weighted` combines the two.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByWeight;
+
+impl<'a, T> TreapPriority<'a, T> for ByWeight
+where
+    T: IntrusiveNode<'a>,
+    T::Elem: TreapWeight<'a, T>,
+{
+    #[inline]
+    fn priority(elem: &'a T::Elem) -> u64 {
+        // Treating the hash as a uniform `u` in `(0, 1]`, the priority is
+        // `u^(1/w)`, which is distributed like the maximum of `w` uniform
+        // samples. Compare `-log2(u) / w` instead, smaller being better, with
+        // a 16-bit fixed point `log2` that linearly interpolates between powers
+        // of two.
+        let h = hash(T::elem_to_node(elem)) | 1;
+        let lz = h.leading_zeros() as u64;
+        let frac = ((h << lz) >> 47) & 0xffff;
+        let log2 = ((63 - lz) << 16) | frac;
+        let neg_log2 = (64 << 16) - log2;
+        let weight = u64::from(elem.weight().max(1));
+        u64::MAX - (neg_log2 << 24) / weight
+    }
+}
+
+/// The type-erased form of a `TreapPriority`.
+trait NodePriority<'a> {
+    fn priority(&self, node: &'a Node<'a>) -> u64;
+}
+
+struct Priorities<T, P>(PhantomData<(T, P)>);
+
+impl<'a, T, P> NodePriority<'a> for Priorities<T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    P: TreapPriority<'a, T>,
+{
+    #[inline]
+    fn priority(&self, node: &'a Node<'a>) -> u64 {
+        // Only ever called with nodes of `T` treaps.
+        P::priority(unsafe { T::node_to_elem(node) })
+    }
+}
+
+/// Split the subtree rooted at `current` into the nodes less than `key` and
+/// the nodes greater than or equal to `key`.
+unsafe fn split<'a>(
+    current: Option<&'a Node<'a>>,
+    key: &dyn CompareToNode<'a>,
+) -> (Option<&'a Node<'a>>, Option<&'a Node<'a>>) {
+    let Some(node) = current else {
+        return (None, None);
+    };
+    if key.compare_to_node(node).is_gt() {
+        let (less, greater_or_equal) = split(node.right.get(), key);
+        node.right.set(less);
+        (Some(node), greater_or_equal)
+    } else {
+        let (less, greater_or_equal) = split(node.left.get(), key);
+        node.left.set(greater_or_equal);
+        (less, Some(node))
+    }
+}
+
+/// Join two subtrees, where every node in `left` is less than every node in
+/// `right`.
+fn join<'a>(
+    prio: &dyn NodePriority<'a>,
+    left: Option<&'a Node<'a>>,
+    right: Option<&'a Node<'a>>,
+) -> Option<&'a Node<'a>> {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(l), Some(r)) => {
+            if prio.priority(l) > prio.priority(r) {
+                l.right.set(join(prio, l.right.get(), Some(r)));
+                Some(l)
+            } else {
+                r.left.set(join(prio, Some(l), r.left.get()));
+                Some(r)
+            }
+        }
+    }
+}
+
+/// Insert `node` into the subtree rooted at `current`, which must not already
+/// contain a node equal to it. Returns the new root of the subtree.
+unsafe fn insert<'a>(
+    prio: &dyn NodePriority<'a>,
+    current: Option<&'a Node<'a>>,
+    key: &dyn CompareToNode<'a>,
+    node: &'a Node<'a>,
+) -> &'a Node<'a> {
+    match current {
+        Some(current) if prio.priority(current) > prio.priority(node) => {
+            if key.compare_to_node(current).is_lt() {
+                current
+                    .left
+                    .set(Some(insert(prio, current.left.get(), key, node)));
+            } else {
+                current
+                    .right
+                    .set(Some(insert(prio, current.right.get(), key, node)));
+            }
+            current
+        }
+        _ => {
+            let (less, greater) = split(current, key);
+            node.left.set(less);
+            node.right.set(greater);
+            node
+        }
+    }
+}
+
+/// Remove the node matching `key` from the subtree rooted at `current`,
+/// storing it in `removed`. Returns the new root of the subtree.
+unsafe fn remove<'a>(
+    prio: &dyn NodePriority<'a>,
+    current: Option<&'a Node<'a>>,
+    key: &dyn CompareToNode<'a>,
+    removed: &mut Option<&'a Node<'a>>,
+) -> Option<&'a Node<'a>> {
+    let node = current?;
+    match key.compare_to_node(node) {
+        cmp::Ordering::Less => node.left.set(remove(prio, node.left.get(), key, removed)),
+        cmp::Ordering::Greater => node.right.set(remove(prio, node.right.get(), key, removed)),
+        cmp::Ordering::Equal => {
+            *removed = Some(node);
+            return join(prio, node.left.take(), node.right.take());
+        }
+    }
+    Some(node)
+}
+
+/// Remove the minimum or maximum node, as chosen by `key`, from the subtree
+/// rooted at `current`, storing it in `removed`. Returns the new root of the
+/// subtree.
+fn remove_extreme<'a>(
+    prio: &dyn NodePriority<'a>,
+    current: &'a Node<'a>,
+    key: &dyn CompareToNode<'a>,
+    removed: &mut Option<&'a Node<'a>>,
+) -> Option<&'a Node<'a>> {
+    // Safe because `MinNode` and `MaxNode` never look at the node.
+    let (child, link) = if unsafe { key.compare_to_node(current) }.is_lt() {
+        (current.left.get(), &current.left)
+    } else {
+        (current.right.get(), &current.right)
+    };
+    match child {
+        Some(child) => {
+            link.set(remove_extreme(prio, child, key, removed));
+            Some(current)
+        }
+        None => {
+            *removed = Some(current);
+            join(prio, current.left.take(), current.right.take())
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Heap<'a> {
+    root: Option<&'a Node<'a>>,
+}
+
+impl<'a> Heap<'a> {
+    #[inline(never)]
+    unsafe fn find(&self, key: &dyn CompareToNode<'a>) -> Option<&'a Node<'a>> {
+        let mut current = self.root;
+        while let Some(node) = current {
+            current = match key.compare_to_node(node) {
+                cmp::Ordering::Less => node.left.get(),
+                cmp::Ordering::Greater => node.right.get(),
+                cmp::Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    #[inline(never)]
+    unsafe fn insert(
+        &mut self,
+        prio: &dyn NodePriority<'a>,
+        key: &dyn CompareToNode<'a>,
+        node: &'a Node<'a>,
+    ) -> bool {
+        debug_assert!(node.left.get().is_none() && node.right.get().is_none());
+        if self.find(key).is_some() {
+            return false;
+        }
+        self.root = Some(insert(prio, self.root, key, node));
+        true
+    }
+
+    #[inline(never)]
+    unsafe fn remove(
+        &mut self,
+        prio: &dyn NodePriority<'a>,
+        key: &dyn CompareToNode<'a>,
+    ) -> Option<&'a Node<'a>> {
+        let mut removed = None;
+        self.root = remove(prio, self.root, key, &mut removed);
+        removed
+    }
+
+    #[inline(never)]
+    fn pop_extreme(
+        &mut self,
+        prio: &dyn NodePriority<'a>,
+        key: &dyn CompareToNode<'a>,
+    ) -> Option<&'a Node<'a>> {
+        let mut removed = None;
+        self.root = remove_extreme(prio, self.root?, key, &mut removed);
+        removed
+    }
+
+    #[inline(never)]
+    unsafe fn split_off(&mut self, key: &dyn CompareToNode<'a>) -> Heap<'a> {
+        let (less, greater_or_equal) = split(self.root, key);
+        self.root = less;
+        Heap {
+            root: greater_or_equal,
+        }
+    }
+
+    #[inline(never)]
+    fn append(&mut self, prio: &dyn NodePriority<'a>, other: &mut Heap<'a>) {
+        self.root = join(prio, self.root, other.root.take());
+    }
+}
+
+/// An intrusive treap.
+///
+/// A treap is a binary search tree that is also a heap over per-node
+/// priorities, which keeps it balanced in expectation: all operations take
+/// expected *O(log n)* time and stack space. By default, each node's priority
+/// is a hash of its address, so `Treap` uses plain `Node`s, the same
+/// `IntrusiveNode` and `TreeOrd` implementations as `SplayTree`, and needs no
+/// source of randomness. The `P` parameter can choose other priorities, such
+/// as `ByWeight`, which keeps frequently used elements near the root.
+///
+/// Unlike a splay tree, a treap does not restructure itself on lookups, so
+/// `find`, `min`, and `max` only need `&self`. Treaps also support cheap
+/// `split_off` and `append`.
+pub struct Treap<'a, T, P = ByAddress>
+where
+    T: IntrusiveNode<'a>,
+    T::Elem: 'a,
+{
+    tree: Heap<'a>,
+    brand: Brand,
+    _phantom: PhantomData<(&'a T::Elem, P)>,
+}
+
+impl<'a, T, P> Default for Treap<'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + TreeOrd<'a, T>,
+    P: TreapPriority<'a, T>,
+{
+    #[inline]
+    fn default() -> Treap<'a, T, P> {
+        Treap::new()
+    }
+}
+
+impl<'a, T, P> fmt::Debug for Treap<'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: 'a + TreeOrd<'a, T> + fmt::Debug,
+    P: TreapPriority<'a, T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set = &mut f.debug_set();
+        self.walk(|x| {
+            set.entry(&x);
+        });
+        set.finish()
+    }
+}
+
+impl<'a, T, P> Extend<&'a T::Elem> for Treap<'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    P: TreapPriority<'a, T>,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T::Elem>>(&mut self, iter: I) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl<'a, T, P> iter::FromIterator<&'a T::Elem> for Treap<'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: TreeOrd<'a, T>,
+    P: TreapPriority<'a, T>,
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = &'a T::Elem>>(iter: I) -> Self {
+        let mut me = Treap::new();
+        me.extend(iter);
+        me
+    }
+}
+
+impl<'a, T, P> Treap<'a, T, P>
+where
+    T: 'a + IntrusiveNode<'a>,
+    P: TreapPriority<'a, T>,
+    T::Elem: TreeOrd<'a, T>,
+{
+    /// Construct a new, empty treap.
+    #[inline]
+    pub const fn new() -> Self {
+        Treap {
+            tree: Heap { root: None },
+            brand: Brand::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Is this treap empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.root.is_none()
+    }
+
+    /// Get a reference to the root element, if any exists.
+    #[inline]
+    pub fn root(&self) -> Option<&'a T::Elem> {
+        self.tree.root.map(|r| unsafe { self.brand.elem::<T>(r) })
+    }
+
+    /// Find an element in the treap.
+    ///
+    /// This does not restructure the treap.
+    #[inline]
+    pub fn find<K>(&self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .find(&query)
+                .map(|node| self.brand.elem::<T>(node))
+        }
+    }
+
+    /// Insert a new element into this treap.
+    ///
+    /// Returns `true` if the element was inserted into the treap.
+    ///
+    /// Returns `false` if there was already an element in the treap for which
+    /// `TreeOrd` returned `Ordering::Equal`. In this case, the extant element
+    /// is left in the treap, and `elem` is not inserted.
+    ///
+    /// It is a logic error to insert an element that is already inserted in a
+    /// `T` tree.
+    ///
+    /// ## Panics
+    ///
+    /// If `debug_assertions` are enabled, then this function may panic if
+    /// `elem` is already in a `T` tree. If `debug_assertions` are not defined,
+    /// the behavior is safe, but unspecified.
+    ///
+    /// If the `paranoid` feature is enabled, then this function panics if
+    /// `elem`'s node is already in any tree.
+    #[inline]
+    pub fn insert(&mut self, elem: &'a T::Elem) -> bool {
+        unsafe {
+            let query: Query<_, T> = Query::new(elem, &self.brand);
+            let node = T::elem_to_node(elem);
+            self.brand.check_unbranded(node);
+            let inserted = self
+                .tree
+                .insert(&Priorities::<T, P>(PhantomData), &query, node);
+            if inserted {
+                self.brand.brand(node);
+            }
+            inserted
+        }
+    }
+
+    /// Find and remove an element from the treap.
+    ///
+    /// If a matching element is found and removed, then `Some(removed_element)`
+    /// is returned. Otherwise `None` is returned.
+    #[inline]
+    pub fn remove<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            self.tree
+                .remove(&Priorities::<T, P>(PhantomData), &query)
+                .map(|node| self.brand.release::<T>(node))
+        }
+    }
+
+    /// Get the minimum element in the treap, if any.
+    #[inline]
+    pub fn min(&self) -> Option<&'a T::Elem> {
+        let mut node = self.tree.root?;
+        while let Some(left) = node.left.get() {
+            node = left;
+        }
+        Some(unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Get the maximum element in the treap, if any.
+    #[inline]
+    pub fn max(&self) -> Option<&'a T::Elem> {
+        let mut node = self.tree.root?;
+        while let Some(right) = node.right.get() {
+            node = right;
+        }
+        Some(unsafe { self.brand.elem::<T>(node) })
+    }
+
+    /// Pop the minimum element from the treap.
+    #[inline]
+    pub fn pop_min(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .pop_extreme(&Priorities::<T, P>(PhantomData), &MinNode)
+            .map(|node| unsafe { self.brand.release::<T>(node) })
+    }
+
+    /// Pop the maximum element from the treap.
+    #[inline]
+    pub fn pop_max(&mut self) -> Option<&'a T::Elem> {
+        self.tree
+            .pop_extreme(&Priorities::<T, P>(PhantomData), &MaxNode)
+            .map(|node| unsafe { self.brand.release::<T>(node) })
+    }
+
+    /// Split the treap in two at the given key.
+    ///
+    /// Afterwards, `self` contains the elements less than `key`, and the
+    /// returned treap contains the elements greater than or equal to `key`.
+    #[inline]
+    pub fn split_off<K>(&mut self, key: &K) -> Treap<'a, T, P>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            Treap {
+                tree: self.tree.split_off(&query),
+                brand: self.brand.share(),
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    /// Move all of the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// Every element of `self` must be less than every element of `other`,
+    /// for example because they are the two halves of a `split_off`. It is a
+    /// logic error if this is not the case.
+    ///
+    /// ## Panics
+    ///
+    /// If `debug_assertions` are enabled, then this function panics if some
+    /// element of `self` is not less than some element of `other`.
+    #[inline]
+    pub fn append(&mut self, other: &mut Treap<'a, T, P>) {
+        debug_assert!(match (self.max(), other.min()) {
+            (Some(max), Some(min)) => max.tree_cmp(min).is_lt(),
+            _ => true,
+        });
+        self.brand.adopt(&other.brand, other.tree.root);
+        self.tree
+            .append(&Priorities::<T, P>(PhantomData), &mut other.tree);
+    }
+
+    /// Walk the treap in order.
+    ///
+    /// See `SplayTree::walk` for how the `C` type controls iteration.
+    #[inline]
+    pub fn walk<F, C>(&self, mut f: F) -> Option<C::Result>
+    where
+        F: FnMut(&'a T::Elem) -> C,
+        C: WalkControl,
+    {
+        let mut result = None;
+        if let Some(root) = self.tree.root {
+            root.walk(&mut |node| unsafe {
+                let elem = self.brand.elem::<T>(node);
+                result = f(elem).should_break();
+                result.is_none()
+            });
+        }
+        result
+    }
+}
@@ -0,0 +1,151 @@
+//! Kani proof harnesses for the splay core.
+//!
+//! These model-check insertion, lookup, and removal over every tree of up to
+//! `N` elements with arbitrary keys. Kani checks the raw pointer work for
+//! memory safety on its own, and the harnesses check that the tree is still a
+//! binary search tree afterwards, along with the subtree sizes and parent
+//! pointers when those features are enabled. Run them with:
+//!
+//! ```text
+//! cargo kani --features rank,parent
+//! ```
+
+use crate::{IntrusiveNode, Node, SplayTree, TreeOrd};
+use core::cmp::Ordering;
+
+const N: usize = 4;
+
+struct Elem<'a> {
+    key: u8,
+    node: Node<'a>,
+}
+
+struct ByKey;
+
+crate::impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByKey
+    where
+        type Elem = Elem<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByKey> for Elem<'a> {
+    fn tree_cmp(&self, rhs: &'a Elem<'a>) -> Ordering {
+        self.key.cmp(&rhs.key)
+    }
+}
+
+impl<'a> TreeOrd<'a, ByKey> for u8 {
+    fn tree_cmp(&self, rhs: &'a Elem<'a>) -> Ordering {
+        self.cmp(&rhs.key)
+    }
+}
+
+fn elems<'a>() -> [Elem<'a>; N] {
+    core::array::from_fn(|_| Elem {
+        key: kani::any(),
+        node: Node::new(),
+    })
+}
+
+/// Insert every element, and return how many were inserted.
+fn build<'a>(tree: &mut SplayTree<'a, ByKey>, elems: &'a [Elem<'a>; N]) -> usize {
+    let mut inserted = 0;
+    for (i, elem) in elems.iter().enumerate() {
+        let fresh = elems[..i].iter().all(|e| e.key != elem.key);
+        assert_eq!(tree.insert(elem), fresh);
+        inserted += usize::from(fresh);
+        assert_eq!(check(tree), inserted);
+    }
+    inserted
+}
+
+/// Check that `tree` is a binary search tree with consistent bookkeeping,
+/// and return how many elements it has.
+fn check(tree: &SplayTree<'_, ByKey>) -> usize {
+    let count = match tree.root() {
+        Some(root) => {
+            let root = ByKey::elem_to_node(root);
+            #[cfg(feature = "parent")]
+            assert!(root.parent.get().is_none());
+            check_subtree(root, None, None)
+        }
+        None => 0,
+    };
+    assert!(count <= N);
+    count
+}
+
+/// Check that every key under `node` is strictly between `lo` and `hi`, and
+/// return how many nodes there are.
+fn check_subtree<'a>(node: &'a Node<'a>, lo: Option<u8>, hi: Option<u8>) -> usize {
+    let key = unsafe { ByKey::node_to_elem(node) }.key;
+    assert!(lo.is_none_or(|lo| lo < key));
+    assert!(hi.is_none_or(|hi| key < hi));
+
+    let mut count = 1;
+    if let Some(left) = node.left.get() {
+        #[cfg(feature = "parent")]
+        assert!(left.parent.get().is_some_and(|p| core::ptr::eq(p, node)));
+        count += check_subtree(left, lo, Some(key));
+    }
+    if let Some(right) = node.right.get() {
+        #[cfg(feature = "parent")]
+        assert!(right.parent.get().is_some_and(|p| core::ptr::eq(p, node)));
+        count += check_subtree(right, Some(key), hi);
+    }
+
+    #[cfg(feature = "rank")]
+    assert_eq!(node.size.get(), count);
+    count
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn insert_preserves_invariants() {
+    let elems = elems();
+    let mut tree = SplayTree::new();
+    build(&mut tree, &elems);
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn find_preserves_invariants() {
+    let elems = elems();
+    let mut tree = SplayTree::new();
+    let len = build(&mut tree, &elems);
+
+    let key: u8 = kani::any();
+    let found = tree.find(&key);
+    assert_eq!(found.is_some(), elems.iter().any(|e| e.key == key));
+    assert!(found.is_none_or(|e| e.key == key));
+    assert_eq!(check(&tree), len);
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn remove_preserves_invariants() {
+    let elems = elems();
+    let mut tree = SplayTree::new();
+    let len = build(&mut tree, &elems);
+
+    let key: u8 = kani::any();
+    let present = elems.iter().any(|e| e.key == key);
+    let removed = tree.remove(&key);
+    assert_eq!(removed.is_some(), present);
+    assert!(removed.is_none_or(|e| e.key == key));
+    assert_eq!(check(&tree), len - usize::from(present));
+    assert!(tree.find(&key).is_none());
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn pop_min_removes_the_smallest() {
+    let elems = elems();
+    let mut tree = SplayTree::new();
+    let len = build(&mut tree, &elems);
+
+    let min = elems.iter().map(|e| e.key).min();
+    assert_eq!(tree.pop_min().map(|e| e.key), min);
+    assert_eq!(check(&tree), len - 1);
+}
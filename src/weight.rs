@@ -0,0 +1,92 @@
+//! Splaying that is biased by static per-element weights.
+
+use crate::{internal, IntrusiveNode, Node, Query, SplayTree, TreeOrd};
+
+/// A per-element access weight, for `SplayTree::find_weighted`.
+pub trait SplayWeight<'a, T>
+where
+    T: IntrusiveNode<'a>,
+{
+    /// Get this element's weight, for example how often it is expected to be
+    /// looked up, relative to the others.
+    fn weight(&self) -> u32;
+}
+
+impl<'a, T> SplayTree<'a, T>
+where
+    T: 'a + IntrusiveNode<'a>,
+    T::Elem: SplayWeight<'a, T>,
+{
+    /// Find an element in the tree, and splay it towards the root, but no
+    /// higher than the nodes above it that are heavier.
+    ///
+    /// `find` always splays the found element all the way to the root, so a
+    /// lookup of a rarely used element pushes the popular ones down. This
+    /// instead splays the found element to just beneath the deepest heavier
+    /// element on its search path, so elements settle at depths ordered by
+    /// their `SplayWeight`, which approximates an optimal binary search tree
+    /// for a known, skewed distribution of lookups, while what lies beneath
+    /// each heavy element still adapts as `find` does. Elements of equal
+    /// weight splay past each other.
+    ///
+    /// This descends the search path three times, once to find the element,
+    /// once to find how far it may rise, and once to splay it.
+    ///
+    /// ```
+    /// use intrusive_splay_tree::{impl_intrusive_node, impl_tree_ord_by_field};
+    /// use intrusive_splay_tree::{Node, SplayTree, SplayWeight};
+    ///
+    /// struct Page<'a> {
+    ///     path: &'static str,
+    ///     hits: u32,
+    ///     node: Node<'a>,
+    /// }
+    ///
+    /// struct Pages;
+    ///
+    /// impl_intrusive_node! {
+    ///     impl<'a> IntrusiveNode<'a> for Pages
+    ///     where
+    ///         type Elem = Page<'a>,
+    ///         node = node;
+    /// }
+    ///
+    /// impl_tree_ord_by_field! {
+    ///     impl<'a> TreeOrd<'a, Pages> for Page<'a>
+    ///     where
+    ///         field = path: &'static str;
+    /// }
+    ///
+    /// impl<'a> SplayWeight<'a, Pages> for Page<'a> {
+    ///     fn weight(&self) -> u32 {
+    ///         self.hits
+    ///     }
+    /// }
+    ///
+    /// let pages = [("/", 1000), ("/about", 10), ("/blog", 100), ("/jobs", 1)]
+    ///     .map(|(path, hits)| Page { path, hits, node: Node::new() });
+    /// let mut site = SplayTree::<Pages>::new();
+    /// for page in &pages {
+    ///     site.insert(page);
+    /// }
+    ///
+    /// assert_eq!(site.find_weighted(&"/").unwrap().path, "/");
+    /// // Lighter pages stay beneath the front page.
+    /// assert_eq!(site.find_weighted(&"/jobs").unwrap().path, "/jobs");
+    /// assert_eq!(site.find_weighted(&"/about").unwrap().path, "/about");
+    /// assert_eq!(site.root().unwrap().path, "/");
+    /// ```
+    pub fn find_weighted<K>(&mut self, key: &K) -> Option<&'a T::Elem>
+    where
+        K: ?Sized + TreeOrd<'a, T>,
+    {
+        let heavier = |a: &'a Node<'a>, b: &'a Node<'a>| unsafe {
+            T::node_to_elem(a).weight() > T::node_to_elem(b).weight()
+        };
+        unsafe {
+            let query: Query<_, T> = Query::new(key, &self.brand);
+            let node = self.tree.find_weighted(internal::erase(&query), &heavier)?;
+            Some(self.brand.elem::<T>(node))
+        }
+    }
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "avl")]
+
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::{AvlTree, IntrusiveNode, Node, SplayTree};
+use single::{Single, SingleTree};
+use std::collections::BTreeSet;
+
+/// Check the AVL height invariant, returning the subtree's height.
+fn check_height(node: Option<&Node<'_>>) -> Option<usize> {
+    match node {
+        None => Some(0),
+        Some(node) => {
+            let left = check_height(node.left())?;
+            let right = check_height(node.right())?;
+            if left.abs_diff(right) > 1 {
+                return None;
+            }
+            Some(1 + left.max(right))
+        }
+    }
+}
+
+fn is_balanced<'a>(tree: &AvlTree<'a, SingleTree<'a>>) -> bool {
+    check_height(tree.root().map(SingleTree::elem_to_node)).is_some()
+}
+
+fn values<'a>(tree: &AvlTree<'a, SingleTree<'a>>) -> Vec<usize> {
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    values
+}
+
+quickcheck! {
+    fn avl_insert_and_find(xs: Vec<usize>, x: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = AvlTree::<SingleTree>::new();
+        let mut model = BTreeSet::new();
+
+        for x in xs {
+            if tree.insert(arena.alloc(Single::new(x))) != model.insert(x) {
+                return false;
+            }
+        }
+
+        is_balanced(&tree)
+            && values(&tree) == model.iter().copied().collect::<Vec<_>>()
+            && tree.find(&x).map(|s| s.value) == model.get(&x).copied()
+            && tree.min().map(|s| s.value) == model.first().copied()
+            && tree.max().map(|s| s.value) == model.last().copied()
+    }
+
+    fn avl_remove(xs: Vec<usize>, removes: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = AvlTree::<SingleTree>::from_iter(
+            xs.iter().map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        for x in removes.into_iter().chain(model.clone()) {
+            if tree.remove(&x).map(|s| s.value) != model.take(&x) {
+                return false;
+            }
+            if !is_balanced(&tree) || values(&tree) != model.iter().copied().collect::<Vec<_>>() {
+                return false;
+            }
+        }
+
+        tree.is_empty()
+    }
+
+    fn avl_pop_min_and_max(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = AvlTree::<SingleTree>::from_iter(
+            xs.iter().map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        let mut from_min = true;
+        loop {
+            let (popped, expected) = if from_min {
+                (tree.pop_min(), model.pop_first())
+            } else {
+                (tree.pop_max(), model.pop_last())
+            };
+            if popped.map(|s| s.value) != expected || !is_balanced(&tree) {
+                return false;
+            }
+            if expected.is_none() {
+                return true;
+            }
+            from_min = !from_min;
+        }
+    }
+}
+
+#[test]
+fn element_removed_from_a_splay_tree_can_join_an_avl_tree() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let mut splay = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        splay.insert(e);
+    }
+    let moved = splay.remove(&2).unwrap();
+
+    let mut tree = AvlTree::<SingleTree>::new();
+    assert!(tree.insert(moved));
+    assert_eq!(values(&tree), [2]);
+}
@@ -0,0 +1,62 @@
+// `export_splay_tree_c_api!` refuses to build with the `hooks` feature.
+#![cfg(not(feature = "hooks"))]
+
+use intrusive_splay_tree::{declare_multi_index, export_splay_tree_c_api};
+use std::mem::MaybeUninit;
+
+declare_multi_index! {
+    #[repr(C)]
+    pub struct Monster<'a> {
+        pub id: u32,
+        pub health: u32,
+    }
+
+    pub index MonstersById(by_id_node) by id: u32;
+    pub index MonstersByHealth(by_health_node) by health: u32;
+}
+
+export_splay_tree_c_api! {
+    type Tree = MonstersById, key = u32;
+    fn new = new_id_tree;
+    fn insert = insert_by_id;
+    fn find = query_by_id;
+    fn remove = remove_by_id;
+
+    type Tree = MonstersByHealth, key = u32;
+    fn new = new_health_tree;
+    fn insert = insert_by_health;
+    fn find = query_by_health;
+    fn remove = remove_by_health;
+}
+
+#[test]
+fn multi_index_through_c_api() {
+    let monsters: &'static [Monster<'static>] = Box::leak(Box::new([
+        Monster::new(1, 50),
+        Monster::new(2, 9001),
+        Monster::new(3, 10),
+    ]));
+
+    unsafe {
+        // C only knows the trees' sizes, so they start out uninitialized.
+        let mut by_id = MaybeUninit::uninit();
+        let mut by_health = MaybeUninit::uninit();
+        new_id_tree(by_id.as_mut_ptr());
+        new_health_tree(by_health.as_mut_ptr());
+        let by_id = by_id.as_mut_ptr();
+        let by_health = by_health.as_mut_ptr();
+        for monster in monsters {
+            assert!(insert_by_id(by_id, monster));
+            assert!(insert_by_health(by_health, monster));
+        }
+
+        assert_eq!((*query_by_id(by_id, 2)).health, 9001);
+        assert_eq!((*query_by_health(by_health, 10)).id, 3);
+        assert!(query_by_id(by_id, 4).is_null());
+
+        assert_eq!(remove_by_id(by_id, 1), &monsters[0] as *const _);
+        assert!(remove_by_id(by_id, 1).is_null());
+        assert_eq!(remove_by_health(by_health, 50), &monsters[0] as *const _);
+        assert!(query_by_health(by_health, 50).is_null());
+    }
+}
@@ -0,0 +1,20 @@
+//! Misuses that must not compile. Each case in `tests/ui` explains itself, and
+//! its `.stderr` file holds the expected errors. After a change to the errors,
+//! like a new compiler version's wording, regenerate them with
+//! `TRYBUILD=overwrite cargo test --test compile_fail`.
+
+#[test]
+#[cfg_attr(
+    any(
+        feature = "avl",
+        feature = "linked",
+        feature = "paranoid",
+        feature = "rank",
+        feature = "parent",
+        feature = "hooks",
+    ),
+    ignore = "the features that add fields to `Node` or `SplayTree` change the expected errors"
+)]
+fn misuse_fails_to_compile() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,68 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{impl_intrusive_node, DeadlineQueue, IntrusiveDeadline, Node};
+use std::collections::BTreeSet;
+
+#[derive(Debug)]
+struct Timer<'a> {
+    id: usize,
+    at: u8,
+    node: Node<'a>,
+}
+
+struct Timers;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Timers
+    where
+        type Elem = Timer<'a>,
+        node = node;
+}
+
+impl<'a> IntrusiveDeadline<'a> for Timers {
+    type Instant = u8;
+
+    fn deadline(timer: &'a Timer<'a>) -> u8 {
+        timer.at
+    }
+}
+
+quickcheck! {
+    fn expires_everything_due(deadlines: Vec<u8>, ops: Vec<(bool, usize, u8)>) -> bool {
+        // Few distinct deadlines, so that plenty of timers share one.
+        let timers: Vec<_> = deadlines
+            .iter()
+            .enumerate()
+            .map(|(id, &at)| Timer { id, at: at % 16, node: Node::default() })
+            .collect();
+        let mut queue = DeadlineQueue::<Timers>::new();
+        let mut model = BTreeSet::new();
+
+        for (schedule, which, now) in ops {
+            if timers.is_empty() {
+                break;
+            }
+            let timer = &timers[which % timers.len()];
+            if schedule {
+                if model.insert((timer.at, timer.id)) {
+                    queue.schedule(timer);
+                }
+            } else if queue.cancel(timer) != model.remove(&(timer.at, timer.id)) {
+                return false;
+            }
+
+            let now = now % 20;
+            let mut expired: Vec<_> = queue.expire(now).map(|t| (t.at, t.id)).collect();
+            let mut due = vec![];
+            while model.first().is_some_and(|&(at, _)| at <= now) {
+                due.push(model.pop_first().unwrap());
+            }
+            expired.sort();
+            if expired != due || queue.next_deadline() != model.first().map(|&(at, _)| at) {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,179 @@
+//! Exhaustive testing of small trees against a `BTreeSet` model.
+//!
+//! Randomized tests can take a long time to stumble on one particular small
+//! interleaving, like removing a root that only has a right child. These tests
+//! enumerate all of them instead, up to a size that keeps them fast: every
+//! order of inserting up to 7 keys followed by any single operation, every
+//! order of inserting and then removing up to 5 keys, and every sequence of up
+//! to 6 inserts and removes over 3 keys. The tree is checked against the model
+//! after every step, without splaying, so that checking doesn't change which
+//! shapes the next step sees.
+
+mod single;
+
+use intrusive_splay_tree::SplayTree;
+use single::{Single, SingleTree};
+use std::collections::BTreeSet;
+
+/// Every permutation of `0..n`, in lexicographic order.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    fn extend(prefix: &mut Vec<usize>, n: usize, out: &mut Vec<Vec<usize>>) {
+        if prefix.len() == n {
+            out.push(prefix.clone());
+            return;
+        }
+        for x in 0..n {
+            if !prefix.contains(&x) {
+                prefix.push(x);
+                extend(prefix, n, out);
+                prefix.pop();
+            }
+        }
+    }
+
+    let mut out = vec![];
+    extend(&mut vec![], n, &mut out);
+    out
+}
+
+/// Check the tree's contents, membership, and root against the model, without
+/// splaying. Keys are even, so that the odd probes fall between them.
+fn check<'a>(
+    tree: &SplayTree<'a, SingleTree<'a>>,
+    model: &BTreeSet<usize>,
+    context: &dyn Fn() -> String,
+) {
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert!(
+        values.iter().eq(model),
+        "contents differ after {}",
+        context()
+    );
+
+    let root = tree.root().map(|s| s.value);
+    assert_eq!(root.is_some(), !model.is_empty(), "{}", context());
+    assert!(root.is_none_or(|r| model.contains(&r)), "{}", context());
+
+    let probes = model.last().map_or(0, |max| max + 2);
+    for k in 0..=probes {
+        let found = tree.get(&k).map(|s| s.value);
+        assert_eq!(
+            found,
+            model.get(&k).copied(),
+            "get({}) after {}",
+            k,
+            context()
+        );
+    }
+}
+
+#[test]
+fn every_insert_order_then_each_operation() {
+    for n in 0..=7 {
+        for order in permutations(n) {
+            // Probe every key, and every gap between and around them.
+            for op in 0..8 {
+                for probe in 0..=2 * n {
+                    let elems: Vec<_> = order.iter().map(|&x| Single::new(2 * x)).collect();
+                    let mut tree = SplayTree::<SingleTree>::new();
+                    let mut model = BTreeSet::new();
+                    for e in &elems {
+                        assert!(tree.insert(e));
+                        model.insert(e.value);
+                    }
+                    let context = || format!("inserting {:?}, then op {} on {}", order, op, probe);
+                    check(&tree, &model, &context);
+
+                    let (actual, expected) = match op {
+                        0 => (tree.find(&probe), model.get(&probe).copied()),
+                        1 => (tree.remove(&probe), model.take(&probe)),
+                        2 => (tree.remove_ge(&probe), {
+                            let ge = model.range(probe..).next().copied();
+                            ge.and_then(|x| model.take(&x))
+                        }),
+                        3 => (tree.min(), model.first().copied()),
+                        4 => (tree.max(), model.last().copied()),
+                        5 => (tree.pop_min(), model.pop_first()),
+                        6 => (tree.pop_max(), model.pop_last()),
+                        _ => {
+                            let root = tree.root().map(|s| s.value);
+                            (tree.pop_root(), root.and_then(|r| model.take(&r)))
+                        }
+                    };
+                    assert_eq!(actual.map(|s| s.value), expected, "{}", context());
+                    check(&tree, &model, &context);
+
+                    // Keys that don't need a probe, like `min`'s, only need
+                    // one pass.
+                    if op >= 3 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn every_insert_order_then_every_remove_order() {
+    for n in 0..=5 {
+        let orders = permutations(n);
+        for inserts in &orders {
+            for removes in &orders {
+                let elems: Vec<_> = (0..n).map(|x| Single::new(2 * x)).collect();
+                let mut tree = SplayTree::<SingleTree>::new();
+                let mut model = BTreeSet::new();
+                let context = || format!("inserting {:?}, then removing {:?}", inserts, removes);
+
+                for &x in inserts {
+                    assert!(tree.insert(&elems[x]));
+                    model.insert(2 * x);
+                    check(&tree, &model, &context);
+                }
+                for &x in removes {
+                    assert_eq!(tree.remove(&(2 * x)).map(|s| s.value), Some(2 * x));
+                    model.remove(&(2 * x));
+                    check(&tree, &model, &context);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn every_short_sequence_of_inserts_and_removes() {
+    const KEYS: usize = 3;
+    const LEN: u32 = 6;
+
+    // Each sequence is a number in base `2 * KEYS`, whose digits are an
+    // insert or a remove of one key.
+    for len in 0..=LEN {
+        for seq in 0..(2 * KEYS).pow(len) {
+            let ops: Vec<_> = (0..len)
+                .scan(seq, |rest, _| {
+                    let digit = *rest % (2 * KEYS);
+                    *rest /= 2 * KEYS;
+                    Some((digit < KEYS, 2 * (digit % KEYS)))
+                })
+                .collect();
+            let context = || format!("{:?}", ops);
+
+            // A fresh element for every insert, so that an insert of a key
+            // that is already in the tree is rejected instead of relinking a
+            // node that's in use.
+            let elems: Vec<_> = ops.iter().map(|&(_, x)| Single::new(x)).collect();
+            let mut tree = SplayTree::<SingleTree>::new();
+            let mut model = BTreeSet::new();
+            for (&(insert, x), elem) in ops.iter().zip(&elems) {
+                if insert {
+                    assert_eq!(tree.insert(elem), model.insert(x), "{}", context());
+                } else {
+                    let removed = tree.remove(&x).map(|s| s.value);
+                    assert_eq!(removed, model.take(&x), "{}", context());
+                }
+                check(&tree, &model, &context);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{FreeBlock, FreeList};
+
+const HEAP: usize = 256;
+
+/// The free blocks, which must be sorted, disjoint, and non-adjacent.
+fn blocks(list: &FreeList) -> Vec<(usize, usize)> {
+    let mut blocks = vec![];
+    list.walk(|b| blocks.push((b.start(), b.start() + b.size())));
+    blocks
+}
+
+quickcheck! {
+    fn best_fit_matches_model(ops: Vec<(bool, usize, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut list = FreeList::new();
+        list.free(arena.alloc(FreeBlock::new(0, HEAP)));
+        let mut live: Vec<(usize, usize)> = vec![];
+
+        for (allocate, size, which) in ops {
+            if allocate || live.is_empty() {
+                let size = size % 32 + 1;
+                // The model's best fit: the smallest block, then the lowest.
+                let best = blocks(&list)
+                    .into_iter()
+                    .filter(|&(s, e)| e - s >= size)
+                    .min_by_key(|&(s, e)| (e - s, s));
+                let start = list.allocate(size);
+                if start != best.map(|(s, _)| s) {
+                    return false;
+                }
+                if let Some(start) = start {
+                    live.push((start, size));
+                }
+            } else {
+                let (start, size) = live.swap_remove(which % live.len());
+                list.free(arena.alloc(FreeBlock::new(start, size)));
+            }
+
+            let free = blocks(&list);
+            let coalesced = free.windows(2).all(|w| w[0].1 < w[1].0);
+            let total: usize = free.iter().map(|&(s, e)| e - s).sum();
+            let used: usize = live.iter().map(|&(_, size)| size).sum();
+            if !coalesced || total + used != HEAP {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn freeing_reports_absorbed_blocks() {
+    let arena = bumpalo::Bump::new();
+    let mut list = FreeList::new();
+    list.free(arena.alloc(FreeBlock::new(0, 10)));
+    list.free(arena.alloc(FreeBlock::new(20, 10)));
+    assert_eq!(blocks(&list), [(0, 10), (20, 30)]);
+
+    let mut absorbed = vec![];
+    list.free_with(arena.alloc(FreeBlock::new(10, 10)), |b| {
+        absorbed.push(b.start())
+    });
+    assert_eq!(absorbed, [0, 20]);
+    assert_eq!(blocks(&list), [(0, 30)]);
+
+    let mut released = None;
+    assert_eq!(
+        list.allocate_with(30, |b| released = Some(b.size())),
+        Some(0)
+    );
+    assert_eq!(released, Some(30));
+    assert!(list.is_empty());
+}
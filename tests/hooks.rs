@@ -0,0 +1,130 @@
+#![cfg(feature = "hooks")]
+
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::{SplayHooks, SplayTree};
+use single::{Single, SingleTree};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+
+/// Mirrors the tree's contents from its hooks alone.
+#[derive(Default)]
+struct Mirror {
+    values: RefCell<BTreeSet<usize>>,
+    rotations: Cell<usize>,
+    // Set when a rotation involves an element that isn't in the tree.
+    stray_rotation: Cell<bool>,
+}
+
+impl<'a> SplayHooks<'a, SingleTree<'a>> for Mirror {
+    fn on_insert(&self, elem: &'a Single<'a>) {
+        assert!(self.values.borrow_mut().insert(elem.value));
+    }
+
+    fn on_remove(&self, elem: &'a Single<'a>) {
+        assert!(self.values.borrow_mut().remove(&elem.value));
+    }
+
+    fn on_rotate(&self, child: &'a Single<'a>, parent: &'a Single<'a>) {
+        self.rotations.set(self.rotations.get() + 1);
+        let values = self.values.borrow();
+        if child.value == parent.value
+            || !values.contains(&child.value)
+            || !values.contains(&parent.value)
+        {
+            self.stray_rotation.set(true);
+        }
+    }
+}
+
+quickcheck! {
+    fn hooks_mirror_the_tree(ops: Vec<(u8, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mirror = Mirror::default();
+        let mut tree = SplayTree::<SingleTree>::new();
+        tree.set_hooks(&mirror);
+
+        for (op, x) in ops {
+            let x = x % 32;
+            match op % 6 {
+                0 | 1 => {
+                    tree.insert(arena.alloc(Single::new(x)));
+                }
+                2 => {
+                    tree.remove(&x);
+                }
+                3 => {
+                    tree.find(&x);
+                }
+                4 => {
+                    tree.remove_ge(&x);
+                }
+                _ => {
+                    tree.pop_max();
+                }
+            }
+        }
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        !mirror.stray_rotation.get() && values.into_iter().eq(mirror.values.into_inner())
+    }
+
+    fn hooks_follow_splits(xs: Vec<usize>, ops: Vec<(u8, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mirror = Mirror::default();
+        let mut tree = SplayTree::<SingleTree>::new();
+        tree.set_hooks(&mirror);
+        for x in xs {
+            tree.insert(arena.alloc(Single::new(x % 64)));
+        }
+
+        // Split some elements off, and sometimes drain them back in.
+        for (op, k) in ops {
+            let k = k % 16;
+            let mut split = match op % 6 {
+                0 => tree.take_smallest(k),
+                1 => tree.take_largest(k),
+                2 => tree.truncate_smallest(k),
+                3 => tree.truncate_largest(k),
+                #[cfg(feature = "rank")]
+                4 => tree.split_at_rank(k),
+                _ => tree.split_by(|s| s.value >= k * 4),
+            };
+            if op & 0x80 != 0 {
+                tree.extend_by_draining(&mut split);
+            }
+        }
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values.into_iter().eq(mirror.values.into_inner())
+    }
+}
+
+#[test]
+fn splaying_reports_rotations_until_hooks_are_cleared() {
+    let elems: Vec<_> = (0..8).map(Single::new).collect();
+    let mirror = Mirror::default();
+    let mut tree = SplayTree::<SingleTree>::new();
+    tree.set_hooks(&mirror);
+
+    // Ascending inserts build a left spine, which finding the minimum
+    // rotates all the way down.
+    for e in &elems {
+        tree.insert(e);
+    }
+    assert_eq!(mirror.rotations.get(), 0);
+    assert_eq!(tree.min().unwrap().value, 0);
+    assert!(mirror.rotations.get() > 0);
+
+    tree.clear_hooks();
+    let rotations = mirror.rotations.get();
+    tree.max();
+    tree.pop_min();
+    assert_eq!(mirror.rotations.get(), rotations);
+    assert_eq!(mirror.values.borrow().len(), elems.len());
+}
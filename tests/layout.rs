@@ -0,0 +1,66 @@
+use intrusive_splay_tree::Node;
+use std::mem;
+use std::ptr;
+
+/// The C-compatible definition of `Node` documented in its rustdoc.
+#[repr(C)]
+struct CNode {
+    left: *const CNode,
+    right: *const CNode,
+    #[cfg(feature = "avl")]
+    balance: i8,
+    #[cfg(feature = "linked")]
+    linked: bool,
+    #[cfg(feature = "paranoid")]
+    brand: usize,
+    #[cfg(feature = "rank")]
+    size: usize,
+    #[cfg(feature = "parent")]
+    parent: *const CNode,
+}
+
+#[test]
+fn node_layout_matches_c_struct() {
+    assert_eq!(mem::size_of::<Node>(), mem::size_of::<CNode>());
+    assert_eq!(mem::align_of::<Node>(), mem::align_of::<CNode>());
+}
+
+#[test]
+fn default_node_is_two_null_pointers() {
+    let node = Node::default();
+    let c = unsafe { &*(&node as *const Node as *const CNode) };
+    assert!(c.left.is_null());
+    assert!(c.right.is_null());
+    #[cfg(feature = "avl")]
+    assert_eq!(c.balance, 0);
+    #[cfg(feature = "linked")]
+    assert!(!c.linked);
+    #[cfg(feature = "paranoid")]
+    assert_eq!(c.brand, 0);
+    #[cfg(feature = "rank")]
+    assert_eq!(c.size, 0);
+    #[cfg(feature = "parent")]
+    assert!(c.parent.is_null());
+}
+
+#[test]
+fn links_point_at_child_nodes() {
+    let child = Node::default();
+    let parent = CNode {
+        left: &child as *const Node as *const CNode,
+        right: ptr::null(),
+        #[cfg(feature = "avl")]
+        balance: 0,
+        #[cfg(feature = "linked")]
+        linked: false,
+        #[cfg(feature = "paranoid")]
+        brand: 0,
+        #[cfg(feature = "rank")]
+        size: 0,
+        #[cfg(feature = "parent")]
+        parent: ptr::null(),
+    };
+    let parent = unsafe { &*(&parent as *const CNode as *const Node) };
+    assert!(ptr::eq(parent.left().unwrap(), &child));
+    assert!(parent.right().is_none());
+}
@@ -0,0 +1,150 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{impl_intrusive_vertex, LinkCutNode, LinkCutTree};
+use std::collections::BTreeSet;
+
+const VERTICES: usize = 12;
+
+#[derive(Debug)]
+struct Vertex<'a> {
+    id: usize,
+    node: LinkCutNode<'a, u64>,
+}
+
+struct Forest;
+
+impl_intrusive_vertex! {
+    impl<'a> IntrusiveVertex<'a> for Forest
+    where
+        type Elem = Vertex<'a>,
+        type Aggregate = u64,
+        node = node,
+        combine = |a, b| a + b;
+}
+
+/// A forest as a set of undirected edges.
+struct Model {
+    edges: BTreeSet<(usize, usize)>,
+    values: Vec<u64>,
+}
+
+impl Model {
+    fn edge(u: usize, v: usize) -> (usize, usize) {
+        (u.min(v), u.max(v))
+    }
+
+    /// The vertices on the path from `u` to `v`, if they are connected.
+    fn path(&self, u: usize, v: usize) -> Option<Vec<usize>> {
+        let mut prev = [None; VERTICES];
+        let mut seen = [false; VERTICES];
+        let mut stack = vec![u];
+        seen[u] = true;
+        while let Some(x) = stack.pop() {
+            for &(a, b) in &self.edges {
+                let y = if a == x {
+                    b
+                } else if b == x {
+                    a
+                } else {
+                    continue;
+                };
+                if !seen[y] {
+                    seen[y] = true;
+                    prev[y] = Some(x);
+                    stack.push(y);
+                }
+            }
+        }
+        if !seen[v] {
+            return None;
+        }
+        let mut path = vec![v];
+        let mut x = v;
+        while let Some(p) = prev[x] {
+            path.push(p);
+            x = p;
+        }
+        Some(path)
+    }
+}
+
+quickcheck! {
+    fn matches_naive_forest(ops: Vec<(u8, usize, usize, u64)>) -> bool {
+        let vertices: Vec<_> = (0..VERTICES)
+            .map(|id| Vertex { id, node: LinkCutNode::new(id as u64) })
+            .collect();
+        let mut forest = LinkCutTree::<Forest>::new();
+        let mut model = Model {
+            edges: BTreeSet::new(),
+            values: (0..VERTICES as u64).collect(),
+        };
+
+        for (op, u, v, value) in ops {
+            let (u, v, value) = (u % VERTICES, v % VERTICES, value % 1000);
+            let (a, b) = (&vertices[u], &vertices[v]);
+            let path = model.path(u, v);
+            let ok = match op % 5 {
+                0 | 1 => {
+                    let linked = forest.link(a, b);
+                    if linked {
+                        model.edges.insert(Model::edge(u, v));
+                    }
+                    linked == path.is_none()
+                }
+                2 => forest.cut(a, b) == model.edges.remove(&Model::edge(u, v)),
+                3 => {
+                    forest.set_value(a, value);
+                    model.values[u] = value;
+                    a.node.value() == value
+                }
+                _ => {
+                    let expected = path.map(|p| p.iter().map(|&x| model.values[x]).sum());
+                    forest.path_aggregate(a, b) == expected
+                }
+            };
+            if !ok || forest.connected(a, b) != model.path(u, v).is_some() {
+                return false;
+            }
+            let root = forest.find_root(a);
+            if model.path(u, root.id).is_none() || !std::ptr::eq(forest.find_root(a), root) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn link_cut_long_path() {
+    let vertices: Vec<_> = (0..1000)
+        .map(|id| Vertex {
+            id,
+            node: LinkCutNode::new(1),
+        })
+        .collect();
+    let mut forest = LinkCutTree::<Forest>::new();
+    for w in vertices.windows(2) {
+        assert!(forest.link(&w[0], &w[1]));
+    }
+    assert_eq!(
+        forest.path_aggregate(&vertices[0], &vertices[999]),
+        Some(1000)
+    );
+    assert_eq!(
+        forest.path_aggregate(&vertices[250], &vertices[749]),
+        Some(500)
+    );
+
+    assert!(forest.cut(&vertices[500], &vertices[499]));
+    assert!(!forest.cut(&vertices[500], &vertices[499]));
+    assert!(!forest.connected(&vertices[0], &vertices[999]));
+    assert_eq!(
+        forest.find_root(&vertices[999]).id,
+        forest.find_root(&vertices[500]).id
+    );
+    assert_eq!(
+        forest.path_aggregate(&vertices[500], &vertices[999]),
+        Some(500)
+    );
+}
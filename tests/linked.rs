@@ -0,0 +1,47 @@
+#![cfg(feature = "linked")]
+
+mod single;
+
+use intrusive_splay_tree::{IntrusiveNode, SplayTree, Treap};
+use single::{Single, SingleTree};
+
+fn is_linked<'a>(s: &'a Single<'a>) -> bool {
+    SingleTree::elem_to_node(s).is_linked()
+}
+
+#[test]
+fn splay_tree_links_and_unlinks() {
+    let (a, b, dup) = (Single::new(1), Single::new(2), Single::new(1));
+    let mut tree = SplayTree::<SingleTree>::new();
+    assert!(!is_linked(&a));
+
+    assert!(tree.insert(&a));
+    assert!(tree.insert(&b));
+    assert!(!tree.insert(&dup));
+    assert!(is_linked(&a) && is_linked(&b));
+    assert!(!is_linked(&dup));
+
+    assert!(tree.remove(&1).is_some());
+    assert!(!is_linked(&a));
+    assert!(tree.pop_min().is_some());
+    assert!(!is_linked(&b));
+}
+
+#[test]
+fn treap_links_and_unlinks() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let mut tree = Treap::<SingleTree>::from_iter(&elems);
+    assert!(elems.iter().all(is_linked));
+
+    let mut upper = tree.split_off(&2);
+    assert!(elems.iter().all(is_linked));
+
+    assert!(upper.pop_max().is_some());
+    assert!(!is_linked(&elems[3]));
+    tree.append(&mut upper);
+    assert!(tree.remove(&0).is_some());
+    assert_eq!(
+        elems.iter().map(is_linked).collect::<Vec<_>>(),
+        [false, true, true, false]
+    );
+}
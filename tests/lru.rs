@@ -0,0 +1,201 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{
+    impl_intrusive_lru_node, impl_intrusive_node, LargestKey, Node, SmallestKey, SplayCache,
+    SplayLru, TreeOrd,
+};
+use std::cmp::Ordering;
+
+#[derive(Debug, Default)]
+struct Entry<'a> {
+    key: u8,
+    tree_node: Node<'a>,
+    lru_node: Node<'a>,
+}
+
+struct Cache;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Cache
+    where
+        type Elem = Entry<'a>,
+        node = tree_node;
+}
+
+impl_intrusive_lru_node! {
+    impl<'a> IntrusiveLruNode<'a> for Cache
+    where
+        lru_node = lru_node;
+}
+
+impl<'a> TreeOrd<'a, Cache> for Entry<'a> {
+    fn tree_cmp(&self, rhs: &Entry<'a>) -> Ordering {
+        self.key.cmp(&rhs.key)
+    }
+}
+
+impl<'a> TreeOrd<'a, Cache> for u8 {
+    fn tree_cmp(&self, rhs: &Entry<'a>) -> Ordering {
+        self.cmp(&rhs.key)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(u8),
+    Find(u8),
+    Peek(u8),
+    Remove(u8),
+    PopLeastRecent,
+}
+
+impl quickcheck::Arbitrary for Op {
+    fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Op {
+        let key = u8::arbitrary(g) % 16;
+        match g.gen_range(0, 5) {
+            0 => Op::Insert(key),
+            1 => Op::Find(key),
+            2 => Op::Peek(key),
+            3 => Op::Remove(key),
+            _ => Op::PopLeastRecent,
+        }
+    }
+}
+
+quickcheck! {
+    fn lru_matches_model(ops: Vec<Op>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut lru = SplayLru::<Cache>::new();
+
+        // Most recently used first.
+        let mut model: Vec<u8> = vec![];
+
+        for op in ops {
+            match op {
+                Op::Insert(k) => {
+                    let inserted = lru.insert(arena.alloc(Entry { key: k, ..Default::default() }));
+                    if inserted == model.contains(&k) {
+                        return false;
+                    }
+                    if inserted {
+                        model.insert(0, k);
+                    }
+                }
+                Op::Find(k) => {
+                    let found = lru.find(&k).map(|e| e.key);
+                    if let Some(i) = model.iter().position(|&m| m == k) {
+                        model.remove(i);
+                        model.insert(0, k);
+                        if found != Some(k) {
+                            return false;
+                        }
+                    } else if found.is_some() {
+                        return false;
+                    }
+                }
+                Op::Peek(k) => {
+                    if lru.peek(&k).map(|e| e.key) != model.iter().copied().find(|&m| m == k) {
+                        return false;
+                    }
+                }
+                Op::Remove(k) => {
+                    let removed = lru.remove(&k).map(|e| e.key);
+                    let expected = model.iter().position(|&m| m == k).map(|i| model.remove(i));
+                    if removed != expected {
+                        return false;
+                    }
+                }
+                Op::PopLeastRecent => {
+                    if lru.pop_least_recent().map(|e| e.key) != model.pop() {
+                        return false;
+                    }
+                }
+            }
+
+            let mut by_recency = vec![];
+            lru.walk_by_recency(|e| by_recency.push(e.key));
+            if by_recency != model
+                || lru.len() != model.len()
+                || lru.most_recent().map(|e| e.key) != model.first().copied()
+                || lru.least_recent().map(|e| e.key) != model.last().copied()
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[test]
+fn cache_evicts_least_recent_by_default() {
+    let entries: Vec<_> = (0..4)
+        .map(|key| Entry {
+            key,
+            ..Default::default()
+        })
+        .collect();
+    let mut cache = SplayCache::<Cache>::new(3);
+
+    for e in &entries[..3] {
+        assert!(matches!(cache.insert(e), Ok(None)));
+    }
+    let dup = Entry {
+        key: 1,
+        ..Default::default()
+    };
+    assert!(cache.insert(&dup).is_err());
+
+    // Touch 0, so 1 is least recent; peeking at 1 doesn't save it.
+    assert!(cache.find(&0).is_some());
+    assert!(cache.peek(&1).is_some());
+    let evicted = cache.insert(&entries[3]).unwrap();
+    assert_eq!(evicted.map(|e| e.key), Some(1));
+    assert_eq!(cache.len(), 3);
+    assert!(cache.peek(&1).is_none());
+
+    assert_eq!(cache.evict().map(|e| e.key), Some(2));
+    assert_eq!(cache.remove(&0).map(|e| e.key), Some(0));
+    assert_eq!(cache.evict().map(|e| e.key), Some(3));
+    assert!(cache.is_empty());
+    assert!(cache.evict().is_none());
+}
+
+#[test]
+fn cache_evicts_by_key() {
+    let entries: Vec<_> = [5, 1, 9, 3]
+        .map(|key| Entry {
+            key,
+            ..Default::default()
+        })
+        .into();
+
+    let mut smallest = SplayCache::<Cache, SmallestKey>::new(2);
+    let evicted: Vec<_> = entries
+        .iter()
+        .filter_map(|e| smallest.insert(e).unwrap())
+        .map(|e| e.key)
+        .collect();
+    assert_eq!(evicted, [1, 3]);
+    assert!(smallest.peek(&5).is_some() && smallest.peek(&9).is_some());
+}
+
+#[test]
+fn cache_evicts_largest_key() {
+    let entries: Vec<_> = [5, 1, 9, 3]
+        .map(|key| Entry {
+            key,
+            ..Default::default()
+        })
+        .into();
+
+    let mut largest = SplayCache::<Cache, LargestKey>::new(2);
+    let evicted: Vec<_> = entries
+        .iter()
+        .filter_map(|e| largest.insert(e).unwrap())
+        .map(|e| e.key)
+        .collect();
+    assert_eq!(evicted, [9, 5]);
+    assert!(largest.peek(&1).is_some() && largest.peek(&3).is_some());
+}
@@ -0,0 +1,105 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{impl_intrusive_node, IntrusiveGap, MinGapSet, Node};
+use std::cell::Cell;
+use std::collections::BTreeSet;
+
+#[derive(Debug)]
+struct Point<'a> {
+    id: usize,
+    at: u8,
+    gap: Cell<u8>,
+    by_key: Node<'a>,
+    by_gap: Node<'a>,
+}
+
+struct ByKey;
+struct ByGap;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByKey
+    where
+        type Elem = Point<'a>,
+        node = by_key;
+}
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByGap
+    where
+        type Elem = Point<'a>,
+        node = by_gap;
+}
+
+impl<'a> IntrusiveGap<'a> for ByKey {
+    type Key = u8;
+    type Gap = u8;
+    type ByGap = ByGap;
+
+    fn key(point: &'a Point<'a>) -> u8 {
+        point.at
+    }
+
+    fn gap(point: &'a Point<'a>) -> &'a Cell<u8> {
+        &point.gap
+    }
+
+    fn distance(lo: u8, hi: u8) -> u8 {
+        hi - lo
+    }
+}
+
+quickcheck! {
+    fn tracks_the_smallest_gap(keys: Vec<u8>, ops: Vec<(bool, usize)>) -> bool {
+        // Few distinct keys, so that some points share one.
+        let points: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(id, &at)| Point {
+                id,
+                at: at % 64,
+                gap: Cell::new(0),
+                by_key: Node::new(),
+                by_gap: Node::new(),
+            })
+            .collect();
+        let mut set = MinGapSet::<ByKey>::new();
+        let mut model = BTreeSet::new();
+
+        for (insert, which) in ops {
+            if points.is_empty() {
+                break;
+            }
+            let point = &points[which % points.len()];
+            if insert {
+                if model.insert((point.at, point.id)) {
+                    set.insert(point);
+                }
+            } else if set.remove(point) != model.remove(&(point.at, point.id)) {
+                return false;
+            }
+
+            let keys: Vec<_> = model.iter().map(|&(at, _)| at).collect();
+            let min_gap = keys.windows(2).map(|w| w[1] - w[0]).min();
+            if set.min_gap() != min_gap {
+                return false;
+            }
+            match set.tightest_pair() {
+                Some((lo, hi)) => {
+                    // The pair must be adjacent, and as close as any.
+                    let lo = (lo.at, lo.id);
+                    let hi = (hi.at, hi.id);
+                    if model.range(lo..).nth(1) != Some(&hi) || Some(hi.0 - lo.0) != min_gap {
+                        return false;
+                    }
+                }
+                None => {
+                    if min_gap.is_some() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
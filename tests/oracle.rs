@@ -0,0 +1,144 @@
+//! Differential testing of `SplayTree` against `BTreeSet`.
+//!
+//! Every operation is applied to both a tree and a `BTreeSet` model, and every
+//! observable result must agree: what each operation returns, the tree's
+//! membership, its minimum and maximum, and its in-order contents. Keys are
+//! drawn from a small range, so that operations keep running into each other's
+//! elements, and failing sequences shrink to a minimal list of operations.
+
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::SplayTree;
+use quickcheck::{Arbitrary, Gen};
+use single::{Single, SingleTree};
+use std::collections::BTreeSet;
+
+const KEYS: usize = 64;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(usize),
+    Remove(usize),
+    Find(usize),
+    RemoveGe(usize),
+    Min,
+    Max,
+    PopMin,
+    PopMax,
+    PopRoot,
+}
+
+impl Arbitrary for Op {
+    fn arbitrary<G: Gen>(g: &mut G) -> Op {
+        let x = g.gen_range(0, KEYS);
+        match g.gen_range(0, 11) {
+            0..=2 => Op::Insert(x),
+            3 | 4 => Op::Remove(x),
+            5 => Op::Find(x),
+            6 => Op::RemoveGe(x),
+            7 => Op::Min,
+            8 => Op::Max,
+            9 => {
+                if g.gen() {
+                    Op::PopMin
+                } else {
+                    Op::PopMax
+                }
+            }
+            _ => Op::PopRoot,
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Op>> {
+        match *self {
+            Op::Insert(x) => Box::new(x.shrink().map(Op::Insert)),
+            Op::Remove(x) => Box::new(x.shrink().map(Op::Remove)),
+            Op::Find(x) => Box::new(x.shrink().map(Op::Find)),
+            Op::RemoveGe(x) => Box::new(x.shrink().map(Op::RemoveGe)),
+            _ => quickcheck::empty_shrinker(),
+        }
+    }
+}
+
+/// Apply `ops` to a tree and a model, panicking at the first disagreement.
+fn run(ops: &[Op]) {
+    let arena = bumpalo::Bump::new();
+    let mut tree = SplayTree::<SingleTree>::new();
+    let mut model = BTreeSet::new();
+
+    for (i, op) in ops.iter().enumerate() {
+        let value = |s: Option<&Single>| s.map(|s| s.value);
+        match *op {
+            Op::Insert(x) => {
+                let inserted = tree.insert(arena.alloc(Single::new(x)));
+                assert_eq!(inserted, model.insert(x), "op {}: {:?}", i, op);
+            }
+            Op::Remove(x) => {
+                let removed = value(tree.remove(&x));
+                assert_eq!(removed, model.take(&x), "op {}: {:?}", i, op);
+            }
+            Op::Find(x) => {
+                let found = value(tree.find(&x));
+                assert_eq!(found, model.get(&x).copied(), "op {}: {:?}", i, op);
+            }
+            Op::RemoveGe(x) => {
+                let removed = value(tree.remove_ge(&x));
+                let expected = model.range(x..).next().copied();
+                if let Some(y) = expected {
+                    model.remove(&y);
+                }
+                assert_eq!(removed, expected, "op {}: {:?}", i, op);
+            }
+            Op::Min => {
+                let min = value(tree.min());
+                assert_eq!(min, model.first().copied(), "op {}: {:?}", i, op);
+            }
+            Op::Max => {
+                let max = value(tree.max());
+                assert_eq!(max, model.last().copied(), "op {}: {:?}", i, op);
+            }
+            Op::PopMin => {
+                let popped = value(tree.pop_min());
+                assert_eq!(popped, model.pop_first(), "op {}: {:?}", i, op);
+            }
+            Op::PopMax => {
+                let popped = value(tree.pop_max());
+                assert_eq!(popped, model.pop_last(), "op {}: {:?}", i, op);
+            }
+            Op::PopRoot => {
+                let root = value(tree.root());
+                let popped = value(tree.pop_root());
+                assert_eq!(popped, root, "op {}: {:?}", i, op);
+                assert_eq!(
+                    popped.map(|x| model.remove(&x)),
+                    root.map(|_| true),
+                    "op {}: {:?}",
+                    i,
+                    op
+                );
+            }
+        }
+
+        let mut contents = vec![];
+        tree.walk(|s| contents.push(s.value));
+        assert!(
+            contents.iter().eq(model.iter()),
+            "op {}: {:?}: tree has {:?}, model has {:?}",
+            i,
+            op,
+            contents,
+            model
+        );
+        assert_eq!(tree.is_empty(), model.is_empty(), "op {}: {:?}", i, op);
+    }
+}
+
+quickcheck! {
+    fn matches_btreeset(ops: Vec<Op>) -> bool {
+        run(&ops);
+        true
+    }
+}
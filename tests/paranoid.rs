@@ -0,0 +1,97 @@
+#![cfg(feature = "paranoid")]
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, Treap, TreeOrd};
+use std::cmp::Ordering;
+
+#[derive(Debug, Default)]
+struct Monster<'a> {
+    id: u32,
+    health: u32,
+    by_id_node: Node<'a>,
+}
+
+struct MonstersById;
+struct MonstersByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for MonstersById
+    where
+        type Elem = Monster<'a>,
+        node = by_id_node;
+
+    // Oops: this should have a separate `by_health_node`.
+    impl<'a> IntrusiveNode<'a> for MonstersByHealth
+    where
+        type Elem = Monster<'a>,
+        node = by_id_node;
+}
+
+impl<'a> TreeOrd<'a, MonstersById> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.id.cmp(&rhs.id)
+    }
+}
+
+impl<'a> TreeOrd<'a, MonstersByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn monster<'a>(id: u32, health: u32) -> Monster<'a> {
+    Monster {
+        id,
+        health,
+        ..Default::default()
+    }
+}
+
+#[test]
+#[should_panic(expected = "node is already in a tree")]
+fn shared_node_field_is_caught() {
+    let monster = monster(1, 10);
+    let mut by_id = SplayTree::<MonstersById>::new();
+    let mut by_health = SplayTree::<MonstersByHealth>::new();
+    assert!(by_id.insert(&monster));
+    by_health.insert(&monster);
+}
+
+#[test]
+#[should_panic(expected = "node is already in a tree")]
+fn inserting_into_two_trees_of_the_same_type_is_caught() {
+    let monster = monster(1, 10);
+    let mut a = SplayTree::<MonstersById>::new();
+    let mut b = SplayTree::<MonstersById>::new();
+    assert!(a.insert(&monster));
+    b.insert(&monster);
+}
+
+#[test]
+fn removed_nodes_can_move_to_another_tree() {
+    let monster = monster(1, 10);
+    let mut by_id = SplayTree::<MonstersById>::new();
+    let mut by_health = SplayTree::<MonstersByHealth>::new();
+    assert!(by_id.insert(&monster));
+    assert!(by_id.remove(&monster).is_some());
+    assert!(by_health.insert(&monster));
+    assert_eq!(by_health.find(&monster).map(|m| m.id), Some(1));
+}
+
+#[test]
+fn split_and_appended_treaps_keep_working() {
+    let monsters: Vec<_> = (0..10).map(|i| monster(i, 0)).collect();
+    let mut low = Treap::<MonstersById>::new();
+    let mut high = Treap::<MonstersById>::new();
+    for m in &monsters[..5] {
+        assert!(low.insert(m));
+    }
+    for m in &monsters[5..] {
+        assert!(high.insert(m));
+    }
+
+    low.append(&mut high);
+    let mut upper = low.split_off(&monsters[3]);
+    assert_eq!(low.pop_max().map(|m| m.id), Some(2));
+    assert_eq!(upper.pop_min().map(|m| m.id), Some(3));
+    assert!(high.insert(&monsters[3]));
+}
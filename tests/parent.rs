@@ -0,0 +1,87 @@
+#![cfg(feature = "parent")]
+
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::SplayTree;
+use single::{Single, SingleTree};
+use std::collections::BTreeMap;
+
+quickcheck! {
+    fn bottom_up_splay_keeps_the_tree_intact(ops: Vec<(u8, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = SplayTree::<SingleTree>::new();
+        let mut model = BTreeMap::new();
+
+        // Mix every operation that restructures the tree with bottom-up
+        // splays, which only work if all of them kept the parent pointers up
+        // to date.
+        for (op, x) in ops {
+            let x = x % 64;
+            match op % 8 {
+                0 | 1 => {
+                    let elem = &*arena.alloc(Single::new(x));
+                    if tree.insert(elem) {
+                        model.insert(x, elem);
+                    }
+                }
+                2 => {
+                    tree.remove(&x);
+                    model.remove(&x);
+                }
+                3 => {
+                    if tree.find_bottom_up(&x).map(|s| s.value) != model.get(&x).map(|s| s.value) {
+                        return false;
+                    }
+                }
+                4 => {
+                    tree.find(&x);
+                }
+                5 => {
+                    if let Some(s) = tree.remove_ge(&x) {
+                        model.remove(&s.value);
+                    }
+                }
+                6 => {
+                    tree.pop_min();
+                    model.pop_first();
+                }
+                _ => {
+                    if let Some((_, elem)) = model.range(x..).next() {
+                        tree.splay_elem(elem);
+                        if !tree.root().is_some_and(|r| std::ptr::eq(r, *elem)) {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "rank")]
+        if tree.len() != model.len() {
+            return false;
+        }
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values.into_iter().eq(model.into_keys())
+    }
+}
+
+#[test]
+fn misses_do_not_restructure() {
+    let elems: Vec<_> = (0..8).map(|x| Single::new(x * 2)).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    for elem in &elems {
+        tree.insert(elem);
+    }
+
+    let root = tree.root().unwrap().value;
+    assert!(tree.find_bottom_up(&3).is_none());
+    assert_eq!(tree.root().unwrap().value, root);
+
+    assert_eq!(tree.find_bottom_up(&4).unwrap().value, 4);
+    assert_eq!(tree.root().unwrap().value, 4);
+}
@@ -0,0 +1,61 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::PersistentTree;
+use std::collections::BTreeSet;
+
+fn keys(tree: PersistentTree<u8>) -> Vec<u8> {
+    let mut keys = vec![];
+    tree.walk(|&k| keys.push(k));
+    keys
+}
+
+quickcheck! {
+    fn old_versions_are_unchanged(ops: Vec<(bool, u8, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut alloc = |node| &*arena.alloc(node);
+        let mut versions = vec![(PersistentTree::new(), BTreeSet::new())];
+
+        for (insert, key, base) in ops {
+            // Branch off of any earlier version, not just the latest.
+            let (tree, model) = versions[base % versions.len()].clone();
+            let (mut tree, mut model): (PersistentTree<u8>, BTreeSet<u8>) = (tree, model);
+            if insert {
+                tree = tree.insert(key, &mut alloc);
+                model.insert(key);
+            } else {
+                tree = tree.remove(&key, &mut alloc);
+                model.remove(&key);
+            }
+            if tree.contains(&key) != insert {
+                return false;
+            }
+            versions.push((tree, model));
+        }
+
+        versions.iter().all(|(tree, model)| {
+            keys(*tree).iter().eq(model.iter())
+                && tree.len() == model.len()
+                && tree.min() == model.first()
+                && tree.max() == model.last()
+        })
+    }
+}
+
+#[test]
+fn persistent_insertions_stay_shallow() {
+    let arena = bumpalo::Bump::new();
+    let mut alloc = |node| &*arena.alloc(node);
+    let mut tree = PersistentTree::new();
+    let before = arena.allocated_bytes();
+    for i in 0..10_000u32 {
+        tree = tree.insert(i, &mut alloc);
+    }
+    assert_eq!(tree.len(), 10_000);
+    assert_eq!(tree.get(&1234), Some(&1234));
+
+    // Each insertion copies only a logarithmic path.
+    let per_insert = (arena.allocated_bytes() - before) / 10_000;
+    let node = std::mem::size_of::<intrusive_splay_tree::PersistentNode<u32>>();
+    assert!(per_insert < node * 64, "{} bytes per insert", per_insert);
+}
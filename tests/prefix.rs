@@ -0,0 +1,98 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{
+    impl_intrusive_node, IntrusivePrefix, Node, PrefixBits, SplayTree, TreeOrd,
+};
+use std::cmp::Ordering;
+
+#[derive(Debug)]
+struct Block<'a> {
+    bits: u8,
+    len: u32,
+    node: Node<'a>,
+}
+
+impl<'a> Block<'a> {
+    fn new(bits: u8, len: u32) -> Block<'a> {
+        Block {
+            bits: bits.truncate(len),
+            len,
+            node: Node::default(),
+        }
+    }
+
+    fn covers(&self, bits: u8) -> bool {
+        bits.truncate(self.len) == self.bits
+    }
+}
+
+struct Blocks;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Blocks
+    where
+        type Elem = Block<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, Blocks> for Block<'a> {
+    fn tree_cmp(&self, rhs: &Block<'a>) -> Ordering {
+        (self.bits, self.len).cmp(&(rhs.bits, rhs.len))
+    }
+}
+
+impl<'a> IntrusivePrefix<'a> for Blocks {
+    type Bits = u8;
+
+    fn prefix(block: &'a Block<'a>) -> (u8, u32) {
+        (block.bits, block.len)
+    }
+}
+
+quickcheck! {
+    fn matches_longest_covering_block(blocks: Vec<(u8, u8)>, probes: Vec<u8>) -> bool {
+        let blocks: Vec<_> = blocks
+            .into_iter()
+            .map(|(bits, len)| Block::new(bits, u32::from(len % 9)))
+            .collect();
+        let mut tree = SplayTree::<Blocks>::new();
+        for block in &blocks {
+            tree.insert(block);
+        }
+
+        probes.into_iter().all(|bits| {
+            let expected = blocks.iter().filter(|b| b.covers(bits)).map(|b| b.len).max();
+            let actual = tree.longest_prefix_match(bits);
+            actual.is_none_or(|b| b.covers(bits)) && actual.map(|b| b.len) == expected
+        })
+    }
+}
+
+#[test]
+fn nested_prefixes_skip_uncovering_siblings() {
+    let blocks = [
+        Block::new(0b1000_0000, 1),
+        Block::new(0b1010_0000, 4),
+        Block::new(0b1010_0100, 6),
+        Block::new(0b1011_1100, 6),
+        Block::new(0b1100_0000, 2),
+    ];
+    let mut tree = SplayTree::<Blocks>::new();
+    for block in &blocks {
+        tree.insert(block);
+    }
+
+    let len = |tree: &mut SplayTree<Blocks>, bits| tree.longest_prefix_match(bits).map(|b| b.len);
+    assert_eq!(len(&mut tree, 0b1010_0101), Some(6));
+    assert_eq!(len(&mut tree, 0b1011_1111), Some(6));
+    assert_eq!(len(&mut tree, 0b1111_1111), Some(2));
+    assert_eq!(len(&mut tree, 0b0111_1111), None);
+
+    // The nearest block below is `1010_0100/6`, which misses, so the match
+    // is its parent.
+    assert_eq!(len(&mut tree, 0b1010_1000), Some(4));
+    // The nearest block below is `1010_0100/6` again, which only shares
+    // `101` with it, so its parent `1010_0000/4` is skipped too.
+    assert_eq!(len(&mut tree, 0b1011_0000), Some(1));
+}
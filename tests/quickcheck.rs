@@ -6,11 +6,16 @@ extern crate quickcheck;
 
 mod single;
 
-use intrusive_splay_tree::{IntrusiveNode, Node, SplayTree, TreeOrd};
+use intrusive_splay_tree::{
+    BatchOp, Cursor, Descending, DiffEntry, IntrusiveNode, Merged, Node, SplayTree, SplayWeight,
+    TreeOrd,
+};
 use single::{Single, SingleTree};
 use std::cmp::{min, Ordering};
+use std::collections::BTreeSet;
 use std::iter::FromIterator;
 use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 
 quickcheck! {
     fn find(xs: Vec<usize>, x: usize) -> bool {
@@ -58,7 +63,32 @@ quickcheck! {
         );
 
         let is_new_entry = tree.insert(arena.alloc(Single::new(x)));
-        ((is_new_entry && !x_in_xs) || x_in_xs) && tree.find(&x).map_or(false, |c| c.value == x)
+        (is_new_entry || x_in_xs) && tree.find(&x).is_some_and(|c| c.value == x)
+    }
+
+    fn splay_to(xs: Vec<usize>, x: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model: BTreeSet<usize> = xs.into_iter().collect();
+
+        match tree.splay_to(&x) {
+            None => model.is_empty(),
+            Some((root, order)) => {
+                let below = model.range(..x).next_back().copied();
+                let above = model.range(x..).next().copied();
+                tree.root().map(|r| r.value) == Some(root.value)
+                    && x.cmp(&root.value) == order
+                    && match order {
+                        Ordering::Equal => root.value == x,
+                        Ordering::Less => Some(root.value) == above,
+                        Ordering::Greater => Some(root.value) == below,
+                    }
+            }
+        }
     }
 
     fn tree_min(xs: Vec<usize>) -> bool {
@@ -87,6 +117,21 @@ quickcheck! {
         tree.max().map(|s| s.value) == max
     }
 
+    fn tree_min_max(xs: Vec<usize>) -> bool {
+        let expected = xs.iter().copied().min().zip(xs.iter().copied().max());
+
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.into_iter()
+                .map(|x| &*arena.alloc(Single::new(x)))
+        );
+
+        let once = tree.min_max().map(|(min, max)| (min.value, max.value));
+        let twice = tree.min_max().map(|(min, max)| (min.value, max.value));
+        once == expected && twice == expected && tree.root().map(|s| s.value) == expected.map(|e| e.0)
+    }
+
     fn pop_min(xs: Vec<usize>) -> bool {
         if xs.is_empty() {
             return true;
@@ -144,6 +189,530 @@ quickcheck! {
         let root = tree.root().map(|n| n.value);
         tree.pop_root().map(|n| n.value) == root
     }
+
+    fn descending(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let tree = SplayTree::<Descending<SingleTree>>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+
+        let mut expected = xs;
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        expected.dedup();
+
+        let mut actual = vec![];
+        tree.walk(|s| actual.push(s.value));
+        actual == expected
+    }
+
+    fn remove_ge(xs: Vec<usize>, keys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        for key in keys {
+            let expected = model.range(key..).next().copied();
+            if let Some(x) = expected {
+                model.remove(&x);
+            }
+            if tree.remove_ge(&key).map(|s| s.value) != expected {
+                return false;
+            }
+        }
+
+        let mut rest = vec![];
+        tree.walk(|s| rest.push(s.value));
+        rest == model.into_iter().collect::<Vec<_>>()
+    }
+
+    fn remove_with_neighbors(xs: Vec<usize>, keys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.iter().copied().collect();
+
+        for key in keys.into_iter().chain(xs) {
+            let expected = model.take(&key).map(|x| {
+                (model.range(..x).next_back().copied(), x, model.range(x..).next().copied())
+            });
+            let actual = tree
+                .remove_with_neighbors(&key)
+                .map(|(p, x, s)| (p.map(|p| p.value), x.value, s.map(|s| s.value)));
+            if actual != expected {
+                return false;
+            }
+        }
+
+        tree.is_empty()
+    }
+
+    fn take_smallest_and_largest(xs: Vec<usize>, k: usize, largest: bool) -> bool {
+        let k = k % (xs.len() + 2);
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+
+        let mut expected = xs;
+        expected.sort_unstable();
+        expected.dedup();
+        let split = if largest { expected.len().saturating_sub(k) } else { k.min(expected.len()) };
+        let (low, high) = expected.split_at(split);
+
+        let taken = if largest { tree.take_largest(k) } else { tree.take_smallest(k) };
+        let (expected_taken, expected_rest) = if largest { (high, low) } else { (low, high) };
+
+        let mut taken_values = vec![];
+        taken.walk(|s| taken_values.push(s.value));
+        let mut rest = vec![];
+        tree.walk(|s| rest.push(s.value));
+        taken_values == expected_taken && rest == expected_rest
+    }
+
+    fn truncate_smallest_and_largest(xs: Vec<usize>, k: usize, largest: bool) -> bool {
+        let k = k % (xs.len() + 2);
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+
+        let mut expected = xs;
+        expected.sort_unstable();
+        expected.dedup();
+        let split = if largest { expected.len().saturating_sub(k) } else { k.min(expected.len()) };
+        let (low, high) = expected.split_at(split);
+
+        let removed = if largest { tree.truncate_largest(k) } else { tree.truncate_smallest(k) };
+        let (expected_kept, expected_removed) = if largest { (high, low) } else { (low, high) };
+
+        let mut kept = vec![];
+        tree.walk(|s| kept.push(s.value));
+        let mut removed_values = vec![];
+        removed.walk(|s| removed_values.push(s.value));
+        kept == expected_kept && removed_values == expected_removed
+    }
+
+    fn split_by(xs: Vec<usize>, at: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+        let expected_high = model.split_off(&at);
+
+        let high = tree.split_by(|s| s.value >= at);
+        let mut low_values = vec![];
+        tree.walk(|s| low_values.push(s.value));
+        let mut high_values = vec![];
+        high.walk(|s| high_values.push(s.value));
+        low_values.into_iter().eq(model) && high_values.into_iter().eq(expected_high)
+    }
+
+    fn insert_all(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let elems: Vec<&Single> = xs.iter().map(|x| &*arena.alloc(Single::new(*x))).collect();
+        let mut tree = SplayTree::<SingleTree>::new();
+
+        let mut model = BTreeSet::new();
+        let expected_rejected: Vec<_> = elems.iter().copied().filter(|s| !model.insert(s.value)).collect();
+
+        let mut rejected = vec![];
+        let report = tree.insert_all(elems.iter().copied(), |s| rejected.push(s));
+        if report.inserted() != model.len() || report.rejected() != expected_rejected.len() {
+            return false;
+        }
+        if rejected.len() != expected_rejected.len()
+            || !rejected.iter().zip(&expected_rejected).all(|(a, b)| std::ptr::eq(*a, *b))
+            || tree.validate().is_err()
+        {
+            return false;
+        }
+
+        // The duplicates were left alone, so they can go in trees of their
+        // own.
+        for s in &rejected {
+            let mut own = SplayTree::<SingleTree>::new();
+            own.insert(s);
+        }
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values.into_iter().eq(model)
+    }
+
+    fn apply_batch(xs: Vec<usize>, ops: Vec<(bool, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        // At most one operation per key.
+        let mut seen = BTreeSet::new();
+        let mut batch = vec![];
+        let mut expected_count = 0;
+        for (insert, x) in ops {
+            if !seen.insert(x) {
+                continue;
+            }
+            let elem = &*arena.alloc(Single::new(x));
+            let took_effect = if insert {
+                batch.push(BatchOp::Insert(elem));
+                model.insert(x)
+            } else {
+                batch.push(BatchOp::Remove(elem));
+                model.remove(&x)
+            };
+            expected_count += took_effect as usize;
+        }
+
+        let count = tree.apply_batch(&mut batch);
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        count == expected_count && values == model.into_iter().collect::<Vec<_>>()
+    }
+
+    fn find_many(xs: Vec<usize>, keys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model: BTreeSet<usize> = xs.into_iter().collect();
+
+        let mut keys = keys;
+        keys.sort_unstable();
+        let expected: Vec<_> = keys.iter().map(|k| model.get(k).copied()).collect();
+        let actual: Vec<_> = tree.find_many(&keys).map(|s| s.map(|s| s.value)).collect();
+        actual == expected
+    }
+
+    fn remove_many(xs: Vec<usize>, keys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        let mut keys = keys;
+        keys.sort_unstable();
+        let expected: Vec<_> = keys.iter().map(|k| model.take(k)).collect();
+        let actual: Vec<_> = tree.remove_many(&keys).map(|s| s.map(|s| s.value)).collect();
+
+        let mut rest = vec![];
+        tree.walk(|s| rest.push(s.value));
+        actual == expected && rest == model.into_iter().collect::<Vec<_>>()
+    }
+
+    fn pop_while(xs: Vec<usize>, limit: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+
+        let mut expected = xs;
+        expected.sort_unstable();
+        expected.dedup();
+        let split = expected.partition_point(|x| *x <= limit);
+
+        let popped: Vec<_> = tree.pop_while(|s| s.value <= limit).map(|s| s.value).collect();
+        let mut rest = vec![];
+        tree.walk(|s| rest.push(s.value));
+        popped == expected[..split] && rest == expected[split..]
+    }
+
+    fn cmp_trees(xs: Vec<usize>, ys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let xs_tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let ys_tree = SplayTree::<SingleTree>::from_iter(
+            ys.iter()
+                .map(|y| &*arena.alloc(Single::new(*y)))
+        );
+
+        let xs = BTreeSet::from_iter(xs);
+        let ys = BTreeSet::from_iter(ys);
+        xs_tree.partial_cmp(&ys_tree) == xs.partial_cmp(&ys)
+            && (xs_tree == ys_tree) == (xs == ys)
+    }
+
+    fn copy_into(xs: Vec<usize>, x: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut copy = tree.copy_into(|s| arena.alloc(Single::new(s.value))).unwrap();
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        let mut copied = vec![];
+        copy.walk(|s| copied.push(s.value));
+
+        // The copy is independent of the original.
+        let in_tree = tree.find(&x).map(|s| s as *const Single);
+        let removed = copy.remove(&x).map(|s| s as *const Single);
+        values == copied
+            && in_tree.is_some() == removed.is_some()
+            && (in_tree.is_none() || in_tree != removed)
+            && tree.find(&x).is_some() == in_tree.is_some()
+    }
+
+    fn frozen_view(xs: Vec<usize>, lo: usize, hi: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model = BTreeSet::from_iter(xs);
+
+        let mut buf = vec![MaybeUninit::uninit(); model.len()];
+        let values = |elems: &[&Single]| elems.iter().map(|s| s.value).collect::<Vec<_>>();
+        let view = tree.freeze_into(&mut buf).unwrap();
+
+        let (lo, hi) = (min(lo, hi), lo.max(hi));
+        view.len() == model.len()
+            && values(view.as_slice()) == model.iter().copied().collect::<Vec<_>>()
+            && view.get(&lo).map(|s| s.value) == model.get(&lo).copied()
+            && view.rank(&lo) == model.range(..lo).count()
+            && view.select(view.rank(&lo)).map(|s| s.value) == model.range(lo..).next().copied()
+            && values(view.range(lo..hi)) == model.range(lo..hi).copied().collect::<Vec<_>>()
+            && values(view.range(lo..=hi)) == model.range(lo..=hi).copied().collect::<Vec<_>>()
+            && values(view.range(..=lo)) == model.range(..=lo).copied().collect::<Vec<_>>()
+            && (model.is_empty() || tree.freeze_into(&mut buf[1..]).is_none())
+    }
+
+    fn extend_by_draining(xs: Vec<usize>, ys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut xs_tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut ys_tree = SplayTree::<SingleTree>::from_iter(
+            ys.iter()
+                .map(|y| &*arena.alloc(Single::new(*y)))
+        );
+
+        let xs = BTreeSet::from_iter(xs);
+        let ys = BTreeSet::from_iter(ys);
+        let moved = xs_tree.extend_by_draining(&mut ys_tree);
+
+        let mut merged = vec![];
+        xs_tree.walk(|s| merged.push(s.value));
+        let mut rest = vec![];
+        ys_tree.walk(|s| rest.push(s.value));
+        moved == ys.difference(&xs).count()
+            && merged == xs.union(&ys).copied().collect::<Vec<_>>()
+            && rest == xs.intersection(&ys).copied().collect::<Vec<_>>()
+    }
+
+    fn cursor_survives_mutation(xs: Vec<usize>, ops: Vec<(bool, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model = BTreeSet::from_iter(xs);
+        let mut untouched = model.clone();
+
+        // Interleave a scan with an insertion or removal after each step.
+        let mut cursor = Cursor::<SingleTree>::new();
+        let mut scanned = vec![];
+        let mut ops = ops.into_iter();
+        while let Some(s) = cursor.next(&mut tree) {
+            scanned.push(s.value);
+            if let Some((insert, x)) = ops.next() {
+                untouched.remove(&x);
+                if insert {
+                    if model.insert(x) {
+                        tree.insert(arena.alloc(Single::new(x)));
+                    }
+                } else if model.remove(&x) {
+                    tree.remove(&x);
+                }
+            }
+        }
+
+        // The scan is in order, and sees everything that was there all along.
+        scanned.windows(2).all(|w| w[0] < w[1])
+            && untouched.iter().all(|x| scanned.contains(x))
+            && scanned.iter().all(|x| model.contains(x) || !untouched.contains(x))
+    }
+
+    fn merge_iter(xs: Vec<usize>, ys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let xs_tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let ys_tree = SplayTree::<SingleTree>::from_iter(
+            ys.iter()
+                .map(|y| &*arena.alloc(Single::new(*y)))
+        );
+
+        let xs = BTreeSet::from_iter(xs);
+        let ys = BTreeSet::from_iter(ys);
+        let expected: Vec<_> = xs
+            .union(&ys)
+            .map(|k| (xs.contains(k).then_some(*k), ys.contains(k).then_some(*k)))
+            .collect();
+        let actual: Vec<_> = xs_tree
+            .merge_iter(&ys_tree)
+            .map(|m| match m {
+                Merged::Left(a) => (Some(a.value), None),
+                Merged::Right(b) => (None, Some(b.value)),
+                Merged::Both(a, b) => (Some(a.value), Some(b.value)),
+            })
+            .collect();
+        actual == expected
+    }
+
+    fn diff(xs: Vec<usize>, ys: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+
+        let old = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let new = SplayTree::<SingleTree>::from_iter(
+            ys.iter()
+                .map(|y| &*arena.alloc(Single::new(*y)))
+        );
+
+        let xs = BTreeSet::from_iter(xs);
+        let ys = BTreeSet::from_iter(ys);
+        let mut removed = vec![];
+        let mut added = vec![];
+        let mut kept = vec![];
+        for entry in old.diff(&new) {
+            match entry {
+                DiffEntry::Removed(a) => removed.push(a.value),
+                DiffEntry::Added(b) => added.push(b.value),
+                DiffEntry::Kept(a, b) => kept.push((a.value, b.value)),
+            }
+        }
+        removed.iter().eq(xs.difference(&ys))
+            && added.iter().eq(ys.difference(&xs))
+            && kept.iter().map(|(a, _)| a).eq(xs.intersection(&ys))
+            && kept.iter().all(|(a, b)| a == b)
+    }
+
+    fn insert_after_hint(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = SplayTree::<SingleTree>::new();
+        let mut model = BTreeSet::new();
+
+        // Hint with the last inserted element, which is sometimes the root
+        // and in the right place, and sometimes not.
+        let mut hint = None;
+        for x in xs {
+            let elem = &*arena.alloc(Single::new(x));
+            let inserted = match hint {
+                Some(hint) => tree.insert_after_hint(hint, elem),
+                None => tree.insert(elem),
+            };
+            if inserted != model.insert(x) {
+                return false;
+            }
+            if inserted {
+                hint = Some(elem);
+            }
+        }
+
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values.into_iter().eq(model)
+    }
+
+    fn fold(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model: BTreeSet<usize> = xs.into_iter().collect();
+
+        let values = tree.fold(vec![], |mut values, s| {
+            values.push(s.value);
+            values
+        });
+        values.into_iter().eq(model)
+    }
+
+    fn any_all_find_first(xs: Vec<usize>, d: usize) -> bool {
+        let d = d % 8 + 1;
+        let arena = bumpalo::Bump::new();
+        let tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model: BTreeSet<usize> = xs.into_iter().collect();
+        let divisible = |x: usize| x.is_multiple_of(d);
+
+        tree.any(|s| divisible(s.value)) == model.iter().any(|&x| divisible(x))
+            && tree.all(|s| divisible(s.value)) == model.iter().all(|&x| divisible(x))
+            && tree.find_first(|s| divisible(s.value)).map(|s| s.value)
+                == model.iter().copied().find(|&x| divisible(x))
+    }
+
+    fn find_weighted(xs: Vec<usize>, lookups: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = SplayTree::<SingleTree>::from_iter(
+            xs.iter()
+                .map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let model: BTreeSet<usize> = xs.into_iter().collect();
+
+        for x in lookups {
+            let found = tree.find_weighted(&x).map(|s| s.value);
+            if found != model.get(&x).copied() || tree.validate().is_err() {
+                return false;
+            }
+            // Nothing is heavier than the heaviest element, so it always
+            // reaches the root.
+            if found.is_some_and(|x| x % 4 == 3) && tree.root().map(|s| s.value) != found {
+                return false;
+            }
+        }
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values.into_iter().eq(model)
+    }
+}
+
+impl<'a> SplayWeight<'a, SingleTree<'a>> for Single<'a> {
+    fn weight(&self) -> u32 {
+        (self.value % 4) as u32
+    }
 }
 
 #[derive(Debug, Default)]
@@ -295,7 +864,7 @@ quickcheck! {
         let x_is_new = by_x.insert(elem);
         let y_is_new = by_y.insert(elem);
 
-        ((x_is_new && !x_in_xs) || x_in_xs) && by_x.find(&x).map_or(false, |m| m.x == x) &&
-        ((y_is_new && !y_in_ys) || y_in_ys) && by_y.find(&y).map_or(false, |m| m.y == y)
+        (x_is_new || x_in_xs) && by_x.find(&x).is_some_and(|m| m.x == x) &&
+        (y_is_new || y_in_ys) && by_y.find(&y).is_some_and(|m| m.y == y)
     }
 }
@@ -0,0 +1,131 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{impl_intrusive_node, IntrusiveRange, Node, RangeSet, TreeOrd};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Default)]
+struct Span<'a> {
+    start: Cell<u8>,
+    end: Cell<u8>,
+    node: Node<'a>,
+}
+
+impl<'a> Span<'a> {
+    fn new(start: u8, end: u8) -> Span<'a> {
+        Span {
+            start: Cell::new(start),
+            end: Cell::new(end),
+            node: Node::default(),
+        }
+    }
+}
+
+struct Spans;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Spans
+    where
+        type Elem = Span<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, Spans> for Span<'a> {
+    fn tree_cmp(&self, rhs: &Span<'a>) -> Ordering {
+        self.start.get().cmp(&rhs.start.get())
+    }
+}
+
+impl<'a> IntrusiveRange<'a> for Spans {
+    type Point = u8;
+
+    fn start(span: &'a Span<'a>) -> &'a Cell<u8> {
+        &span.start
+    }
+
+    fn end(span: &'a Span<'a>) -> &'a Cell<u8> {
+        &span.end
+    }
+}
+
+/// The ranges in the set, which must be sorted, disjoint, and non-adjacent.
+fn ranges(set: &RangeSet<Spans>) -> Vec<(u8, u8)> {
+    let mut ranges = vec![];
+    set.walk(|r| ranges.push((r.start, r.end)));
+    ranges
+}
+
+/// Coalesce a set of points into the ranges a `RangeSet` should hold.
+fn coalesce(points: &BTreeSet<u8>) -> Vec<(u8, u8)> {
+    let mut ranges: Vec<(u8, u8)> = vec![];
+    for &p in points {
+        match ranges.last_mut() {
+            Some(last) if last.1 == p => last.1 = p + 1,
+            _ => ranges.push((p, p + 1)),
+        }
+    }
+    ranges
+}
+
+quickcheck! {
+    fn matches_set_of_points(ops: Vec<(bool, u8, u8)>, probes: Vec<u8>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut set = RangeSet::<Spans>::new();
+        let mut model = BTreeSet::new();
+
+        for (insert, a, b) in ops {
+            let (a, b) = (a % 128, b % 128);
+            let (start, end) = (a.min(b), a.max(b));
+            if insert {
+                set.insert(arena.alloc(Span::new(start, end)));
+                model.extend(start..end);
+            } else {
+                set.remove(start..end, || arena.alloc(Span::default()));
+                for p in start..end {
+                    model.remove(&p);
+                }
+            }
+            if ranges(&set) != coalesce(&model) {
+                return false;
+            }
+        }
+
+        probes.into_iter().all(|p| {
+            let p = p % 130;
+            set.contains(p) == model.contains(&p)
+                && set.covering(p..p + 2).is_some() == (model.contains(&p) && model.contains(&(p + 1)))
+        })
+    }
+}
+
+#[test]
+fn merging_reports_absorbed_elements() {
+    let arena = bumpalo::Bump::new();
+    let mut set = RangeSet::<Spans>::new();
+    set.insert(arena.alloc(Span::new(0, 2)));
+    set.insert(arena.alloc(Span::new(4, 6)));
+    set.insert(arena.alloc(Span::new(8, 9)));
+
+    let mut absorbed = vec![];
+    set.insert_with(arena.alloc(Span::new(2, 4)), |s| {
+        absorbed.push((s.start.get(), s.end.get()))
+    });
+    assert_eq!(absorbed, [(0, 2), (4, 6)]);
+    assert_eq!(ranges(&set), [(0, 6), (8, 9)]);
+
+    let mut split_called = false;
+    let mut removed = vec![];
+    set.remove_with(
+        1..9,
+        || {
+            split_called = true;
+            arena.alloc(Span::default())
+        },
+        |s| removed.push((s.start.get(), s.end.get())),
+    );
+    assert!(!split_called);
+    assert_eq!(removed, [(8, 9)]);
+    assert_eq!(ranges(&set), [(0, 1)]);
+}
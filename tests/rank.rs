@@ -0,0 +1,157 @@
+#![cfg(feature = "rank")]
+
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::{SplaySequence, SplayTree};
+use single::{Single, SingleTree};
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+/// Apply `ops` to both a tree and a model of it, mixing every operation that
+/// restructures the tree, so that subtree sizes get a workout.
+fn tree_and_model<'a>(
+    arena: &'a bumpalo::Bump,
+    ops: &[(u8, usize)],
+) -> (SplayTree<'a, SingleTree<'a>>, Vec<usize>) {
+    let mut tree = SplayTree::<SingleTree>::new();
+    let mut model = BTreeSet::new();
+    for &(op, x) in ops {
+        match op % 6 {
+            0 | 1 => {
+                tree.insert(arena.alloc(Single::new(x)));
+                model.insert(x);
+            }
+            2 => {
+                tree.remove(&x);
+                model.remove(&x);
+            }
+            3 => {
+                tree.find(&x);
+            }
+            4 => {
+                if let Some(s) = tree.remove_ge(&x) {
+                    model.remove(&s.value);
+                }
+            }
+            _ => {
+                if x % 2 == 0 {
+                    tree.pop_min();
+                    model.pop_first();
+                } else {
+                    tree.pop_max();
+                    model.pop_last();
+                }
+            }
+        }
+    }
+    (tree, model.into_iter().collect())
+}
+
+quickcheck! {
+    fn select_range(ops: Vec<(u8, usize)>, i: usize, j: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let (mut tree, model) = tree_and_model(&arena, &ops);
+
+        let (i, j) = (i % (model.len() + 2), j % (model.len() + 2));
+        let expected = model.get(i..j.min(model.len())).unwrap_or(&[]);
+        let selected: Vec<_> = tree.select_range(i..j).map(|s| s.value).collect();
+
+        let each = (0..model.len()).all(|k| {
+            tree.select_range(k..=k).map(|s| s.value).collect::<Vec<_>>() == [model[k]]
+        });
+        tree.len() == model.len() && selected == expected && each
+    }
+
+    fn find_with_rank(ops: Vec<(u8, usize)>, x: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let (mut tree, model) = tree_and_model(&arena, &ops);
+
+        let expected = model.binary_search(&x).ok().map(|rank| (x, rank));
+        tree.find_with_rank(&x).map(|(s, rank)| (s.value, rank)) == expected
+    }
+
+    fn take_keeps_sizes(ops: Vec<(u8, usize)>, k: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let (mut tree, model) = tree_and_model(&arena, &ops);
+
+        let k = k % (model.len() + 1);
+        let mut smallest = tree.take_smallest(k);
+        smallest.len() == k
+            && tree.len() == model.len() - k
+            && smallest.select_range(..).map(|s| s.value).eq(model[..k].iter().copied())
+            && tree.select_range(..).map(|s| s.value).eq(model[k..].iter().copied())
+    }
+
+    fn split_at_rank(ops: Vec<(u8, usize)>, n: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let (mut tree, model) = tree_and_model(&arena, &ops);
+
+        let n = n % (model.len() + 2);
+        let mut front = tree.split_at_rank(n);
+        let k = n.min(model.len());
+        front.len() == k
+            && tree.len() == model.len() - k
+            && front.select_range(..).map(|s| s.value).eq(model[..k].iter().copied())
+            && tree.select_range(..).map(|s| s.value).eq(model[k..].iter().copied())
+    }
+
+    fn sequence_matches_vec(ops: Vec<(u8, usize)>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut seq = SplaySequence::<SingleTree>::new();
+        let mut model = vec![];
+        for (n, &(op, i)) in ops.iter().enumerate() {
+            let i = i % (model.len() + 2);
+            match op % 5 {
+                0 | 1 => {
+                    if i <= model.len() {
+                        seq.insert_at(i, arena.alloc(Single::new(n)));
+                        model.insert(i, n);
+                    }
+                }
+                2 => {
+                    let expected = (i < model.len()).then(|| model.remove(i));
+                    if seq.remove_at(i).map(|s| s.value) != expected {
+                        return false;
+                    }
+                }
+                3 => {
+                    if seq.get(i).map(|s| s.value) != model.get(i).copied() {
+                        return false;
+                    }
+                }
+                _ => {
+                    // Split and concatenate back together, in rotated order.
+                    let mut tail = seq.split_off(i);
+                    tail.append(&mut seq);
+                    seq = tail;
+                    let i = i.min(model.len());
+                    model.rotate_left(i);
+                }
+            }
+        }
+        let mut contents = vec![];
+        seq.walk(|s| contents.push(s.value));
+        seq.len() == model.len() && contents == model
+    }
+}
+
+#[test]
+fn select_range_out_of_bounds() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    assert_eq!(tree.select_range(8..20).len(), 2);
+    assert_eq!(tree.select_range(10..).len(), 0);
+    assert_eq!(
+        tree.select_range((Bound::Excluded(4), Bound::Excluded(5)))
+            .len(),
+        0
+    );
+    assert_eq!(
+        tree.select_range(..=2).map(|s| s.value).collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+}
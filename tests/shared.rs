@@ -0,0 +1,77 @@
+#![cfg(feature = "shared")]
+
+use intrusive_splay_tree::{impl_intrusive_node, impl_tree_ord_by_field, Node};
+use intrusive_splay_tree::{RetireFlag, SharedNode, SharedSplayTree};
+
+struct Entry<'a> {
+    key: u32,
+    retired: RetireFlag,
+    node: Node<'a>,
+}
+
+struct Entries;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for Entries
+    where
+        type Elem = Entry<'a>,
+        node = node;
+}
+
+impl_tree_ord_by_field! {
+    impl<'a> TreeOrd<'a, Entries> for Entry<'a>
+    where
+        field = key: u32;
+}
+
+unsafe impl<'a> SharedNode<'a> for Entries {
+    fn retire_flag(entry: &Self::Elem) -> &RetireFlag {
+        &entry.retired
+    }
+}
+
+#[test]
+fn readers_search_while_a_writer_retires() {
+    let entries: Vec<_> = (0..1000)
+        .map(|key| Entry {
+            key,
+            retired: RetireFlag::new(),
+            node: Node::new(),
+        })
+        .collect();
+    let mut tree = SharedSplayTree::<Entries>::new();
+    for entry in &entries {
+        assert!(tree.insert(entry));
+    }
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for key in 0..1000 {
+                    // Odd keys may or may not have been retired yet.
+                    let found = tree.get(&key);
+                    assert!(found.is_some() || key % 2 == 1);
+                    assert!(found.is_none_or(|e| e.key == key));
+                }
+            });
+        }
+        s.spawn(|| {
+            for key in (1..1000).step_by(2) {
+                assert_eq!(tree.retire(&key).map(|e| e.key), Some(key));
+            }
+        });
+    });
+
+    assert!(tree.retire(&1).is_none());
+    assert!(tree.get(&1).is_none());
+    let mut unlinked = vec![];
+    assert_eq!(tree.unlink_retired(|e| unlinked.push(e.key)), 500);
+    assert!(unlinked.iter().copied().eq((1..1000).step_by(2)));
+    assert!(!entries[1].retired.is_retired());
+
+    let tree = tree.into_inner();
+    assert_eq!(tree.validate(), Ok(()));
+    let mut keys = vec![];
+    tree.walk(|e| keys.push(e.key));
+    assert!(keys.iter().copied().eq((0..1000).step_by(2)));
+}
@@ -1,5 +1,6 @@
 use intrusive_splay_tree::{IntrusiveNode, Node, TreeOrd};
 use std::cmp::Ordering;
+use std::fmt;
 use std::marker::PhantomData;
 
 #[derive(Debug, Default)]
@@ -17,6 +18,32 @@ impl<'a> Single<'a> {
     }
 }
 
+impl fmt::Display for Single<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+impl PartialEq for Single<'_> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.value == rhs.value
+    }
+}
+
+impl Eq for Single<'_> {}
+
+impl PartialOrd for Single<'_> {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl Ord for Single<'_> {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        self.value.cmp(&rhs.value)
+    }
+}
+
 pub struct SingleTree<'a>(PhantomData<&'a Single<'a>>);
 
 unsafe impl<'a> IntrusiveNode<'a> for SingleTree<'a> {
@@ -0,0 +1,176 @@
+#[macro_use]
+extern crate quickcheck;
+
+use intrusive_splay_tree::{SlotNode, SlotOrd, SlotSplayTree, SlotTree};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::panic;
+
+/// A minimal generational arena, which reuses freed slots under a new
+/// generation.
+#[derive(Default)]
+struct Arena {
+    slots: Vec<(u32, Option<Value>)>,
+    free: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Key {
+    index: usize,
+    generation: u32,
+}
+
+struct Value {
+    value: u8,
+    node: SlotNode<Key>,
+}
+
+impl Arena {
+    fn alloc(&mut self, value: u8) -> Key {
+        let value = Some(Value {
+            value,
+            node: SlotNode::new(),
+        });
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.0 += 1;
+                slot.1 = value;
+                Key {
+                    index,
+                    generation: slot.0,
+                }
+            }
+            None => {
+                self.slots.push((0, value));
+                Key {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    fn free(&mut self, key: Key) {
+        self.slots[key.index].1 = None;
+        self.free.push(key.index);
+    }
+}
+
+struct Values;
+
+impl SlotTree for Values {
+    type Storage = Arena;
+    type Key = Key;
+    type Elem = Value;
+
+    fn elem(arena: &Arena, key: Key) -> Option<&Value> {
+        match arena.slots.get(key.index)? {
+            (generation, Some(value)) if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    fn node(value: &Value) -> &SlotNode<Key> {
+        &value.node
+    }
+}
+
+impl SlotOrd<Values> for Value {
+    fn slot_cmp(&self, rhs: &Value) -> Ordering {
+        self.value.cmp(&rhs.value)
+    }
+}
+
+impl SlotOrd<Values> for u8 {
+    fn slot_cmp(&self, rhs: &Value) -> Ordering {
+        self.cmp(&rhs.value)
+    }
+}
+
+fn values(tree: &SlotSplayTree<Values>, arena: &Arena) -> Vec<u8> {
+    let mut values = vec![];
+    tree.walk(arena, |_, v| values.push(v.value));
+    values
+}
+
+quickcheck! {
+    fn matches_btree_set(ops: Vec<(u8, u8)>) -> bool {
+        let mut arena = Arena::default();
+        let mut tree = SlotSplayTree::<Values>::new();
+        let mut model = BTreeSet::new();
+
+        for (op, x) in ops {
+            let x = x % 32;
+            let value = |key: Option<Key>, arena: &Arena| {
+                key.map(|key| Values::elem(arena, key).unwrap().value)
+            };
+            let ok = match op % 6 {
+                0 | 1 => {
+                    let key = arena.alloc(x);
+                    let inserted = tree.insert(&arena, key);
+                    if !inserted {
+                        arena.free(key);
+                    }
+                    inserted == model.insert(x)
+                }
+                2 => {
+                    let removed = tree.remove(&arena, &x);
+                    let ok = value(removed, &arena) == model.take(&x);
+                    if let Some(key) = removed {
+                        arena.free(key);
+                    }
+                    ok
+                }
+                3 => value(tree.find(&arena, &x), &arena) == model.get(&x).copied(),
+                4 => {
+                    let removed = tree.pop_min(&arena);
+                    let ok = value(removed, &arena) == model.pop_first();
+                    if let Some(key) = removed {
+                        arena.free(key);
+                    }
+                    ok
+                }
+                _ => value(tree.max(&arena), &arena) == model.last().copied(),
+            };
+            if !ok || values(&tree, &arena) != model.iter().copied().collect::<Vec<_>>() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn walk_can_stop_early() {
+    let mut arena = Arena::default();
+    let mut tree = SlotSplayTree::<Values>::new();
+    for x in [5, 1, 4, 2, 3] {
+        let key = arena.alloc(x);
+        assert!(tree.insert(&arena, key));
+    }
+    let found = tree.walk(
+        &arena,
+        |_, v| if v.value > 2 { Some(v.value) } else { None },
+    );
+    assert_eq!(found, Some(3));
+}
+
+#[test]
+fn freeing_an_element_in_the_tree_is_caught() {
+    let mut arena = Arena::default();
+    let mut tree = SlotSplayTree::<Values>::new();
+    let keys: Vec<_> = (0..4).map(|x| arena.alloc(x)).collect();
+    for &key in &keys {
+        assert!(tree.insert(&arena, key));
+    }
+
+    // Free an element without removing it, and reuse its slot, so that only
+    // the generation tells the new element apart from the old one.
+    arena.free(keys[1]);
+    let reused = arena.alloc(1);
+    assert_eq!(reused.index, keys[1].index);
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| tree.min(&arena)));
+    assert!(result.is_err());
+}
@@ -0,0 +1,317 @@
+//! A long-running randomized soak test.
+//!
+//! Runs many millions of operations over a fixed pool of elements that are
+//! shared by several trees, and checks every tree against a model of it every
+//! so often. The number of operations and the seed can be set with the
+//! `SOAK_OPS` and `SOAK_SEED` environment variables; a failure reports the seed
+//! and the operation it failed at. By default, a release build runs twenty
+//! million operations, a debug build a tenth of that, and Miri a few thousand.
+//!
+//! ```text
+//! cargo test --release --test soak -- --ignored
+//! ```
+
+use intrusive_splay_tree::{impl_intrusive_node, Cursor, Node, SplayTree, TreeOrd, Verdict};
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::ops::Bound;
+
+const POOL: usize = 512;
+const KEYS: u32 = 384;
+const CHECK_EVERY: u64 = 4096;
+
+#[derive(Debug)]
+struct Item<'a> {
+    index: usize,
+    key: u32,
+    by_key: Node<'a>,
+    by_index: Node<'a>,
+}
+
+struct ByKey;
+struct ByIndex;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByKey
+    where
+        type Elem = Item<'a>,
+        node = by_key;
+
+    impl<'a> IntrusiveNode<'a> for ByIndex
+    where
+        type Elem = Item<'a>,
+        node = by_index;
+}
+
+impl<'a> TreeOrd<'a, ByKey> for Item<'a> {
+    fn tree_cmp(&self, rhs: &'a Item<'a>) -> Ordering {
+        self.key.cmp(&rhs.key)
+    }
+}
+
+impl<'a> TreeOrd<'a, ByKey> for u32 {
+    fn tree_cmp(&self, rhs: &'a Item<'a>) -> Ordering {
+        self.cmp(&rhs.key)
+    }
+}
+
+impl<'a> TreeOrd<'a, ByIndex> for Item<'a> {
+    fn tree_cmp(&self, rhs: &'a Item<'a>) -> Ordering {
+        self.index.cmp(&rhs.index)
+    }
+}
+
+impl<'a> TreeOrd<'a, ByIndex> for usize {
+    fn tree_cmp(&self, rhs: &'a Item<'a>) -> Ordering {
+        self.cmp(&rhs.index)
+    }
+}
+
+/// A xorshift64* generator, so that runs are reproducible from their seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// A tree ordered by key, its model, and a cursor that is kept scanning it
+/// across every operation.
+struct Keyed<'a> {
+    tree: SplayTree<'a, ByKey>,
+    model: BTreeMap<u32, usize>,
+    cursor: Cursor<'a, ByKey>,
+}
+
+impl<'a> Keyed<'a> {
+    fn new() -> Self {
+        Keyed {
+            tree: SplayTree::new(),
+            model: BTreeMap::new(),
+            cursor: Cursor::new(),
+        }
+    }
+
+    fn check(&self, name: &str) {
+        let mut actual = vec![];
+        self.tree.walk(|item| actual.push((item.key, item.index)));
+        let expected: Vec<_> = self.model.iter().map(|(&k, &i)| (k, i)).collect();
+        assert_eq!(actual, expected, "{} does not match its model", name);
+    }
+}
+
+struct Soak<'a> {
+    pool: &'a [Item<'a>],
+    rng: Rng,
+    // Each item's `by_key` node is in at most one of these at a time.
+    keyed: [Keyed<'a>; 2],
+    // Every item's `by_index` node may be in here too, independently.
+    by_index: SplayTree<'a, ByIndex>,
+    by_index_model: BTreeSet<usize>,
+}
+
+impl<'a> Soak<'a> {
+    /// Is `item`'s `by_key` node free to insert into a tree?
+    fn key_free(&self, item: &Item) -> bool {
+        self.keyed
+            .iter()
+            .all(|k| k.model.get(&item.key) != Some(&item.index))
+    }
+
+    fn step(&mut self) {
+        let which = self.rng.below(2);
+        let key = self.rng.below(KEYS as usize) as u32;
+        let item = &self.pool[self.rng.below(POOL)];
+        match self.rng.below(12) {
+            0..=2 => {
+                if self.key_free(item) {
+                    let k = &mut self.keyed[which];
+                    let expected = !k.model.contains_key(&item.key);
+                    assert_eq!(k.tree.insert(item), expected);
+                    if expected {
+                        k.model.insert(item.key, item.index);
+                    }
+                }
+            }
+            3 => {
+                let k = &mut self.keyed[which];
+                let removed = k.tree.remove(&key).map(|i| i.index);
+                assert_eq!(removed, k.model.remove(&key));
+            }
+            4 => {
+                let k = &mut self.keyed[which];
+                let found = k.tree.find(&key).map(|i| i.index);
+                assert_eq!(found, k.model.get(&key).copied());
+            }
+            5 => {
+                let k = &mut self.keyed[which];
+                let (popped, expected) = if key.is_multiple_of(2) {
+                    (k.tree.pop_min(), k.model.pop_first())
+                } else {
+                    (k.tree.pop_max(), k.model.pop_last())
+                };
+                assert_eq!(popped.map(|i| i.index), expected.map(|(_, i)| i));
+            }
+            6 => {
+                let k = &mut self.keyed[which];
+                let last = k.cursor.current().map(|i| i.key);
+                let expected = match last {
+                    None => k.model.iter().next(),
+                    Some(last) => k
+                        .model
+                        .range((Bound::Excluded(last), Bound::Unbounded))
+                        .next(),
+                };
+                let next = k.cursor.next(&mut k.tree).map(|i| i.index);
+                assert_eq!(next, expected.map(|(_, &i)| i));
+                if next.is_none() {
+                    k.cursor = Cursor::new();
+                }
+            }
+            7 => {
+                let [a, b] = &mut self.keyed;
+                let (into, from) = if which == 0 { (a, b) } else { (b, a) };
+                let mut moved = 0;
+                for (&k, &i) in &from.model {
+                    if let Entry::Vacant(e) = into.model.entry(k) {
+                        e.insert(i);
+                        moved += 1;
+                    }
+                }
+                from.model.retain(|k, i| into.model.get(k) != Some(i));
+                assert_eq!(into.tree.extend_by_draining(&mut from.tree), moved);
+            }
+            8 => {
+                let k = &mut self.keyed[which];
+                let salt = self.rng.next() as u32;
+                let mut expected = 0;
+                k.model.retain(|&x, _| {
+                    let remove = x <= key && (x ^ salt).is_multiple_of(3);
+                    expected += remove as usize;
+                    !remove
+                });
+                let removed = k.tree.walk_and_remove(|i| {
+                    if i.key > key {
+                        Verdict::Stop
+                    } else if (i.key ^ salt).is_multiple_of(3) {
+                        Verdict::Remove
+                    } else {
+                        Verdict::Keep
+                    }
+                });
+                assert_eq!(removed, expected);
+            }
+            9 => {
+                // Move the smallest few elements of one tree into the other,
+                // and put back the ones the other already has keys for.
+                let n = self.rng.below(8);
+                let [a, b] = &mut self.keyed;
+                let (from, into) = if which == 0 { (a, b) } else { (b, a) };
+                let mut taken = from.tree.take_smallest(n);
+                let taken_model: Vec<_> = (0..n).filter_map(|_| from.model.pop_first()).collect();
+                into.tree.extend_by_draining(&mut taken);
+                for (k, i) in taken_model {
+                    match into.model.entry(k) {
+                        Entry::Occupied(_) => {
+                            from.model.insert(k, i);
+                        }
+                        Entry::Vacant(e) => {
+                            e.insert(i);
+                        }
+                    }
+                }
+                while let Some(item) = taken.pop_min() {
+                    assert!(from.tree.insert(item));
+                }
+            }
+            10 => {
+                if self.by_index_model.insert(item.index) {
+                    assert!(self.by_index.insert(item));
+                }
+            }
+            _ => {
+                let index = item.index;
+                let removed = self.by_index.remove(&index).map(|i| i.index);
+                assert_eq!(removed.is_some(), self.by_index_model.remove(&index));
+            }
+        }
+    }
+
+    fn check(&self) {
+        self.keyed[0].check("the first keyed tree");
+        self.keyed[1].check("the second keyed tree");
+        let mut actual = vec![];
+        self.by_index.walk(|item| actual.push(item.index));
+        assert!(actual
+            .iter()
+            .copied()
+            .eq(self.by_index_model.iter().copied()));
+    }
+}
+
+#[test]
+#[ignore = "long-running; run with `--ignored` in release mode"]
+fn soak() {
+    let ops: u64 = match env::var("SOAK_OPS") {
+        Ok(ops) => ops.parse().expect("SOAK_OPS should be a number"),
+        Err(_) if cfg!(miri) => 2_000,
+        Err(_) if cfg!(debug_assertions) => 2_000_000,
+        Err(_) => 20_000_000,
+    };
+    let seed: u64 = match env::var("SOAK_SEED") {
+        Ok(seed) => seed.parse().expect("SOAK_SEED should be a number"),
+        Err(_) => 0x5eed_5eed_5eed_5eed,
+    };
+
+    let mut rng = Rng(seed | 1);
+    let pool: Vec<_> = (0..POOL)
+        .map(|index| Item {
+            index,
+            key: rng.below(KEYS as usize) as u32,
+            by_key: Node::default(),
+            by_index: Node::default(),
+        })
+        .collect();
+
+    let mut soak = Soak {
+        pool: &pool,
+        rng,
+        keyed: [Keyed::new(), Keyed::new()],
+        by_index: SplayTree::new(),
+        by_index_model: BTreeSet::new(),
+    };
+
+    for op in 0..ops {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            soak.step();
+            if op % CHECK_EVERY == 0 {
+                soak.check();
+            }
+        }));
+        if let Err(e) = result {
+            eprintln!("soak failed at operation {} with SOAK_SEED={}", op, seed);
+            std::panic::resume_unwind(e);
+        }
+    }
+    soak.check();
+
+    // Every node should be unlinked once every tree is emptied.
+    for k in &mut soak.keyed {
+        while k.tree.pop_root().is_some() {}
+    }
+    while soak.by_index.pop_root().is_some() {}
+    #[cfg(feature = "linked")]
+    assert!(pool
+        .iter()
+        .all(|item| !item.by_key.is_linked() && !item.by_index.is_linked()));
+}
@@ -1,7 +1,12 @@
 mod single;
 
-use intrusive_splay_tree::SplayTree;
+use intrusive_splay_tree::{
+    F64TotalOrd, IntrusiveNode, IntrusiveSerial, Node, Serial32, ShardKey, ShardedSplayTree,
+    SplayMapAdapter, SplayTree, TreeOrd, Verdict,
+};
 use single::{Single, SingleTree};
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::panic;
 
 #[test]
@@ -23,3 +28,1237 @@ fn inserting_already_inserted_panics_in_debug() {
     }));
     assert!(result.is_err());
 }
+
+#[test]
+fn removed_nodes_have_null_links() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        tree.insert(e);
+    }
+
+    for value in [2, 0, 3] {
+        let node = SingleTree::elem_to_node(tree.remove(&value).unwrap());
+        assert!(node.left().is_none() && node.right().is_none());
+    }
+    let node = SingleTree::elem_to_node(tree.pop_min().unwrap());
+    assert!(node.left().is_none() && node.right().is_none());
+}
+
+#[test]
+#[cfg(all(feature = "linked", not(feature = "paranoid"), debug_assertions))]
+fn traversing_a_removed_node_panics_in_debug() {
+    let elems: Vec<_> = (0..2).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        tree.insert(e);
+    }
+
+    // Wrongly share the leaf with another tree, and remove it from there.
+    let mut other = SplayTree::<SingleTree>::new();
+    assert!(other.insert(&elems[0]));
+    assert!(other.remove(&0).is_some());
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| tree.walk(|_| {})));
+    assert!(result.is_err());
+}
+
+intrusive_splay_tree::declare_multi_index! {
+    struct Sample<'a> {
+        value: F64TotalOrd,
+    }
+
+    index SamplesByValue(node) by value: F64TotalOrd;
+}
+
+#[test]
+fn float_keys_with_nans_stay_ordered() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = SplayTree::<SamplesByValue>::new();
+
+    let values = [1.5, f64::NAN, -0.0, 0.0, -f64::NAN, f64::INFINITY, -3.0];
+    for v in values {
+        assert!(tree.insert(arena.alloc(Sample::new(F64TotalOrd(v)))));
+    }
+
+    assert!(tree.find(&F64TotalOrd(f64::NAN)).is_some());
+    assert!(tree.find(&F64TotalOrd(1.5)).is_some());
+    assert!(tree.find(&F64TotalOrd(2.0)).is_none());
+
+    let mut sorted = vec![];
+    tree.walk(|s| sorted.push(s.value.get()));
+    let mut expected = values.to_vec();
+    expected.sort_by(f64::total_cmp);
+    assert_eq!(
+        sorted.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+        expected.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+    );
+}
+
+struct Segment<'a> {
+    seq: Serial32,
+    node: Node<'a>,
+}
+
+impl<'a> Segment<'a> {
+    fn new(seq: Serial32) -> Self {
+        Segment {
+            seq,
+            node: Node::default(),
+        }
+    }
+}
+
+struct SegmentsBySeq;
+
+intrusive_splay_tree::impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for SegmentsBySeq
+    where
+        type Elem = Segment<'a>,
+        node = node;
+}
+
+impl<'a> IntrusiveSerial<'a> for SegmentsBySeq {
+    type Serial = Serial32;
+
+    fn serial(segment: &'a Segment<'a>) -> Serial32 {
+        segment.seq
+    }
+}
+
+impl<'a> TreeOrd<'a, SegmentsBySeq> for Segment<'a> {
+    fn tree_cmp(&self, rhs: &'a Segment<'a>) -> Ordering {
+        self.seq.serial_cmp(rhs.seq)
+    }
+}
+
+#[test]
+fn serial_keys_stay_ordered_across_wraparound() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = SplayTree::<SegmentsBySeq>::new();
+
+    // A sliding window of sequence numbers that crosses `u32::MAX`.
+    let start = Serial32(u32::MAX - 5);
+    for i in 0..8 {
+        assert!(tree.insert(arena.alloc(Segment::new(start.wrapping_add(i)))));
+    }
+    for i in 8..12 {
+        assert_eq!(tree.pop_min().unwrap().seq, start.wrapping_add(i - 8));
+        assert!(tree.insert(arena.alloc(Segment::new(start.wrapping_add(i)))));
+    }
+
+    let mut seqs = vec![];
+    tree.walk(|s| seqs.push(s.seq.get()));
+    assert_eq!(seqs, [u32::MAX - 1, u32::MAX, 0, 1, 2, 3, 4, 5]);
+    assert!(tree.find(&Serial32(0)).is_some());
+    assert_eq!(Serial32(u32::MAX).serial_cmp(Serial32(0)), Ordering::Less);
+    assert_eq!(Serial32(1 << 30).serial_cmp(Serial32(0)), Ordering::Greater);
+    assert_eq!(Serial32(1 << 31).serial_cmp(Serial32(0)), Ordering::Less);
+    assert_eq!(Serial32(0).serial_cmp(Serial32(1 << 31)), Ordering::Less);
+}
+
+#[test]
+fn insert_unchecked_reuses_removed_elements() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let dup = Single::new(2);
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        assert!(unsafe { tree.insert_unchecked(e) });
+    }
+    assert!(!unsafe { tree.insert_unchecked(&dup) });
+
+    // `insert_unchecked` overwrites whatever links the removed elements have.
+    let (a, b) = (tree.pop_min().unwrap(), tree.pop_max().unwrap());
+    assert!(unsafe { tree.insert_unchecked(b) });
+    assert!(unsafe { tree.insert_unchecked(a) });
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [0, 1, 2, 3]);
+}
+
+#[test]
+fn transactions_undo_their_changes_unless_committed() {
+    let elems: Vec<_> = (0..6).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems[..3] {
+        tree.insert(e);
+    }
+
+    {
+        let mut tx = tree.transaction::<3>();
+        assert_eq!(tx.remove(&1).unwrap().unwrap().value, 1);
+        assert_eq!(tx.insert(&elems[1]), Ok(true));
+        assert_eq!(tx.insert(&elems[4]), Ok(true));
+        assert!(tx.is_full());
+        assert!(tx.insert(&elems[5]).is_err());
+        assert!(tx.remove(&0).is_err());
+        // Dropping the transaction rolls it back.
+    }
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [0, 1, 2]);
+
+    let mut tx = tree.transaction::<2>();
+    tx.remove(&0).unwrap();
+    tx.insert(&elems[3]).unwrap();
+    tx.commit();
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [1, 2, 3]);
+}
+
+#[test]
+fn get_and_index_do_not_splay() {
+    let elems: Vec<_> = (0..8).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        tree.insert(e);
+    }
+
+    let root = tree.root().unwrap().value;
+    assert_eq!(tree.get(&2).unwrap().value, 2);
+    assert!(tree.get(&8).is_none());
+    assert_eq!(tree[&5].value, 5);
+    assert_eq!(tree.root().unwrap().value, root);
+}
+
+#[test]
+#[should_panic(expected = "no element found for key")]
+fn indexing_a_missing_key_panics() {
+    let elem = Single::new(1);
+    let mut tree = SplayTree::<SingleTree>::new();
+    tree.insert(&elem);
+    let _ = &tree[&2];
+}
+
+#[test]
+fn insert_scoped_removes_on_drop() {
+    let (a, b, a2) = (Single::new(1), Single::new(2), Single::new(1));
+    let mut tree = SplayTree::<SingleTree>::new();
+    assert!(tree.insert(&b));
+
+    {
+        let mut guard = tree.insert_scoped(&a).unwrap();
+        assert_eq!(guard.find(&1).map(|s| s as *const _), Some(&a as *const _));
+        assert!(guard.insert_scoped(&a2).is_none());
+    }
+    assert!(tree.find(&1).is_none());
+    assert!(tree.find(&2).is_some());
+
+    // Only the guard's own element is removed, even if it was replaced.
+    {
+        let mut guard = tree.insert_scoped(&a).unwrap();
+        assert!(guard.remove(&1).is_some());
+        assert!(guard.insert(&a2));
+    }
+    assert_eq!(tree.find(&1).map(|s| s as *const _), Some(&a2 as *const _));
+    assert!(tree.remove(&1).is_some());
+
+    let kept = tree.insert_scoped(&a).unwrap().keep();
+    assert_eq!(kept.value, 1);
+    assert!(tree.find(&1).is_some());
+}
+
+#[test]
+fn insert_scoped_removes_on_panic() {
+    let a = Single::new(1);
+    let mut tree = SplayTree::<SingleTree>::new();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _guard = tree.insert_scoped(&a).unwrap();
+        panic!("early exit");
+    }));
+    assert!(result.is_err());
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn update_key_reorders() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    let elem = tree.update_key(&4, |_| {}).unwrap().unwrap();
+    assert_eq!(elem.value, 4);
+    assert!(tree.update_key(&42, |_| panic!("not called")).is_none());
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn remove_if_and_pop_root_if() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    assert!(tree.remove_if(&3, |s| s.value % 2 == 0).is_none());
+    assert_eq!(tree.root().map(|s| s.value), Some(3));
+    assert_eq!(
+        tree.remove_if(&4, |s| s.value % 2 == 0).map(|s| s.value),
+        Some(4)
+    );
+    assert!(tree.find(&4).is_none());
+    assert!(tree.remove_if(&42, |_| panic!("not called")).is_none());
+
+    assert!(tree.pop_root_if(|_| false).is_none());
+    let root = tree.root().unwrap().value;
+    assert_eq!(tree.pop_root_if(|_| true).map(|s| s.value), Some(root));
+    assert!(tree.find(&root).is_none());
+
+    let mut empty = SplayTree::<SingleTree>::new();
+    assert!(empty.pop_root_if(|_| panic!("not called")).is_none());
+}
+
+#[test]
+fn compare_deep_trees() {
+    // Inserting in ascending order builds a tree that is one long left spine,
+    // which is deeper than comparison's iterators can track at once.
+    let arena = bumpalo::Bump::new();
+    let a: SplayTree<SingleTree> = (0..1000).map(|x| &*arena.alloc(Single::new(x))).collect();
+    let b: SplayTree<SingleTree> = (0..1000).map(|x| &*arena.alloc(Single::new(x))).collect();
+    let c: SplayTree<SingleTree> = (0..999)
+        .chain(Some(1000))
+        .map(|x| &*arena.alloc(Single::new(x)))
+        .collect();
+
+    assert!(a == b);
+    assert!(a < c);
+    assert_eq!(c.partial_cmp(&a), Some(Ordering::Greater));
+    assert!(SplayTree::<SingleTree>::new() < a);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn collect_refs_and_into_sorted_vec() {
+    let arena = bumpalo::Bump::new();
+    let tree: SplayTree<SingleTree> = [3, 1, 4, 5, 9, 2, 6]
+        .iter()
+        .map(|x| &*arena.alloc(Single::new(*x)))
+        .collect();
+
+    let values = |elems: Vec<&Single>| elems.iter().map(|s| s.value).collect::<Vec<_>>();
+    assert_eq!(values(tree.collect_refs()), [1, 2, 3, 4, 5, 6, 9]);
+
+    let elems = tree.into_sorted_vec();
+    assert_eq!(values(elems.clone()), [1, 2, 3, 4, 5, 6, 9]);
+
+    // The elements were unlinked, so they can go into another tree.
+    let mut other = SplayTree::<SingleTree>::new();
+    for elem in elems {
+        assert!(other.insert(elem));
+    }
+    assert_eq!(values(other.collect_refs()), [1, 2, 3, 4, 5, 6, 9]);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn snapshot_iter_tolerates_mutation() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    // Remove every element as it is yielded, and insert one past the end of
+    // the snapshot for each.
+    let mut seen = vec![];
+    for elem in tree.snapshot_iter() {
+        seen.push(elem.value);
+        assert!(tree.remove(elem).is_some());
+        assert!(tree.insert(arena.alloc(Single::new(elem.value + 10))));
+    }
+    assert_eq!(seen, (0..10).collect::<Vec<_>>());
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, (10..20).collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn freeze_into_vec() {
+    let arena = bumpalo::Bump::new();
+    let tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x * 2))).collect();
+
+    let mut buf = vec![];
+    let view = tree.freeze(&mut buf);
+    assert_eq!(view.len(), 10);
+    assert_eq!(view.get(&4).map(|s| s.value), Some(4));
+    assert!(view.get(&5).is_none());
+    assert_eq!(view.rank(&5), 3);
+    assert_eq!(view.range(3..9).len(), 3);
+}
+
+#[test]
+fn splay_does_not_recompare_the_root() {
+    struct Counted<'c>(usize, &'c Cell<usize>);
+
+    impl<'a> TreeOrd<'a, SingleTree<'a>> for Counted<'_> {
+        fn tree_cmp(&self, rhs: &'a Single<'a>) -> Ordering {
+            self.1.set(self.1.get() + 1);
+            self.0.cmp(&rhs.value)
+        }
+    }
+
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+    let count = Cell::new(0);
+
+    // The last insertion left 9 at the root, so finding it, or finding 10,
+    // which would be its right child, takes a single comparison.
+    assert!(tree.find(&Counted(9, &count)).is_some());
+    assert_eq!(count.replace(0), 1);
+    assert!(tree.find(&Counted(10, &count)).is_none());
+    assert_eq!(count.replace(0), 1);
+    assert!(tree.remove(&Counted(9, &count)).is_some());
+    assert_eq!(count.replace(0), 1);
+}
+
+#[test]
+fn map_adapter_projects_keys_and_values() {
+    let arena = bumpalo::Bump::new();
+    let mut map = SplayMapAdapter::<SingleTree, usize, usize>::new(|s| &s.value, |s| &s.value);
+    for x in [3, 1, 2] {
+        assert!(map.insert(arena.alloc(Single::new(x))));
+    }
+    assert!(!map.insert(arena.alloc(Single::new(2))));
+
+    assert_eq!(map.get(&2), Some(&2));
+    assert_eq!(map.get(&4), None);
+    assert!(map.contains_key(&1));
+    assert_eq!(map.get_key_value(&3), Some((&3, &3)));
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(format!("{:?}", map), "{1: 1, 2: 2, 3: 3}");
+
+    assert_eq!(map.remove(&1).map(|s| s.value), Some(1));
+    assert_eq!(map.values().copied().collect::<Vec<_>>(), [2, 3]);
+    assert_eq!(map.into_tree().min().map(|s| s.value), Some(2));
+}
+
+#[test]
+fn walk_and_remove_unlinks_as_it_goes() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..20).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    let removed = tree.walk_and_remove(|s| match s.value {
+        15 => Verdict::Stop,
+        x if x % 3 == 0 => Verdict::Remove,
+        _ => Verdict::Keep,
+    });
+    assert_eq!(removed, 5);
+
+    let mut left = vec![];
+    tree.walk(|s| left.push(s.value));
+    let expected: Vec<_> = (0..20).filter(|x| x % 3 != 0 || *x >= 15).collect();
+    assert_eq!(left, expected);
+
+    assert_eq!(tree.walk_and_remove(|_| Verdict::Remove), expected.len());
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn dedup_by_keeps_the_first_of_each_run() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..20).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    // Runs of values with the same tens digit.
+    let removed = tree.dedup_by(|kept, next| kept.value / 10 == next.value / 10);
+    let mut left = vec![];
+    tree.walk(|s| left.push(s.value));
+    assert_eq!(left, [0, 10]);
+    let mut dups = vec![];
+    removed.walk(|s| dups.push(s.value));
+    let expected: Vec<_> = (1..10).chain(11..20).collect();
+    assert_eq!(dups, expected);
+
+    assert!(tree.dedup_by(|_, _| false).is_empty());
+    let removed = tree.dedup_by(|_, _| true);
+    assert_eq!(removed.fold(0, |n, _| n + 1), 1);
+    assert_eq!(tree.root().map(|s| s.value), Some(0));
+}
+
+#[test]
+fn display_lists_elements_in_order() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = SplayTree::<SingleTree>::new();
+    assert_eq!(tree.to_string(), "");
+
+    tree.extend([3, 1, 2].map(|x| &*arena.alloc(Single::new(x))));
+    assert_eq!(tree.to_string(), "1, 2, 3");
+    assert_eq!(format!("{:02}", tree.display_separated("-")), "01-02-03");
+}
+
+#[test]
+fn iter_ptrs_yields_elements_in_order() {
+    let arena = bumpalo::Bump::new();
+    let elems: Vec<&Single> = [4, 0, 2]
+        .iter()
+        .map(|&x| &*arena.alloc(Single::new(x)))
+        .collect();
+    let mut tree: SplayTree<SingleTree> = elems.iter().copied().collect();
+    tree.find(&2);
+
+    let ptrs: Vec<_> = tree.iter_ptrs().collect();
+    let expected: Vec<*const Single> = [1, 2, 0].iter().map(|&i| elems[i] as *const _).collect();
+    assert_eq!(ptrs, expected);
+    assert!(SplayTree::<SingleTree>::new().iter_ptrs().next().is_none());
+}
+
+#[test]
+fn metadata_overhead_counts_nodes_and_bytes() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..10).map(|x| &*arena.alloc(Single::new(x))).collect();
+    let empty = SplayTree::<SingleTree>::new().metadata_overhead();
+    assert_eq!(empty.nodes(), 0);
+    assert_eq!(
+        empty.total_bytes(),
+        std::mem::size_of::<SplayTree<SingleTree>>()
+    );
+
+    tree.pop_min();
+    let overhead = tree.metadata_overhead();
+    assert_eq!(overhead.nodes(), 9);
+    assert_eq!(
+        overhead.node_bytes(),
+        9 * std::mem::size_of::<intrusive_splay_tree::Node>()
+    );
+    assert_eq!((overhead + empty).nodes(), 9);
+    assert_eq!(
+        (overhead + empty).total_bytes(),
+        overhead.total_bytes() + empty.tree_bytes()
+    );
+}
+
+#[test]
+fn entries_drain_from_either_end() {
+    let arena = bumpalo::Bump::new();
+    let mut tree: SplayTree<SingleTree> = (0..6).map(|x| &*arena.alloc(Single::new(x))).collect();
+
+    assert_eq!(tree.first_entry().unwrap().get().value, 0);
+    assert_eq!(tree.last_entry().unwrap().remove().value, 5);
+
+    let mut drained = vec![];
+    let mut entry = tree.last_entry();
+    while let Some(e) = entry {
+        let (elem, next) = e.remove_and_next();
+        drained.push(elem.value);
+        entry = next;
+    }
+    assert_eq!(drained, [4, 3, 2, 1, 0]);
+    assert!(tree.first_entry().is_none());
+}
+
+impl<'a> ShardKey<'a, SingleTree<'a>> for Single<'a> {
+    fn shard(&self) -> usize {
+        self.value.shard()
+    }
+}
+
+impl<'a> ShardKey<'a, SingleTree<'a>> for usize {
+    fn shard(&self) -> usize {
+        self.wrapping_mul(0x9e37_79b9)
+    }
+}
+
+#[test]
+fn sharded_tree_merges_its_shards() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = ShardedSplayTree::<SingleTree, 3>::new();
+    for x in [5, 1, 9, 3, 7, 0, 8] {
+        assert!(tree.insert(arena.alloc(Single::new(x))));
+    }
+    assert!(!tree.insert(arena.alloc(Single::new(3))));
+
+    assert_eq!(tree.find(&7).unwrap().value, 7);
+    assert!(tree.find(&4).is_none());
+    assert_eq!(tree.min().unwrap().value, 0);
+    assert_eq!(tree.max().unwrap().value, 9);
+    assert_eq!(tree.remove(&9).unwrap().value, 9);
+    assert_eq!(tree.max().unwrap().value, 8);
+
+    let values: Vec<_> = tree.iter().map(|s| s.value).collect();
+    assert_eq!(values, [0, 1, 3, 5, 7, 8]);
+}
+
+/// Build a tree by ascending inserts, which leaves it a single path of
+/// `len` nodes.
+fn ascending_tree<'a>(arena: &'a bumpalo::Bump, len: usize) -> SplayTree<'a, SingleTree<'a>> {
+    let mut tree = SplayTree::new();
+    for x in 0..len {
+        assert!(tree.insert(arena.alloc(Single::new(x))));
+    }
+    tree
+}
+
+#[test]
+fn take_from_a_path_without_recursing() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = ascending_tree(&arena, 1_000_000);
+    let mut smallest = tree.take_smallest(4);
+    let mut largest = tree.take_largest(2);
+    assert_eq!(smallest.pop_max().unwrap().value, 3);
+    assert_eq!(largest.pop_min().unwrap().value, 999_998);
+    assert_eq!(tree.min().unwrap().value, 4);
+    assert_eq!(tree.max().unwrap().value, 999_997);
+}
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn allocate_and_insert_in_one_step() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = SplayTree::<SingleTree>::from_iter_in(&arena, [3, 1, 2, 1].map(Single::new));
+    assert_eq!(
+        tree.insert_with_alloc(&arena, Single::new(0))
+            .unwrap()
+            .value,
+        0
+    );
+    assert!(tree.insert_with_alloc(&arena, Single::new(2)).is_none());
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [0, 1, 2, 3]);
+}
+
+#[cfg(feature = "typed-arena")]
+#[test]
+fn allocate_in_a_typed_arena() {
+    let arena = typed_arena::Arena::new();
+    let mut tree = SplayTree::<SingleTree>::from_iter_in(&arena, [3, 1, 2, 1].map(Single::new));
+    assert_eq!(
+        tree.insert_with_alloc(&arena, Single::new(0))
+            .unwrap()
+            .value,
+        0
+    );
+    assert!(tree.insert_with_alloc(&arena, Single::new(2)).is_none());
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [0, 1, 2, 3]);
+}
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn interned_strings_are_shared() {
+    use intrusive_splay_tree::InternSet;
+    use std::ptr;
+
+    let arena = bumpalo::Bump::new();
+    let mut strings = InternSet::new(&arena);
+    assert!(strings.is_empty());
+    assert_eq!(strings.get("b"), None);
+
+    let words = ["b", "a", "c", "a", "b"].map(|w| strings.intern(w));
+    assert!(ptr::eq(words[0], words[4]));
+    assert!(ptr::eq(words[1], words[3]));
+    assert!(!ptr::eq(words[0], words[1]));
+    assert!(strings.get("c").is_some_and(|c| ptr::eq(c, words[2])));
+
+    let mut all = vec![];
+    strings.for_each(|s| all.push(s));
+    assert_eq!(all, ["a", "b", "c"]);
+    assert_eq!(format!("{:?}", strings), r#"{"a", "b", "c"}"#);
+}
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn owned_tree_has_no_lifetime() {
+    use intrusive_splay_tree::{OwnedSplayTree, TreeFamily};
+
+    struct Singles;
+
+    impl TreeFamily for Singles {
+        type Tree<'a> = SingleTree<'a>;
+    }
+
+    fn build() -> OwnedSplayTree<Singles> {
+        let mut tree = OwnedSplayTree::new();
+        for x in [4, 2, 6] {
+            assert!(tree.insert(Single::new(x)));
+        }
+        assert!(!tree.insert(Single::new(2)));
+        tree
+    }
+
+    let mut tree = Box::new(build());
+    assert_eq!(tree.find(&4, |s| s.value * 10), Some(40));
+    assert!(tree.remove(&4));
+    assert!(!tree.contains(&4));
+    let values = tree.with(|tree, arena| {
+        tree.insert(arena.alloc(Single::new(5)));
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        values
+    });
+    assert_eq!(values, [2, 5, 6]);
+}
+
+#[test]
+fn comparator_tree_uses_runtime_order() {
+    use intrusive_splay_tree::{Comparator, ComparatorTree};
+
+    /// Orders values by their position in a table that is built at runtime.
+    struct Ranked(Vec<usize>);
+
+    impl<'a> Comparator<'a, SingleTree<'a>> for Ranked {
+        fn compare(&self, key: &Single<'a>, elem: &'a Single<'a>) -> Ordering {
+            self.compare(&key.value, elem)
+        }
+    }
+
+    impl<'a> Comparator<'a, SingleTree<'a>, usize> for Ranked {
+        fn compare(&self, key: &usize, elem: &'a Single<'a>) -> Ordering {
+            self.0[*key].cmp(&self.0[elem.value])
+        }
+    }
+
+    let arena = bumpalo::Bump::new();
+    let mut tree = ComparatorTree::<SingleTree, _>::new(Ranked(vec![2, 0, 3, 1]));
+    for x in 0..4 {
+        assert!(tree.insert(arena.alloc(Single::new(x))));
+    }
+    assert!(!tree.insert(arena.alloc(Single::new(2))));
+
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [1, 3, 0, 2]);
+    assert_eq!(tree.find(&3).unwrap().value, 3);
+    assert_eq!(tree.remove(&1).unwrap().value, 1);
+    assert_eq!(tree.pop_min().unwrap().value, 3);
+    assert_eq!(tree.max().unwrap().value, 2);
+}
+
+#[test]
+fn static_set_reuses_freed_slots() {
+    use intrusive_splay_tree::{Full, StaticSplaySet, TreeFamily};
+    use std::pin::pin;
+
+    struct Singles;
+
+    impl TreeFamily for Singles {
+        type Tree<'a> = SingleTree<'a>;
+    }
+
+    let mut set = pin!(StaticSplaySet::<Singles, 3>::new());
+    for x in [4, 2, 6] {
+        assert!(set.as_mut().insert(Single::new(x)).is_ok());
+    }
+    assert!(set.is_full());
+    assert!(matches!(
+        set.as_mut().insert(Single::new(5)),
+        Err(Full(s)) if s.value == 5
+    ));
+
+    assert!(set.as_mut().remove(&4));
+    assert!(!set.as_mut().remove(&4));
+    assert_eq!(set.len(), 2);
+    assert!(set.as_mut().insert(Single::new(5)).is_ok());
+
+    // An equal value needs a free slot, but frees the old element's.
+    assert!(set.as_mut().remove(&2));
+    assert!(set.as_mut().insert(Single::new(6)).is_ok());
+    assert_eq!(set.len(), 2);
+
+    let mut values = vec![];
+    set.walk(|s| values.push(s.value));
+    assert_eq!(values, [5, 6]);
+    assert!(set.as_mut().contains(&5));
+    assert_eq!(set.as_mut().min(|s| s.value), Some(5));
+    assert_eq!(set.as_mut().find(&6, |s| s.value), Some(6));
+    assert_eq!(set.as_mut().max(|s| s.value), Some(6));
+}
+
+#[test]
+fn static_set_in_a_static() {
+    use intrusive_splay_tree::{StaticElems, StaticSplaySet, TreeFamily};
+    use std::pin::Pin;
+
+    struct Singles;
+
+    impl TreeFamily for Singles {
+        type Tree<'a> = SingleTree<'a>;
+    }
+
+    // Safety: the set starts out empty, so it shares nothing.
+    static SET: StaticElems<StaticSplaySet<Singles, 4>> =
+        unsafe { StaticElems::new(StaticSplaySet::new()) };
+
+    let mut set = Pin::static_mut(SET.claim_mut().unwrap());
+    for x in [3, 1, 2] {
+        assert!(set.as_mut().insert(Single::new(x)).is_ok());
+    }
+    assert!(set.as_mut().remove(&1));
+    assert_eq!(set.as_mut().min(|s| s.value), Some(2));
+    assert_eq!(set.len(), 2);
+    assert!(SET.claim_mut().is_none());
+    assert!(SET.claim().is_none());
+}
+
+#[test]
+fn intrusive_node_through_nested_fields() {
+    use intrusive_splay_tree::{impl_intrusive_node, Node};
+
+    #[derive(Default)]
+    struct Header<'a> {
+        id: u32,
+        by_id: Node<'a>,
+    }
+
+    #[derive(Default)]
+    struct Packet<'a> {
+        payload: [u8; 3],
+        header: Header<'a>,
+    }
+
+    struct PacketsById;
+
+    impl_intrusive_node! {
+        impl<'a> IntrusiveNode<'a> for PacketsById
+        where
+            type Elem = Packet<'a>,
+            node = header.by_id;
+    }
+
+    impl<'a> TreeOrd<'a, PacketsById> for Packet<'a> {
+        fn tree_cmp(&self, rhs: &'a Packet<'a>) -> Ordering {
+            self.header.id.cmp(&rhs.header.id)
+        }
+    }
+
+    let packets = [3, 1, 2].map(|id| Packet {
+        payload: [id as u8; 3],
+        header: Header {
+            id,
+            ..Header::default()
+        },
+    });
+    let mut tree = SplayTree::<PacketsById>::new();
+    for packet in &packets {
+        tree.insert(packet);
+    }
+    let min = tree.min().unwrap();
+    assert!(std::ptr::eq(min, &packets[1]));
+    assert_eq!(min.payload, [1; 3]);
+}
+
+#[test]
+fn tree_ord_by_field_with_generics() {
+    use intrusive_splay_tree::{impl_intrusive_node, impl_tree_ord_by_field, Node};
+    use std::marker::PhantomData;
+
+    struct Entry<'a, T> {
+        id: u32,
+        payload: T,
+        node: Node<'a>,
+    }
+
+    struct ById<'a, T>(PhantomData<&'a T>);
+
+    impl_intrusive_node! {
+        impl<'a, T> IntrusiveNode<'a> for ById<'a, T>
+        where
+            T: 'a,
+            type Elem = Entry<'a, T>,
+            node = node;
+    }
+
+    impl_tree_ord_by_field! {
+        impl<'a, T> TreeOrd<'a, ById<'a, T>> for Entry<'a, T>
+        where
+            T: 'a,
+            field = id: u32;
+    }
+
+    let entries = [(2, "b"), (3, "c"), (1, "a")].map(|(id, payload)| Entry {
+        id,
+        payload,
+        node: Node::default(),
+    });
+    let mut tree = SplayTree::<ById<&str>>::new();
+    for entry in &entries {
+        assert!(tree.insert(entry));
+    }
+    assert_eq!(tree.min().unwrap().payload, "a");
+    assert_eq!(tree.find(&3).unwrap().payload, "c");
+}
+
+#[test]
+fn tree_key_with_generics() {
+    use intrusive_splay_tree::{impl_intrusive_node, impl_tree_key, Node};
+    use std::marker::PhantomData;
+
+    struct Entry<'a, T> {
+        header: Header,
+        payload: T,
+        node: Node<'a>,
+    }
+
+    struct Header {
+        name: String,
+    }
+
+    struct ByName<'a, T>(PhantomData<&'a T>);
+
+    impl_intrusive_node! {
+        impl<'a, T> IntrusiveNode<'a> for ByName<'a, T>
+        where
+            T: 'a,
+            type Elem = Entry<'a, T>,
+            node = node;
+    }
+
+    impl_tree_key! {
+        impl<'a, T> TreeKey<'a> for ByName<'a, T>
+        where
+            T: 'a,
+            type Elem = Entry<'a, T>,
+            key = header.name: String;
+    }
+
+    let entries = [("b", 2), ("c", 3), ("a", 1)].map(|(name, payload)| Entry {
+        header: Header { name: name.into() },
+        payload,
+        node: Node::default(),
+    });
+    let mut tree = SplayTree::<ByName<u32>>::new();
+    for entry in &entries {
+        assert!(tree.insert(entry));
+    }
+    assert_eq!(tree.min().unwrap().payload, 1);
+    assert_eq!(tree.find("c").unwrap().payload, 3);
+}
+
+#[test]
+#[cfg(feature = "rank")]
+fn sequence_with_generics_needs_no_tree_ord() {
+    use intrusive_splay_tree::{impl_intrusive_node, Node, SplaySequence};
+    use std::marker::PhantomData;
+
+    struct Entry<'a, T> {
+        payload: T,
+        node: Node<'a>,
+    }
+
+    struct Entries<'a, T>(PhantomData<&'a T>);
+
+    impl_intrusive_node! {
+        impl<'a, T> IntrusiveNode<'a> for Entries<'a, T>
+        where
+            T: 'a,
+            type Elem = Entry<'a, T>,
+            node = node;
+    }
+
+    let entries = ["b", "c", "a"].map(|payload| Entry {
+        payload,
+        node: Node::default(),
+    });
+    let mut seq = SplaySequence::<Entries<&str>>::new();
+    for entry in &entries {
+        seq.push_front(entry);
+    }
+    assert_eq!(seq.get(0).unwrap().payload, "a");
+    assert_eq!(seq.get(2).unwrap().payload, "b");
+}
+
+#[test]
+fn unsized_elements() {
+    use intrusive_splay_tree::Node;
+    use std::mem;
+
+    #[repr(C)]
+    struct Record<'a, D: ?Sized = [u8]> {
+        len: usize,
+        node: Node<'a>,
+        data: D,
+    }
+
+    fn record<'a, const N: usize>(data: [u8; N]) -> Record<'a, [u8; N]> {
+        Record {
+            len: N,
+            node: Node::new(),
+            data,
+        }
+    }
+
+    struct Records;
+
+    unsafe impl<'a> IntrusiveNode<'a> for Records {
+        type Elem = Record<'a>;
+
+        fn elem_to_node(elem: &'a Record<'a>) -> &'a Node<'a> {
+            &elem.node
+        }
+
+        unsafe fn node_to_elem(node: &'a Node<'a>) -> &'a Record<'a> {
+            let offset = mem::offset_of!(Record<'a>, node);
+            let header = (node as *const Node<'a>).cast::<u8>().wrapping_sub(offset);
+            let len = *header.cast::<usize>();
+            &*(std::ptr::slice_from_raw_parts(header, len) as *const Record<'a>)
+        }
+    }
+
+    impl<'a> TreeOrd<'a, Records> for Record<'a> {
+        fn tree_cmp(&self, rhs: &'a Record<'a>) -> Ordering {
+            self.data.cmp(&rhs.data)
+        }
+    }
+
+    impl<'a> TreeOrd<'a, Records> for [u8] {
+        fn tree_cmp(&self, rhs: &'a Record<'a>) -> Ordering {
+            self.cmp(&rhs.data)
+        }
+    }
+
+    let (a, b, c, dup) = (
+        record(*b"pear"),
+        record(*b"fig"),
+        record(*b"banana"),
+        record(*b"fig"),
+    );
+    let records: [&Record; 3] = [&a, &b, &c];
+    let mut tree = SplayTree::<Records>::new();
+    for r in records {
+        assert!(tree.insert(r));
+    }
+    assert!(!tree.insert(&dup));
+
+    assert_eq!(tree.find(&b"fig"[..]).unwrap().data, *b"fig");
+    assert_eq!(tree.min().unwrap().data, *b"banana");
+    assert_eq!(tree.remove(&b"pear"[..]).unwrap().data.len(), 4);
+
+    let mut all = vec![];
+    tree.walk(|r| all.push(&r.data));
+    assert_eq!(all, [&b"banana"[..], b"fig"]);
+}
+
+/// A key that counts how many elements it is compared with, which for `get`
+/// is one more than the depth of the element it finds.
+struct Counted<'c>(usize, &'c Cell<usize>);
+
+impl<'a> TreeOrd<'a, SingleTree<'a>> for Counted<'_> {
+    fn tree_cmp(&self, elem: &'a Single<'a>) -> Ordering {
+        self.1.set(self.1.get() + 1);
+        self.0.cmp(&elem.value)
+    }
+}
+
+/// The most comparisons that `get` needs to find any element.
+fn deepest_get<'a>(tree: &SplayTree<'a, SingleTree<'a>>, n: usize) -> usize {
+    (0..n)
+        .map(|x| {
+            let comparisons = Cell::new(0);
+            assert_eq!(tree.get(&Counted(x, &comparisons)).unwrap().value, x);
+            comparisons.get()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn rebalance_minimizes_height() {
+    for n in [0, 1, 2, 3, 7, 8, 100, 127] {
+        let elems: Vec<_> = (0..n).map(Single::new).collect();
+        let mut tree = SplayTree::<SingleTree>::new();
+        for e in &elems {
+            tree.insert(e);
+        }
+        tree.rebalance();
+
+        let height = usize::BITS - n.leading_zeros();
+        assert_eq!(deepest_get(&tree, n), height as usize);
+        let mut values = vec![];
+        tree.walk(|s| values.push(s.value));
+        assert_eq!(values, (0..n).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn from_sorted_builds_a_balanced_tree() {
+    use intrusive_splay_tree::Error;
+
+    let elems: Vec<_> = (0..100).map(Single::new).collect();
+    let tree = SplayTree::<SingleTree>::from_sorted(&elems).unwrap();
+    assert_eq!(deepest_get(&tree, 100), 7);
+    assert_eq!(tree.validate(), Ok(()));
+
+    let elems: Vec<_> = [2, 0, 1, 3].map(Single::new).into();
+    let tree = SplayTree::<SingleTree>::from_sorted(&elems).unwrap();
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, [0, 1, 2, 3]);
+
+    let dups: Vec<_> = [0, 1, 1].map(Single::new).into();
+    let result = SplayTree::<SingleTree>::from_sorted(&dups);
+    assert_eq!(result.err(), Some(Error::Duplicate));
+}
+
+#[test]
+fn copy_into_builds_a_balanced_copy() {
+    use intrusive_splay_tree::Error;
+
+    let arena = bumpalo::Bump::new();
+    let tree: SplayTree<SingleTree> = (0..100).map(|x| &*arena.alloc(Single::new(x))).collect();
+    let copy = tree
+        .copy_into(|s| arena.alloc(Single::new(s.value)))
+        .unwrap();
+    assert_eq!(deepest_get(&copy, 100), 7);
+
+    // A copy that compares equal to an earlier one is reported, not dropped.
+    let result = tree.copy_into(|s| arena.alloc(Single::new(s.value / 2)));
+    assert_eq!(result.err(), Some(Error::Duplicate));
+}
+
+#[cfg(feature = "watchdog")]
+#[test]
+fn depth_limit_rebalances_after_deep_splays() {
+    let elems: Vec<_> = (0..1000).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    tree.set_depth_limit(Some(32));
+    assert_eq!(tree.depth_limit(), Some(32));
+
+    // Inserting in order builds a path, which finding the minimum only halves.
+    for e in &elems {
+        tree.insert(e);
+    }
+    assert_eq!(tree.find(&0).unwrap().value, 0);
+    assert_eq!(tree.root().unwrap().value, 0);
+    assert!(deepest_get(&tree, elems.len()) <= 11);
+
+    tree.set_depth_limit(None);
+    assert_eq!(tree.depth_limit(), None);
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    assert_eq!(values, (0..1000).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "watchdog")]
+#[test]
+fn drained_tree_keeps_its_depth_limit() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let dup = Single::new(2);
+    let mut tree = SplayTree::<SingleTree>::new();
+    tree.insert(&dup);
+    let mut other = SplayTree::<SingleTree>::new();
+    other.set_depth_limit(Some(8));
+    for e in &elems {
+        other.insert(e);
+    }
+
+    assert_eq!(tree.extend_by_draining(&mut other), 3);
+    assert_eq!(other.depth_limit(), Some(8));
+    assert_eq!(other.root().unwrap().value, 2);
+    assert_eq!(tree.depth_limit(), None);
+}
+
+#[test]
+fn try_insert_reports_misuse_without_panicking() {
+    use intrusive_splay_tree::Error;
+
+    let elems: Vec<_> = (0..3).map(Single::new).collect();
+    let dup = Single::new(1);
+    let mut tree = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        assert_eq!(tree.try_insert(e), Ok(()));
+    }
+    assert_eq!(tree.try_insert(&dup), Err(Error::Duplicate));
+
+    // Looking for the duplicate splayed its twin, which has a child on either
+    // side, to the root. The root, and any node with children, are always
+    // caught, even in release builds and without the `linked` feature.
+    assert_eq!(tree.root().unwrap().value, 1);
+    assert_eq!(tree.try_insert(&elems[1]), Err(Error::AlreadyLinked));
+    let mut other = SplayTree::<SingleTree>::new();
+    assert_eq!(other.try_insert(&elems[1]), Err(Error::AlreadyLinked));
+
+    // Once removed, an element can be inserted again.
+    assert_eq!(tree.remove(&1).unwrap().value, 1);
+    assert_eq!(other.try_insert(&elems[1]), Ok(()));
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(other.validate(), Ok(()));
+}
+
+#[test]
+fn validate_reports_corruption() {
+    use intrusive_splay_tree::{impl_intrusive_node, Error, Node};
+
+    struct Task<'a> {
+        priority: Cell<u32>,
+        node: Node<'a>,
+    }
+
+    struct Tasks;
+
+    impl_intrusive_node! {
+        impl<'a> IntrusiveNode<'a> for Tasks
+        where
+            type Elem = Task<'a>,
+            node = node;
+    }
+
+    impl<'a> TreeOrd<'a, Tasks> for Task<'a> {
+        fn tree_cmp(&self, rhs: &'a Task<'a>) -> Ordering {
+            self.priority.get().cmp(&rhs.priority.get())
+        }
+    }
+
+    let tasks: Vec<_> = (0..8)
+        .map(|p| Task {
+            priority: Cell::new(p),
+            node: Node::new(),
+        })
+        .collect();
+    let mut tree = SplayTree::<Tasks>::new();
+    assert_eq!(tree.validate(), Ok(()));
+    for task in &tasks {
+        tree.insert(task);
+    }
+    tree.find(&tasks[3]);
+    assert_eq!(tree.validate(), Ok(()));
+
+    // Changing a key in place, instead of with `update_key`, leaves the task
+    // out of order.
+    tasks[5].priority.set(1);
+    assert_eq!(tree.validate(), Err(Error::Corrupt));
+    tasks[5].priority.set(5);
+    assert_eq!(tree.validate(), Ok(()));
+}
+
+#[test]
+fn validate_checks_a_path_without_recursing() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = ascending_tree(&arena, 1_000_000);
+    assert_eq!(tree.validate(), Ok(()));
+    tree.find(&500_000);
+    assert_eq!(tree.validate(), Ok(()));
+    tree.rebalance();
+    assert_eq!(tree.validate(), Ok(()));
+}
+
+// Inserting an element that is a leaf of another tree isn't caught without
+// these features, and `insert_all` must not make that worse by touching it.
+#[cfg(not(any(feature = "linked", feature = "paranoid")))]
+#[test]
+fn insert_all_leaves_rejected_elements_alone() {
+    let elems: Vec<_> = (0..3).map(Single::new).collect();
+    let dups: Vec<_> = (1..3).map(Single::new).collect();
+    let mut tree = SplayTree::<SingleTree>::new();
+    let others: Vec<_> = dups
+        .iter()
+        .map(|d| {
+            let mut other = SplayTree::<SingleTree>::new();
+            other.insert(d);
+            other
+        })
+        .collect();
+
+    let mut rejected = vec![];
+    let report = tree.insert_all(elems.iter().chain(&dups), |s| rejected.push(s.value));
+    assert_eq!((report.inserted(), report.rejected()), (3, 2));
+    assert_eq!(rejected, [1, 2]);
+
+    for (other, dup) in others.iter().zip(&dups) {
+        assert_eq!(other.validate(), Ok(()));
+        let mut values = vec![];
+        other.walk(|s| values.push(s.value));
+        assert_eq!(values, [dup.value]);
+    }
+}
@@ -0,0 +1,171 @@
+#[macro_use]
+extern crate quickcheck;
+
+mod single;
+
+use intrusive_splay_tree::{
+    ByWeight, IntrusiveNode, Node, SplayTree, Treap, TreapPriority, TreapWeight,
+};
+use single::{Single, SingleTree};
+use std::collections::BTreeSet;
+
+/// Check that no path is unreasonably long. Treaps have expected logarithmic
+/// depth, so a very deep treap means the priorities are not doing their job.
+fn is_shallow(node: Option<&Node<'_>>, depth: usize) -> bool {
+    node.is_none_or(|node| {
+        depth < 1000 && is_shallow(node.left(), depth + 1) && is_shallow(node.right(), depth + 1)
+    })
+}
+
+fn values<'a, P>(tree: &Treap<'a, SingleTree<'a>, P>) -> Vec<usize>
+where
+    P: TreapPriority<'a, SingleTree<'a>>,
+{
+    let mut values = vec![];
+    tree.walk(|s| values.push(s.value));
+    values
+}
+
+fn check<'a, P>(tree: &Treap<'a, SingleTree<'a>, P>, model: &BTreeSet<usize>) -> bool
+where
+    P: TreapPriority<'a, SingleTree<'a>>,
+{
+    is_shallow(tree.root().map(SingleTree::elem_to_node), 0)
+        && values(tree) == model.iter().copied().collect::<Vec<_>>()
+}
+
+/// Weigh elements by their value, so bigger values should be nearer the root.
+impl<'a> TreapWeight<'a, SingleTree<'a>> for Single<'a> {
+    fn weight(&self) -> u32 {
+        self.value.try_into().unwrap_or(u32::MAX)
+    }
+}
+
+quickcheck! {
+    fn treap_insert_and_find(xs: Vec<usize>, x: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = Treap::<SingleTree>::new();
+        let mut model = BTreeSet::new();
+
+        for x in xs {
+            if tree.insert(arena.alloc(Single::new(x))) != model.insert(x) {
+                return false;
+            }
+        }
+
+        check(&tree, &model)
+            && tree.find(&x).map(|s| s.value) == model.get(&x).copied()
+            && tree.min().map(|s| s.value) == model.first().copied()
+            && tree.max().map(|s| s.value) == model.last().copied()
+    }
+
+    fn treap_remove(xs: Vec<usize>, removes: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = Treap::<SingleTree>::from_iter(
+            xs.iter().map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        for x in removes.into_iter().chain(model.clone()) {
+            if tree.remove(&x).map(|s| s.value) != model.take(&x) || !check(&tree, &model) {
+                return false;
+            }
+        }
+
+        tree.is_empty()
+    }
+
+    fn treap_pop_min_and_max(xs: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = Treap::<SingleTree>::from_iter(
+            xs.iter().map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        loop {
+            let (popped, expected) = if model.len().is_multiple_of(2) {
+                (tree.pop_min(), model.pop_first())
+            } else {
+                (tree.pop_max(), model.pop_last())
+            };
+            if popped.map(|s| s.value) != expected || !check(&tree, &model) {
+                return false;
+            }
+            if expected.is_none() {
+                return tree.is_empty();
+            }
+        }
+    }
+
+    fn treap_split_off_and_append(xs: Vec<usize>, at: usize) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = Treap::<SingleTree>::from_iter(
+            xs.iter().map(|x| &*arena.alloc(Single::new(*x)))
+        );
+        let mut model: BTreeSet<usize> = xs.into_iter().collect();
+
+        let mut upper = tree.split_off(&at);
+        let mut upper_model = model.split_off(&at);
+        if !check(&tree, &model) || !check(&upper, &upper_model) {
+            return false;
+        }
+
+        tree.append(&mut upper);
+        model.append(&mut upper_model);
+        upper.is_empty() && check(&tree, &model)
+    }
+
+    fn weighted_treap_insert_and_remove(xs: Vec<usize>, removes: Vec<usize>) -> bool {
+        let arena = bumpalo::Bump::new();
+        let mut tree = Treap::<SingleTree, ByWeight>::new();
+        let mut model = BTreeSet::new();
+
+        for x in xs {
+            if tree.insert(arena.alloc(Single::new(x))) != model.insert(x) || !check(&tree, &model) {
+                return false;
+            }
+        }
+        for x in removes {
+            if tree.remove(&x).map(|s| s.value) != model.take(&x) || !check(&tree, &model) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn sorted_inserts_stay_shallow() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = Treap::<SingleTree>::new();
+    let mut model = BTreeSet::new();
+    for x in 0..10_000 {
+        tree.insert(arena.alloc(Single::new(x)));
+        model.insert(x);
+    }
+    assert!(check(&tree, &model));
+}
+
+#[test]
+fn heaviest_element_is_the_root() {
+    let arena = bumpalo::Bump::new();
+    let mut tree = Treap::<SingleTree, ByWeight>::new();
+    for x in (0..1000).chain(Some(u32::MAX as usize)).chain(1000..2000) {
+        tree.insert(arena.alloc(Single::new(x)));
+    }
+    assert_eq!(tree.root().map(|s| s.value), Some(u32::MAX as usize));
+}
+
+#[test]
+fn element_removed_from_a_splay_tree_can_join_a_treap() {
+    let elems: Vec<_> = (0..4).map(Single::new).collect();
+    let mut splay = SplayTree::<SingleTree>::new();
+    for e in &elems {
+        splay.insert(e);
+    }
+    let moved = splay.remove(&2).unwrap();
+
+    let mut tree = Treap::<SingleTree>::new();
+    assert!(tree.insert(moved));
+    assert_eq!(values(&tree), [2]);
+}
@@ -0,0 +1,34 @@
+// An element can't move while it is in a tree, since the tree links to its
+// node's address.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn main() {
+    let monster = Monster { health: 1, node: Node::new() };
+    let mut tree = SplayTree::<ByHealth>::new();
+    tree.insert(&monster);
+    let moved = monster;
+    tree.min();
+    drop(moved);
+}
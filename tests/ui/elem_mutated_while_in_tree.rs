@@ -0,0 +1,34 @@
+// An element can't be mutated through `&mut` while it is in a tree, which
+// could change its key out from under the tree's order. Keys that change
+// need a `Cell` and `SplayTree::update_key`.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn main() {
+    let mut monster = Monster { health: 1, node: Node::new() };
+    let mut tree = SplayTree::<ByHealth>::new();
+    tree.insert(&monster);
+    monster.health = 2;
+    tree.min();
+}
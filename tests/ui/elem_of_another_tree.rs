@@ -0,0 +1,36 @@
+// A tree only takes elements of its own tree type's element type.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+struct Item<'a> {
+    weight: u32,
+    node: Node<'a>,
+}
+
+fn main() {
+    let item = Item { weight: 1, node: Node::new() };
+    let mut tree = SplayTree::<ByHealth>::new();
+    tree.insert(&item);
+    let _ = item.weight;
+}
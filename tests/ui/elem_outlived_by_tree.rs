@@ -0,0 +1,33 @@
+// A tree can't outlive the elements in it.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn main() {
+    let mut tree = SplayTree::<ByHealth>::new();
+    {
+        let monster = Monster { health: 1, node: Node::new() };
+        tree.insert(&monster);
+    }
+    tree.min();
+}
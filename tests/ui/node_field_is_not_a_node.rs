@@ -0,0 +1,26 @@
+// `impl_intrusive_node!` only accepts a field that is a `Node`.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = health;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn main() {}
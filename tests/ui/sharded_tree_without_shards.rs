@@ -0,0 +1,38 @@
+// A sharded tree needs at least one shard to put its elements in. The check is
+// evaluated when `new` is instantiated, which `cargo check` only does for
+// constants, so build the tree in one.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, ShardKey, ShardedSplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+impl<'a> ShardKey<'a, ByHealth> for Monster<'a> {
+    fn shard(&self) -> usize {
+        self.health as usize
+    }
+}
+
+const EMPTY: ShardedSplayTree<'static, ByHealth, 0> = ShardedSplayTree::new();
+
+fn main() {
+    let _tree = EMPTY;
+}
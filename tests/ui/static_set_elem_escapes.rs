@@ -0,0 +1,45 @@
+// A `StaticSplaySet` only lends out its elements to closures that work for any
+// lifetime. Otherwise, one could be put in another tree along with shorter
+// lived elements, whose links it would keep after they are gone.
+
+use intrusive_splay_tree::{
+    impl_intrusive_node, Node, SplayTree, StaticSplaySet, TreeFamily, TreeOrd,
+};
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::pin::pin;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth<'a>(PhantomData<&'a ()>);
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth<'a>
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth<'a>> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+struct Monsters;
+
+impl TreeFamily for Monsters {
+    type Tree<'a> = ByHealth<'a>;
+}
+
+fn main() {
+    let mut set = pin!(StaticSplaySet::<Monsters, 1>::new());
+    let _ = set.as_mut().insert(Monster { health: 1, node: Node::new() });
+    let mut tree = SplayTree::<ByHealth>::new();
+    set.as_mut().min(|monster| {
+        tree.insert(monster);
+    });
+}
@@ -0,0 +1,35 @@
+// Trees link their elements through `Cell`s, so they can't be sent to, or
+// shared with, other threads.
+
+use intrusive_splay_tree::{impl_intrusive_node, Node, SplayTree, TreeOrd};
+use std::cmp::Ordering;
+
+struct Monster<'a> {
+    health: u32,
+    node: Node<'a>,
+}
+
+struct ByHealth;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for ByHealth
+    where
+        type Elem = Monster<'a>,
+        node = node;
+}
+
+impl<'a> TreeOrd<'a, ByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &'a Monster<'a>) -> Ordering {
+        self.health.cmp(&rhs.health)
+    }
+}
+
+fn main() {
+    let monster: &'static Monster<'static> =
+        Box::leak(Box::new(Monster { health: 1, node: Node::new() }));
+    let mut tree = SplayTree::<ByHealth>::new();
+    tree.insert(monster);
+    std::thread::spawn(move || {
+        tree.min();
+    });
+}
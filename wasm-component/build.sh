@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+
+set -eux
+
+cd "$(dirname $0)"
+
+# The `wasm32-wasip2` target links crates into components directly, so this
+# doesn't need `cargo component`, although `cargo component build --release`
+# works too.
+rustup target add wasm32-wasip2
+
+cargo build --release --target wasm32-wasip2
+
+WASM="$(pwd)/target/wasm32-wasip2/release/intrusive_splay_tree_component.wasm"
+
+# Run the tests, which load the component into Wasmtime on the host.
+cargo test
+
+# Print the component's interface, to check that it matches `wit/`.
+if [[ -x "$(which wasm-tools)" ]]; then
+    wasm-tools component wit "$WASM"
+fi
+
+wc -c "$WASM"
+echo -n "$(basename $WASM) gzipped is "
+gzip --best < "$WASM" | wc -c
@@ -0,0 +1,155 @@
+//! The monsters example from `../wasm`, exported through the WIT interface in
+//! `wit/monsters.wit` instead of `wasm-bindgen`'s JavaScript glue, so that any
+//! component-model host, like Wasmtime, can use it.
+
+use intrusive_splay_tree::{impl_intrusive_node, SplayTree};
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::ptr;
+
+wit_bindgen::generate!({
+    world: "example",
+});
+
+use exports::intrusive_splay_tree::example::monsters::{Guest, GuestMonsters};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Id(pub u32);
+
+#[derive(Debug)]
+pub struct Monster<'a> {
+    id: Id,
+    health: Cell<u32>,
+    by_id_node: intrusive_splay_tree::Node<'a>,
+    by_health_node: intrusive_splay_tree::Node<'a>,
+}
+
+pub struct MonstersById;
+
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for MonstersById
+    where
+        type Elem = Monster<'a>,
+        node = by_id_node;
+}
+
+impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersById> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
+        self.id.cmp(&rhs.id)
+    }
+}
+
+pub struct MonstersByHealth;
+impl_intrusive_node! {
+    impl<'a> IntrusiveNode<'a> for MonstersByHealth
+    where
+        type Elem = Monster<'a>,
+        node = by_health_node;
+}
+// Break ties by id, as in `../wasm`, so that every monster is its own key.
+impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersByHealth> for Monster<'a> {
+    fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
+        (self.health.get(), self.id).cmp(&(rhs.health.get(), rhs.id))
+    }
+}
+
+impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersByHealth> for u32 {
+    fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
+        self.cmp(&rhs.health.get())
+    }
+}
+
+impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersById> for Id {
+    fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
+        self.cmp(&rhs.id)
+    }
+}
+
+#[derive(Default)]
+struct Trees {
+    by_id: SplayTree<'static, MonstersById>,
+    by_health: SplayTree<'static, MonstersByHealth>,
+}
+
+/// A set of monsters, indexed both by id and by health.
+///
+/// Resource methods only get `&self`, and every lookup splays, so the trees
+/// are in a `RefCell`. Component instances are single-threaded, and calls
+/// into one can't be reentrant, so the borrows never conflict.
+pub struct Monsters {
+    trees: RefCell<Trees>,
+}
+
+impl GuestMonsters for Monsters {
+    fn new() -> Monsters {
+        Monsters {
+            trees: RefCell::default(),
+        }
+    }
+
+    fn new_monster(&self, id: u32, health: u32) -> bool {
+        let trees = &mut *self.trees.borrow_mut();
+        if trees.by_id.find(&Id(id)).is_some() {
+            return false;
+        }
+        // Leaked, like in `../wasm`: a monster has to outlive every tree it
+        // might be in, and this keeps the example short.
+        let monster: &'static Monster<'static> = Box::leak(Box::new(Monster {
+            id: Id(id),
+            health: Cell::new(health),
+            by_id_node: Default::default(),
+            by_health_node: Default::default(),
+        }));
+        trees.by_id.insert(monster);
+        trees.by_health.insert(monster);
+        true
+    }
+
+    fn query_by_id(&self, id: u32) -> Option<u32> {
+        let trees = &mut *self.trees.borrow_mut();
+        trees.by_id.find(&Id(id)).map(|m| m.health.get())
+    }
+
+    fn query_by_health(&self, health: u32) -> Option<u32> {
+        let trees = &mut *self.trees.borrow_mut();
+        trees.by_health.find(&health).map(|m| m.id.0)
+    }
+
+    fn kill_monster(&self, id: u32) -> bool {
+        let trees = &mut *self.trees.borrow_mut();
+        let monster = match trees.by_id.remove(&Id(id)) {
+            Some(monster) => monster,
+            None => return false,
+        };
+        let removed = trees.by_health.remove(monster);
+        debug_assert!(removed.is_some_and(|m| ptr::eq(m, monster)));
+        true
+    }
+
+    fn set_health(&self, id: u32, health: u32) -> bool {
+        let trees = &mut *self.trees.borrow_mut();
+        let monster = match trees.by_id.find(&Id(id)) {
+            Some(monster) => monster,
+            None => return false,
+        };
+        let updated = trees
+            .by_health
+            .update_key(monster, |m| m.health.set(health));
+        debug_assert!(matches!(updated, Some(Ok(m)) if ptr::eq(m, monster)));
+        true
+    }
+
+    fn ids_by_health(&self) -> Vec<u32> {
+        let mut ids = Vec::new();
+        self.trees.borrow().by_health.walk(|m| ids.push(m.id.0));
+        ids
+    }
+}
+
+struct Component;
+
+impl Guest for Component {
+    type Monsters = Monsters;
+}
+
+export!(Component);
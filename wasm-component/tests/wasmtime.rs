@@ -0,0 +1,157 @@
+//! Integration tests for the component, through Wasmtime. Run `./build.sh`
+//! first, which builds the component and then runs these.
+
+use wasmtime::component::{bindgen, Component, Linker, ResourceAny, ResourceTable};
+use wasmtime::{Engine, Store};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
+
+bindgen!({
+    world: "example",
+});
+
+use exports::intrusive_splay_tree::example::monsters::GuestMonsters;
+
+const COMPONENT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/wasm32-wasip2/release/intrusive_splay_tree_component.wasm"
+);
+
+/// The standard library's WASI support imports a few WASI interfaces, even
+/// though the monsters never use them.
+struct Host {
+    ctx: WasiCtx,
+    table: ResourceTable,
+}
+
+impl WasiView for Host {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+/// A fresh instance of the component, with a set of the given monsters.
+struct Monsters {
+    store: Store<Host>,
+    example: Example,
+    set: ResourceAny,
+}
+
+impl Monsters {
+    fn spawn(monsters: &[(u32, u32)]) -> Monsters {
+        let engine = Engine::default();
+        let component = Component::from_file(&engine, COMPONENT)
+            .unwrap_or_else(|e| panic!("{e:#}; run ./build.sh first"));
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker).unwrap();
+        let host = Host {
+            ctx: WasiCtxBuilder::new().build(),
+            table: ResourceTable::new(),
+        };
+        let mut store = Store::new(&engine, host);
+        let example = Example::instantiate(&mut store, &component, &linker).unwrap();
+        let set = Self::guest(&example).call_constructor(&mut store).unwrap();
+
+        let mut set = Monsters {
+            store,
+            example,
+            set,
+        };
+        for &(id, health) in monsters {
+            assert!(set.new_monster(id, health));
+        }
+        set
+    }
+
+    fn guest(example: &Example) -> GuestMonsters<'_> {
+        example.intrusive_splay_tree_example_monsters().monsters()
+    }
+
+    fn new_monster(&mut self, id: u32, health: u32) -> bool {
+        Self::guest(&self.example)
+            .call_new_monster(&mut self.store, self.set, id, health)
+            .unwrap()
+    }
+
+    fn query_by_id(&mut self, id: u32) -> Option<u32> {
+        Self::guest(&self.example)
+            .call_query_by_id(&mut self.store, self.set, id)
+            .unwrap()
+    }
+
+    fn query_by_health(&mut self, health: u32) -> Option<u32> {
+        Self::guest(&self.example)
+            .call_query_by_health(&mut self.store, self.set, health)
+            .unwrap()
+    }
+
+    fn kill_monster(&mut self, id: u32) -> bool {
+        Self::guest(&self.example)
+            .call_kill_monster(&mut self.store, self.set, id)
+            .unwrap()
+    }
+
+    fn set_health(&mut self, id: u32, health: u32) -> bool {
+        Self::guest(&self.example)
+            .call_set_health(&mut self.store, self.set, id, health)
+            .unwrap()
+    }
+
+    fn ids_by_health(&mut self) -> Vec<u32> {
+        Self::guest(&self.example)
+            .call_ids_by_health(&mut self.store, self.set)
+            .unwrap()
+    }
+}
+
+#[test]
+fn new_monsters_are_indexed_by_id_and_by_health() {
+    let mut set = Monsters::spawn(&[(1, 10), (2, 30), (3, 20)]);
+    assert!(!set.new_monster(2, 99));
+    assert_eq!(set.query_by_id(2), Some(30));
+    assert_eq!(set.query_by_id(4), None);
+    assert_eq!(set.query_by_health(20), Some(3));
+    assert_eq!(set.ids_by_health(), [1, 3, 2]);
+}
+
+#[test]
+fn monsters_with_the_same_health_are_all_kept() {
+    let mut set = Monsters::spawn(&[(1, 10), (2, 10), (3, 10)]);
+    assert_eq!(set.ids_by_health(), [1, 2, 3]);
+    assert!(matches!(set.query_by_health(10), Some(1..=3)));
+}
+
+#[test]
+fn killing_a_monster_removes_it_from_both_indices() {
+    let mut set = Monsters::spawn(&[(1, 10), (2, 10), (3, 5)]);
+    assert!(set.kill_monster(2));
+    assert!(!set.kill_monster(2));
+    assert_eq!(set.query_by_id(2), None);
+
+    // Monster 1 has the same health, and must survive its neighbor's death.
+    assert_eq!(set.query_by_health(10), Some(1));
+    assert_eq!(set.ids_by_health(), [3, 1]);
+
+    // The id is free again.
+    assert!(set.new_monster(2, 1));
+    assert_eq!(set.ids_by_health(), [2, 3, 1]);
+}
+
+#[test]
+fn setting_health_moves_a_monster_in_the_health_index() {
+    let mut set = Monsters::spawn(&[(1, 10), (2, 20), (3, 30)]);
+    assert!(set.set_health(1, 25));
+    assert!(!set.set_health(4, 25));
+    assert_eq!(set.query_by_id(1), Some(25));
+    assert_eq!(set.query_by_health(10), None);
+    assert_eq!(set.ids_by_health(), [2, 1, 3]);
+
+    // Moving onto another monster's health keeps both.
+    assert!(set.set_health(3, 20));
+    assert_eq!(set.ids_by_health(), [2, 3, 1]);
+    assert!(set.kill_monster(2));
+    assert_eq!(set.query_by_health(20), Some(3));
+}
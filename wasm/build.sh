@@ -4,16 +4,19 @@ set -eux
 
 cd "$(dirname $0)"
 
-rustup target add wasm32-unknown-unknown --toolchain nightly
+rustup target add wasm32-unknown-unknown
 
-cargo +nightly build --release --target wasm32-unknown-unknown
+cargo build --release --target wasm32-unknown-unknown
 
 WASM="$(pwd)/target/wasm32-unknown-unknown/release/intrusive_splay_tree_wasm.wasm"
 
-if [[ -x "$(which wasm-gc)" ]]; then
-    new_wasm="${WASM/\.wasm/.gc.wasm}"
-    wasm-gc "$WASM" "$new_wasm"
-    WASM="$new_wasm"
+# Generate the JS glue for the `#[wasm_bindgen]` exports. This also strips
+# unused functions and custom sections, which `wasm-gc` used to do.
+wasm-bindgen --target web --out-dir pkg "$WASM"
+WASM="$(pwd)/pkg/intrusive_splay_tree_wasm_bg.wasm"
+
+if [[ -x "$(which node)" ]]; then
+    node --test test.mjs
 fi
 
 if [[ -x "$(which wasm-opt)" ]];then
@@ -1,37 +1,82 @@
 #![no_std]
-#![feature(core_intrinsics, lang_items)]
 
-#[macro_use]
-extern crate intrusive_splay_tree;
+extern crate alloc;
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::wasm32;
+use core::cell::Cell;
 use core::cmp::Ordering;
-use core::mem;
 use core::ptr;
+use intrusive_splay_tree::{impl_intrusive_node, SplayTree};
+use wasm_bindgen::prelude::*;
+
+// Need to provide a tiny panic handler for `#![no_std]`. This implementation
+// will translate panics into traps in the resulting WebAssembly.
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    wasm32::unreachable()
+}
+
+const PAGE_SIZE: usize = 64 * 1024;
 
-pub use intrusive_splay_tree::SplayTree;
-
-// Need to provide a tiny `panic_fmt` lang-item implementation for `#![no_std]`.
-// This implementation will translate panics into traps in the resulting
-// WebAssembly.
-#[lang = "panic_fmt"]
-extern "C" fn panic_fmt(
-    _args: ::core::fmt::Arguments,
-    _file: &'static str,
-    _line: u32
-) -> ! {
-    use core::intrinsics;
-    unsafe {
-        intrinsics::abort();
+/// A bump allocator that grows linear memory and never frees anything. The
+/// monsters live for the rest of the program anyway, and this keeps the
+/// binary small.
+struct BumpAllocator {
+    next: Cell<usize>,
+    end: Cell<usize>,
+}
+
+// WebAssembly without the threads proposal is single-threaded.
+unsafe impl Sync for BumpAllocator {}
+
+unsafe impl GlobalAlloc for BumpAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        loop {
+            let start = (self.next.get() + layout.align() - 1) & !(layout.align() - 1);
+            let end = match start.checked_add(layout.size()) {
+                Some(end) => end,
+                None => return ptr::null_mut(),
+            };
+            if end <= self.end.get() {
+                self.next.set(end);
+                return start as *mut u8;
+            }
+
+            // Grow by enough pages for the allocation plus its alignment
+            // padding.
+            let pages = layout.size().div_ceil(PAGE_SIZE) + 1;
+            let old_pages = wasm32::memory_grow(0, pages);
+            if old_pages == usize::MAX {
+                return ptr::null_mut();
+            }
+            if old_pages * PAGE_SIZE != self.end.get() {
+                // Someone else grew memory since our last allocation, so our
+                // remaining space isn't contiguous with the new pages.
+                self.next.set(old_pages * PAGE_SIZE);
+            }
+            self.end.set((old_pages + pages) * PAGE_SIZE);
+        }
     }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
 }
 
+#[global_allocator]
+static ALLOCATOR: BumpAllocator = BumpAllocator {
+    next: Cell::new(0),
+    end: Cell::new(0),
+};
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Id(pub u32);
 
 #[derive(Debug)]
 pub struct Monster<'a> {
     id: Id,
-    health: u32,
+    health: Cell<u32>,
     by_id_node: intrusive_splay_tree::Node<'a>,
     by_health_node: intrusive_splay_tree::Node<'a>,
 }
@@ -58,15 +103,21 @@ impl_intrusive_node! {
         type Elem = Monster<'a>,
         node = by_health_node;
 }
+// Many monsters can have the same health, but a tree only holds one element
+// per key, so break ties by id. Each monster is then its own unique key, and
+// removing it by reference can't remove a different monster that happens to
+// have the same health.
 impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersByHealth> for Monster<'a> {
     fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
-        self.health.cmp(&rhs.health)
+        (self.health.get(), self.id).cmp(&(rhs.health.get(), rhs.id))
     }
 }
 
+// Looking up by health alone matches any of the monsters with that health,
+// which are next to each other in the tree's order.
 impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersByHealth> for u32 {
     fn tree_cmp(&self, rhs: &Monster<'a>) -> Ordering {
-        self.cmp(&rhs.health)
+        self.cmp(&rhs.health.get())
     }
 }
 
@@ -76,62 +127,99 @@ impl<'a> intrusive_splay_tree::TreeOrd<'a, MonstersById> for Id {
     }
 }
 
-extern "C" {
-    fn alloc(n: usize) -> *mut u8;
+/// A set of monsters, indexed both by id and by health.
+#[wasm_bindgen]
+pub struct Monsters {
+    by_id: SplayTree<'static, MonstersById>,
+    by_health: SplayTree<'static, MonstersByHealth>,
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn new_id_tree() -> *mut SplayTree<'static, MonstersById> {
-    let p = alloc(mem::size_of::<SplayTree<MonstersById>>());
-    let p = p as *mut SplayTree<MonstersById>;
-    ptr::write(p, SplayTree::default());
-    p
+impl Default for Monsters {
+    fn default() -> Monsters {
+        Monsters::new()
+    }
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn new_health_tree() -> *mut SplayTree<'static, MonstersByHealth> {
-    let p = alloc(mem::size_of::<SplayTree<MonstersByHealth>>());
-    let p = p as *mut SplayTree<MonstersByHealth>;
-    ptr::write(p, SplayTree::default());
-    p
-}
+#[wasm_bindgen]
+impl Monsters {
+    /// Create a new, empty set of monsters.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Monsters {
+        Monsters {
+            by_id: SplayTree::new(),
+            by_health: SplayTree::new(),
+        }
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn new_monster(
-    id: u32,
-    health: u32,
-    by_id_tree: *mut SplayTree<'static, MonstersById>,
-    by_health_tree: *mut SplayTree<'static, MonstersByHealth>,
-) -> *const Monster<'static> {
-    let p = alloc(mem::size_of::<Monster>());
-    let p = p as *mut Monster<'static>;
-    ptr::write(
-        p,
-        Monster {
+    /// Add a new monster.
+    ///
+    /// Returns `false` if there is already a monster with this id, in which
+    /// case nothing is added.
+    pub fn new_monster(&mut self, id: u32, health: u32) -> bool {
+        if self.by_id.find(&Id(id)).is_some() {
+            return false;
+        }
+        let monster: &'static Monster<'static> = Box::leak(Box::new(Monster {
             id: Id(id),
-            health,
+            health: Cell::new(health),
             by_id_node: Default::default(),
             by_health_node: Default::default(),
-        },
-    );
-    let monster = &*p;
-    (*by_id_tree).insert(monster);
-    (*by_health_tree).insert(monster);
-    monster
-}
+        }));
+        self.by_id.insert(monster);
+        self.by_health.insert(monster);
+        true
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn query_by_id(
-    tree: *mut SplayTree<'static, MonstersById>,
-    id: u32,
-) -> *const Monster<'static> {
-    (*tree).find(&Id(id)).map_or(ptr::null(), |m| m as *const _)
-}
+    /// Get the health of the monster with the given id, if any.
+    pub fn query_by_id(&mut self, id: u32) -> Option<u32> {
+        self.by_id.find(&Id(id)).map(|m| m.health.get())
+    }
+
+    /// Get the id of a monster with the given health, if any.
+    pub fn query_by_health(&mut self, health: u32) -> Option<u32> {
+        self.by_health.find(&health).map(|m| m.id.0)
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn query_by_health(
-    tree: *mut SplayTree<'static, MonstersByHealth>,
-    health: u32,
-) -> *const Monster<'static> {
-    (*tree).find(&health).map_or(ptr::null(), |m| m as *const _)
+    /// Remove the monster with the given id from both indices.
+    ///
+    /// Returns `false` if there is no such monster. The monster's memory is
+    /// never reused, since the allocator never frees anything.
+    pub fn kill_monster(&mut self, id: u32) -> bool {
+        let monster = match self.by_id.remove(&Id(id)) {
+            Some(monster) => monster,
+            None => return false,
+        };
+        // Remove by the monster itself rather than by its health, so that the
+        // health index drops this monster and not another with the same
+        // health.
+        let removed = self.by_health.remove(monster);
+        debug_assert!(removed.is_some_and(|m| ptr::eq(m, monster)));
+        true
+    }
+
+    /// Set the health of the monster with the given id.
+    ///
+    /// Returns `false` if there is no such monster.
+    pub fn set_health(&mut self, id: u32, health: u32) -> bool {
+        let monster = match self.by_id.find(&Id(id)) {
+            Some(monster) => monster,
+            None => return false,
+        };
+        // Health is the health index's key, so the monster has to come out of
+        // that tree while it changes, and go back in at its new position.
+        // Its id breaks ties, so there is never another monster in the way.
+        let updated = self
+            .by_health
+            .update_key(monster, |m| m.health.set(health));
+        debug_assert!(matches!(updated, Some(Ok(m)) if ptr::eq(m, monster)));
+        true
+    }
+
+    /// Get the ids of every monster, from least to most healthy, with ties
+    /// in order of id.
+    pub fn ids_by_health(&self) -> Vec<u32> {
+        let mut ids = Vec::new();
+        self.by_health.walk(|m| ids.push(m.id.0));
+        ids
+    }
 }
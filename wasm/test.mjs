@@ -0,0 +1,63 @@
+// Integration tests for the WebAssembly exports. Run `./build.sh` first, which
+// generates `pkg/`, and then `node --test test.mjs`.
+
+import assert from "node:assert/strict";
+import { readFile } from "node:fs/promises";
+import { test } from "node:test";
+import { initSync, Monsters } from "./pkg/intrusive_splay_tree_wasm.js";
+
+const wasm = await readFile(new URL("./pkg/intrusive_splay_tree_wasm_bg.wasm", import.meta.url));
+initSync({ module: wasm });
+
+function spawn(monsters) {
+  const set = new Monsters();
+  for (const [id, health] of monsters) {
+    assert.ok(set.new_monster(id, health));
+  }
+  return set;
+}
+
+test("new monsters are indexed by id and by health", () => {
+  const set = spawn([[1, 10], [2, 30], [3, 20]]);
+  assert.equal(set.new_monster(2, 99), false);
+  assert.equal(set.query_by_id(2), 30);
+  assert.equal(set.query_by_id(4), undefined);
+  assert.equal(set.query_by_health(20), 3);
+  assert.deepEqual([...set.ids_by_health()], [1, 3, 2]);
+});
+
+test("monsters with the same health are all kept", () => {
+  const set = spawn([[1, 10], [2, 10], [3, 10]]);
+  assert.deepEqual([...set.ids_by_health()], [1, 2, 3]);
+  assert.ok([1, 2, 3].includes(set.query_by_health(10)));
+});
+
+test("killing a monster removes it from both indices", () => {
+  const set = spawn([[1, 10], [2, 10], [3, 5]]);
+  assert.ok(set.kill_monster(2));
+  assert.equal(set.kill_monster(2), false);
+  assert.equal(set.query_by_id(2), undefined);
+
+  // Monster 1 has the same health, and must survive its neighbor's death.
+  assert.equal(set.query_by_health(10), 1);
+  assert.deepEqual([...set.ids_by_health()], [3, 1]);
+
+  // The id is free again.
+  assert.ok(set.new_monster(2, 1));
+  assert.deepEqual([...set.ids_by_health()], [2, 3, 1]);
+});
+
+test("setting health moves a monster in the health index", () => {
+  const set = spawn([[1, 10], [2, 20], [3, 30]]);
+  assert.ok(set.set_health(1, 25));
+  assert.equal(set.set_health(4, 25), false);
+  assert.equal(set.query_by_id(1), 25);
+  assert.equal(set.query_by_health(10), undefined);
+  assert.deepEqual([...set.ids_by_health()], [2, 1, 3]);
+
+  // Moving onto another monster's health keeps both.
+  assert.ok(set.set_health(3, 20));
+  assert.deepEqual([...set.ids_by_health()], [2, 3, 1]);
+  assert.ok(set.kill_monster(2));
+  assert.equal(set.query_by_health(20), 3);
+});